@@ -11,6 +11,7 @@
 //! The buildtools project can be found at: https://github.com/bazelbuild/buildtools
 
 use anyhow::{Context, Result};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 
@@ -169,6 +170,44 @@ impl Buildifier {
         Ok(())
     }
 
+    /// Format in-memory source, returning the formatted text.
+    ///
+    /// `path` is only used by buildifier to pick the file type and for error messages.
+    pub fn format_str(&self, path: &Path, content: &str) -> Result<String> {
+        let mut child = Command::new(&self.binary_path)
+            .arg(format!("--path={}", path.to_string_lossy()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute buildifier")?;
+
+        // Feed stdin from another thread: buildifier may fill the stdout pipe before it has
+        // read all of its input, and neither side would make progress.
+        let mut stdin = child
+            .stdin
+            .take()
+            .context("Failed to open buildifier stdin")?;
+        let content = content.to_owned();
+        let writer = std::thread::spawn(move || stdin.write_all(content.as_bytes()));
+
+        let output = child
+            .wait_with_output()
+            .context("Failed to wait for buildifier")?;
+        let written = writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("buildifier stdin writer panicked"))?;
+
+        // A buildifier that exits early breaks the pipe; its stderr says why.
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to format source: {stderr}");
+        }
+        written.context("Failed to write to buildifier stdin")?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
     /// Get the diff that would be applied to format a file
     pub fn diff_file(&self, file_path: &Path) -> Result<String> {
         let output = self.run_with_io(&[
@@ -213,6 +252,15 @@ mod tests {
         assert!(version.contains("buildifier"));
     }
 
+    #[test]
+    fn test_format_str() {
+        let buildifier = Buildifier::new().unwrap();
+        let formatted = buildifier
+            .format_str(Path::new("test.zen"), "x=[1,2]\n")
+            .unwrap();
+        assert_eq!(formatted, "x = [1, 2]\n");
+    }
+
     #[test]
     fn test_caching() {
         // Create two instances and verify they use the same cached binary
@@ -41,6 +41,9 @@ impl KicadSymbol {
 pub(super) struct KicadPin {
     pub(super) name: String,
     pub(super) number: String,
+    pub(super) unit: u32,
+    pub(super) electrical_type: String,
+    pub(super) hidden: bool,
 }
 
 impl From<KicadSymbol> for Symbol {
@@ -61,6 +64,9 @@ impl From<KicadSymbol> for Symbol {
                 .map(|pin| Pin {
                     name: pin.name,
                     number: pin.number,
+                    unit: pin.unit,
+                    electrical_type: pin.electrical_type,
+                    hidden: pin.hidden,
                 })
                 .collect(),
             raw_sexp: symbol.raw_sexp,
@@ -149,13 +155,37 @@ pub(super) fn parse_symbol(symbol_data: &[Sexpr]) -> Result<KicadSymbol> {
     Ok(symbol)
 }
 
+/// Split a sub-symbol name of the form `<name>_<unit>_<style>` into its unit
+/// and body style. Unit 0 holds graphics and pins shared by every unit.
+fn parse_unit_suffix(section_name: &str) -> Option<(u32, u32)> {
+    let mut parts = section_name.rsplitn(3, '_');
+    let style = parts.next()?.parse().ok()?;
+    let unit = parts.next()?.parse().ok()?;
+    parts.next()?;
+    Some((unit, style))
+}
+
 // New function to parse the nested symbol section which contains pins in new format
 fn parse_symbol_section(symbol: &mut KicadSymbol, section_data: &[Sexpr]) {
+    let (unit, style) = match section_data.get(1) {
+        Some(Sexpr::Symbol(name) | Sexpr::String(name)) => {
+            parse_unit_suffix(name).unwrap_or((0, 0))
+        }
+        _ => (0, 0),
+    };
+
+    // Body style 2 is the De Morgan alternate, which repeats the pins of the
+    // normal body style.
+    if style == 2 {
+        return;
+    }
+
     for item in section_data {
         if let Sexpr::List(pin_data) = item {
             if let Some(Sexpr::Symbol(type_name)) = pin_data.first() {
                 if type_name == "pin" {
-                    if let Some(pin) = parse_pin_from_section(pin_data) {
+                    if let Some(mut pin) = parse_pin_from_section(pin_data) {
+                        pin.unit = unit;
                         symbol.pins.push(pin);
                     }
                 }
@@ -167,7 +197,11 @@ fn parse_symbol_section(symbol: &mut KicadSymbol, section_data: &[Sexpr]) {
 // New function to parse pins from the nested symbol section
 fn parse_pin_from_section(pin_data: &[Sexpr]) -> Option<KicadPin> {
     // Format: (pin unspecified line (at X Y Z) (length L) (name "Name") (number "N"))
-    let mut pin = KicadPin::default();
+    let mut pin = KicadPin {
+        electrical_type: pin_electrical_type(pin_data),
+        hidden: pin_is_hidden(pin_data),
+        ..Default::default()
+    };
 
     // Extract name and number from the pin data
     for item in pin_data {
@@ -196,6 +230,26 @@ fn parse_pin_from_section(pin_data: &[Sexpr]) -> Option<KicadPin> {
     }
 }
 
+fn pin_electrical_type(pin_data: &[Sexpr]) -> String {
+    match pin_data.get(1) {
+        Some(Sexpr::Symbol(kind)) => kind.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Pins are hidden either with a bare `hide` flag (KiCad 7 and older) or with
+/// `(hide yes)` (KiCad 8).
+fn pin_is_hidden(pin_data: &[Sexpr]) -> bool {
+    pin_data.iter().any(|item| match item {
+        Sexpr::Symbol(flag) => flag == "hide",
+        Sexpr::List(items) => {
+            matches!(items.first(), Some(Sexpr::Symbol(tag)) if tag == "hide")
+                && matches!(items.get(1), Some(Sexpr::Symbol(value)) if value == "yes")
+        }
+        _ => false,
+    })
+}
+
 fn parse_in_bom(symbol: &mut KicadSymbol, prop_list: &[Sexpr]) {
     symbol.in_bom = prop_list
         .get(1)
@@ -259,7 +313,11 @@ fn parse_property(symbol: &mut KicadSymbol, prop_list: &[Sexpr]) {
 }
 
 fn parse_pin(pin_list: &[Sexpr]) -> Option<KicadPin> {
-    let mut pin = KicadPin::default();
+    let mut pin = KicadPin {
+        electrical_type: pin_electrical_type(pin_list),
+        hidden: pin_is_hidden(pin_list),
+        ..Default::default()
+    };
 
     for item in pin_list {
         if let Sexpr::List(prop_list) = item {
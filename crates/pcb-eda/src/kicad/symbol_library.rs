@@ -815,4 +815,51 @@ mod tests {
             panic!("CustomIC should have raw_sexp after extends resolution");
         }
     }
+
+    #[test]
+    fn test_units_skip_de_morgan_body_style() {
+        let content = r#"(kicad_symbol_lib
+            (symbol "Gate"
+                (property "Reference" "U" (at 0 0 0))
+                (symbol "Gate_0_1"
+                    (pin power_in line (at 0 5.08 270) (length 2.54) hide
+                        (name "VCC" (effects (font (size 1.27 1.27))))
+                        (number "14" (effects (font (size 1.27 1.27))))
+                    )
+                )
+                (symbol "Gate_1_1"
+                    (pin input line (at -5.08 0 0) (length 2.54)
+                        (name "A" (effects (font (size 1.27 1.27))))
+                        (number "1" (effects (font (size 1.27 1.27))))
+                    )
+                )
+                (symbol "Gate_1_2"
+                    (pin input line (at -5.08 0 0) (length 2.54)
+                        (name "A" (effects (font (size 1.27 1.27))))
+                        (number "1" (effects (font (size 1.27 1.27))))
+                    )
+                )
+                (symbol "Gate_2_1"
+                    (pin input line (at -5.08 0 0) (length 2.54)
+                        (name "A" (effects (font (size 1.27 1.27))))
+                        (number "4" (effects (font (size 1.27 1.27))))
+                    )
+                )
+            )
+        )"#;
+
+        let lib = KicadSymbolLibrary::from_string(content).unwrap();
+        let gate = lib.get_symbol("Gate").unwrap();
+
+        let pins: Vec<_> = gate
+            .pins
+            .iter()
+            .map(|p| (p.number.as_str(), p.unit, p.hidden))
+            .collect();
+        assert_eq!(
+            pins,
+            vec![("14", 0, true), ("1", 1, false), ("4", 2, false)]
+        );
+        assert_eq!(gate.pins[0].electrical_type, "power_in");
+    }
 }
@@ -4,14 +4,14 @@ use anyhow::Result;
 use kicad::symbol::KicadSymbol;
 use kicad::symbol_library::KicadSymbolLibrary;
 use pcb_sexpr::Sexpr;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 use std::io;
 use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Debug, Default, Clone, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Symbol {
     pub name: String,
     pub footprint: String,
@@ -27,16 +27,27 @@ pub struct Symbol {
     pub raw_sexp: Option<Sexpr>,
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Part {
     pub part_number: String,
     pub url: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pin {
     pub name: String,
     pub number: String,
+    /// Unit (gate) the pin belongs to. Unit 0 pins are shared by every unit.
+    pub unit: u32,
+    /// KiCad electrical type, e.g. `input`, `output` or `power_in`.
+    pub electrical_type: String,
+    pub hidden: bool,
+}
+
+impl Pin {
+    pub fn is_power(&self) -> bool {
+        matches!(self.electrical_type.as_str(), "power_in" | "power_out")
+    }
 }
 
 impl Symbol {
@@ -59,6 +70,29 @@ impl Symbol {
     pub fn raw_sexp(&self) -> Option<&Sexpr> {
         self.raw_sexp.as_ref()
     }
+
+    /// Number of units (gates) in the symbol. Symbols without explicit units
+    /// count as a single unit.
+    pub fn unit_count(&self) -> u32 {
+        self.pins
+            .iter()
+            .map(|pin| pin.unit)
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+}
+
+/// KiCad's display name for a unit: 1 -> "A", 2 -> "B", ..., 27 -> "AA".
+pub fn unit_name(unit: u32) -> String {
+    let mut name = String::new();
+    let mut n = unit;
+    while n > 0 {
+        n -= 1;
+        name.insert(0, (b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    name
 }
 
 /// A symbol library that can contain multiple symbols
@@ -445,3 +445,48 @@ fn test_lan9252ti_pin_names() {
     assert_eq!(pin_map.get("64"), Some(&"VDD3TXRX2".to_string()));
     assert_eq!(pin_map.get("EPAD"), Some(&"VSS".to_string()));
 }
+
+#[test]
+fn test_lm358_unit_count() {
+    let symbol = setup_symbol("LM358");
+    assert_eq!(symbol.unit_count(), 3);
+    assert_eq!(symbol.pins.len(), 8);
+}
+
+#[test]
+fn test_lm358_pin_units() {
+    let symbol = setup_symbol("LM358");
+    let pin_units: HashMap<_, _> = symbol
+        .pins
+        .iter()
+        .map(|pin| (pin.number.as_str(), pin.unit))
+        .collect();
+
+    assert_eq!(pin_units.get("1"), Some(&1));
+    assert_eq!(pin_units.get("3"), Some(&1));
+    assert_eq!(pin_units.get("5"), Some(&2));
+    assert_eq!(pin_units.get("7"), Some(&2));
+    assert_eq!(pin_units.get("4"), Some(&3));
+    assert_eq!(pin_units.get("8"), Some(&3));
+}
+
+#[test]
+fn test_lm358_power_pins() {
+    let symbol = setup_symbol("LM358");
+    let mut power: Vec<_> = symbol
+        .pins
+        .iter()
+        .filter(|pin| pin.is_power())
+        .map(|pin| pin.name.as_str())
+        .collect();
+    power.sort();
+    assert_eq!(power, vec!["V+", "V-"]);
+}
+
+#[test]
+fn test_unit_names() {
+    assert_eq!(pcb_eda::unit_name(1), "A");
+    assert_eq!(pcb_eda::unit_name(4), "D");
+    assert_eq!(pcb_eda::unit_name(26), "Z");
+    assert_eq!(pcb_eda::unit_name(27), "AA");
+}
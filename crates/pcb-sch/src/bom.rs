@@ -29,16 +29,25 @@ pub struct BomEntry {
     pub dnp: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct AggregatedBomEntry {
     pub designators: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mpn: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub alternatives: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub package: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub well_known_module: Option<WellKnownModule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub voltage: Option<PhysicalValue>,
     pub dnp: bool,
 }
@@ -120,7 +129,7 @@ impl FromStr for Dielectric {
 }
 
 /// Generate ungrouped BOM entries from a schematic
-pub fn generate_bom_entries(schematic: &mut Schematic) -> BTreeMap<String, BomEntry> {
+pub fn generate_bom_entries(schematic: &Schematic) -> BTreeMap<String, BomEntry> {
     let mut bom_entries = BTreeMap::new();
 
     // Iterate through all instances and find components
@@ -0,0 +1,389 @@
+//! Structural diff between two schematics, e.g. of the same board at two revisions.
+//!
+//! Components are matched by their hierarchical instance path (`S1.R1`), so the
+//! diff does not depend on where each revision was checked out. Nets are matched
+//! by name and compared by the ports (`S1.R1.1`) they connect.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{AttributeValue, Instance, InstanceKind, Schematic};
+
+/// One revision of a design: its schematic and the directory it was evaluated in,
+/// which is stripped from paths in attribute values.
+pub struct DiffSide<'a> {
+    pub schematic: &'a Schematic,
+    pub root: &'a Path,
+}
+
+/// Differences between an old and a new schematic.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SchematicDiff {
+    pub added_components: Vec<ComponentSummary>,
+    pub removed_components: Vec<ComponentSummary>,
+    pub changed_components: Vec<ComponentChange>,
+    pub added_nets: Vec<NetSummary>,
+    pub removed_nets: Vec<NetSummary>,
+    /// Nets that kept their ports but changed name
+    pub renamed_nets: Vec<NetRename>,
+    pub changed_nets: Vec<NetChange>,
+}
+
+impl SchematicDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_components.is_empty()
+            && self.removed_components.is_empty()
+            && self.changed_components.is_empty()
+            && self.added_nets.is_empty()
+            && self.removed_nets.is_empty()
+            && self.renamed_nets.is_empty()
+            && self.changed_nets.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentSummary {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub designator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mpn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentChange {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub designator: Option<String>,
+    pub attributes: Vec<AttributeChange>,
+}
+
+/// An attribute (property, footprint, MPN, ...) that was added, removed or changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttributeChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetSummary {
+    pub name: String,
+    pub ports: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetRename {
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetChange {
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_ports: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_ports: Vec<String>,
+}
+
+/// Compare the components and nets of two revisions of a design.
+pub fn diff_schematics(old: DiffSide, new: DiffSide) -> SchematicDiff {
+    let mut diff = SchematicDiff::default();
+
+    let old_components = components(old.schematic);
+    let new_components = components(new.schematic);
+    for (path, inst) in &old_components {
+        match new_components.get(path) {
+            None => diff
+                .removed_components
+                .push(component_summary(path, inst, old.root)),
+            Some(new_inst) => {
+                let attributes = attribute_changes(inst, old.root, new_inst, new.root);
+                if !attributes.is_empty() {
+                    diff.changed_components.push(ComponentChange {
+                        path: path.clone(),
+                        designator: new_inst.reference_designator.clone(),
+                        attributes,
+                    });
+                }
+            }
+        }
+    }
+    for (path, inst) in &new_components {
+        if !old_components.contains_key(path) {
+            diff.added_components
+                .push(component_summary(path, inst, new.root));
+        }
+    }
+
+    let old_nets = net_ports(old.schematic);
+    let new_nets = net_ports(new.schematic);
+    let mut removed: BTreeMap<&String, &BTreeSet<String>> = BTreeMap::new();
+    for (name, ports) in &old_nets {
+        match new_nets.get(name) {
+            None => {
+                removed.insert(name, ports);
+            }
+            Some(new_ports) if new_ports != ports => diff.changed_nets.push(NetChange {
+                name: name.clone(),
+                added_ports: new_ports.difference(ports).cloned().collect(),
+                removed_ports: ports.difference(new_ports).cloned().collect(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, ports) in &new_nets {
+        if old_nets.contains_key(name) {
+            continue;
+        }
+        // A removed net with exactly the same ports was renamed
+        let renamed_from = removed
+            .iter()
+            .find(|(_, old_ports)| !ports.is_empty() && **old_ports == ports)
+            .map(|(old_name, _)| (*old_name).clone());
+        match renamed_from {
+            Some(old_name) => {
+                removed.remove(&old_name);
+                diff.renamed_nets.push(NetRename {
+                    old: old_name,
+                    new: name.clone(),
+                });
+            }
+            None => diff.added_nets.push(NetSummary {
+                name: name.clone(),
+                ports: ports.iter().cloned().collect(),
+            }),
+        }
+    }
+    diff.removed_nets = removed
+        .into_iter()
+        .map(|(name, ports)| NetSummary {
+            name: name.clone(),
+            ports: ports.iter().cloned().collect(),
+        })
+        .collect();
+
+    diff
+}
+
+/// Components keyed by their instance path
+fn components(schematic: &Schematic) -> BTreeMap<String, &Instance> {
+    schematic
+        .instances
+        .iter()
+        .filter(|(_, inst)| inst.kind == InstanceKind::Component)
+        .map(|(r, inst)| (r.instance_path.join("."), inst))
+        .collect()
+}
+
+/// Ports of every net, keyed by net name
+fn net_ports(schematic: &Schematic) -> BTreeMap<String, BTreeSet<String>> {
+    schematic
+        .nets
+        .values()
+        .map(|net| {
+            let ports = net
+                .ports
+                .iter()
+                .map(|port| port.instance_path.join("."))
+                .collect();
+            (net.name.clone(), ports)
+        })
+        .collect()
+}
+
+fn component_summary(path: &str, inst: &Instance, root: &Path) -> ComponentSummary {
+    let attr = |key: &str| inst.attributes.get(key).map(|v| render(v, root));
+    ComponentSummary {
+        path: path.to_string(),
+        designator: inst.reference_designator.clone(),
+        mpn: attr("mpn"),
+        footprint: attr("footprint"),
+    }
+}
+
+fn attribute_changes(
+    old: &Instance,
+    old_root: &Path,
+    new: &Instance,
+    new_root: &Path,
+) -> Vec<AttributeChange> {
+    let names: BTreeSet<&String> = old.attributes.keys().chain(new.attributes.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old_value = old.attributes.get(name).map(|v| render(v, old_root));
+            let new_value = new.attributes.get(name).map(|v| render(v, new_root));
+            (old_value != new_value).then(|| AttributeChange {
+                name: name.clone(),
+                old: old_value,
+                new: new_value,
+            })
+        })
+        .collect()
+}
+
+/// Render an attribute value for display, with `root` stripped from paths
+fn render(value: &AttributeValue, root: &Path) -> String {
+    let rendered = match value {
+        AttributeValue::String(s) | AttributeValue::Port(s) => s.clone(),
+        AttributeValue::Number(n) => n.to_string(),
+        AttributeValue::Boolean(b) => b.to_string(),
+        AttributeValue::Physical(p) => p.to_string(),
+        AttributeValue::Array(items) => {
+            let items: Vec<String> = items.iter().map(|item| render(item, root)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        AttributeValue::Json(json) => json.to_string(),
+    };
+    let prefix = format!("{}/", root.display());
+    rendered.replace(&prefix, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InstanceRef, ModuleRef, Net, NetKind};
+
+    /// A schematic rooted at `root` with `components` (name, mpn, footprint) and nets
+    /// connecting `component.pin` ports
+    fn schematic(
+        root: &str,
+        components: &[(&str, &str, &str)],
+        nets: &[(&str, &[&str])],
+    ) -> Schematic {
+        let module = ModuleRef::from_path(&Path::new(root).join("board.zen"), "<root>");
+        let mut sch = Schematic::new();
+        for (name, mpn, footprint) in components {
+            let inst = Instance::component(module.clone())
+                .with_attribute("mpn", AttributeValue::String(mpn.to_string()))
+                .with_attribute("footprint", AttributeValue::String(footprint.to_string()));
+            sch.add_instance(
+                InstanceRef::new(module.clone(), vec![name.to_string()]),
+                inst,
+            );
+        }
+        for (name, ports) in nets {
+            let mut net = Net::new(NetKind::Normal, *name, 0);
+            for port in *ports {
+                let path = port.split('.').map(str::to_string).collect();
+                net.add_port(InstanceRef::new(module.clone(), path));
+            }
+            sch.add_net(net);
+        }
+        sch
+    }
+
+    fn diff(old: &Schematic, new: &Schematic) -> SchematicDiff {
+        diff_schematics(
+            DiffSide {
+                schematic: old,
+                root: Path::new("/a"),
+            },
+            DiffSide {
+                schematic: new,
+                root: Path::new("/b"),
+            },
+        )
+    }
+
+    #[test]
+    fn identical_designs_in_different_checkouts_have_no_diff() {
+        let old = schematic(
+            "/a",
+            &[("R1", "RC0603", "/a/fp/R0603.kicad_mod")],
+            &[("VCC", &["R1.1"])],
+        );
+        let new = schematic(
+            "/b",
+            &[("R1", "RC0603", "/b/fp/R0603.kicad_mod")],
+            &[("VCC", &["R1.1"])],
+        );
+        assert!(diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn reports_component_and_attribute_changes() {
+        let old = schematic(
+            "/a",
+            &[("R1", "RC0603", "0603"), ("C1", "CL10", "0603")],
+            &[],
+        );
+        let new = schematic(
+            "/b",
+            &[("R1", "RC0805", "0805"), ("U1", "LM1117", "SOT-223")],
+            &[],
+        );
+        let diff = diff(&old, &new);
+
+        assert_eq!(diff.removed_components.len(), 1);
+        assert_eq!(diff.removed_components[0].path, "C1");
+        assert_eq!(diff.added_components[0].path, "U1");
+        assert_eq!(diff.added_components[0].mpn.as_deref(), Some("LM1117"));
+
+        let change = &diff.changed_components[0];
+        assert_eq!(change.path, "R1");
+        let names: Vec<&str> = change.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["footprint", "mpn"]);
+        assert_eq!(change.attributes[1].old.as_deref(), Some("RC0603"));
+        assert_eq!(change.attributes[1].new.as_deref(), Some("RC0805"));
+    }
+
+    #[test]
+    fn reports_port_changes_and_renames() {
+        let old = schematic(
+            "/a",
+            &[],
+            &[
+                ("OUT", &["R1.2", "R2.1"]),
+                ("GND", &["R2.2"]),
+                ("N1", &["C1.1", "U1.3"]),
+                ("DEAD", &["X1.1"]),
+            ],
+        );
+        let new = schematic(
+            "/b",
+            &[],
+            &[
+                ("OUT", &["R1.2"]),
+                ("GND", &["R2.1", "R2.2"]),
+                ("VREF", &["C1.1", "U1.3"]),
+                ("NEW", &["X2.1"]),
+            ],
+        );
+        let diff = diff(&old, &new);
+
+        let changed: Vec<(&str, &[String], &[String])> = diff
+            .changed_nets
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.added_ports.as_slice(),
+                    c.removed_ports.as_slice(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                ("GND", &["R2.1".to_string()][..], &[][..]),
+                ("OUT", &[][..], &["R2.1".to_string()][..]),
+            ]
+        );
+        assert_eq!(
+            diff.renamed_nets,
+            [NetRename {
+                old: "N1".into(),
+                new: "VREF".into()
+            }]
+        );
+        assert_eq!(diff.removed_nets[0].name, "DEAD");
+        assert_eq!(diff.added_nets[0].name, "NEW");
+    }
+}
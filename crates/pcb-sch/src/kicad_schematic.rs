@@ -8,7 +8,7 @@ use pcb_sexpr::{format_sexpr, parse, Sexpr};
 use uuid::Uuid;
 
 use crate::hierarchical_layout::{HierarchicalLayout, Size};
-use crate::{Instance, InstanceKind, InstanceRef, Net, Schematic};
+use crate::{AttributeValue, Instance, InstanceKind, InstanceRef, Net, Schematic};
 
 /// Enable debug mode to render component bounding boxes
 /// Set this to true to visualize component bounds, layout allocations, and module boundaries
@@ -45,9 +45,75 @@ struct SymbolInfo {
     reference: String,
     value: String,
     footprint: Option<String>,
-    raw_sexpr: Sexpr,             // Store the complete symbol S-expression
-    bounds: (f64, f64, f64, f64), // (min_x, min_y, max_x, max_y) of the symbol
-    origin_offset: (f64, f64),    // Offset from symbol origin to top-left of bounds
+    raw_sexpr: Sexpr,                // Store the complete symbol S-expression
+    bounds: (f64, f64, f64, f64),    // (min_x, min_y, max_x, max_y) of the symbol
+    origin_offset: (f64, f64),       // Offset from symbol origin to top-left of bounds
+    units: Vec<UnitInfo>,            // Units placed for every instance of the symbol
+    pin_units: HashMap<String, i32>, // Pin number -> unit (0 = shared by all units)
+}
+
+impl SymbolInfo {
+    /// Look up a unit, falling back to the first one for pins shared by all units, or
+    /// to the symbol's top-level graphics for a symbol without units.
+    fn unit(&self, unit: i32) -> UnitInfo {
+        self.units
+            .iter()
+            .find(|u| u.unit == unit)
+            .or_else(|| self.units.first())
+            .cloned()
+            .unwrap_or(UnitInfo {
+                unit: 1,
+                bounds: self.bounds,
+                origin_offset: self.origin_offset,
+            })
+    }
+}
+
+/// Geometry of a single unit (gate) of a symbol. Single-unit symbols have
+/// exactly one unit covering the whole symbol.
+#[derive(Debug, Clone)]
+struct UnitInfo {
+    unit: i32,
+    bounds: (f64, f64, f64, f64),
+    origin_offset: (f64, f64),
+}
+
+/// Split a sub-symbol name of the form `<name>_<unit>_<style>` into its unit
+/// and body style.
+fn sub_symbol_unit(section_name: &str) -> Option<(i32, i32)> {
+    let mut parts = section_name.rsplitn(3, '_');
+    let style = parts.next()?.parse().ok()?;
+    let unit = parts.next()?.parse().ok()?;
+    parts.next()?;
+    Some((unit, style))
+}
+
+/// Unit and body style of a nested `(symbol "<name>_<unit>_<style>" ...)` block.
+fn sub_symbol_unit_of(item_data: &[Sexpr]) -> Option<(i32, i32)> {
+    item_data
+        .get(1)
+        .and_then(|s| s.as_atom())
+        .and_then(sub_symbol_unit)
+}
+
+/// KiCad connects hidden `power_in` pins to a global net named after the pin,
+/// which would silently override the labels we attach to them. Make those
+/// pins visible so they are wired like every other pin.
+fn reveal_hidden_power_pins(sexpr: &mut Sexpr) {
+    if let Sexpr::List(items) = sexpr {
+        let is_power_pin = items.first().and_then(|s| s.as_atom()) == Some("pin")
+            && items.get(1).and_then(|s| s.as_atom()) == Some("power_in");
+
+        if is_power_pin {
+            items.retain(|item| match item {
+                Sexpr::Symbol(flag) => flag != "hide",
+                Sexpr::List(sub) => sub.first().and_then(|s| s.as_atom()) != Some("hide"),
+                _ => true,
+            });
+        } else {
+            items.iter_mut().for_each(reveal_hidden_power_pins);
+        }
+    }
 }
 
 /// Stores basic information about a global label that is attached to a component
@@ -73,8 +139,8 @@ pub fn to_kicad_schematic(sch: &Schematic, output_path: &Path) -> Result<String,
 struct SchematicConverter {
     /// Map from component instance ref to its KiCad symbol
     symbols: Vec<SchematicSymbol>,
-    /// Map from instance ref to assigned UUID
-    uuid_map: HashMap<InstanceRef, String>,
+    /// Map from instance ref to the UUIDs of its placed units
+    uuid_map: HashMap<InstanceRef, Vec<String>>,
     /// Collected library symbols (symbol name -> symbol info)
     lib_symbols: HashMap<String, SymbolInfo>,
     /// Global labels for nets
@@ -239,13 +305,24 @@ impl SchematicConverter {
         log::debug!("Calculating hierarchical layout");
         let bounding_boxes = self.layout_engine.layout();
 
+        // Map every placed unit UUID back to its component
+        let unit_owners: HashMap<String, InstanceRef> = self
+            .uuid_map
+            .iter()
+            .flat_map(|(inst_ref, uuids)| {
+                uuids
+                    .iter()
+                    .map(move |uuid| (uuid.clone(), inst_ref.clone()))
+            })
+            .collect();
+
         // Convert BoundingBox positions to (f64, f64) positions for symbols
         let mut positions = HashMap::new();
         for (id, bbox) in &bounding_boxes {
             // Find the InstanceRef that corresponds to this string ID
-            for (inst_ref, uuid) in &self.uuid_map {
+            for (uuid, inst_ref) in &unit_owners {
                 if uuid == id {
-                    positions.insert(inst_ref.clone(), (bbox.position.x, bbox.position.y));
+                    positions.insert(uuid.clone(), (bbox.position.x, bbox.position.y));
 
                     // In debug mode, create a rectangle for the layout engine's bounding box
                     if self.debug_mode {
@@ -279,7 +356,7 @@ impl SchematicConverter {
         // Create rectangles and labels for modules
         for (id, bbox) in &bounding_boxes {
             // Check if this is a module (not a component UUID)
-            if !self.uuid_map.values().any(|uuid| uuid == id) {
+            if !unit_owners.contains_key(id) {
                 // Check if this module has more than one child
                 let has_multiple_children = self.layout_engine.module_has_multiple_children(id);
 
@@ -342,39 +419,41 @@ impl SchematicConverter {
             .symbols
             .iter()
             .filter_map(|symbol| {
-                self.uuid_map
-                    .iter()
-                    .find(|(_, uuid)| *uuid == &symbol.uuid)
-                    .map(|(inst_ref, _)| (symbol.uuid.clone(), inst_ref.clone()))
+                unit_owners
+                    .get(&symbol.uuid)
+                    .map(|inst_ref| (symbol.uuid.clone(), inst_ref.clone()))
             })
             .collect();
 
         // Now update positions
         for (uuid, inst_ref) in symbol_data {
-            if let Some(position) = positions.get(&inst_ref) {
+            if let Some(position) = positions.get(&uuid) {
                 // Find the symbol with this UUID and update its position
                 if let Some(symbol) = self.symbols.iter_mut().find(|s| s.uuid == uuid) {
                     // Get the symbol info to find the origin offset
                     if let Some(symbol_info) = self.lib_symbols.get(&symbol.lib_id) {
-                        // Adjust position by the symbol's origin offset
+                        let unit_info = symbol_info.unit(symbol.unit);
+
+                        // Adjust position by the unit's origin offset
                         symbol.position = (
-                            position.0 + symbol_info.origin_offset.0,
-                            position.1 + symbol_info.origin_offset.1,
+                            position.0 + unit_info.origin_offset.0,
+                            position.1 + unit_info.origin_offset.1,
                         );
 
                         log::debug!(
-                            "Component {} positioned at ({}, {}) with offset ({}, {})",
+                            "Component {} unit {} positioned at ({}, {}) with offset ({}, {})",
                             inst_ref,
+                            symbol.unit,
                             symbol.position.0,
                             symbol.position.1,
-                            symbol_info.origin_offset.0,
-                            symbol_info.origin_offset.1
+                            unit_info.origin_offset.0,
+                            unit_info.origin_offset.1
                         );
 
                         // In debug mode, create a rectangle for the component's actual bounds
                         if self.debug_mode {
-                            // Get the actual symbol bounds
-                            let (min_x, min_y, max_x, max_y) = symbol_info.bounds;
+                            // Get the actual unit bounds
+                            let (min_x, min_y, max_x, max_y) = unit_info.bounds;
 
                             // Create a rectangle showing the component's actual bounds
                             // Note: KiCad schematic has Y increasing downward
@@ -518,62 +597,71 @@ impl SchematicConverter {
             }
         };
 
-        // Generate UUID for this instance
-        let uuid = Uuid::new_v4().to_string();
-        self.uuid_map.insert(inst_ref.clone(), uuid.clone());
-
-        // Calculate extended bounds that include space for labels
-        let extended_bounds = self.calculate_extended_bounds(inst_ref, symbol_info.bounds);
-
-        log::debug!(
-            "Component {} - Symbol bounds: {:?}, Extended bounds: {:?}",
-            inst_ref,
-            symbol_info.bounds,
-            extended_bounds
-        );
-
-        // Add component to hierarchical layout engine using its UUID as ID
-        let size = Size::new(
-            extended_bounds.2 - extended_bounds.0, // width
-            extended_bounds.3 - extended_bounds.1, // height
-        );
-        self.layout_engine.set_component_size(uuid.clone(), size);
-
-        // Create the symbol instance (position will be set later)
-        let symbol = SchematicSymbol {
-            lib_id: lib_id.clone(),
-            position: (0.0, 0.0), // Will be updated after layout calculation
-            unit: 1,
-            in_bom: true,
-            on_board: true,
-            uuid: uuid.clone(),
-            reference: instance
-                .reference_designator
-                .clone()
-                .unwrap_or_else(|| format!("U{}", self.symbols.len() + 1)),
-            value: instance
+        let reference = instance
+            .reference_designator
+            .clone()
+            .unwrap_or_else(|| format!("U{}", self.symbols.len() + 1));
+        let value = instance
+            .attributes
+            .get("mpn")
+            .or_else(|| instance.attributes.get("type"))
+            .and_then(|v| v.string())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| symbol_info.value.clone());
+        let properties: HashMap<String, String> = {
+            let mut props: HashMap<String, String> = instance
                 .attributes
-                .get("mpn")
-                .or_else(|| instance.attributes.get("type"))
-                .and_then(|v| v.string())
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| symbol_info.value.clone()),
-            footprint: symbol_info.footprint.clone(),
-            properties: {
-                let mut props: HashMap<String, String> = instance
-                    .attributes
-                    .iter()
-                    .filter_map(|(k, v)| v.string().map(|s| (k.clone(), s.to_string())))
-                    .collect();
+                .iter()
+                .filter_map(|(k, v)| v.string().map(|s| (k.clone(), s.to_string())))
+                .collect();
 
-                // Add the instance path as a property
-                props.insert("Path".to_string(), inst_ref.to_string());
+            // Add the instance path as a property
+            props.insert("Path".to_string(), inst_ref.to_string());
 
-                props
-            },
+            props
         };
 
-        self.symbols.push(symbol);
+        // Place every unit of the symbol separately. They share the reference
+        // designator and are told apart by their unit number.
+        let mut unit_uuids = Vec::new();
+        for unit in &symbol_info.units {
+            // Generate UUID for this unit
+            let uuid = Uuid::new_v4().to_string();
+            unit_uuids.push(uuid.clone());
+
+            // Calculate extended bounds that include space for labels
+            let extended_bounds = self.calculate_extended_bounds(inst_ref, unit.bounds);
+
+            log::debug!(
+                "Component {} unit {} - Symbol bounds: {:?}, Extended bounds: {:?}",
+                inst_ref,
+                unit.unit,
+                unit.bounds,
+                extended_bounds
+            );
+
+            // Add the unit to hierarchical layout engine using its UUID as ID
+            let size = Size::new(
+                extended_bounds.2 - extended_bounds.0, // width
+                extended_bounds.3 - extended_bounds.1, // height
+            );
+            self.layout_engine.set_component_size(uuid.clone(), size);
+
+            // Create the symbol instance (position will be set later)
+            self.symbols.push(SchematicSymbol {
+                lib_id: lib_id.clone(),
+                position: (0.0, 0.0), // Will be updated after layout calculation
+                unit: unit.unit,
+                in_bom: true,
+                on_board: true,
+                uuid,
+                reference: reference.clone(),
+                value: value.clone(),
+                footprint: symbol_info.footprint.clone(),
+                properties: properties.clone(),
+            });
+        }
+        self.uuid_map.insert(inst_ref.clone(), unit_uuids);
 
         // Add the library symbol if not already added
         // Use the full lib_id as the key to ensure uniqueness
@@ -588,6 +676,7 @@ impl SchematicConverter {
                     items[1] = Sexpr::string(lib_id.clone());
                 }
             }
+            reveal_hidden_power_pins(&mut updated_symbol_info.raw_sexpr);
 
             updated_symbol_info
         });
@@ -754,6 +843,8 @@ impl SchematicConverter {
                 raw_sexpr: symbol_sexpr.clone(),
                 bounds: (0.0, 0.0, 0.0, 0.0),
                 origin_offset: (0.0, 0.0),
+                units: Vec::new(),
+                pin_units: HashMap::new(),
             };
 
             // Extract just the properties we need
@@ -788,6 +879,9 @@ impl SchematicConverter {
             info.origin_offset = (-info.bounds.0, -info.bounds.1);
             log::debug!("Symbol '{}' origin offset: {:?}", name, info.origin_offset);
 
+            self.collect_units(&mut info, symbol_data);
+            log::debug!("Symbol '{}' has {} unit(s)", name, info.units.len());
+
             log::debug!("Extracted {prop_count} properties for symbol '{name}'");
             Some(info)
         } else {
@@ -796,6 +890,71 @@ impl SchematicConverter {
         }
     }
 
+    /// Split a symbol into its units. Every unit is drawn with its own graphics
+    /// plus the graphics and pins shared by all units (unit 0). The De Morgan
+    /// body style is ignored since we always place the normal style.
+    fn collect_units(&self, info: &mut SymbolInfo, symbol_data: &[Sexpr]) {
+        let mut sections: HashMap<i32, Vec<Sexpr>> = HashMap::new();
+
+        for item in &symbol_data[2..] {
+            if let Sexpr::List(item_data) = item {
+                if item_data.first().and_then(|s| s.as_atom()) != Some("symbol") {
+                    continue;
+                }
+                let Some((unit, style)) = sub_symbol_unit_of(item_data) else {
+                    continue;
+                };
+                if style == 2 {
+                    continue;
+                }
+
+                sections.entry(unit).or_default().push(item.clone());
+
+                for pin in item_data.iter().filter_map(|s| s.as_list()) {
+                    if pin.first().and_then(|s| s.as_atom()) != Some("pin") {
+                        continue;
+                    }
+                    let number = pin.iter().filter_map(|s| s.as_list()).find_map(|attr| {
+                        match (attr.first().and_then(|s| s.as_atom()), attr.get(1)) {
+                            (Some("number"), Some(number)) => number.as_atom(),
+                            _ => None,
+                        }
+                    });
+                    if let Some(number) = number {
+                        info.pin_units.insert(number.to_string(), unit);
+                    }
+                }
+            }
+        }
+
+        let mut unit_numbers: Vec<i32> = sections.keys().copied().filter(|u| *u > 0).collect();
+        unit_numbers.sort();
+
+        if unit_numbers.len() <= 1 {
+            info.units = vec![UnitInfo {
+                unit: 1,
+                bounds: info.bounds,
+                origin_offset: info.origin_offset,
+            }];
+            return;
+        }
+
+        let common = sections.get(&0).cloned().unwrap_or_default();
+        info.units = unit_numbers
+            .into_iter()
+            .map(|unit| {
+                let mut items = common.clone();
+                items.extend(sections[&unit].iter().cloned());
+                let bounds = self.calculate_symbol_bounds(&items);
+                UnitInfo {
+                    unit,
+                    bounds,
+                    origin_offset: (-bounds.0, -bounds.1),
+                }
+            })
+            .collect();
+    }
+
     fn calculate_symbol_bounds(&self, symbol_data: &[Sexpr]) -> (f64, f64, f64, f64) {
         let mut min_x = f64::MAX;
         let mut max_x = f64::MIN;
@@ -976,137 +1135,161 @@ impl SchematicConverter {
                 }
             };
 
-            // Try to obtain the pin *number* first (from the port's "pad" attribute).
-            let pin_identifier_owned: Option<String> = sch
+            // Label every pad of the port. Fall back to the port name if the
+            // port has no pads.
+            let mut pin_identifiers: Vec<String> = sch
                 .instances
                 .get(port_ref)
-                .and_then(|inst| inst.attributes.get("pad"))
-                .and_then(|v| v.string())
-                .map(|s| s.to_string());
-
-            // Fallback to the port name if no pad attribute present.
-            let pin_identifier = pin_identifier_owned.as_deref().unwrap_or_else(|| {
-                port_ref
-                    .instance_path
-                    .last()
-                    .map(|s| s.as_str())
-                    .unwrap_or("")
-            });
-
-            // Get the symbol position and lib_id
-            if let Some(symbol_uuid) = self.uuid_map.get(&comp_ref) {
-                if let Some(symbol) = self.symbols.iter().find(|s| &s.uuid == symbol_uuid) {
-                    // Get the symbol definition to find pin position
-                    if let Some(symbol_info) = self.lib_symbols.get(&symbol.lib_id) {
-                        // Find the actual pin position
-                        if let Some((pin_pos, pin_angle)) = self.find_pin_position(
-                            &symbol_info.raw_sexpr,
-                            pin_identifier,
-                            symbol.position,
-                        ) {
-                            // Justification based on pin orientation:
-                            // 0° (pin points right): label on left side, right-justified
-                            // 90° (pin points up): label below, left-justified
-                            // 180° (pin points left): label on right side, left-justified
-                            // 270° (pin points down): label above, right-justified
-                            let justify = match pin_angle.round() as i32 % 360 {
-                                0 => Some("right".to_string()),
-                                90 => Some("right".to_string()),
-                                180 => Some("left".to_string()),
-                                270 => Some("left".to_string()),
-                                _ => Some("left".to_string()), // Default
-                            };
-
-                            let global_label = GlobalLabel {
-                                text: net_name.to_string(),
-                                position: pin_pos,
-                                angle: pin_angle, // Use the pin angle for label orientation
-                                uuid: Uuid::new_v4().to_string(),
-                                justify,
-                            };
-
-                            self.global_labels.push(global_label);
-
-                            // Estimate label dimensions based on number of characters.
-                            // Default KiCad font height is 1.27 mm. Empirically each
-                            // character is roughly 0.6× the height wide.
-                            const FONT_HEIGHT: f64 = 1.27;
-                            const CHAR_WIDTH_FACTOR: f64 = 0.6; // very rough
-                            let mut est_width =
-                                net_name.chars().count() as f64 * FONT_HEIGHT * CHAR_WIDTH_FACTOR;
-                            let mut est_height = FONT_HEIGHT;
-
-                            // Provide a small margin around the text so we don't clip
-                            // descenders/etc.
-                            est_width += 0.5;
-                            est_height += 0.3;
-
-                            // If the text is rotated 90° or 270°, swap width/height.
-                            match (pin_angle.round() as i32).rem_euclid(360) {
-                                90 | 270 => {
-                                    std::mem::swap(&mut est_width, &mut est_height);
-                                }
-                                _ => {}
-                            }
-
-                            let label_info = LabelInfo {
-                                position: pin_pos,
-                                width: est_width,
-                                height: est_height,
-                            };
+                .and_then(|inst| inst.attributes.get("pads"))
+                .and_then(|v| match v {
+                    AttributeValue::Array(pads) => Some(
+                        pads.iter()
+                            .filter_map(|pad| pad.string().map(|s| s.to_string()))
+                            .collect(),
+                    ),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            if pin_identifiers.is_empty() {
+                pin_identifiers.extend(port_ref.instance_path.last().cloned());
+            }
 
-                            // Track the label info for this component
-                            self.component_label_positions
-                                .entry(comp_ref.clone())
-                                .or_default()
-                                .push(label_info);
-                        } else {
-                            // Fallback to default position if pin not found
-                            log::warn!(
-                                "Pin '{}' not found in symbol {}, using default position",
+            for pin_identifier in pin_identifiers.iter().map(|s| s.as_str()) {
+                // Get the symbol position and lib_id
+                if let Some(unit_uuids) = self.uuid_map.get(&comp_ref) {
+                    if let Some(symbol) = self.symbols.iter().find(|s| unit_uuids.contains(&s.uuid))
+                    {
+                        // Get the symbol definition to find pin position
+                        if let Some(symbol_info) = self.lib_symbols.get(&symbol.lib_id) {
+                            // Pins shared by all units are labelled on the first unit only
+                            let unit = symbol_info
+                                .unit(
+                                    symbol_info
+                                        .pin_units
+                                        .get(pin_identifier)
+                                        .copied()
+                                        .unwrap_or(0),
+                                )
+                                .unit;
+                            let symbol = self
+                                .symbols
+                                .iter()
+                                .find(|s| unit_uuids.contains(&s.uuid) && s.unit == unit)
+                                .unwrap_or(symbol);
+
+                            // Find the actual pin position
+                            if let Some((pin_pos, pin_angle)) = self.find_pin_position(
+                                &symbol_info.raw_sexpr,
                                 pin_identifier,
-                                symbol.lib_id
-                            );
-
-                            let default_pos = (symbol.position.0 + 10.0, symbol.position.1);
-                            let global_label = GlobalLabel {
-                                text: net_name.to_string(),
-                                position: default_pos,
-                                angle: 0.0,
-                                uuid: Uuid::new_v4().to_string(),
-                                justify: None,
-                            };
+                                symbol.position,
+                                symbol.unit,
+                            ) {
+                                // Justification based on pin orientation:
+                                // 0° (pin points right): label on left side, right-justified
+                                // 90° (pin points up): label below, left-justified
+                                // 180° (pin points left): label on right side, left-justified
+                                // 270° (pin points down): label above, right-justified
+                                let justify = match pin_angle.round() as i32 % 360 {
+                                    0 => Some("right".to_string()),
+                                    90 => Some("right".to_string()),
+                                    180 => Some("left".to_string()),
+                                    270 => Some("left".to_string()),
+                                    _ => Some("left".to_string()), // Default
+                                };
+
+                                let global_label = GlobalLabel {
+                                    text: net_name.to_string(),
+                                    position: pin_pos,
+                                    angle: pin_angle, // Use the pin angle for label orientation
+                                    uuid: Uuid::new_v4().to_string(),
+                                    justify,
+                                };
+
+                                self.global_labels.push(global_label);
+
+                                // Estimate label dimensions based on number of characters.
+                                // Default KiCad font height is 1.27 mm. Empirically each
+                                // character is roughly 0.6× the height wide.
+                                const FONT_HEIGHT: f64 = 1.27;
+                                const CHAR_WIDTH_FACTOR: f64 = 0.6; // very rough
+                                let mut est_width = net_name.chars().count() as f64
+                                    * FONT_HEIGHT
+                                    * CHAR_WIDTH_FACTOR;
+                                let mut est_height = FONT_HEIGHT;
+
+                                // Provide a small margin around the text so we don't clip
+                                // descenders/etc.
+                                est_width += 0.5;
+                                est_height += 0.3;
+
+                                // If the text is rotated 90° or 270°, swap width/height.
+                                match (pin_angle.round() as i32).rem_euclid(360) {
+                                    90 | 270 => {
+                                        std::mem::swap(&mut est_width, &mut est_height);
+                                    }
+                                    _ => {}
+                                }
 
-                            self.global_labels.push(global_label);
+                                let label_info = LabelInfo {
+                                    position: pin_pos,
+                                    width: est_width,
+                                    height: est_height,
+                                };
+
+                                // Track the label info for this component
+                                self.component_label_positions
+                                    .entry(comp_ref.clone())
+                                    .or_default()
+                                    .push(label_info);
+                            } else {
+                                // Fallback to default position if pin not found
+                                log::warn!(
+                                    "Pin '{}' not found in symbol {}, using default position",
+                                    pin_identifier,
+                                    symbol.lib_id
+                                );
 
-                            // Same rough size estimation for fallback case.
-                            const FONT_HEIGHT: f64 = 1.27;
-                            const CHAR_WIDTH_FACTOR: f64 = 0.6;
-                            let est_width =
-                                net_name.chars().count() as f64 * FONT_HEIGHT * CHAR_WIDTH_FACTOR
+                                let default_pos = (symbol.position.0 + 10.0, symbol.position.1);
+                                let global_label = GlobalLabel {
+                                    text: net_name.to_string(),
+                                    position: default_pos,
+                                    angle: 0.0,
+                                    uuid: Uuid::new_v4().to_string(),
+                                    justify: None,
+                                };
+
+                                self.global_labels.push(global_label);
+
+                                // Same rough size estimation for fallback case.
+                                const FONT_HEIGHT: f64 = 1.27;
+                                const CHAR_WIDTH_FACTOR: f64 = 0.6;
+                                let est_width = net_name.chars().count() as f64
+                                    * FONT_HEIGHT
+                                    * CHAR_WIDTH_FACTOR
                                     + 0.5;
-                            let est_height = FONT_HEIGHT + 0.3;
-
-                            let label_info = LabelInfo {
-                                position: default_pos,
-                                width: est_width,
-                                height: est_height,
-                            };
-
-                            self.component_label_positions
-                                .entry(comp_ref.clone())
-                                .or_default()
-                                .push(label_info);
+                                let est_height = FONT_HEIGHT + 0.3;
+
+                                let label_info = LabelInfo {
+                                    position: default_pos,
+                                    width: est_width,
+                                    height: est_height,
+                                };
+
+                                self.component_label_positions
+                                    .entry(comp_ref.clone())
+                                    .or_default()
+                                    .push(label_info);
+                            }
+                        } else {
+                            log::warn!("Symbol definition not found for {}", symbol.lib_id);
                         }
-                    } else {
-                        log::warn!("Symbol definition not found for {}", symbol.lib_id);
                     }
+                } else {
+                    // Component was likely skipped due to symbol loading error
+                    log::warn!(
+                        "Component {comp_ref} not found in schematic (likely skipped due to symbol loading error)"
+                    );
                 }
-            } else {
-                // Component was likely skipped due to symbol loading error
-                log::warn!(
-                    "Component {comp_ref} not found in schematic (likely skipped due to symbol loading error)"
-                );
             }
         }
 
@@ -1436,10 +1619,11 @@ impl SchematicConverter {
         symbol_data: &Sexpr,
         pin_name: &str,
         symbol_position: (f64, f64),
+        unit: i32,
     ) -> Option<((f64, f64), f64)> {
         // Delegate to recursive helper that understands nested sub-symbols.
         // For now we ignore rotation inside sub-symbols as most library parts keep rotation at 0°.
-        self.find_pin_with_transform(symbol_data, pin_name, symbol_position, (0.0, 0.0))
+        self.find_pin_with_transform(symbol_data, pin_name, symbol_position, (0.0, 0.0), unit)
     }
 
     /// Recursively search for the pin while accumulating local offsets from any nested sub-symbols.
@@ -1450,6 +1634,7 @@ impl SchematicConverter {
         pin_name: &str,
         symbol_position: (f64, f64),
         local_offset: (f64, f64),
+        unit: i32,
     ) -> Option<((f64, f64), f64)> {
         if let Sexpr::List(items) = sexpr {
             // First, attempt to match a pin at this level (using current local_offset)
@@ -1474,6 +1659,14 @@ impl SchematicConverter {
                 if let Sexpr::List(item_data) = item {
                     if let Some(tag) = item_data.first().and_then(|s| s.as_atom()) {
                         if tag == "symbol" {
+                            // Only look at the placed unit (and the pins shared by all
+                            // units) in the normal body style.
+                            if let Some((sub_unit, style)) = sub_symbol_unit_of(item_data) {
+                                if style == 2 || (sub_unit != 0 && sub_unit != unit) {
+                                    continue;
+                                }
+                            }
+
                             // Extract the local "at" offset of this sub-symbol if present.
                             let mut sub_offset = (0.0, 0.0);
                             for sub_item in item_data {
@@ -1502,6 +1695,7 @@ impl SchematicConverter {
                                 pin_name,
                                 symbol_position,
                                 combined_offset,
+                                unit,
                             ) {
                                 return Some(res);
                             }
@@ -1510,6 +1704,7 @@ impl SchematicConverter {
                             pin_name,
                             symbol_position,
                             local_offset,
+                            unit,
                         ) {
                             return Some(res);
                         }
@@ -1709,8 +1904,8 @@ impl SchematicConverter {
                         match child_instance.kind {
                             InstanceKind::Component => {
                                 // This is a component - add it to the layout engine
-                                if let Some(uuid) = self.uuid_map.get(child_ref) {
-                                    children_ids.push(uuid.clone());
+                                if let Some(uuids) = self.uuid_map.get(child_ref) {
+                                    children_ids.extend(uuids.iter().cloned());
                                 }
                             }
                             InstanceKind::Module => {
@@ -1812,3 +2007,118 @@ impl SchematicConverter {
 pub fn write_schematic_file(schematic_content: &str, path: &Path) -> Result<(), std::io::Error> {
     fs::write(path, schematic_content)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ModuleRef, NetKind};
+
+    /// The dual op-amp fixture shared with the pcb-eda and pcb-zen-core tests
+    fn lm358_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../pcb-eda/tests/resources/kicad/LM358/LM358.kicad_sym")
+    }
+
+    fn atom_list<'a>(sexpr: &'a Sexpr, tag: &str) -> Vec<&'a [Sexpr]> {
+        sexpr
+            .as_list()
+            .unwrap()
+            .iter()
+            .filter_map(|item| item.as_list())
+            .filter(|item| item.first().and_then(|s| s.as_atom()) == Some(tag))
+            .collect()
+    }
+
+    fn field<'a>(item: &'a [Sexpr], tag: &str) -> Option<&'a str> {
+        item.iter()
+            .filter_map(|s| s.as_list())
+            .find(|s| s.first().and_then(|t| t.as_atom()) == Some(tag))
+            .and_then(|s| s.get(1))
+            .and_then(|s| s.as_atom())
+    }
+
+    #[test]
+    fn places_each_unit_separately() {
+        let symbol_path = lm358_path();
+
+        let mod_ref = ModuleRef::from_path(Path::new("/test.zen"), "<root>");
+        let root_ref = InstanceRef::new(mod_ref.clone(), vec![]);
+        let u1_ref = root_ref.append("U1".into());
+
+        let mut sch = Schematic::new();
+        let mut u1 = Instance::component(mod_ref.clone())
+            .with_attribute("symbol_path", symbol_path.to_string_lossy().to_string())
+            .with_reference_designator("U1");
+        for (signal, pad, net) in [
+            ("A.+", "3", "IN_A"),
+            ("B.+", "5", "IN_B"),
+            ("V+", "8", "VCC"),
+        ] {
+            let port_ref = u1_ref.append(signal.into());
+            let port = Instance::port(mod_ref.clone()).with_attribute(
+                "pads",
+                AttributeValue::Array(vec![AttributeValue::String(pad.to_string())]),
+            );
+            sch.add_instance(port_ref.clone(), port);
+            u1.add_child(signal, port_ref.clone());
+            sch.add_net(Net::new(NetKind::Normal, net, 0).with_port(port_ref));
+        }
+        sch.add_instance(u1_ref.clone(), u1);
+        sch.add_instance(
+            root_ref.clone(),
+            Instance::module(mod_ref.clone()).with_child("U1", u1_ref),
+        );
+        sch.set_root_ref(root_ref);
+
+        let output = to_kicad_schematic(&sch, Path::new("/test.kicad_sch")).unwrap();
+        let parsed = parse(&output).unwrap();
+
+        // One placed symbol per unit, all sharing the reference designator.
+        let placed = atom_list(&parsed, "symbol");
+        let mut units: Vec<_> = placed.iter().map(|s| field(s, "unit").unwrap()).collect();
+        units.sort();
+        assert_eq!(units, vec!["1", "2", "3"]);
+
+        // Each net label sits on the unit that owns the pad.
+        let position = |unit: &str| {
+            let symbol = placed
+                .iter()
+                .find(|s| field(s, "unit") == Some(unit))
+                .unwrap();
+            let at = symbol
+                .iter()
+                .filter_map(|s| s.as_list())
+                .find(|s| s.first().and_then(|t| t.as_atom()) == Some("at"))
+                .unwrap();
+            let x: f64 = at[1].as_atom().unwrap().parse().unwrap();
+            let y: f64 = at[2].as_atom().unwrap().parse().unwrap();
+            (x, y)
+        };
+        let labels = atom_list(&parsed, "global_label");
+        assert_eq!(labels.len(), 3);
+        for (net, unit, offset) in [
+            ("IN_A", "1", (-7.62, 2.54)),
+            ("IN_B", "2", (-7.62, 2.54)),
+            ("VCC", "3", (-2.54, 7.62)),
+        ] {
+            let label = labels.iter().find(|l| l[1].as_atom() == Some(net)).unwrap();
+            let at = label
+                .iter()
+                .filter_map(|s| s.as_list())
+                .find(|s| s.first().and_then(|t| t.as_atom()) == Some("at"))
+                .unwrap();
+            let (x, y) = position(unit);
+            assert_eq!(at[1].as_atom().unwrap(), (x + offset.0).to_string());
+            assert_eq!(at[2].as_atom().unwrap(), (y - offset.1).to_string());
+        }
+
+        // The hidden V+ pin is made visible so the VCC label connects to it.
+        let lib_symbols = format_sexpr(
+            &Sexpr::List(atom_list(&parsed, "lib_symbols")[0].to_vec()),
+            0,
+        );
+        let v_plus = lib_symbols.find("power_in").unwrap();
+        assert!(lib_symbols[v_plus..].contains("\"V+\""));
+        assert!(!lib_symbols[v_plus..].contains("hide"));
+    }
+}
@@ -12,12 +12,16 @@
 //! * `nets` – all electrical nets keyed by their deduplicated name.
 
 pub mod bom;
+pub mod diff;
 pub mod hierarchical_layout;
 pub mod kicad_netlist;
 pub mod kicad_schematic;
+pub mod power;
 
 // Re-export BOM functionality
 pub use bom::{generate_bom_entries, group_bom_entries, AggregatedBomEntry, BomEntry};
+pub use diff::{diff_schematics, DiffSide, SchematicDiff};
+pub use power::{analyze_power, PowerReport, PowerViolation, Rail};
 
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
@@ -375,6 +379,104 @@ impl Schematic {
         serde_json::to_string_pretty(self)
     }
 
+    /// Deserialize a schematic previously written by [`Schematic::to_json`].
+    ///
+    /// Instance references are serialized by their display string, which is ambiguous
+    /// once names contain `.`, so they are rebuilt by walking the hierarchy down from
+    /// the root and following each instance's named children.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        struct RawInstance {
+            type_ref: ModuleRef,
+            kind: InstanceKind,
+            attributes: HashMap<Symbol, AttributeValue>,
+            children: HashMap<Symbol, String>,
+            reference_designator: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct RawNet {
+            kind: NetKind,
+            id: u64,
+            name: String,
+            ports: Vec<String>,
+            properties: HashMap<Symbol, AttributeValue>,
+        }
+
+        #[derive(Deserialize)]
+        struct RawSchematic {
+            instances: HashMap<String, RawInstance>,
+            nets: HashMap<String, RawNet>,
+            root_ref: Option<String>,
+            symbols: HashMap<String, String>,
+        }
+
+        let raw: RawSchematic = serde_json::from_str(json)?;
+        let mut schematic = Schematic {
+            symbols: raw.symbols,
+            ..Default::default()
+        };
+        let Some(root_key) = raw.root_ref else {
+            if raw.instances.is_empty() {
+                return Ok(schematic);
+            }
+            return Err(serde_json::Error::custom(
+                "schematic has instances but no root",
+            ));
+        };
+
+        // The root reference has an empty instance path: `<source_path>:<module_name>`.
+        let (source_path, module_name) = root_key
+            .rsplit_once(':')
+            .ok_or_else(|| serde_json::Error::custom(format!("invalid root `{root_key}`")))?;
+        let root_ref = InstanceRef::new(ModuleRef::new(source_path, module_name), Vec::new());
+
+        let mut resolved: HashMap<String, InstanceRef> = HashMap::new();
+        let mut pending = vec![(root_key.clone(), root_ref.clone())];
+        while let Some((key, reference)) = pending.pop() {
+            let Some(raw_instance) = raw.instances.get(&key) else {
+                return Err(serde_json::Error::custom(format!(
+                    "missing instance `{key}`"
+                )));
+            };
+            for (name, child_key) in &raw_instance.children {
+                pending.push((child_key.clone(), reference.append(name.clone())));
+            }
+            resolved.insert(key, reference);
+        }
+
+        let lookup = |key: &String| {
+            resolved
+                .get(key)
+                .cloned()
+                .ok_or_else(|| serde_json::Error::custom(format!("unreachable instance `{key}`")))
+        };
+
+        for (key, raw_instance) in &raw.instances {
+            let mut instance = Instance::new(raw_instance.type_ref.clone(), raw_instance.kind);
+            instance.attributes = raw_instance.attributes.clone();
+            instance.reference_designator = raw_instance.reference_designator.clone();
+            for (name, child_key) in &raw_instance.children {
+                instance.add_child(name.clone(), lookup(child_key)?);
+            }
+            schematic.add_instance(lookup(key)?, instance);
+        }
+
+        for raw_net in raw.nets.into_values() {
+            let mut net = Net::new(raw_net.kind, raw_net.name, raw_net.id);
+            net.properties = raw_net.properties;
+            for port in &raw_net.ports {
+                net.add_port(lookup(port)?);
+            }
+            schematic.add_net(net);
+        }
+
+        schematic.set_root_ref(root_ref);
+        Ok(schematic)
+    }
+
     /// Insert (or replace) an instance.
     pub fn add_instance(&mut self, reference: InstanceRef, instance: Instance) -> &mut Self {
         self.instances.insert(reference, instance);
@@ -594,6 +696,42 @@ mod tests {
         assert_eq!(h1.finish(), h2.finish());
     }
 
+    #[test]
+    fn json_roundtrip_preserves_dotted_names() {
+        let mod_ref = ModuleRef::from_path(Path::new("/tmp/board.zen"), "<root>");
+        let root_ref = InstanceRef::new(mod_ref.clone(), vec![]);
+        let u1_ref = root_ref.append("U1".into());
+        // Multi-unit pin names contain a dot, e.g. `A.+`.
+        let pin_ref = u1_ref.append("A.+".into());
+
+        let mut schematic = Schematic::new();
+        schematic.add_instance(
+            root_ref.clone(),
+            Instance::module(mod_ref.clone()).with_child("U1", u1_ref.clone()),
+        );
+        schematic.add_instance(
+            u1_ref.clone(),
+            Instance::component(mod_ref.clone())
+                .with_attribute("mpn", "LM358".to_string())
+                .with_child("A.+", pin_ref.clone()),
+        );
+        schematic.add_instance(pin_ref.clone(), Instance::port(mod_ref.clone()));
+        schematic.add_net(Net::new(NetKind::Normal, "IN", 1).with_port(pin_ref.clone()));
+        schematic.set_root_ref(root_ref.clone());
+
+        let restored = Schematic::from_json(&schematic.to_json().unwrap()).unwrap();
+        assert_eq!(restored.root_ref, Some(root_ref));
+        assert_eq!(
+            restored.instances.get(&pin_ref).map(|i| i.kind),
+            Some(InstanceKind::Port)
+        );
+        assert_eq!(restored.nets["IN"].ports, vec![pin_ref]);
+        assert_eq!(
+            restored.instances[&u1_ref].attributes["mpn"].string(),
+            Some("LM358")
+        );
+    }
+
     #[test]
     fn test_assign_reference_designators() {
         let mut schematic = Schematic::new();
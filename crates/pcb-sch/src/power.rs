@@ -0,0 +1,612 @@
+//! Power budget and voltage-domain analysis.
+//!
+//! Power rails are nets of kind [`NetKind::Power`] (or any net carrying a rail
+//! voltage annotation). Components declare how they interact with rails through
+//! a handful of well-known attributes:
+//!
+//! * `__voltage__` – the part's voltage rating (the same value surfaced as
+//!   [`BomEntry::voltage`](crate::BomEntry)).
+//! * `__load_current__` – current drawn from every rail the part sits on.
+//! * `__supply_current__` – current the part can source on its output rail(s).
+//! * `__supply_pins__` – pins that drive the output rail(s) of a supply. When
+//!   omitted, every rail the supply touches is treated as an output.
+//!
+//! Nets carry `__voltage__` (the nominal rail voltage) and optionally
+//! `__max_current__` for rails fed from outside the design (connectors,
+//! batteries, bench supplies).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    generate_bom_entries, AttributeValue, Instance, InstanceKind, NetKind, PhysicalUnit,
+    PhysicalValue, Schematic,
+};
+
+// The single definition of the power attributes; `pcb_zen_core::attrs` re-exports them
+// for the Zen side so the two never drift apart.
+pub const ATTR_VOLTAGE: &str = "__voltage__";
+pub const ATTR_MAX_CURRENT: &str = "__max_current__";
+pub const ATTR_LOAD_CURRENT: &str = "__load_current__";
+pub const ATTR_SUPPLY_CURRENT: &str = "__supply_current__";
+pub const ATTR_SUPPLY_PINS: &str = "__supply_pins__";
+
+/// Result of running [`analyze_power`] over a schematic.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerReport {
+    pub rails: Vec<Rail>,
+    pub violations: Vec<PowerViolation>,
+}
+
+impl PowerReport {
+    pub fn has_violations(&self) -> bool {
+        !self.violations.is_empty()
+    }
+}
+
+/// A single power rail with its loads and supplies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rail {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<PhysicalValue>,
+    /// Total current drawn from the rail, including everything downstream of
+    /// regulators fed by it.
+    pub load: PhysicalValue,
+    /// Total current the rail can deliver, if any supply declares it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<PhysicalValue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub loads: Vec<RailLoad>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub supplies: Vec<RailSupply>,
+    /// Rails feeding the supplies of this rail.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+/// Current drawn from a rail by one component.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RailLoad {
+    pub path: String,
+    pub designator: String,
+    pub current: PhysicalValue,
+    /// Set when the load is a supply passing on the demand of the named rails.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub downstream: Vec<String>,
+}
+
+/// A component driving a rail.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RailSupply {
+    pub path: String,
+    pub designator: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capability: Option<PhysicalValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PowerViolation {
+    /// A component's voltage rating is below the voltage of a rail it sits on.
+    Overvoltage {
+        path: String,
+        designator: String,
+        rating: PhysicalValue,
+        rail: String,
+        rail_voltage: PhysicalValue,
+    },
+    /// The load on a rail exceeds what its supplies can deliver.
+    Overcurrent {
+        rail: String,
+        load: PhysicalValue,
+        capacity: PhysicalValue,
+    },
+}
+
+impl std::fmt::Display for PowerViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerViolation::Overvoltage {
+                designator,
+                rating,
+                rail,
+                rail_voltage,
+                ..
+            } => write!(
+                f,
+                "{designator} is rated for {rating} but sits on {rail} ({rail_voltage})"
+            ),
+            PowerViolation::Overcurrent {
+                rail,
+                load,
+                capacity,
+            } => write!(f, "{rail} draws {load} but its supplies provide {capacity}"),
+        }
+    }
+}
+
+/// How a component is attached to the rails in the design.
+#[derive(Default)]
+struct RailUsage {
+    /// rail name -> pins on that rail
+    pins: BTreeMap<String, BTreeSet<String>>,
+}
+
+struct Supply {
+    path: String,
+    designator: String,
+    capability: Option<Decimal>,
+    inputs: BTreeSet<String>,
+    outputs: BTreeSet<String>,
+}
+
+/// Sum loads per rail, trace rails through regulators and flag parts whose
+/// voltage rating is below the rail they sit on.
+pub fn analyze_power(schematic: &Schematic) -> PowerReport {
+    let bom = generate_bom_entries(schematic);
+
+    // Collect power rails.
+    let mut rail_voltage: BTreeMap<String, Option<PhysicalValue>> = BTreeMap::new();
+    let mut rail_limit: HashMap<String, Decimal> = HashMap::new();
+    for net in schematic.nets.values() {
+        let voltage = net
+            .properties
+            .get(ATTR_VOLTAGE)
+            .and_then(|v| physical_value(v, PhysicalUnit::Volts));
+        if net.kind != NetKind::Power && voltage.is_none() {
+            continue;
+        }
+        if let Some(limit) = net
+            .properties
+            .get(ATTR_MAX_CURRENT)
+            .and_then(|v| physical_value(v, PhysicalUnit::Amperes))
+        {
+            rail_limit.insert(net.name.clone(), limit.value);
+        }
+        rail_voltage.insert(net.name.clone(), voltage);
+    }
+
+    let components: HashMap<String, &Instance> = schematic
+        .instances
+        .iter()
+        .filter(|(_, i)| i.kind == InstanceKind::Component)
+        .map(|(r, i)| (r.instance_path.join("."), i))
+        .collect();
+
+    // Map each component to the rails it touches.
+    let mut usage: BTreeMap<String, RailUsage> = BTreeMap::new();
+    for net in schematic.nets.values() {
+        if !rail_voltage.contains_key(&net.name) {
+            continue;
+        }
+        for port in &net.ports {
+            let Some((pin, comp_path)) = port.instance_path.split_last() else {
+                continue;
+            };
+            let comp_path = comp_path.join(".");
+            if !components.contains_key(&comp_path) {
+                continue;
+            }
+            usage
+                .entry(comp_path)
+                .or_default()
+                .pins
+                .entry(net.name.clone())
+                .or_default()
+                .insert(pin.clone());
+        }
+    }
+
+    let mut loads: BTreeMap<String, Vec<RailLoad>> = BTreeMap::new();
+    let mut supplies: Vec<Supply> = Vec::new();
+    let mut violations = Vec::new();
+
+    for (path, rails) in &usage {
+        let Some(entry) = bom.get(path) else {
+            continue;
+        };
+        if entry.dnp {
+            continue;
+        }
+        let attrs = &components[path].attributes;
+
+        // Voltage rating checks. Ratings given as plain strings never make it
+        // into the BOM, so fall back to parsing the raw attribute.
+        let rating = entry
+            .voltage
+            .clone()
+            .filter(|v| v.unit == PhysicalUnit::Volts)
+            .or_else(|| {
+                attrs
+                    .get(ATTR_VOLTAGE)
+                    .and_then(|v| physical_value(v, PhysicalUnit::Volts))
+            });
+        if let Some(rating) = rating {
+            for rail in rails.pins.keys() {
+                let Some(Some(voltage)) = rail_voltage.get(rail) else {
+                    continue;
+                };
+                if rating.value < voltage.value.abs() {
+                    violations.push(PowerViolation::Overvoltage {
+                        path: path.clone(),
+                        designator: entry.designator.clone(),
+                        rating: rating.clone(),
+                        rail: rail.clone(),
+                        rail_voltage: voltage.clone(),
+                    });
+                }
+            }
+        }
+
+        let supply_current = attrs
+            .get(ATTR_SUPPLY_CURRENT)
+            .and_then(|v| physical_value(v, PhysicalUnit::Amperes));
+        let supply_pins = attrs.get(ATTR_SUPPLY_PINS).map(string_list);
+
+        // A supply's own quiescent draw comes from its inputs only; charging it to
+        // the rails it drives would count it again through `supply_demand`.
+        let mut load_rails: BTreeSet<String> = rails.pins.keys().cloned().collect();
+        if supply_current.is_some() || supply_pins.is_some() {
+            let outputs: BTreeSet<String> = rails
+                .pins
+                .iter()
+                .filter(|(_, pins)| {
+                    supply_pins
+                        .as_ref()
+                        .is_none_or(|names| pins.iter().any(|p| names.contains(p)))
+                })
+                .map(|(rail, _)| rail.clone())
+                .collect();
+            let inputs: BTreeSet<String> = rails
+                .pins
+                .keys()
+                .filter(|rail| !outputs.contains(*rail))
+                .cloned()
+                .collect();
+            load_rails = inputs.clone();
+            supplies.push(Supply {
+                path: path.clone(),
+                designator: entry.designator.clone(),
+                capability: supply_current.map(|c| c.value),
+                inputs,
+                outputs,
+            });
+        }
+
+        if let Some(current) = attrs
+            .get(ATTR_LOAD_CURRENT)
+            .and_then(|v| physical_value(v, PhysicalUnit::Amperes))
+        {
+            // A part sitting on several rails is charged against each of them;
+            // this keeps the budget conservative when the split is unknown.
+            for rail in &load_rails {
+                loads.entry(rail.clone()).or_default().push(RailLoad {
+                    path: path.clone(),
+                    designator: entry.designator.clone(),
+                    current: amperes(current.value),
+                    downstream: Vec::new(),
+                });
+            }
+        }
+    }
+
+    // Total demand per rail, following supplies upstream.
+    let mut totals: HashMap<String, Decimal> = HashMap::new();
+    for rail in rail_voltage.keys() {
+        rail_total(rail, &loads, &supplies, &mut totals, &mut HashSet::new());
+    }
+
+    let mut rails = Vec::new();
+    for (name, voltage) in &rail_voltage {
+        let mut rail_loads = loads.get(name).cloned().unwrap_or_default();
+        for supply in supplies.iter().filter(|s| s.inputs.contains(name)) {
+            let passed_on = supply_demand(supply, &supplies, &totals);
+            if passed_on.is_zero() {
+                continue;
+            }
+            rail_loads.push(RailLoad {
+                path: supply.path.clone(),
+                designator: supply.designator.clone(),
+                current: amperes(passed_on),
+                downstream: supply.outputs.iter().cloned().collect(),
+            });
+        }
+        rail_loads.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let rail_supplies: Vec<&Supply> = supplies
+            .iter()
+            .filter(|s| s.outputs.contains(name))
+            .collect();
+        let capacities: Vec<Decimal> = rail_supplies
+            .iter()
+            .filter_map(|s| s.capability)
+            .chain(rail_limit.get(name).copied())
+            .collect();
+        let capacity = (!capacities.is_empty()).then(|| capacities.iter().sum::<Decimal>());
+        let load = totals.get(name).copied().unwrap_or_default();
+
+        if let Some(capacity) = capacity {
+            if load > capacity {
+                violations.push(PowerViolation::Overcurrent {
+                    rail: name.clone(),
+                    load: amperes(load),
+                    capacity: amperes(capacity),
+                });
+            }
+        }
+
+        let sources: BTreeSet<String> = rail_supplies
+            .iter()
+            .flat_map(|s| s.inputs.iter().cloned())
+            .collect();
+
+        rails.push(Rail {
+            name: name.clone(),
+            voltage: voltage.clone(),
+            load: amperes(load),
+            capacity: capacity.map(amperes),
+            loads: rail_loads,
+            supplies: rail_supplies
+                .iter()
+                .map(|s| RailSupply {
+                    path: s.path.clone(),
+                    designator: s.designator.clone(),
+                    capability: s.capability.map(amperes),
+                })
+                .collect(),
+            sources: sources.into_iter().collect(),
+        });
+    }
+
+    PowerReport { rails, violations }
+}
+
+/// Demand a supply passes on to each of its input rails: its share of the load
+/// on every rail it drives.
+fn supply_demand(
+    supply: &Supply,
+    supplies: &[Supply],
+    totals: &HashMap<String, Decimal>,
+) -> Decimal {
+    supply
+        .outputs
+        .iter()
+        .map(|rail| {
+            let drivers = supplies.iter().filter(|s| s.outputs.contains(rail)).count();
+            totals.get(rail).copied().unwrap_or_default() / Decimal::from(drivers.max(1))
+        })
+        .sum()
+}
+
+fn rail_total(
+    rail: &str,
+    loads: &BTreeMap<String, Vec<RailLoad>>,
+    supplies: &[Supply],
+    totals: &mut HashMap<String, Decimal>,
+    visiting: &mut HashSet<String>,
+) -> Decimal {
+    if let Some(total) = totals.get(rail) {
+        return *total;
+    }
+    // Supplies that feed back into their own input are ignored past the first visit.
+    if !visiting.insert(rail.to_string()) {
+        return Decimal::ZERO;
+    }
+
+    let mut total: Decimal = loads
+        .get(rail)
+        .map(|l| l.iter().map(|l| l.current.value).sum())
+        .unwrap_or_default();
+    for supply in supplies.iter().filter(|s| s.inputs.contains(rail)) {
+        for out in &supply.outputs {
+            let drivers = supplies.iter().filter(|s| s.outputs.contains(out)).count();
+            total +=
+                rail_total(out, loads, supplies, totals, visiting) / Decimal::from(drivers.max(1));
+        }
+    }
+
+    visiting.remove(rail);
+    totals.insert(rail.to_string(), total);
+    total
+}
+
+fn amperes(value: Decimal) -> PhysicalValue {
+    PhysicalValue {
+        value: value.normalize(),
+        tolerance: Decimal::ZERO,
+        unit: PhysicalUnit::Amperes,
+    }
+}
+
+fn string_list(value: &AttributeValue) -> Vec<String> {
+    match value {
+        AttributeValue::String(s) => vec![s.clone()],
+        AttributeValue::Array(items) => items
+            .iter()
+            .filter_map(|i| i.string().map(str::to_owned))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Read a physical value with the expected unit from an attribute, accepting
+/// either a structured unit record or a string such as `"3.3V"` or `"500mA"`.
+fn physical_value(value: &AttributeValue, unit: PhysicalUnit) -> Option<PhysicalValue> {
+    match value {
+        AttributeValue::Physical(pv) if pv.unit == unit => Some(pv.clone()),
+        AttributeValue::Number(n) => Some(PhysicalValue::new(*n, 0.0, unit)),
+        AttributeValue::String(s) => parse_quantity(s, &unit).map(|value| PhysicalValue {
+            value,
+            tolerance: Decimal::ZERO,
+            unit,
+        }),
+        _ => None,
+    }
+}
+
+fn parse_quantity(s: &str, unit: &PhysicalUnit) -> Option<Decimal> {
+    let s = s.trim();
+    let s = s.strip_suffix(&unit.to_string()).unwrap_or(s).trim_end();
+    let (number, multiplier) = match s.chars().last()? {
+        'p' => (&s[..s.len() - 1], Decimal::new(1, 12)),
+        'n' => (&s[..s.len() - 1], Decimal::new(1, 9)),
+        'u' => (&s[..s.len() - 1], Decimal::new(1, 6)),
+        'µ' => (&s[..s.len() - 'µ'.len_utf8()], Decimal::new(1, 6)),
+        'm' => (&s[..s.len() - 1], Decimal::new(1, 3)),
+        'k' => (&s[..s.len() - 1], Decimal::from(1_000)),
+        'M' => (&s[..s.len() - 1], Decimal::from(1_000_000)),
+        _ => (s, Decimal::ONE),
+    };
+    let number: Decimal = number.trim().parse().ok()?;
+    Some(number * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InstanceRef, ModuleRef, Net};
+    use std::path::Path;
+
+    fn add_component(
+        sch: &mut Schematic,
+        name: &str,
+        attrs: &[(&str, AttributeValue)],
+        pins: &[(&str, &str)],
+    ) {
+        let module = ModuleRef::from_path(Path::new("/board.zen"), "<root>");
+        let comp_ref = InstanceRef::new(module.clone(), vec![name.to_string()]);
+        let mut inst = Instance::component(module.clone());
+        for (k, v) in attrs {
+            inst.add_attribute(*k, v.clone());
+        }
+        sch.add_instance(comp_ref.clone(), inst);
+        for (pin, net) in pins {
+            let port = comp_ref.append(pin.to_string());
+            sch.add_instance(port.clone(), Instance::port(module.clone()));
+            sch.net_mut(net).unwrap().add_port(port);
+        }
+    }
+
+    fn rail(name: &str, voltage: &str) -> Net {
+        Net::new(NetKind::Power, name, 0)
+            .with_property(ATTR_VOLTAGE, AttributeValue::String(voltage.to_string()))
+    }
+
+    fn str_attr(s: &str) -> AttributeValue {
+        AttributeValue::String(s.to_string())
+    }
+
+    fn sample() -> Schematic {
+        let mut sch = Schematic::new();
+        sch.add_net(rail("VIN", "12V").with_property(ATTR_MAX_CURRENT, str_attr("1A")));
+        sch.add_net(rail("3V3", "3.3V"));
+        sch.add_net(Net::new(NetKind::Ground, "GND", 0));
+
+        add_component(
+            &mut sch,
+            "LDO",
+            &[
+                (ATTR_SUPPLY_CURRENT, str_attr("300mA")),
+                (
+                    ATTR_SUPPLY_PINS,
+                    AttributeValue::Array(vec![str_attr("VOUT")]),
+                ),
+                (ATTR_VOLTAGE, str_attr("16V")),
+            ],
+            &[("VIN", "VIN"), ("VOUT", "3V3"), ("GND", "GND")],
+        );
+        add_component(
+            &mut sch,
+            "MCU",
+            &[(ATTR_LOAD_CURRENT, str_attr("250mA"))],
+            &[("VDD", "3V3"), ("VSS", "GND")],
+        );
+        add_component(
+            &mut sch,
+            "SENSOR",
+            &[(ATTR_LOAD_CURRENT, str_attr("100mA"))],
+            &[("VDD", "3V3"), ("VSS", "GND")],
+        );
+        add_component(
+            &mut sch,
+            "C1",
+            &[(
+                ATTR_VOLTAGE,
+                AttributeValue::Physical(PhysicalValue::new(6.3, 0.0, PhysicalUnit::Volts)),
+            )],
+            &[("P1", "VIN"), ("P2", "GND")],
+        );
+        sch.assign_reference_designators();
+        sch
+    }
+
+    #[test]
+    fn sums_loads_through_regulators() {
+        let report = analyze_power(&sample());
+        assert_eq!(report.rails.len(), 2);
+
+        let out = report.rails.iter().find(|r| r.name == "3V3").unwrap();
+        assert_eq!(out.load.value, Decimal::new(35, 2));
+        assert_eq!(out.capacity.as_ref().unwrap().value, Decimal::new(3, 1));
+        assert_eq!(out.sources, vec!["VIN".to_string()]);
+        assert_eq!(out.loads.len(), 2);
+
+        let input = report.rails.iter().find(|r| r.name == "VIN").unwrap();
+        assert_eq!(input.load.value, Decimal::new(35, 2));
+        assert_eq!(input.loads[0].path, "LDO");
+        assert_eq!(input.loads[0].downstream, vec!["3V3".to_string()]);
+        assert_eq!(input.capacity.as_ref().unwrap().value, Decimal::ONE);
+    }
+
+    #[test]
+    fn flags_overcurrent_and_overvoltage() {
+        let report = analyze_power(&sample());
+        assert_eq!(report.violations.len(), 2);
+        assert!(report.violations.iter().any(|v| matches!(
+            v,
+            PowerViolation::Overcurrent { rail, .. } if rail == "3V3"
+        )));
+        assert!(report.violations.iter().any(|v| matches!(
+            v,
+            PowerViolation::Overvoltage { path, rail, .. } if path == "C1" && rail == "VIN"
+        )));
+    }
+
+    #[test]
+    fn supply_quiescent_current_is_charged_to_its_input_only() {
+        let mut sch = sample();
+        let ldo = sch
+            .instances
+            .iter_mut()
+            .find(|(r, _)| r.instance_path == ["LDO"])
+            .map(|(_, i)| i)
+            .unwrap();
+        ldo.add_attribute(ATTR_LOAD_CURRENT, str_attr("5mA"));
+
+        let report = analyze_power(&sch);
+        let out = report.rails.iter().find(|r| r.name == "3V3").unwrap();
+        assert_eq!(out.load.value, Decimal::new(35, 2));
+        let input = report.rails.iter().find(|r| r.name == "VIN").unwrap();
+        assert_eq!(input.load.value, Decimal::new(355, 3));
+    }
+
+    #[test]
+    fn parses_quantities() {
+        assert_eq!(
+            parse_quantity("3.3V", &PhysicalUnit::Volts),
+            Some(Decimal::new(33, 1))
+        );
+        assert_eq!(
+            parse_quantity("500mA", &PhysicalUnit::Amperes),
+            Some(Decimal::new(5, 1))
+        );
+        assert_eq!(
+            parse_quantity("20 uA", &PhysicalUnit::Amperes),
+            Some(Decimal::new(2, 5))
+        );
+        assert_eq!(parse_quantity("fast", &PhysicalUnit::Amperes), None);
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Call hierarchy over module instantiation.
+//!
+//! Every item is a module file. The incoming calls of a module are the modules that
+//! instantiate it, and its outgoing calls are the modules it instantiates, as recorded by
+//! [`LspContext::get_module_dependencies`] during the last evaluation. Call sites are the
+//! calls to a global that [`LspContext::get_url_for_global_symbol`] resolves to the child,
+//! plus the `Module("...")` calls loading it.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use dupe::Dupe;
+use lsp_types::CallHierarchyIncomingCall;
+use lsp_types::CallHierarchyIncomingCallsParams;
+use lsp_types::CallHierarchyItem;
+use lsp_types::CallHierarchyOutgoingCall;
+use lsp_types::CallHierarchyOutgoingCallsParams;
+use lsp_types::CallHierarchyPrepareParams;
+use lsp_types::Range;
+use lsp_types::SymbolKind;
+use lsp_types::Url;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::definition::LspModule;
+use crate::references::name_argument;
+use crate::references::same_file;
+use crate::references::LocalTarget;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// The function whose first argument is the path of a module to load.
+const MODULE_FUNCTION: &str = "Module";
+
+impl<T: LspContext> Backend<T> {
+    /// The module under the cursor: a child module if the cursor is on something that
+    /// instantiates one, otherwise the current file.
+    pub(crate) fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyItem>>> {
+        let uri: LspUrl = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .try_into()?;
+        let position = params.text_document_position_params.position;
+        let Some(module) = self.get_ast_or_load_from_disk(&uri)? else {
+            return Ok(None);
+        };
+
+        let callee =
+            match module.find_reference_target_at_location(position.line, position.character) {
+                Some(LocalTarget::Variable { name, .. }) => Some(name),
+                Some(LocalTarget::Keyword { callee, .. }) => Some(callee),
+                _ => None,
+            };
+        let child =
+            callee.and_then(
+                |callee| match self.context.get_url_for_global_symbol(&uri, &callee) {
+                    Ok(Some(url @ LspUrl::File(_))) => Some(url),
+                    _ => None,
+                },
+            );
+        Ok(Some(vec![module_item(&child.unwrap_or(uri))?]))
+    }
+
+    /// The modules that instantiate the given one.
+    pub(crate) fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let LspUrl::File(child) = params.item.uri.try_into()? else {
+            return Ok(None);
+        };
+        let documents: Vec<(LspUrl, Arc<LspModule>)> = {
+            let last_valid_parse = self.last_valid_parse.read().unwrap();
+            last_valid_parse
+                .iter()
+                .map(|(uri, module)| (uri.clone(), module.dupe()))
+                .collect()
+        };
+
+        let mut calls = Vec::new();
+        for (uri, module) in documents {
+            let LspUrl::File(path) = &uri else {
+                continue;
+            };
+            let instantiates = self
+                .context
+                .get_module_dependencies(path)
+                .iter()
+                .any(|dep| same_file(dep, &child));
+            if !instantiates {
+                continue;
+            }
+            calls.push(CallHierarchyIncomingCall {
+                from_ranges: self.instantiation_ranges(&uri, &module, &child),
+                from: module_item(&uri)?,
+            });
+        }
+        calls.sort_by(|a, b| a.from.uri.as_str().cmp(b.from.uri.as_str()));
+        Ok(Some(calls))
+    }
+
+    /// The modules instantiated by the given one.
+    pub(crate) fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let uri: LspUrl = params.item.uri.try_into()?;
+        let LspUrl::File(path) = &uri else {
+            return Ok(None);
+        };
+        let Some(module) = self.get_ast_or_load_from_disk(&uri)? else {
+            return Ok(None);
+        };
+
+        let children: BTreeMap<PathBuf, Vec<Range>> = self
+            .context
+            .get_module_dependencies(path)
+            .into_iter()
+            .map(|child| {
+                let ranges = self.instantiation_ranges(&uri, &module, &child);
+                (child, ranges)
+            })
+            .collect();
+        let calls = children
+            .into_iter()
+            .map(|(child, from_ranges)| {
+                Ok(CallHierarchyOutgoingCall {
+                    to: module_item(&LspUrl::File(child))?,
+                    from_ranges,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Some(calls))
+    }
+
+    /// Where `module` (at `uri`) loads or instantiates the module at `child`.
+    fn instantiation_ranges(&self, uri: &LspUrl, module: &LspModule, child: &Path) -> Vec<Range> {
+        let codemap = module.ast.codemap();
+        module
+            .calls()
+            .into_iter()
+            .filter(|call| {
+                if call.callee == MODULE_FUNCTION {
+                    name_argument(call.args).is_some_and(|(path, _)| {
+                        matches!(
+                            self.resolve_load_path(path, uri, None),
+                            Ok(LspUrl::File(loaded)) if same_file(&loaded, child)
+                        )
+                    })
+                } else {
+                    matches!(
+                        self.context.get_url_for_global_symbol(uri, call.callee),
+                        Ok(Some(LspUrl::File(path))) if same_file(&path, child)
+                    )
+                }
+            })
+            .map(|call| codemap.resolve_span(call.callee_span).into())
+            .collect()
+    }
+}
+
+/// The call hierarchy item for a whole module file.
+fn module_item(uri: &LspUrl) -> anyhow::Result<CallHierarchyItem> {
+    let (name, detail) = match uri {
+        LspUrl::File(path) => (
+            path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            path.parent().map(|parent| parent.display().to_string()),
+        ),
+        _ => (uri.to_string(), None),
+    };
+    Ok(CallHierarchyItem {
+        name,
+        kind: SymbolKind::MODULE,
+        tags: None,
+        detail,
+        uri: Url::try_from(uri)?,
+        range: Range::default(),
+        selection_range: Range::default(),
+        data: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_items_are_named_after_the_file() -> anyhow::Result<()> {
+        let item = module_item(&LspUrl::File(PathBuf::from("/ws/modules/Resistor.zen")))?;
+        assert_eq!("Resistor.zen", item.name);
+        assert_eq!(Some("/ws/modules"), item.detail.as_deref());
+        assert_eq!(SymbolKind::MODULE, item.kind);
+        assert_eq!("file:///ws/modules/Resistor.zen", item.uri.as_str());
+        Ok(())
+    }
+}
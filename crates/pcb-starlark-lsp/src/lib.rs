@@ -22,6 +22,7 @@
 #[allow(clippy::needless_lifetimes)]
 #[allow(clippy::type_complexity)]
 mod bind;
+mod call_hierarchy;
 pub mod completion;
 mod definition;
 pub(crate) mod docs;
@@ -29,6 +30,9 @@ pub mod error;
 mod exported;
 pub(crate) mod inspect;
 pub(crate) mod loaded;
+mod outline;
+mod references;
+mod scheduler;
 pub mod server;
 mod symbols;
 #[cfg(test)]
@@ -0,0 +1,482 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Document symbols and folding ranges.
+//!
+//! The outline of a file lists what it declares: `io()` ports, `config()` parameters,
+//! nets, components and child module instances. A call instantiates a child module when
+//! the [`LspContext`] resolves its callee to a file, the same way go-to-definition does.
+
+use lsp_types::DocumentSymbol;
+use lsp_types::DocumentSymbolParams;
+use lsp_types::DocumentSymbolResponse;
+use lsp_types::FoldingRange;
+use lsp_types::FoldingRangeKind;
+use lsp_types::FoldingRangeParams;
+use lsp_types::SymbolKind;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AssignTargetP;
+use starlark_syntax::syntax::ast::AstArgument;
+use starlark_syntax::syntax::ast::AstAssignIdent;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::AstNoPayload;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::ExprP;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::top_level_stmts::top_level_stmts;
+use starlark_syntax::syntax::uniplate::Visit;
+
+use crate::definition::LspModule;
+use crate::references::name_argument;
+use crate::references::unquote;
+use crate::references::NET_FUNCTION;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// What a call declares, as far as the outline is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Declaration {
+    Io,
+    Config,
+    Net,
+    Component,
+    ModuleInstance,
+}
+
+impl Declaration {
+    fn classify(callee: &str, is_module: &dyn Fn(&str) -> bool) -> Option<Self> {
+        match callee {
+            "io" => Some(Self::Io),
+            "config" => Some(Self::Config),
+            NET_FUNCTION => Some(Self::Net),
+            "Component" => Some(Self::Component),
+            _ if is_module(callee) => Some(Self::ModuleInstance),
+            _ => None,
+        }
+    }
+
+    fn symbol_kind(self) -> SymbolKind {
+        match self {
+            Self::Io => SymbolKind::INTERFACE,
+            Self::Config => SymbolKind::PROPERTY,
+            Self::Net => SymbolKind::VARIABLE,
+            Self::Component => SymbolKind::OBJECT,
+            Self::ModuleInstance => SymbolKind::MODULE,
+        }
+    }
+}
+
+impl LspModule {
+    /// The declarations in this module, nested under the functions that contain them.
+    ///
+    /// `is_module` tells whether calling the named global instantiates a child module.
+    pub(crate) fn document_symbols(&self, is_module: &dyn Fn(&str) -> bool) -> Vec<DocumentSymbol> {
+        let mut symbols = Vec::new();
+        self.stmt_symbols(self.ast.statement(), is_module, &mut symbols);
+        symbols
+    }
+
+    fn stmt_symbols(
+        &self,
+        stmt: &AstStmt,
+        is_module: &dyn Fn(&str) -> bool,
+        out: &mut Vec<DocumentSymbol>,
+    ) {
+        match &stmt.node {
+            StmtP::Def(def) => {
+                let mut children = Vec::new();
+                self.stmt_symbols(&def.body, is_module, &mut children);
+                // Only functions that build something are interesting in the outline.
+                if !children.is_empty() {
+                    out.push(self.symbol(
+                        def.name.ident.clone(),
+                        Some("def".to_owned()),
+                        SymbolKind::FUNCTION,
+                        stmt.span,
+                        def.name.span,
+                        Some(children),
+                    ));
+                }
+            }
+            StmtP::Assign(assign) => {
+                let target = match &assign.lhs.node {
+                    AssignTargetP::Identifier(ident) => Some(ident),
+                    _ => None,
+                };
+                self.expr_symbols(&assign.rhs, target, stmt.span, is_module, out);
+            }
+            _ => stmt.visit_children(|child| match child {
+                Visit::Stmt(stmt) => self.stmt_symbols(stmt, is_module, out),
+                Visit::Expr(expr) => self.expr_symbols(expr, None, expr.span, is_module, out),
+            }),
+        }
+    }
+
+    /// Symbols for the calls in `expr`. If `expr` itself is a declaration, it is named after
+    /// `target` (when it has no name of its own) and covers `span`.
+    fn expr_symbols(
+        &self,
+        expr: &AstExpr,
+        target: Option<&AstAssignIdent>,
+        span: Span,
+        is_module: &dyn Fn(&str) -> bool,
+        out: &mut Vec<DocumentSymbol>,
+    ) {
+        if let ExprP::Call(callee, args) = &expr.node {
+            if let ExprP::Identifier(ident) = &callee.node {
+                if let Some(declaration) = Declaration::classify(&ident.node.ident, is_module) {
+                    let args = &args.args;
+                    let named = match declaration {
+                        // Module and component names are only ever passed as `name = ...`.
+                        Declaration::Component | Declaration::ModuleInstance => {
+                            keyword_string(args, "name")
+                        }
+                        _ => name_argument(args),
+                    };
+                    let (name, selection) = match (named, target) {
+                        (Some((name, name_span)), _) => {
+                            (name.to_owned(), unquote(self.ast.codemap(), name_span))
+                        }
+                        (None, Some(target)) => (target.ident.clone(), target.span),
+                        (None, None) => (ident.node.ident.clone(), callee.span),
+                    };
+                    let detail = match declaration {
+                        Declaration::Io | Declaration::Config => {
+                            let ty = args
+                                .iter()
+                                .filter_map(|arg| match &arg.node {
+                                    ArgumentP::Positional(expr) => Some(expr),
+                                    _ => None,
+                                })
+                                .nth(1)
+                                .map(|ty| self.ast.codemap().source_span(ty.span).to_owned());
+                            match ty {
+                                Some(ty) => format!("{}: {ty}", ident.node.ident),
+                                None => ident.node.ident.clone(),
+                            }
+                        }
+                        _ => ident.node.ident.clone(),
+                    };
+                    out.push(self.symbol(
+                        name,
+                        Some(detail),
+                        declaration.symbol_kind(),
+                        span,
+                        selection,
+                        None,
+                    ));
+                }
+            }
+        }
+        expr.visit_expr(|child| self.expr_symbols(child, None, child.span, is_module, out));
+    }
+
+    fn symbol(
+        &self,
+        name: String,
+        detail: Option<String>,
+        kind: SymbolKind,
+        span: Span,
+        selection: Span,
+        children: Option<Vec<DocumentSymbol>>,
+    ) -> DocumentSymbol {
+        let codemap = self.ast.codemap();
+        #[allow(deprecated)]
+        DocumentSymbol {
+            name,
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: codemap.resolve_span(span).into(),
+            selection_range: codemap.resolve_span(selection).into(),
+            children,
+        }
+    }
+
+    /// Foldable regions: blocks, bracketed expressions spanning several lines, runs of
+    /// `load()` statements and comment blocks.
+    pub(crate) fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let codemap = self.ast.codemap();
+        let lines = |span: Span| {
+            let span = codemap.resolve_span(span);
+            (span.begin.line as u32, span.end.line as u32)
+        };
+        let mut ranges: Vec<(u32, u32, FoldingRangeKind)> = Vec::new();
+
+        // Consecutive top-level `load()`s fold together.
+        let mut loads: Option<(u32, u32)> = None;
+        for stmt in top_level_stmts(self.ast.statement()) {
+            match (&stmt.node, &mut loads) {
+                (StmtP::Load(_), Some((_, end))) => *end = lines(stmt.span).1,
+                (StmtP::Load(_), None) => loads = Some(lines(stmt.span)),
+                (_, _) => {
+                    if let Some((start, end)) = loads.take() {
+                        ranges.push((start, end, FoldingRangeKind::Imports));
+                    }
+                }
+            }
+        }
+        if let Some((start, end)) = loads {
+            ranges.push((start, end, FoldingRangeKind::Imports));
+        }
+
+        fn visit(
+            node: Visit<AstNoPayload>,
+            lines: &dyn Fn(Span) -> (u32, u32),
+            ranges: &mut Vec<(u32, u32, FoldingRangeKind)>,
+        ) {
+            let span = match node {
+                Visit::Stmt(stmt) => match &stmt.node {
+                    StmtP::Def(_) | StmtP::For(_) | StmtP::If(..) | StmtP::IfElse(..) => {
+                        Some(stmt.span)
+                    }
+                    _ => None,
+                },
+                Visit::Expr(expr) => match &expr.node {
+                    ExprP::Call(..)
+                    | ExprP::List(_)
+                    | ExprP::Dict(_)
+                    | ExprP::Tuple(_)
+                    | ExprP::ListComprehension(..)
+                    | ExprP::DictComprehension(..) => Some(expr.span),
+                    _ => None,
+                },
+            };
+            if let Some(span) = span {
+                ranges.push({
+                    let (start, end) = lines(span);
+                    (start, end, FoldingRangeKind::Region)
+                });
+            }
+            node.visit_children(|child| visit(child, lines, ranges));
+        }
+        visit(Visit::Stmt(self.ast.statement()), &lines, &mut ranges);
+
+        // Runs of full-line comments.
+        let mut comment: Option<(u32, u32)> = None;
+        for (line, text) in codemap.source().lines().enumerate() {
+            let line = line as u32;
+            if text.trim_start().starts_with('#') {
+                match &mut comment {
+                    Some((_, end)) => *end = line,
+                    None => comment = Some((line, line)),
+                }
+            } else if let Some((start, end)) = comment.take() {
+                ranges.push((start, end, FoldingRangeKind::Comment));
+            }
+        }
+        if let Some((start, end)) = comment {
+            ranges.push((start, end, FoldingRangeKind::Comment));
+        }
+
+        // Keep the outermost multi-line range starting on each line.
+        ranges.retain(|(start, end, _)| end > start);
+        ranges.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+        ranges.dedup_by_key(|(start, _, _)| *start);
+        ranges
+            .into_iter()
+            .map(|(start_line, end_line, kind)| FoldingRange {
+                start_line,
+                end_line,
+                kind: Some(kind),
+                ..FoldingRange::default()
+            })
+            .collect()
+    }
+}
+
+/// The string literal passed as keyword argument `keyword`, with its span.
+fn keyword_string<'a>(args: &'a [AstArgument], keyword: &str) -> Option<(&'a str, Span)> {
+    args.iter().find_map(|arg| match &arg.node {
+        ArgumentP::Named(name, expr) if name.node == keyword => match &expr.node {
+            ExprP::Literal(AstLiteral::String(s)) => Some((s.node.as_str(), expr.span)),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+impl<T: LspContext> Backend<T> {
+    /// The outline of a document.
+    pub(crate) fn document_symbols(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> anyhow::Result<Option<DocumentSymbolResponse>> {
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        let Some(module) = self.get_ast_or_load_from_disk(&uri)? else {
+            return Ok(None);
+        };
+        let is_module = |callee: &str| {
+            matches!(
+                self.context.get_url_for_global_symbol(&uri, callee),
+                Ok(Some(LspUrl::File(_)))
+            )
+        };
+        Ok(Some(DocumentSymbolResponse::Nested(
+            module.document_symbols(&is_module),
+        )))
+    }
+
+    /// The foldable regions of a document.
+    pub(crate) fn folding_ranges(
+        &self,
+        params: FoldingRangeParams,
+    ) -> anyhow::Result<Option<Vec<FoldingRange>>> {
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        Ok(self
+            .get_ast_or_load_from_disk(&uri)?
+            .map(|module| module.folding_ranges()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::syntax::AstModule;
+    use starlark::syntax::Dialect;
+    use textwrap::dedent;
+
+    use super::*;
+
+    fn module(source: &str) -> LspModule {
+        LspModule::new(
+            AstModule::parse("test.zen", dedent(source), &Dialect::AllOptionsInternal).unwrap(),
+        )
+    }
+
+    fn outline(symbols: &[DocumentSymbol]) -> Vec<(String, String, SymbolKind, usize)> {
+        symbols
+            .iter()
+            .map(|s| {
+                (
+                    s.name.clone(),
+                    s.detail.clone().unwrap_or_default(),
+                    s.kind,
+                    s.children.as_ref().map_or(0, Vec::len),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_zen_declarations() {
+        let module = module(
+            r#"
+            load("@stdlib/interfaces.zen", "Power")
+
+            Resistor = Module("./Resistor.zen")
+
+            VCC = io("VCC", Net)
+            value = config("value", str, default = "10k")
+            internal = Net("INTERNAL")
+            helper = 1
+
+            Resistor(name = "R1", P1 = VCC, P2 = Net("MID"))
+
+            def decouple(n):
+                Component(name = "C" + str(n), footprint = "0402")
+                x = Net()
+            "#,
+        );
+        let symbols = module.document_symbols(&|callee| callee == "Resistor");
+        assert_eq!(
+            outline(&symbols),
+            vec![
+                (
+                    "VCC".to_owned(),
+                    "io: Net".to_owned(),
+                    SymbolKind::INTERFACE,
+                    0
+                ),
+                (
+                    "value".to_owned(),
+                    "config: str".to_owned(),
+                    SymbolKind::PROPERTY,
+                    0
+                ),
+                (
+                    "INTERNAL".to_owned(),
+                    "Net".to_owned(),
+                    SymbolKind::VARIABLE,
+                    0
+                ),
+                (
+                    "R1".to_owned(),
+                    "Resistor".to_owned(),
+                    SymbolKind::MODULE,
+                    0
+                ),
+                ("MID".to_owned(), "Net".to_owned(), SymbolKind::VARIABLE, 0),
+                (
+                    "decouple".to_owned(),
+                    "def".to_owned(),
+                    SymbolKind::FUNCTION,
+                    2
+                ),
+            ]
+        );
+        assert_eq!(
+            outline(symbols[5].children.as_ref().unwrap()),
+            vec![
+                (
+                    "Component".to_owned(),
+                    "Component".to_owned(),
+                    SymbolKind::OBJECT,
+                    0
+                ),
+                ("x".to_owned(), "Net".to_owned(), SymbolKind::VARIABLE, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_blocks_loads_and_comments() {
+        let module = module(
+            r#"
+            load("a.zen", "A")
+            load("b.zen", "B")
+
+            # A comment
+            # spanning lines
+            def f():
+                return [
+                    1,
+                ]
+
+            x = 1
+            "#,
+        );
+        let ranges: Vec<(u32, u32, Option<FoldingRangeKind>)> = module
+            .folding_ranges()
+            .into_iter()
+            .map(|r| (r.start_line, r.end_line, r.kind))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (1, 2, Some(FoldingRangeKind::Imports)),
+                (4, 5, Some(FoldingRangeKind::Comment)),
+                (6, 9, Some(FoldingRangeKind::Region)),
+                (7, 9, Some(FoldingRangeKind::Region)),
+            ]
+        );
+    }
+}
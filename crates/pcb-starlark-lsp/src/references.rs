@@ -0,0 +1,842 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Find-references and rename across the workspace.
+//!
+//! Besides plain variables, three Zen-specific kinds of names are tracked:
+//! - `io()`/`config()` inputs, which are also referenced by keyword arguments wherever the
+//!   module is instantiated (found through [`LspContext::get_module_dependencies`]),
+//! - top-level symbols, which are also referenced by the `load()` statements importing them,
+//! - net names, i.e. the name passed to `Net()`. A name names the net created by that one
+//!   call, so its references are the variable the net is assigned to, followed through the
+//!   files loading it, and a rename only rewrites the name itself.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use dupe::Dupe;
+use lsp_types::Location;
+use lsp_types::Range;
+use lsp_types::ReferenceParams;
+use lsp_types::RenameParams;
+use lsp_types::TextEdit;
+use lsp_types::Url;
+use lsp_types::WorkspaceEdit;
+use starlark::codemap::CodeMap;
+use starlark::codemap::Pos;
+use starlark::codemap::Span;
+use starlark::codemap::Spanned;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AssignTargetP;
+use starlark_syntax::syntax::ast::AstArgument;
+use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::AstNoPayload;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::top_level_stmts::top_level_stmts;
+use starlark_syntax::syntax::uniplate::Visit;
+
+use crate::bind::scope;
+use crate::bind::Assigner;
+use crate::bind::Bind;
+use crate::bind::Scope;
+use crate::definition::LspModule;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// Functions whose name argument declares a module input.
+pub(crate) const INPUT_FUNCTIONS: [&str; 2] = ["io", "config"];
+
+/// Function whose name argument names a net.
+pub(crate) const NET_FUNCTION: &str = "Net";
+
+/// The binding a variable resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Binding {
+    /// Where the variable is first assigned in its scope.
+    pub(crate) span: Span,
+    pub(crate) assigner: Assigner,
+    /// Whether the variable is bound at module level.
+    pub(crate) top_level: bool,
+}
+
+/// A use or assignment of a variable.
+#[derive(Debug)]
+struct Occurrence {
+    name: String,
+    span: Span,
+    /// `None` if the variable is not bound in this module (e.g. a builtin).
+    binding: Option<Binding>,
+}
+
+/// A call to a function referenced by name.
+pub(crate) struct Call<'a> {
+    pub(crate) callee: &'a str,
+    /// The span of the callee identifier.
+    pub(crate) callee_span: Span,
+    pub(crate) args: &'a [AstArgument],
+}
+
+/// What the cursor is on, as far as a single module can tell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LocalTarget {
+    /// A variable, and the binding it resolves to in this module.
+    Variable {
+        name: String,
+        binding: Option<Binding>,
+    },
+    /// A symbol named in a `load()` statement.
+    Loaded { path: String, name: String },
+    /// The name of an `io()` or `config()` input declared by this module.
+    Input(String),
+    /// A keyword argument in a call to `callee`.
+    Keyword { callee: String, name: String },
+    /// The name of a `Net()` at `span`, and the variable the net is assigned to, if any.
+    Net {
+        name: String,
+        span: Span,
+        variable: Option<(String, Binding)>,
+    },
+}
+
+/// What the cursor is on, resolved across the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReferenceTarget {
+    /// A variable only visible in `uri`, e.g. a function parameter or a builtin.
+    Local {
+        uri: LspUrl,
+        name: String,
+        binding: Option<Span>,
+    },
+    /// A top-level symbol of `uri`, visible to every file that loads it.
+    Exported { uri: LspUrl, name: String },
+    /// An `io()`/`config()` input of the module in `uri`.
+    ModuleInput { uri: LspUrl, name: String },
+    /// The name of the net created by the `Net()` call in `uri` whose name argument is at
+    /// `span`, and the variable holding the net.
+    Net {
+        uri: LspUrl,
+        span: Span,
+        variable: Option<Box<ReferenceTarget>>,
+    },
+}
+
+/// A single reference, with spans of string literals narrowed to their contents.
+struct Reference {
+    uri: LspUrl,
+    range: Range,
+    is_declaration: bool,
+}
+
+impl LspModule {
+    /// Find what the symbol at `line`/`col` (zero based) refers to.
+    pub(crate) fn find_reference_target_at_location(
+        &self,
+        line: u32,
+        col: u32,
+    ) -> Option<LocalTarget> {
+        let line_span = self.ast.codemap().line_span_opt(line as usize)?;
+        let pos = std::cmp::min(line_span.begin() + col, line_span.end());
+
+        for stmt in top_level_stmts(self.ast.statement()) {
+            if let StmtP::Load(load) = &stmt.node {
+                for arg in &load.args {
+                    if arg.their.span.contains(pos) || arg.local.span.contains(pos) {
+                        return Some(LocalTarget::Loaded {
+                            path: load.module.node.clone(),
+                            name: arg.their.node.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        for call in self.calls() {
+            if let Some((name, span)) = name_argument(call.args) {
+                if span.contains(pos) {
+                    if INPUT_FUNCTIONS.contains(&call.callee) {
+                        return Some(LocalTarget::Input(name.to_owned()));
+                    }
+                    if call.callee == NET_FUNCTION {
+                        let variable = self.net_variable(span).and_then(|(name, span)| {
+                            let occurrence =
+                                self.occurrences().into_iter().find(|o| o.span == span)?;
+                            Some((name, occurrence.binding?))
+                        });
+                        return Some(LocalTarget::Net {
+                            name: name.to_owned(),
+                            span,
+                            variable,
+                        });
+                    }
+                }
+            }
+            for arg in call.args {
+                if let ArgumentP::Named(name, _) = &arg.node {
+                    if name.span.contains(pos) {
+                        return Some(LocalTarget::Keyword {
+                            callee: call.callee.to_owned(),
+                            name: name.node.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        self.occurrences()
+            .into_iter()
+            .find(|occurrence| occurrence.span.contains(pos))
+            .map(|occurrence| LocalTarget::Variable {
+                name: occurrence.name,
+                binding: occurrence.binding,
+            })
+    }
+
+    /// Spans of every use or assignment of `name` that resolves to the binding at `binding`.
+    pub(crate) fn find_variable_references(&self, name: &str, binding: Option<Span>) -> Vec<Span> {
+        self.occurrences()
+            .into_iter()
+            .filter(|o| o.name == name && o.binding.as_ref().map(|b| b.span) == binding)
+            .map(|o| o.span)
+            .collect()
+    }
+
+    /// The span where the top-level symbol `name` is first assigned, unless it is loaded.
+    fn find_top_level_binding(&self, name: &str) -> Option<Span> {
+        match scope(&self.ast).bound.get(name) {
+            Some((Assigner::Load { .. }, _)) | None => None,
+            Some((_, span)) => Some(*span),
+        }
+    }
+
+    /// Spans referring to the symbol `name` loaded from a file for which `is_target` holds:
+    /// the name in the `load()` statement and, unless the symbol is aliased, its uses.
+    fn find_load_references(
+        &self,
+        name: &str,
+        mut is_target: impl FnMut(&str) -> bool,
+    ) -> Vec<Span> {
+        let mut spans = Vec::new();
+        for stmt in top_level_stmts(self.ast.statement()) {
+            let StmtP::Load(load) = &stmt.node else {
+                continue;
+            };
+            for arg in &load.args {
+                if arg.their.node != name || !is_target(&load.module.node) {
+                    continue;
+                }
+                spans.push(arg.their.span);
+                if arg.local.ident == name {
+                    spans.extend(self.find_variable_references(name, Some(arg.local.span)));
+                }
+            }
+        }
+        spans
+    }
+
+    /// Spans of the name arguments of the `io()`/`config()` calls declaring `name`.
+    fn find_input_declarations(&self, name: &str) -> Vec<Span> {
+        self.find_name_arguments(name, |callee| INPUT_FUNCTIONS.contains(&callee))
+    }
+
+    /// The variable, and the span where it is assigned, holding the net created by the
+    /// `Net()` call whose name argument is at `name_span`, e.g. `gnd` in `gnd = Net("GND")`.
+    fn net_variable(&self, name_span: Span) -> Option<(String, Span)> {
+        fn visit(node: Visit<AstNoPayload>, name_span: Span, out: &mut Option<(String, Span)>) {
+            if out.is_some() {
+                return;
+            }
+            if let Visit::Stmt(Spanned {
+                node: StmtP::Assign(assign),
+                ..
+            }) = node
+            {
+                if let (AssignTargetP::Identifier(ident), Expr::Call(callee, args)) =
+                    (&assign.lhs.node, &assign.rhs.node)
+                {
+                    let is_net = matches!(&callee.node, Expr::Identifier(id) if id.node.ident == NET_FUNCTION);
+                    if is_net
+                        && name_argument(&args.args).is_some_and(|(_, span)| span == name_span)
+                    {
+                        *out = Some((ident.ident.clone(), ident.span));
+                        return;
+                    }
+                }
+            }
+            node.visit_children(|node| visit(node, name_span, out));
+        }
+
+        let mut out = None;
+        visit(Visit::Stmt(self.ast.statement()), name_span, &mut out);
+        out
+    }
+
+    fn find_name_arguments(&self, name: &str, is_callee: impl Fn(&str) -> bool) -> Vec<Span> {
+        self.calls()
+            .into_iter()
+            .filter(|call| is_callee(call.callee))
+            .filter_map(|call| name_argument(call.args))
+            .filter(|(value, _)| *value == name)
+            .map(|(_, span)| span)
+            .collect()
+    }
+
+    /// Spans of the keyword arguments called `name` in calls to a function for which
+    /// `is_callee` holds.
+    fn find_keyword_arguments(
+        &self,
+        name: &str,
+        mut is_callee: impl FnMut(&str) -> bool,
+    ) -> Vec<Span> {
+        let mut spans = Vec::new();
+        for call in self.calls() {
+            let keyword = call.args.iter().find_map(|arg| match &arg.node {
+                ArgumentP::Named(arg_name, _) if arg_name.node == name => Some(arg_name.span),
+                _ => None,
+            });
+            if let Some(span) = keyword {
+                if is_callee(call.callee) {
+                    spans.push(span);
+                }
+            }
+        }
+        spans
+    }
+
+    /// Every use or assignment of a variable in the module, with the binding it resolves to.
+    fn occurrences(&self) -> Vec<Occurrence> {
+        fn resolve(name: &str, scopes: &[&Scope]) -> Option<Binding> {
+            scopes.iter().enumerate().rev().find_map(|(depth, scope)| {
+                scope.bound.get(name).map(|(assigner, span)| Binding {
+                    span: *span,
+                    assigner: assigner.clone(),
+                    top_level: depth == 0,
+                })
+            })
+        }
+
+        fn collect<'a>(scopes: &mut Vec<&'a Scope>, out: &mut Vec<Occurrence>) {
+            let current = *scopes.last().expect("at least the module scope");
+            for bind in &current.inner {
+                let (name, span) = match bind {
+                    Bind::Set(_, ident) => (ident.ident.as_str(), ident.span),
+                    Bind::Get(ident) => (ident.node.ident.as_str(), ident.span),
+                    Bind::GetDotted(dotted) => {
+                        (dotted.variable.node.ident.as_str(), dotted.variable.span)
+                    }
+                    Bind::Scope(inner) => {
+                        scopes.push(inner);
+                        collect(scopes, out);
+                        scopes.pop();
+                        continue;
+                    }
+                    Bind::Flow => continue,
+                };
+                out.push(Occurrence {
+                    name: name.to_owned(),
+                    span,
+                    binding: resolve(name, scopes),
+                });
+            }
+        }
+
+        let module_scope = scope(&self.ast);
+        let mut out = Vec::new();
+        collect(&mut vec![&module_scope], &mut out);
+        out
+    }
+
+    /// Every call in the module whose callee is a plain identifier.
+    pub(crate) fn calls(&self) -> Vec<Call<'_>> {
+        fn visit<'a>(node: Visit<'a, AstNoPayload>, calls: &mut Vec<Call<'a>>) {
+            if let Visit::Expr(Spanned {
+                node: Expr::Call(callee, args),
+                ..
+            }) = node
+            {
+                if let Expr::Identifier(ident) = &callee.node {
+                    calls.push(Call {
+                        callee: ident.node.ident.as_str(),
+                        callee_span: callee.span,
+                        args: &args.args,
+                    });
+                }
+            }
+            node.visit_children(|node| visit(node, calls));
+        }
+
+        let mut calls = Vec::new();
+        visit(Visit::Stmt(self.ast.statement()), &mut calls);
+        calls
+    }
+
+    /// Resolve `span` to a range, narrowing string literals to their contents so that
+    /// renames keep the quotes.
+    fn reference_range(&self, span: Span) -> Range {
+        let codemap = self.ast.codemap();
+        codemap.resolve_span(unquote(codemap, span)).into()
+    }
+}
+
+/// The name passed to `io()`, `config()` or `Net()`: the first positional argument or the
+/// `name` keyword argument, if it is a string literal.
+pub(crate) fn name_argument(args: &[AstArgument]) -> Option<(&str, Span)> {
+    let expr = args.iter().find_map(|arg| match &arg.node {
+        ArgumentP::Positional(expr) => Some(expr),
+        ArgumentP::Named(name, expr) if name.node == "name" => Some(expr),
+        _ => None,
+    })?;
+    match &expr.node {
+        Expr::Literal(AstLiteral::String(s)) => Some((s.node.as_str(), expr.span)),
+        _ => None,
+    }
+}
+
+/// Strip the quotes from `span` if it covers a string literal.
+pub(crate) fn unquote(codemap: &CodeMap, span: Span) -> Span {
+    let text = codemap.source_span(span);
+    let quotes = ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find(|q| text.len() >= 2 * q.len() && text.starts_with(q) && text.ends_with(q))
+        .map_or(0, |q| q.len() as u32);
+    if quotes == 0 {
+        return span;
+    }
+    Span::new(span.begin() + quotes, Pos::new(span.end().get() - quotes))
+}
+
+fn is_identifier(name: &str) -> bool {
+    const KEYWORDS: [&str; 18] = [
+        "and", "break", "continue", "def", "elif", "else", "for", "if", "in", "lambda", "load",
+        "not", "or", "pass", "return", "None", "True", "False",
+    ];
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+}
+
+impl<T: LspContext> Backend<T> {
+    /// Find every reference to the symbol at the given position in the workspace.
+    pub(crate) fn find_references(
+        &self,
+        params: ReferenceParams,
+    ) -> anyhow::Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri.try_into()?;
+        let position = params.text_document_position.position;
+        let Some(target) = self.reference_target(&uri, position.line, position.character)? else {
+            return Ok(None);
+        };
+
+        let locations = self
+            .references(&target)?
+            .into_iter()
+            .filter(|r| params.context.include_declaration || !r.is_declaration)
+            .map(|r| {
+                Ok(Location {
+                    uri: Url::try_from(&r.uri)?,
+                    range: r.range,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Some(locations))
+    }
+
+    /// Rename the symbol at the given position everywhere it is referenced in the workspace.
+    pub(crate) fn rename_symbol(
+        &self,
+        params: RenameParams,
+    ) -> anyhow::Result<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri.try_into()?;
+        let position = params.text_document_position.position;
+        let Some(target) = self.reference_target(&uri, position.line, position.character)? else {
+            return Ok(None);
+        };
+
+        let new_name = params.new_name;
+        match &target {
+            ReferenceTarget::Local {
+                name,
+                binding: None,
+                ..
+            } => {
+                return Err(anyhow::anyhow!(
+                    "`{name}` is not defined in the workspace and cannot be renamed"
+                ));
+            }
+            ReferenceTarget::Net { .. } => {
+                if new_name.is_empty() || new_name.contains(['"', '\'', '\\', '\n']) {
+                    return Err(anyhow::anyhow!("`{new_name}` is not a valid net name"));
+                }
+            }
+            _ => {
+                if !is_identifier(&new_name) {
+                    return Err(anyhow::anyhow!("`{new_name}` is not a valid identifier"));
+                }
+            }
+        }
+
+        // Uses of the variable holding a net keep their name when the net is renamed.
+        let is_net = matches!(target, ReferenceTarget::Net { .. });
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for reference in self.references(&target)? {
+            if is_net && !reference.is_declaration {
+                continue;
+            }
+            changes
+                .entry(Url::try_from(&reference.uri)?)
+                .or_default()
+                .push(TextEdit {
+                    range: reference.range,
+                    new_text: new_name.clone(),
+                });
+        }
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..WorkspaceEdit::default()
+        }))
+    }
+
+    /// Resolve what the symbol at the given position refers to across the workspace.
+    fn reference_target(
+        &self,
+        uri: &LspUrl,
+        line: u32,
+        character: u32,
+    ) -> anyhow::Result<Option<ReferenceTarget>> {
+        let Some(module) = self.get_ast_or_load_from_disk(uri)? else {
+            return Ok(None);
+        };
+        let target = match module.find_reference_target_at_location(line, character) {
+            None => None,
+            Some(LocalTarget::Variable { name, binding }) => {
+                Some(self.variable_target(uri, name, binding))
+            }
+            Some(LocalTarget::Loaded { path, name }) => self
+                .resolve_load_path(&path, uri, None)
+                .ok()
+                .map(|load_uri| ReferenceTarget::Exported {
+                    uri: load_uri,
+                    name,
+                }),
+            Some(LocalTarget::Input(name)) => Some(ReferenceTarget::ModuleInput {
+                uri: uri.clone(),
+                name,
+            }),
+            Some(LocalTarget::Keyword { callee, name }) => {
+                match self.context.get_url_for_global_symbol(uri, &callee)? {
+                    Some(module_uri @ LspUrl::File(_)) => Some(ReferenceTarget::ModuleInput {
+                        uri: module_uri,
+                        name,
+                    }),
+                    _ => None,
+                }
+            }
+            Some(LocalTarget::Net { span, variable, .. }) => Some(ReferenceTarget::Net {
+                uri: uri.clone(),
+                span,
+                variable: variable.map(|(name, binding)| {
+                    Box::new(self.variable_target(uri, name, Some(binding)))
+                }),
+            }),
+        };
+        Ok(target)
+    }
+
+    /// The target of the variable `name` in `uri`, following loads to the defining file.
+    fn variable_target(
+        &self,
+        uri: &LspUrl,
+        name: String,
+        binding: Option<Binding>,
+    ) -> ReferenceTarget {
+        match binding {
+            Some(Binding {
+                assigner: Assigner::Load { path, name: their },
+                span,
+                ..
+            }) => match self.resolve_load_path(&path.node, uri, None) {
+                Ok(load_uri) => ReferenceTarget::Exported {
+                    uri: load_uri,
+                    name: their.node,
+                },
+                Err(_) => ReferenceTarget::Local {
+                    uri: uri.clone(),
+                    name,
+                    binding: Some(span),
+                },
+            },
+            Some(Binding {
+                top_level: true, ..
+            }) => ReferenceTarget::Exported {
+                uri: uri.clone(),
+                name,
+            },
+            binding => ReferenceTarget::Local {
+                uri: uri.clone(),
+                name,
+                binding: binding.map(|b| b.span),
+            },
+        }
+    }
+
+    /// Every reference to `target` in the files known to the server, sorted and deduplicated.
+    fn references(&self, target: &ReferenceTarget) -> anyhow::Result<Vec<Reference>> {
+        let documents: Vec<(LspUrl, Arc<LspModule>)> = {
+            let last_valid_parse = self.last_valid_parse.read().unwrap();
+            last_valid_parse
+                .iter()
+                .map(|(uri, module)| (uri.clone(), module.dupe()))
+                .collect()
+        };
+        let module_for = |uri: &LspUrl| -> anyhow::Result<Option<Arc<LspModule>>> {
+            self.get_ast_or_load_from_disk(uri)
+        };
+
+        let mut references = Vec::new();
+        let mut add =
+            |uri: &LspUrl, module: &LspModule, spans: Vec<Span>, declaration: Option<Span>| {
+                for span in spans {
+                    references.push(Reference {
+                        uri: uri.clone(),
+                        range: module.reference_range(span),
+                        is_declaration: declaration == Some(span),
+                    });
+                }
+            };
+
+        match target {
+            ReferenceTarget::Local { uri, name, binding } => {
+                if let Some(module) = module_for(uri)? {
+                    add(
+                        uri,
+                        &module,
+                        module.find_variable_references(name, *binding),
+                        *binding,
+                    );
+                }
+            }
+            ReferenceTarget::Exported { uri, name } => {
+                if let Some(module) = module_for(uri)? {
+                    let binding = module.find_top_level_binding(name);
+                    if binding.is_some() {
+                        add(
+                            uri,
+                            &module,
+                            module.find_variable_references(name, binding),
+                            binding,
+                        );
+                    }
+                }
+                for (doc_uri, module) in documents.iter().filter(|(u, _)| u != uri) {
+                    let spans = module.find_load_references(name, |path| {
+                        self.resolve_load_path(path, doc_uri, None)
+                            .is_ok_and(|loaded| &loaded == uri)
+                    });
+                    add(doc_uri, module, spans, None);
+                }
+            }
+            ReferenceTarget::ModuleInput { uri, name } => {
+                if let Some(module) = module_for(uri)? {
+                    let spans = module.find_input_declarations(name);
+                    let declaration = spans.first().copied();
+                    add(uri, &module, spans, declaration);
+                }
+                for (doc_uri, module) in &documents {
+                    let (LspUrl::File(module_path), LspUrl::File(doc_path)) = (uri, doc_uri) else {
+                        continue;
+                    };
+                    let instantiates_module = self
+                        .context
+                        .get_module_dependencies(doc_path)
+                        .iter()
+                        .any(|dep| same_file(dep, module_path));
+                    if !instantiates_module {
+                        continue;
+                    }
+                    let spans = module.find_keyword_arguments(name, |callee| {
+                        matches!(
+                            self.context.get_url_for_global_symbol(doc_uri, callee),
+                            Ok(Some(LspUrl::File(path))) if same_file(&path, module_path)
+                        )
+                    });
+                    add(doc_uri, module, spans, None);
+                }
+            }
+            ReferenceTarget::Net {
+                uri,
+                span,
+                variable,
+            } => {
+                if let Some(module) = module_for(uri)? {
+                    add(uri, &module, vec![*span], Some(*span));
+                }
+                if let Some(variable) = variable {
+                    references.extend(self.references(variable)?.into_iter().map(|r| Reference {
+                        is_declaration: false,
+                        ..r
+                    }));
+                }
+            }
+        }
+
+        let key = |r: &Reference| {
+            (
+                r.uri.to_string(),
+                r.range.start.line,
+                r.range.start.character,
+                r.range.end.line,
+                r.range.end.character,
+            )
+        };
+        references.sort_by_key(key);
+        references.dedup_by(|a, b| key(a) == key(b));
+        Ok(references)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::syntax::AstModule;
+    use starlark::syntax::Dialect;
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    fn module(fixture: &str) -> anyhow::Result<(FixtureWithRanges, LspModule)> {
+        let parsed = FixtureWithRanges::from_fixture("foo.zen", &dedent(fixture))?;
+        let ast = AstModule::parse("foo.zen", parsed.program(), &Dialect::AllOptionsInternal)?;
+        Ok((parsed, LspModule::new(ast)))
+    }
+
+    fn target_at(parsed: &FixtureWithRanges, module: &LspModule, id: &str) -> Option<LocalTarget> {
+        module.find_reference_target_at_location(parsed.begin_line(id), parsed.begin_column(id))
+    }
+
+    #[test]
+    fn variable_references_respect_scopes() -> anyhow::Result<()> {
+        let (parsed, module) = module(
+            r#"
+            <x1>x</x1> = 1
+            def f(x):
+                return x
+            <x2>x</x2> + 1
+            def g():
+                return <x3>x</x3>
+            "#,
+        )?;
+
+        let Some(LocalTarget::Variable { name, binding }) = target_at(&parsed, &module, "x2")
+        else {
+            panic!("expected a variable");
+        };
+        assert_eq!("x", name);
+        let binding = binding.expect("x is bound");
+        assert!(binding.top_level);
+
+        let ranges: Vec<_> = module
+            .find_variable_references(&name, Some(binding.span))
+            .into_iter()
+            .map(|span| module.reference_range(span))
+            .collect();
+        let expected: Vec<Range> = ["x1", "x2", "x3"]
+            .iter()
+            .map(|id| parsed.resolved_span(id).into())
+            .collect();
+        assert_eq!(expected, ranges);
+        Ok(())
+    }
+
+    #[test]
+    fn finds_zen_names() -> anyhow::Result<()> {
+        let (parsed, module) = module(
+            r#"
+            Sub = Module("sub.zen")
+            VCC = io(<io>"VCC"</io>, Net)
+            gnd = Net(<net>"GND"</net>)
+            Sub(name = "S1", <kw>VCC</kw> = VCC)
+            "#,
+        )?;
+
+        assert_eq!(
+            Some(LocalTarget::Input("VCC".to_owned())),
+            target_at(&parsed, &module, "io")
+        );
+        let Some(LocalTarget::Net {
+            name,
+            span,
+            variable,
+        }) = target_at(&parsed, &module, "net")
+        else {
+            panic!("expected a net name");
+        };
+        assert_eq!("GND", name);
+        assert_eq!(
+            parsed.resolved_span("net"),
+            module.ast.codemap().resolve_span(span)
+        );
+        let (variable, binding) = variable.expect("the net is assigned to a variable");
+        assert_eq!("gnd", variable);
+        assert!(binding.top_level);
+        assert_eq!(
+            Some(LocalTarget::Keyword {
+                callee: "Sub".to_owned(),
+                name: "VCC".to_owned(),
+            }),
+            target_at(&parsed, &module, "kw")
+        );
+
+        let declarations: Vec<_> = module
+            .find_input_declarations("VCC")
+            .into_iter()
+            .map(|span| module.reference_range(span))
+            .collect();
+        let io: Range = parsed.resolved_span("io").into();
+        assert_eq!(1, declarations.len());
+        // The quotes are not part of the reference.
+        assert_eq!(io.start.character + 1, declarations[0].start.character);
+        assert_eq!(io.end.character - 1, declarations[0].end.character);
+
+        let keywords = module.find_keyword_arguments("VCC", |callee| callee == "Sub");
+        assert_eq!(1, keywords.len());
+        Ok(())
+    }
+
+    #[test]
+    fn validates_identifiers() {
+        assert!(is_identifier("VCC_3V3"));
+        assert!(is_identifier("_private"));
+        assert!(!is_identifier("3V3"));
+        assert!(!is_identifier("def"));
+        assert!(!is_identifier("a-b"));
+        assert!(!is_identifier(""));
+    }
+}
@@ -0,0 +1,584 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Incremental text sync and debounced, cancellable evaluation.
+//!
+//! Edits are applied to an in-memory copy of each open document as soon as they arrive,
+//! but evaluation is only scheduled once a document has been quiet for the debounce
+//! interval, so a burst of keystrokes costs a single evaluation of the final text.
+//! When the [`LspContext`] provides a [`LspContext::background_context`], evaluation runs
+//! on a worker thread; otherwise it runs on the main thread between messages.
+//!
+//! Work is cancelled by document version: a job whose document has moved on to a newer
+//! version is skipped, its results are discarded if it was already running, and any
+//! propagation to dependent documents stops early.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossbeam_channel::Sender;
+use lsp_server::Message;
+use lsp_server::Notification;
+use lsp_server::Request;
+use lsp_types::notification::LogMessage;
+use lsp_types::notification::Progress;
+use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::Request as _;
+use lsp_types::request::WorkDoneProgressCreate;
+use lsp_types::LogMessageParams;
+use lsp_types::MessageType;
+use lsp_types::NumberOrString;
+use lsp_types::Position;
+use lsp_types::ProgressParams;
+use lsp_types::ProgressParamsValue;
+use lsp_types::PublishDiagnosticsParams;
+use lsp_types::TextDocumentContentChangeEvent;
+use lsp_types::Url;
+use lsp_types::WorkDoneProgress;
+use lsp_types::WorkDoneProgressBegin;
+use lsp_types::WorkDoneProgressCreateParams;
+use lsp_types::WorkDoneProgressEnd;
+use lsp_types::WorkDoneProgressReport;
+
+use crate::definition::LspModule;
+use crate::server::new_notification;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// Source of unique `$/progress` tokens.
+static NEXT_PROGRESS_TOKEN: AtomicU64 = AtomicU64::new(0);
+
+/// The editor's view of an open document.
+#[derive(Debug, Clone)]
+pub(crate) struct Document {
+    /// The version from the last `didOpen`/`didChange`, if the client sent one.
+    pub(crate) version: Option<i32>,
+    pub(crate) text: String,
+}
+
+/// A request to evaluate one document.
+#[derive(Debug, Clone)]
+pub(crate) struct EvalJob {
+    pub(crate) uri: LspUrl,
+    /// The document version `text` corresponds to; `None` for files that aren't open.
+    pub(crate) version: Option<i32>,
+    pub(crate) text: String,
+    /// Whether documents that depend on this one should be re-validated afterwards.
+    pub(crate) propagate: bool,
+}
+
+/// Apply a `didChange` content change to `text`.
+///
+/// A change without a range replaces the whole document.
+pub(crate) fn apply_change(text: &mut String, change: TextDocumentContentChangeEvent) {
+    match change.range {
+        None => *text = change.text,
+        Some(range) => {
+            let start = offset_at(text, range.start);
+            let end = offset_at(text, range.end).max(start);
+            text.replace_range(start..end, &change.text);
+        }
+    }
+}
+
+/// The byte offset of an LSP position, whose column counts UTF-16 code units.
+///
+/// Positions past the end of a line or of the document are clamped, as the spec asks.
+fn offset_at(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+    let line = text[line_start..].split('\n').next().unwrap_or_default();
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    let mut column = 0;
+    for (offset, c) in line.char_indices() {
+        if column >= position.character as usize {
+            return line_start + offset;
+        }
+        column += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// Pending evaluations, keyed by document, waiting for their debounce interval to pass.
+pub(crate) struct Scheduler {
+    debounce: Duration,
+    pending: Mutex<HashMap<LspUrl, (Instant, EvalJob)>>,
+    /// Where due jobs are sent when evaluating on a worker thread.
+    worker: Option<Sender<EvalJob>>,
+}
+
+impl Scheduler {
+    pub(crate) fn new(debounce: Duration, worker: Option<Sender<EvalJob>>) -> Self {
+        Self {
+            debounce,
+            pending: Mutex::default(),
+            worker,
+        }
+    }
+
+    /// Queue `job`, replacing any pending job for the same document. Debounced jobs wait
+    /// for the document to be quiet; others are due immediately.
+    pub(crate) fn schedule(&self, job: EvalJob, debounced: bool) {
+        let due = if debounced {
+            Instant::now() + self.debounce
+        } else {
+            Instant::now()
+        };
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(job.uri.clone(), (due, job));
+    }
+
+    /// Drop the pending job for `uri`, if any.
+    pub(crate) fn cancel(&self, uri: &LspUrl) {
+        self.pending.lock().unwrap().remove(uri);
+    }
+
+    /// When the earliest pending job becomes due.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(due, _)| *due)
+            .min()
+    }
+
+    /// Remove the jobs that are due at `now`. Jobs are handed to the worker thread if there
+    /// is one; whatever is returned must be evaluated by the caller.
+    pub(crate) fn take_due(&self, now: Instant) -> Vec<EvalJob> {
+        let due: Vec<EvalJob> = {
+            let mut pending = self.pending.lock().unwrap();
+            let uris: Vec<LspUrl> = pending
+                .iter()
+                .filter(|(_, (due, _))| *due <= now)
+                .map(|(uri, _)| uri.clone())
+                .collect();
+            uris.iter()
+                .filter_map(|uri| pending.remove(uri).map(|(_, job)| job))
+                .collect()
+        };
+        match &self.worker {
+            Some(worker) => {
+                for job in due {
+                    // The worker only goes away when the server is shutting down.
+                    let _ = worker.send(job);
+                }
+                Vec::new()
+            }
+            None => due,
+        }
+    }
+}
+
+/// Evaluates documents, caches their ASTs and publishes their diagnostics.
+pub(crate) struct Evaluator<'a, T: LspContext> {
+    pub(crate) context: &'a T,
+    pub(crate) sender: &'a Sender<Message>,
+    pub(crate) last_valid_parse: &'a RwLock<HashMap<LspUrl, Arc<LspModule>>>,
+    pub(crate) documents: &'a RwLock<HashMap<LspUrl, Document>>,
+    /// Whether the client accepts server-initiated `$/progress`.
+    pub(crate) work_done_progress: bool,
+}
+
+impl<T: LspContext> Evaluator<'_, T> {
+    /// Evaluate a batch of jobs, keeping only the most recent job for each document.
+    pub(crate) fn run(&self, jobs: Vec<EvalJob>) {
+        let mut latest: Vec<EvalJob> = Vec::new();
+        for job in jobs {
+            match latest.iter_mut().find(|queued| queued.uri == job.uri) {
+                Some(queued) => *queued = job,
+                None => latest.push(job),
+            }
+        }
+        let jobs: Vec<EvalJob> = latest
+            .into_iter()
+            .filter(|job| self.is_current(&job.uri, job.version))
+            .collect();
+        if jobs.is_empty() {
+            return;
+        }
+
+        let progress = self
+            .work_done_progress
+            .then(|| ProgressReporter::begin(self.sender, "Evaluating", jobs.len()));
+        let total = jobs.len();
+        for (index, job) in jobs.into_iter().enumerate() {
+            if let Some(progress) = &progress {
+                progress.report(&job.uri, index, total);
+            }
+            if let Err(e) = self.evaluate(job) {
+                self.log_message(MessageType::WARNING, &format!("Evaluation failed: {e:#}"));
+            }
+        }
+    }
+
+    /// Whether `version` is still the latest version of `uri`. Jobs for documents that were
+    /// closed since being scheduled are stale too.
+    fn is_current(&self, uri: &LspUrl, version: Option<i32>) -> bool {
+        let Some(version) = version else {
+            return true;
+        };
+        match self.documents.read().unwrap().get(uri) {
+            Some(document) => document.version.is_none_or(|latest| latest <= version),
+            None => false,
+        }
+    }
+
+    fn evaluate(&self, job: EvalJob) -> anyhow::Result<()> {
+        if !self.is_current(&job.uri, job.version) {
+            return Ok(());
+        }
+        let eval_result = self.context.parse_file_with_contents(&job.uri, job.text);
+        if !self.is_current(&job.uri, job.version) {
+            // Superseded while we were evaluating; a newer job will publish instead.
+            return Ok(());
+        }
+
+        if let Some(ast) = eval_result.ast {
+            let module = Arc::new(LspModule::new(ast));
+            let mut last_valid_parse = self.last_valid_parse.write().unwrap();
+            last_valid_parse.insert(job.uri.clone(), module);
+        }
+        let notifications = self
+            .context
+            .did_evaluate(&job.uri, &eval_result.diagnostics);
+        self.publish_diagnostics((&job.uri).try_into()?, eval_result.diagnostics, job.version);
+        self.send_notifications(notifications);
+
+        // Propagate changes: if `job.uri` was modified, re-validate any other
+        // open documents that `load()` this file so that their diagnostics are
+        // kept up-to-date without requiring the user to touch them.
+        if job.propagate {
+            self.propagate_change(&job.uri, || !self.is_current(&job.uri, job.version))?;
+        }
+        Ok(())
+    }
+
+    /// Re-validate all open documents that (transitively) depend on `changed`, stopping
+    /// early once `cancelled` returns `true`.
+    fn propagate_change(
+        &self,
+        changed: &LspUrl,
+        cancelled: impl Fn() -> bool,
+    ) -> anyhow::Result<()> {
+        // Snapshot the list of currently cached documents.
+        let docs: Vec<LspUrl> = {
+            let map = self.last_valid_parse.read().unwrap();
+            map.keys().cloned().collect()
+        };
+
+        // Breadth-first exploration of dependents.
+        let mut queue: VecDeque<LspUrl> = VecDeque::new();
+        let mut visited: HashSet<LspUrl> = HashSet::new();
+
+        queue.push_back(changed.clone());
+        visited.insert(changed.clone());
+
+        while let Some(current) = queue.pop_front() {
+            for uri in &docs {
+                // Skip self and already-visited nodes.
+                if uri == &current || visited.contains(uri) {
+                    continue;
+                }
+                if cancelled() {
+                    return Ok(());
+                }
+
+                if self.depends_on(uri, &current) {
+                    // Re-validate the dependent file so its diagnostics refresh.
+                    self.quick_validate(uri)?;
+
+                    // Enqueue for further propagation (transitive deps).
+                    queue.push_back(uri.clone());
+                    visited.insert(uri.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `uri` loads `current`, either through `load()` or `Module()`.
+    fn depends_on(&self, uri: &LspUrl, current: &LspUrl) -> bool {
+        let module = self.last_valid_parse.read().unwrap().get(uri).cloned();
+        let loads_current = module.is_some_and(|module| {
+            module.get_loaded_symbols().iter().any(|sym| match uri {
+                LspUrl::File(_) => self
+                    .context
+                    .resolve_load(sym.loaded_from, uri, None)
+                    .is_ok_and(|dep_uri| &dep_uri == current),
+                LspUrl::Starlark(_) | LspUrl::Other(_) => false,
+            })
+        });
+        loads_current
+            || match (uri, current) {
+                (LspUrl::File(from_path), LspUrl::File(to_path)) => {
+                    self.context.has_module_dependency(from_path, to_path)
+                }
+                _ => false,
+            }
+    }
+
+    /// Lightweight validation used during dependency propagation. Parses the
+    /// current contents (the open document, or whatever the context loads) of `uri`,
+    /// updates caches and publishes diagnostics. Does **not** trigger further
+    /// propagation to avoid infinite loops.
+    fn quick_validate(&self, uri: &LspUrl) -> anyhow::Result<()> {
+        let document = self.documents.read().unwrap().get(uri).cloned();
+        let (text, version) = match document {
+            Some(document) => (document.text, document.version),
+            None => match self.context.get_load_contents(uri)? {
+                Some(text) => (text, None),
+                None => {
+                    // If the file cannot be read, clear any existing diagnostics.
+                    self.publish_diagnostics(uri.try_into()?, Vec::new(), None);
+                    return Ok(());
+                }
+            },
+        };
+
+        let eval_result = self.context.parse_file_with_contents(uri, text);
+
+        if let Some(ast) = eval_result.ast {
+            let module = Arc::new(LspModule::new(ast));
+            let mut last_valid_parse = self.last_valid_parse.write().unwrap();
+            last_valid_parse.insert(uri.clone(), module);
+        }
+
+        let notifications = self.context.did_evaluate(uri, &eval_result.diagnostics);
+        self.publish_diagnostics(uri.try_into()?, eval_result.diagnostics, version);
+        self.send_notifications(notifications);
+        Ok(())
+    }
+
+    fn send_notifications(&self, notifications: Vec<Notification>) {
+        for notification in notifications {
+            let _ = self.sender.send(Message::Notification(notification));
+        }
+    }
+
+    fn publish_diagnostics(
+        &self,
+        uri: Url,
+        diags: Vec<lsp_types::Diagnostic>,
+        version: Option<i32>,
+    ) {
+        let _ = self.sender.send(Message::Notification(
+            new_notification::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+                uri, diags, version,
+            )),
+        ));
+    }
+
+    fn log_message(&self, typ: MessageType, message: &str) {
+        let _ = self
+            .sender
+            .send(Message::Notification(new_notification::<LogMessage>(
+                LogMessageParams {
+                    typ,
+                    message: message.to_owned(),
+                },
+            )));
+    }
+}
+
+/// Reports the progress of one batch of evaluations through `$/progress`; the progress
+/// ends when this is dropped.
+struct ProgressReporter<'a> {
+    sender: &'a Sender<Message>,
+    token: NumberOrString,
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn begin(sender: &'a Sender<Message>, title: &str, total: usize) -> Self {
+        let id = NEXT_PROGRESS_TOKEN.fetch_add(1, Ordering::Relaxed);
+        let token = NumberOrString::String(format!("starlark/evaluate/{id}"));
+
+        // Responses to this request are ignored by the main loop.
+        let _ = sender.send(Message::Request(Request {
+            id: format!("starlark/progress/{id}").into(),
+            method: WorkDoneProgressCreate::METHOD.to_owned(),
+            params: serde_json::to_value(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .unwrap(),
+        }));
+
+        let reporter = Self { sender, token };
+        reporter.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.to_owned(),
+            cancellable: Some(false),
+            message: Some(format!("{total} file(s)")),
+            percentage: Some(0),
+        }));
+        reporter
+    }
+
+    fn report(&self, uri: &LspUrl, index: usize, total: usize) {
+        let name = uri
+            .path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(false),
+            message: Some(name),
+            percentage: Some((index * 100 / total.max(1)) as u32),
+        }));
+    }
+
+    fn send(&self, progress: WorkDoneProgress) {
+        let _ = self
+            .sender
+            .send(Message::Notification(new_notification::<Progress>(
+                ProgressParams {
+                    token: self.token.clone(),
+                    value: ProgressParamsValue::WorkDone(progress),
+                },
+            )));
+    }
+}
+
+impl Drop for ProgressReporter<'_> {
+    fn drop(&mut self) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }));
+    }
+}
+
+/// Start the worker thread that evaluates jobs with the context built by `make_context`.
+///
+/// The thread exits once the returned sender is dropped.
+pub(crate) fn spawn_worker<T: LspContext + 'static>(
+    make_context: Box<dyn FnOnce() -> T + Send>,
+    sender: Sender<Message>,
+    last_valid_parse: Arc<RwLock<HashMap<LspUrl, Arc<LspModule>>>>,
+    documents: Arc<RwLock<HashMap<LspUrl, Document>>>,
+    work_done_progress: bool,
+) -> (Sender<EvalJob>, JoinHandle<()>) {
+    let (jobs_sender, jobs) = crossbeam_channel::unbounded::<EvalJob>();
+    let handle = std::thread::Builder::new()
+        .name("starlark-lsp-eval".to_owned())
+        .spawn(move || {
+            let context = make_context();
+            let evaluator = Evaluator {
+                context: &context,
+                sender: &sender,
+                last_valid_parse: &last_valid_parse,
+                documents: &documents,
+                work_done_progress,
+            };
+            while let Ok(job) = jobs.recv() {
+                // Pick up everything that queued up while we were busy, so that only the
+                // latest version of each document gets evaluated.
+                let mut batch = vec![job];
+                batch.extend(jobs.try_iter());
+                evaluator.run(batch);
+            }
+        })
+        .expect("failed to spawn the evaluation thread");
+    (jobs_sender, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn change(range: Option<Range>, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range,
+            range_length: None,
+            text: text.to_owned(),
+        }
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Option<Range> {
+        Some(Range::new(
+            Position::new(start.0, start.1),
+            Position::new(end.0, end.1),
+        ))
+    }
+
+    #[test]
+    fn applies_incremental_changes() {
+        let mut text = "x = 1\ny = 2\n".to_owned();
+        apply_change(&mut text, change(range((1, 4), (1, 5)), "42"));
+        assert_eq!(text, "x = 1\ny = 42\n");
+        apply_change(&mut text, change(range((0, 5), (1, 0)), "\nz = 3\n"));
+        assert_eq!(text, "x = 1\nz = 3\ny = 42\n");
+        apply_change(&mut text, change(None, "w = 0\n"));
+        assert_eq!(text, "w = 0\n");
+    }
+
+    #[test]
+    fn columns_count_utf16_code_units() {
+        // "🔌" is two UTF-16 code units and four bytes.
+        let mut text = "n = \"🔌\" + \"a\"\n".to_owned();
+        apply_change(&mut text, change(range((0, 11), (0, 14)), "\"b\""));
+        assert_eq!(text, "n = \"🔌\" + \"b\"\n");
+    }
+
+    #[test]
+    fn clamps_positions_past_the_end() {
+        let mut text = "a\r\nb".to_owned();
+        apply_change(&mut text, change(range((0, 10), (0, 10)), "!"));
+        assert_eq!(text, "a!\r\nb");
+        apply_change(&mut text, change(range((5, 0), (5, 0)), "\nc"));
+        assert_eq!(text, "a!\r\nb\nc");
+    }
+
+    #[test]
+    fn scheduler_debounces_and_replaces_jobs() {
+        let scheduler = Scheduler::new(Duration::from_secs(60), None);
+        let uri = LspUrl::File("/a.star".into());
+        let job = |version: i32, text: &str| EvalJob {
+            uri: uri.clone(),
+            version: Some(version),
+            text: text.to_owned(),
+            propagate: true,
+        };
+
+        scheduler.schedule(job(1, "a"), true);
+        assert!(scheduler.take_due(Instant::now()).is_empty());
+        scheduler.schedule(job(2, "b"), true);
+        let due = scheduler.take_due(Instant::now() + Duration::from_secs(61));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].text, "b");
+        assert!(scheduler.next_deadline().is_none());
+
+        scheduler.schedule(job(3, "c"), false);
+        assert_eq!(scheduler.take_due(Instant::now()).len(), 1);
+    }
+}
@@ -24,6 +24,8 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
 
 use derivative::Derivative;
 use derive_more::Display;
@@ -39,9 +41,20 @@ use lsp_types::notification::DidCloseTextDocument;
 use lsp_types::notification::DidOpenTextDocument;
 use lsp_types::notification::LogMessage;
 use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::CallHierarchyIncomingCalls;
+use lsp_types::request::CallHierarchyOutgoingCalls;
+use lsp_types::request::CallHierarchyPrepare;
 use lsp_types::request::Completion;
+use lsp_types::request::DocumentSymbolRequest;
+use lsp_types::request::FoldingRangeRequest;
 use lsp_types::request::GotoDefinition;
 use lsp_types::request::HoverRequest;
+use lsp_types::request::References;
+use lsp_types::request::Rename;
+use lsp_types::CallHierarchyIncomingCallsParams;
+use lsp_types::CallHierarchyOutgoingCallsParams;
+use lsp_types::CallHierarchyPrepareParams;
+use lsp_types::CallHierarchyServerCapability;
 use lsp_types::CompletionItem;
 use lsp_types::CompletionItemKind;
 use lsp_types::CompletionOptions;
@@ -52,7 +65,10 @@ use lsp_types::Diagnostic;
 use lsp_types::DidChangeTextDocumentParams;
 use lsp_types::DidCloseTextDocumentParams;
 use lsp_types::DidOpenTextDocumentParams;
+use lsp_types::DocumentSymbolParams;
 use lsp_types::Documentation;
+use lsp_types::FoldingRangeParams;
+use lsp_types::FoldingRangeProviderCapability;
 use lsp_types::GotoDefinitionParams;
 use lsp_types::GotoDefinitionResponse;
 use lsp_types::Hover;
@@ -70,9 +86,12 @@ use lsp_types::MessageType;
 use lsp_types::OneOf;
 use lsp_types::PublishDiagnosticsParams;
 use lsp_types::Range;
+use lsp_types::ReferenceParams;
+use lsp_types::RenameParams;
 use lsp_types::ServerCapabilities;
 use lsp_types::TextDocumentSyncCapability;
 use lsp_types::TextDocumentSyncKind;
+use lsp_types::TextDocumentSyncOptions;
 use lsp_types::Url;
 use lsp_types::WorkDoneProgressOptions;
 use lsp_types::WorkspaceFolder;
@@ -103,6 +122,12 @@ use crate::definition::IdentifierDefinition;
 use crate::definition::LspModule;
 use crate::inspect::AstModuleInspect;
 use crate::inspect::AutocompleteType;
+use crate::scheduler::apply_change;
+use crate::scheduler::spawn_worker;
+use crate::scheduler::Document;
+use crate::scheduler::EvalJob;
+use crate::scheduler::Evaluator;
+use crate::scheduler::Scheduler;
 use crate::symbols::find_symbols_at_location;
 
 /// The request to get the file contents for a starlark: URI
@@ -279,12 +304,20 @@ pub struct LspEvalResult {
 pub struct LspServerSettings {
     /// Whether goto definition should work.
     pub enable_goto_definition: bool,
+    /// How long a document must go without changes before it is re-evaluated.
+    #[serde(default = "default_eval_debounce_ms")]
+    pub eval_debounce_ms: u64,
+}
+
+fn default_eval_debounce_ms() -> u64 {
+    300
 }
 
 impl Default for LspServerSettings {
     fn default() -> Self {
         Self {
             enable_goto_definition: true,
+            eval_debounce_ms: default_eval_debounce_ms(),
         }
     }
 }
@@ -402,6 +435,26 @@ pub trait LspContext {
     ) {
     }
 
+    /// Create a context for evaluating documents on a background worker thread.
+    ///
+    /// The returned closure is called once, on the worker thread. The context it builds
+    /// should share caches with `self` so that requests handled on the main thread see the
+    /// results of background evaluation. The default implementation returns `None`, in which
+    /// case documents are evaluated on the main thread in between messages.
+    fn background_context(&self) -> Option<Box<dyn FnOnce() -> Self + Send>>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Notifications to send to the client after `uri` was evaluated and its diagnostics
+    /// published, e.g. to push state derived from the evaluation. Called on whichever
+    /// thread evaluated the document. The default implementation sends nothing.
+    fn did_evaluate(&self, _uri: &LspUrl, _diagnostics: &[Diagnostic]) -> Vec<Notification> {
+        Vec::new()
+    }
+
     /// Should the server eagerly preload all files in the workspace. When this returns
     /// `true` the server will call [`workspace_files`] once at start-up and parse the
     /// returned set of files so that cross-file features (e.g. workspace symbol, future
@@ -431,6 +484,14 @@ pub trait LspContext {
         false
     }
 
+    /// Get the files that `path` instantiates as child modules.
+    ///
+    /// Used to find the keyword arguments that bind a module's inputs when finding
+    /// references to, or renaming, them.
+    fn get_module_dependencies(&self, _path: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
     /// Provide custom hover information for a loaded symbol.
     ///
     /// This hook allows implementations to handle special cases like directory imports
@@ -471,7 +532,13 @@ pub(crate) struct Backend<T: LspContext> {
     pub(crate) context: T,
     /// The `AstModule` from the last time that a file was opened / changed and parsed successfully.
     /// Entries are evicted when the file is closed.
-    pub(crate) last_valid_parse: RwLock<HashMap<LspUrl, Arc<LspModule>>>,
+    pub(crate) last_valid_parse: Arc<RwLock<HashMap<LspUrl, Arc<LspModule>>>>,
+    /// The current text of open documents, kept up to date from incremental changes.
+    documents: Arc<RwLock<HashMap<LspUrl, Document>>>,
+    /// Evaluations waiting for their document to settle.
+    scheduler: Scheduler,
+    /// Whether the client accepts server-initiated `$/progress`.
+    work_done_progress: bool,
 }
 
 /// The logic implementations of stuff
@@ -485,7 +552,13 @@ impl<T: LspContext> Backend<T> {
             })
         });
         ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            text_document_sync: Some(TextDocumentSyncCapability::Options(
+                TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::INCREMENTAL),
+                    ..TextDocumentSyncOptions::default()
+                },
+            )),
             definition_provider,
             completion_provider: Some(CompletionOptions::default()),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
@@ -494,6 +567,11 @@ impl<T: LspContext> Backend<T> {
             } else {
                 None
             },
+            references_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
             ..T::capabilities()
         }
     }
@@ -517,43 +595,85 @@ impl<T: LspContext> Backend<T> {
         Ok(module)
     }
 
-    fn validate(&self, uri: Url, version: Option<i64>, text: String) -> anyhow::Result<()> {
-        let lsp_url = uri.clone().try_into()?;
-        let eval_result = self.context.parse_file_with_contents(&lsp_url, text);
-        if let Some(ast) = eval_result.ast {
-            let module = Arc::new(LspModule::new(ast));
-            let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.insert(lsp_url.clone(), module);
+    /// The evaluator used when there is no background worker.
+    fn evaluator(&self) -> Evaluator<'_, T> {
+        Evaluator {
+            context: &self.context,
+            sender: &self.connection.sender,
+            last_valid_parse: &self.last_valid_parse,
+            documents: &self.documents,
+            work_done_progress: self.work_done_progress,
         }
-        self.publish_diagnostics(uri, eval_result.diagnostics, version);
-
-        // Propagate changes: if `lsp_url` was modified, re-validate any other
-        // open documents that `load()` this file so that their diagnostics are
-        // kept up-to-date without requiring the user to touch them.
-        self.propagate_change(&lsp_url)?;
+    }
 
-        Ok(())
+    /// Evaluate the jobs whose debounce interval has passed, or hand them to the worker.
+    fn run_due_evaluations(&self) {
+        let jobs = self.scheduler.take_due(Instant::now());
+        if !jobs.is_empty() {
+            self.evaluator().run(jobs);
+        }
     }
 
     fn did_open(&self, params: DidOpenTextDocumentParams) -> anyhow::Result<()> {
-        self.validate(
-            params.text_document.uri,
-            Some(params.text_document.version as i64),
-            params.text_document.text,
-        )
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        let version = params.text_document.version;
+        self.documents.write().unwrap().insert(
+            uri.clone(),
+            Document {
+                version: Some(version),
+                text: params.text_document.text.clone(),
+            },
+        );
+        // Opening a file is a one-off, so there's nothing to wait for.
+        self.scheduler.schedule(
+            EvalJob {
+                uri,
+                version: Some(version),
+                text: params.text_document.text,
+                propagate: true,
+            },
+            false,
+        );
+        Ok(())
     }
 
     fn did_change(&self, params: DidChangeTextDocumentParams) -> anyhow::Result<()> {
-        // We asked for Sync full, so can just grab all the text from params
-        let change = params.content_changes.into_iter().next().unwrap();
-        self.validate(
-            params.text_document.uri,
-            Some(params.text_document.version as i64),
-            change.text,
-        )
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        let version = params.text_document.version;
+        let text = {
+            let mut documents = self.documents.write().unwrap();
+            let document = documents.entry(uri.clone()).or_insert_with(|| Document {
+                version: None,
+                text: self
+                    .context
+                    .get_load_contents(&uri)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
+            });
+            for change in params.content_changes {
+                apply_change(&mut document.text, change);
+            }
+            document.version = Some(version);
+            document.text.clone()
+        };
+        self.scheduler.schedule(
+            EvalJob {
+                uri,
+                version: Some(version),
+                text,
+                propagate: true,
+            },
+            true,
+        );
+        Ok(())
     }
 
     fn did_close(&self, params: DidCloseTextDocumentParams) -> anyhow::Result<()> {
+        let uri: LspUrl = params.text_document.uri.clone().try_into()?;
+        self.documents.write().unwrap().remove(&uri);
+        self.scheduler.cancel(&uri);
+
         // In eager mode we keep the cached AST so that other features continue to work even
         // when the user closes the document in the editor.
         if self.context.is_eager() {
@@ -561,7 +681,7 @@ impl<T: LspContext> Backend<T> {
         }
         {
             let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.remove(&params.text_document.uri.clone().try_into()?);
+            last_valid_parse.remove(&uri);
         }
         self.publish_diagnostics(params.text_document.uri, Vec::new(), None);
         Ok(())
@@ -603,6 +723,44 @@ impl<T: LspContext> Backend<T> {
         self.send_response(new_response(id, self.hover_info(params, initialize_params)));
     }
 
+    /// Find all references to the symbol at the current cursor across the workspace.
+    ///
+    /// NOTE: Files are only searched if the server has parsed them, i.e. they are open or
+    /// were preloaded because the context is eager.
+    fn references(&self, id: RequestId, params: ReferenceParams) {
+        self.send_response(new_response(id, self.find_references(params)));
+    }
+
+    /// Rename the symbol at the current cursor, and every reference to it.
+    fn rename(&self, id: RequestId, params: RenameParams) {
+        self.send_response(new_response(id, self.rename_symbol(params)));
+    }
+
+    /// List the declarations in a document.
+    fn document_symbol(&self, id: RequestId, params: DocumentSymbolParams) {
+        self.send_response(new_response(id, self.document_symbols(params)));
+    }
+
+    /// List the foldable regions of a document.
+    fn folding_range(&self, id: RequestId, params: FoldingRangeParams) {
+        self.send_response(new_response(id, self.folding_ranges(params)));
+    }
+
+    /// Find the module a call hierarchy starts from.
+    fn call_hierarchy_prepare(&self, id: RequestId, params: CallHierarchyPrepareParams) {
+        self.send_response(new_response(id, self.prepare_call_hierarchy(params)));
+    }
+
+    /// List the modules instantiating a module.
+    fn call_hierarchy_incoming(&self, id: RequestId, params: CallHierarchyIncomingCallsParams) {
+        self.send_response(new_response(id, self.incoming_calls(params)));
+    }
+
+    /// List the modules a module instantiates.
+    fn call_hierarchy_outgoing(&self, id: RequestId, params: CallHierarchyOutgoingCallsParams) {
+        self.send_response(new_response(id, self.outgoing_calls(params)));
+    }
+
     /// Get the file contents of a starlark: URI.
     fn get_starlark_file_contents(&self, id: RequestId, params: StarlarkFileContentsParams) {
         let response: anyhow::Result<_> = match params.uri {
@@ -1200,10 +1358,19 @@ impl<T: LspContext> Backend<T> {
             Ok(paths) => {
                 for path in paths {
                     if let Ok(url) = Url::from_file_path(&path) {
-                        if let Ok(lsp_url) = LspUrl::try_from(url.clone()) {
+                        if let Ok(lsp_url) = LspUrl::try_from(url) {
                             if let Ok(Some(contents)) = self.context.get_load_contents(&lsp_url) {
-                                // Ignore any error – they are surfaced via diagnostics when needed.
-                                let _ = self.validate(url, None, contents);
+                                // Every workspace file gets evaluated, so there is no need to
+                                // propagate to dependents.
+                                self.scheduler.schedule(
+                                    EvalJob {
+                                        uri: lsp_url,
+                                        version: None,
+                                        text: contents,
+                                        propagate: false,
+                                    },
+                                    false,
+                                );
                             }
                         }
                     }
@@ -1218,88 +1385,6 @@ impl<T: LspContext> Backend<T> {
         }
     }
 
-    /// Re-validate all open documents that (transitively) depend on `changed`.
-    fn propagate_change(&self, changed: &LspUrl) -> anyhow::Result<()> {
-        use std::collections::{HashSet, VecDeque};
-
-        // Snapshot the list of currently cached documents.
-        let docs: Vec<LspUrl> = {
-            let map = self.last_valid_parse.read().unwrap();
-            map.keys().cloned().collect()
-        };
-
-        // Breadth-first exploration of dependents.
-        let mut queue: VecDeque<LspUrl> = VecDeque::new();
-        let mut visited: HashSet<LspUrl> = HashSet::new();
-
-        queue.push_back(changed.clone());
-        visited.insert(changed.clone());
-
-        while let Some(current) = queue.pop_front() {
-            for uri in &docs {
-                // Skip self and already-visited nodes.
-                if uri == &current || visited.contains(uri) {
-                    continue;
-                }
-
-                // See if `uri` has a `load()` that resolves to `current`.
-                let mut depends_on_current = if let Some(module) = self.get_ast(uri) {
-                    module.get_loaded_symbols().iter().any(|sym| {
-                        self.resolve_load_path(sym.loaded_from, uri, None)
-                            .map(|dep_uri| dep_uri == current)
-                            .unwrap_or(false)
-                    })
-                } else {
-                    false
-                };
-
-                // Also check for dependencies introduced via `Module()`.
-                if !depends_on_current {
-                    if let (LspUrl::File(from_path), LspUrl::File(to_path)) = (uri, &current) {
-                        depends_on_current = self.context.has_module_dependency(from_path, to_path);
-                    }
-                }
-
-                if depends_on_current {
-                    // Re-validate the dependent file so its diagnostics refresh.
-                    self.quick_validate(uri)?;
-
-                    // Enqueue for further propagation (transitive deps).
-                    queue.push_back(uri.clone());
-                    visited.insert(uri.clone());
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Lightweight validation used during dependency propagation. Parses the
-    /// current contents (either the in-memory buffer or on-disk) of `uri`,
-    /// updates caches and publishes diagnostics.  Does **not** trigger further
-    /// propagation to avoid infinite loops.
-    fn quick_validate(&self, uri: &LspUrl) -> anyhow::Result<()> {
-        use std::convert::TryInto;
-
-        // Obtain the latest contents via the Context's loader.
-        let Some(text) = self.context.get_load_contents(uri)? else {
-            // If the file cannot be read, clear any existing diagnostics.
-            self.publish_diagnostics(uri.clone().try_into()?, Vec::new(), None);
-            return Ok(());
-        };
-
-        let eval_result = self.context.parse_file_with_contents(uri, text);
-
-        if let Some(ast) = eval_result.ast {
-            let module = Arc::new(LspModule::new(ast));
-            let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.insert(uri.clone(), module);
-        }
-
-        self.publish_diagnostics(uri.clone().try_into()?, eval_result.diagnostics, None);
-        Ok(())
-    }
-
     /// When a load() statement refers to a *directory* and not a file we try to resolve the
     /// requested module name to a file inside that directory (e.g. `load("dir", "foo")`
     /// might resolve to `dir/foo.star`).  This helper searches the directory for the most
@@ -1363,11 +1448,23 @@ impl<T: LspContext> Backend<T> {
 
         // Pre-parse relevant files.
         self.preload_workspace(&initialize_params);
-        for msg in &self.connection.receiver {
+        loop {
+            // Wait for the next message, waking up whenever a pending evaluation falls due.
+            let received = match self.scheduler.next_deadline() {
+                Some(deadline) => match self.connection.receiver.recv_deadline(deadline) {
+                    Err(e) if e.is_timeout() => {
+                        self.run_due_evaluations();
+                        continue;
+                    }
+                    received => received.ok(),
+                },
+                None => self.connection.receiver.recv().ok(),
+            };
+            let Some(msg) = received else {
+                break;
+            };
             match msg {
                 Message::Request(req) => {
-                    // TODO(nmj): Also implement DocumentSymbols so that some logic can
-                    //            be handled client side.
                     if let Some(params) = as_request::<GotoDefinition>(&req) {
                         self.goto_definition(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<StarlarkFileContentsRequest>(&req) {
@@ -1376,6 +1473,20 @@ impl<T: LspContext> Backend<T> {
                         self.completion(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<HoverRequest>(&req) {
                         self.hover(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<References>(&req) {
+                        self.references(req.id, params);
+                    } else if let Some(params) = as_request::<Rename>(&req) {
+                        self.rename(req.id, params);
+                    } else if let Some(params) = as_request::<DocumentSymbolRequest>(&req) {
+                        self.document_symbol(req.id, params);
+                    } else if let Some(params) = as_request::<FoldingRangeRequest>(&req) {
+                        self.folding_range(req.id, params);
+                    } else if let Some(params) = as_request::<CallHierarchyPrepare>(&req) {
+                        self.call_hierarchy_prepare(req.id, params);
+                    } else if let Some(params) = as_request::<CallHierarchyIncomingCalls>(&req) {
+                        self.call_hierarchy_incoming(req.id, params);
+                    } else if let Some(params) = as_request::<CallHierarchyOutgoingCalls>(&req) {
+                        self.call_hierarchy_outgoing(req.id, params);
                     } else if self.connection.handle_shutdown(&req)? {
                         return Ok(());
                     } else if let Some(resp) =
@@ -1398,7 +1509,7 @@ impl<T: LspContext> Backend<T> {
                     }
                 }
                 Message::Response(_) => {
-                    // Don't expect any of these
+                    // Only replies to `window/workDoneProgress/create`, which need no handling
                 }
             }
         }
@@ -1407,7 +1518,7 @@ impl<T: LspContext> Backend<T> {
 }
 
 /// Instantiate an LSP server that reads on stdin, and writes to stdout
-pub fn stdio_server<T: LspContext>(context: T) -> anyhow::Result<()> {
+pub fn stdio_server<T: LspContext + 'static>(context: T) -> anyhow::Result<()> {
     // Note that  we must have our logging only write out to stderr.
     eprintln!("Starting Rust Starlark server");
 
@@ -1421,7 +1532,7 @@ pub fn stdio_server<T: LspContext>(context: T) -> anyhow::Result<()> {
 }
 
 /// Instantiate an LSP server that reads and writes using the given connection.
-pub fn server_with_connection<T: LspContext>(
+pub fn server_with_connection<T: LspContext + 'static>(
     connection: Connection,
     context: T,
 ) -> anyhow::Result<()> {
@@ -1434,6 +1545,7 @@ pub fn server_with_connection<T: LspContext>(
         .as_ref()
         .and_then(|opts| serde_json::from_value(opts.clone()).ok())
         .unwrap_or_default();
+    let debounce = Duration::from_millis(server_settings.eval_debounce_ms);
     let capabilities_payload = Backend::<T>::server_capabilities(&context, server_settings);
     let server_capabilities = serde_json::to_value(capabilities_payload).unwrap();
 
@@ -1442,13 +1554,44 @@ pub fn server_with_connection<T: LspContext>(
     });
     connection.initialize_finish(init_request_id, initialize_data)?;
 
+    let work_done_progress = initialization_params
+        .capabilities
+        .window
+        .as_ref()
+        .and_then(|window| window.work_done_progress)
+        .unwrap_or(false);
+    let last_valid_parse = Arc::new(RwLock::default());
+    let documents = Arc::new(RwLock::default());
+    let (worker, worker_thread) = match context.background_context() {
+        Some(make_context) => {
+            let (worker, handle) = spawn_worker(
+                make_context,
+                connection.sender.clone(),
+                last_valid_parse.dupe(),
+                documents.dupe(),
+                work_done_progress,
+            );
+            (Some(worker), Some(handle))
+        }
+        None => (None, None),
+    };
+
     Backend {
         connection,
         context,
-        last_valid_parse: RwLock::default(),
+        last_valid_parse,
+        documents,
+        scheduler: Scheduler::new(debounce, worker),
+        work_done_progress,
     }
     .main_loop(initialization_params)?;
 
+    // The backend (and with it the job sender) is gone, so the worker finishes its
+    // current batch and exits.
+    if let Some(worker_thread) = worker_thread {
+        let _ = worker_thread.join();
+    }
+
     Ok(())
 }
 
@@ -1543,6 +1686,7 @@ mod tests {
     use textwrap::dedent;
 
     use crate::definition::helpers::FixtureWithRanges;
+    use crate::server::new_notification;
     use crate::server::LspServerSettings;
     use crate::server::LspUrl;
     use crate::server::StarlarkFileContentsParams;
@@ -1704,6 +1848,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn evaluates_incremental_changes_once_debounced() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("incremental.star");
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), "x = 1\n".to_owned())?;
+
+        // Two quick edits: the first leaves an unterminated string, the second fixes it.
+        for (version, column, text) in [(10, 4, "\""), (11, 6, "\"")] {
+            let position = Position::new(0, column);
+            let params = lsp_types::DidChangeTextDocumentParams {
+                text_document: lsp_types::VersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version,
+                },
+                content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                    range: Some(Range::new(position, position)),
+                    range_length: None,
+                    text: text.to_owned(),
+                }],
+            };
+            server.send_notification(new_notification::<
+                lsp_types::notification::DidChangeTextDocument,
+            >(params))?;
+        }
+
+        // Only the final text is evaluated.
+        let published = server.get_notification::<lsp_types::notification::PublishDiagnostics>()?;
+        assert_eq!(published.uri, uri);
+        assert_eq!(published.version, Some(11));
+        assert!(published.diagnostics.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn returns_old_definitions_if_current_file_does_not_parse() -> anyhow::Result<()> {
         if is_wasm() {
@@ -2398,6 +2579,7 @@ mod tests {
 
         let server = TestServer::new_with_settings(Some(LspServerSettings {
             enable_goto_definition: false,
+            ..LspServerSettings::default()
         }))?;
 
         let goto_definition_disabled = server
@@ -2411,6 +2593,7 @@ mod tests {
 
         let server = TestServer::new_with_settings(Some(LspServerSettings {
             enable_goto_definition: true,
+            ..LspServerSettings::default()
         }))?;
 
         let goto_definition_enabled = server
@@ -2685,6 +2868,80 @@ mod tests {
         assert_eq!(response, "echo:ping");
         Ok(())
     }
+
+    #[test]
+    fn renames_symbol_across_loaded_files() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let foo_contents = dedent(
+            r#"
+            load("{load}", <load>"baz"</load>)
+            <call>baz</call>()
+            "#,
+        )
+        .replace("{load}", &uri_to_load_string(&bar_uri))
+        .trim()
+        .to_owned();
+        let bar_contents = "def <def>baz</def>():\n    pass\n<use>baz</use>()";
+        let foo = FixtureWithRanges::from_fixture(foo_uri.path(), &foo_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), bar_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(foo_uri.clone(), foo.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+
+        let rename = server.new_request::<lsp_types::request::Rename>(lsp_types::RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: foo_uri.clone(),
+                },
+                position: Position {
+                    line: foo.begin_line("call"),
+                    character: foo.begin_column("call"),
+                },
+            },
+            new_name: "qux".to_owned(),
+            work_done_progress_params: Default::default(),
+        });
+        let request_id = server.send_request(rename)?;
+        let response: Option<lsp_types::WorkspaceEdit> = server.get_response(request_id)?;
+        let changes = response.and_then(|edit| edit.changes).unwrap_or_default();
+
+        let ranges = |uri: &Url| -> Vec<Range> {
+            let mut ranges: Vec<Range> = changes
+                .get(uri)
+                .into_iter()
+                .flatten()
+                .inspect(|edit| assert_eq!("qux", edit.new_text))
+                .map(|edit| edit.range)
+                .collect();
+            ranges.sort_by_key(|r| (r.start.line, r.start.character));
+            ranges
+        };
+
+        let load: Range = foo.resolved_span("load").into();
+        let load_contents = Range::new(
+            Position::new(load.start.line, load.start.character + 1),
+            Position::new(load.end.line, load.end.character - 1),
+        );
+        assert_eq!(
+            vec![load_contents, foo.resolved_span("call").into()],
+            ranges(&foo_uri)
+        );
+        assert_eq!(
+            vec![
+                Range::from(bar.resolved_span("def")),
+                bar.resolved_span("use").into()
+            ],
+            ranges(&bar_uri)
+        );
+        Ok(())
+    }
 }
 
 /// Rich metadata about a symbol used to enhance completion items.
@@ -442,6 +442,16 @@ impl Sandbox {
             .replace_all(&result, r#""cli_version": "<CLI_VERSION>""#)
             .to_string();
 
+        // Sanitize test durations in JSON and JUnit XML
+        let duration_json_pattern = Regex::new(r#""duration_secs":\s*[0-9.eE+-]+"#).unwrap();
+        result = duration_json_pattern
+            .replace_all(&result, r#""duration_secs": "<DURATION>""#)
+            .to_string();
+        let duration_xml_pattern = Regex::new(r#"\btime="[0-9.]+""#).unwrap();
+        result = duration_xml_pattern
+            .replace_all(&result, r#"time="<DURATION>""#)
+            .to_string();
+
         result
     }
 
@@ -23,7 +23,7 @@ pub use progress::{ProgressBar, ProgressBarBuilder};
 pub use spinner::{Spinner, SpinnerBuilder};
 pub use style::{icons, Style, StyledText};
 pub use terminal::{
-    clear_line, get_terminal_size, pad_text, truncate_text, Alignment, TerminalSize,
+    clear_line, clear_screen, get_terminal_size, pad_text, truncate_text, Alignment, TerminalSize,
 };
 
 // Re-export commonly used items from dependencies
@@ -38,6 +38,11 @@ pub fn clear_line() {
     print!("\r\x1b[K");
 }
 
+/// Clear the screen and move the cursor to the top-left corner
+pub fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+}
+
 /// Calculate the display width of a string, accounting for Unicode characters
 pub fn text_width(text: &str) -> usize {
     text.chars()
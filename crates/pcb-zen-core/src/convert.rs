@@ -96,7 +96,8 @@ impl ModuleConverter {
         }
 
         for (net_id, unique_name) in ids_and_names {
-            // Determine net kind from properties.
+            // Determine net kind from properties. Nets annotated with a rail
+            // voltage are treated as power rails unless typed explicitly.
             let net_kind = if let Some(props) = self.net_to_properties.get(&net_id) {
                 if let Some(type_prop) = props.get(crate::attrs::TYPE) {
                     match type_prop.string() {
@@ -104,6 +105,8 @@ impl ModuleConverter {
                         Some(crate::attrs::net::kind::POWER) => NetKind::Power,
                         _ => NetKind::Normal,
                     }
+                } else if props.contains_key(crate::attrs::VOLTAGE) {
+                    NetKind::Power
                 } else {
                     NetKind::Normal
                 }
@@ -71,9 +71,24 @@ pub struct Diagnostic {
     pub source_error: Option<Arc<anyhow::Error>>,
 }
 
+/// Errors raised through Starlark that keep their type as the diagnostic's source error,
+/// so that tools (e.g. LSP quick fixes) can match on them instead of on the message.
+fn structured_error(src: &(dyn std::error::Error + 'static)) -> Option<anyhow::Error> {
+    use crate::lang::error::{MissingInputError, UnknownPinError};
+
+    if let Some(err) = src.downcast_ref::<MissingInputError>() {
+        return Some(err.clone().into());
+    }
+    if let Some(err) = src.downcast_ref::<UnknownPinError>() {
+        return Some(err.clone().into());
+    }
+    None
+}
+
 impl From<starlark::Error> for Diagnostic {
     fn from(err: starlark::Error) -> Self {
         // Check the source chain of the error kind
+        let mut structured = None;
         if let Some(source) = err.kind().source() {
             let mut current: Option<&(dyn std::error::Error + 'static)> = Some(source);
             while let Some(src) = current {
@@ -81,6 +96,7 @@ impl From<starlark::Error> for Diagnostic {
                 if let Some(diag_err) = src.downcast_ref::<DiagnosticError>() {
                     return diag_err.0.clone();
                 }
+                structured = structured.or_else(|| structured_error(src));
                 current = src.source();
             }
         }
@@ -96,7 +112,7 @@ impl From<starlark::Error> for Diagnostic {
             body: err.kind().to_string(),
             call_stack: Some(err.call_stack().clone()),
             child: None,
-            source_error: Some(Arc::new(err.into_anyhow())),
+            source_error: Some(Arc::new(structured.unwrap_or_else(|| err.into_anyhow()))),
         }
     }
 }
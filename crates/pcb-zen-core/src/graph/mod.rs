@@ -1,5 +1,6 @@
 pub mod csr;
 pub mod path;
+pub mod query;
 pub mod starlark;
 
 use csr::{CsrError, CsrList};
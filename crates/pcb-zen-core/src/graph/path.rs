@@ -52,6 +52,24 @@ impl CircuitGraph {
         }
     }
 
+    /// Resolve a label to the ports a graph query starts or ends at: every port on the
+    /// named net (public or internal), or the single port of a (component, pin) tuple
+    pub fn resolve_label_to_ports<'v>(
+        &self,
+        label: Value<'v>,
+        heap: &'v Heap,
+    ) -> starlark::Result<Vec<PortId>> {
+        if let Some(net_name) = label.unpack_str() {
+            return self
+                .net_ports(net_name)
+                .map(|ports| ports.to_vec())
+                .ok_or_else(|| {
+                    starlark::Error::new_other(anyhow::anyhow!("Net '{}' not found", net_name))
+                });
+        }
+        Ok(vec![self.resolve_label_to_port(label, heap)?])
+    }
+
     /// Create a PathValue object from a path of PortIds and factors
     pub fn create_path_value<'v>(
         &self,
@@ -0,0 +1,267 @@
+use crate::graph::{CircuitGraph, FactorId, FactorType, PortId};
+use fixedbitset::FixedBitSet;
+use std::collections::VecDeque;
+
+/// Result of a breadth-first search from a set of ports. Distances count the components
+/// traversed, so moving along a net is free.
+#[derive(Debug)]
+pub struct GraphSearch {
+    /// Components traversed to reach each port (None if unreached)
+    distance: Vec<Option<u32>>,
+    /// The port and factor each reached port was reached through
+    parent: Vec<Option<(PortId, FactorId)>>,
+    /// Factors the search passed through
+    visited_factors: FixedBitSet,
+}
+
+impl GraphSearch {
+    /// Number of components on the shortest path to `port`
+    pub fn distance(&self, port: PortId) -> Option<u32> {
+        self.distance[port.0 as usize]
+    }
+
+    pub fn reached(&self, port: PortId) -> bool {
+        self.distance(port).is_some()
+    }
+
+    /// The reached port of `goals` with the fewest components in between
+    pub fn nearest(&self, goals: &[PortId]) -> Option<PortId> {
+        goals
+            .iter()
+            .copied()
+            .filter_map(|goal| self.distance(goal).map(|d| (d, goal)))
+            .min_by_key(|&(d, goal)| (d, goal.0))
+            .map(|(_, goal)| goal)
+    }
+
+    /// Ports from a start port to `port` and the factors traversed between them, in the
+    /// same shape as [`CircuitGraph::all_simple_paths_with_factors`]
+    pub fn path_to(&self, port: PortId) -> Option<(Vec<PortId>, Vec<FactorId>)> {
+        self.distance(port)?;
+        let mut ports = vec![port];
+        let mut factors = Vec::new();
+        let mut cur = port;
+        while let Some((prev, factor)) = self.parent[cur.0 as usize] {
+            ports.push(prev);
+            factors.push(factor);
+            cur = prev;
+        }
+        ports.reverse();
+        factors.reverse();
+        Some((ports, factors))
+    }
+
+    /// Every port the search reached
+    pub fn reached_ports(&self) -> impl Iterator<Item = PortId> + '_ {
+        self.distance
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.is_some())
+            .map(|(i, _)| PortId(i as u32))
+    }
+
+    /// Every net and component the search passed through
+    pub fn visited_factors(&self) -> impl Iterator<Item = FactorId> + '_ {
+        self.visited_factors.ones().map(|i| FactorId(i as u32))
+    }
+}
+
+impl CircuitGraph {
+    /// Ports on the net called `name`
+    pub fn net_ports(&self, name: &str) -> Option<&[PortId]> {
+        let fid = self.factor_id(name)?;
+        matches!(self.factor_type(fid), FactorType::Net(_)).then(|| self.factor_ports(fid))
+    }
+
+    /// Breadth-first search from `starts`, only passing through components for which
+    /// `traversable` holds and at most `max_components` of them. Nets are always
+    /// traversable.
+    ///
+    /// Runs in O(ports + connections): each factor is expanded once, from the first port
+    /// that reaches it, which is also the nearest one.
+    pub fn search(
+        &self,
+        starts: &[PortId],
+        mut traversable: impl FnMut(FactorId) -> bool,
+        max_components: Option<u32>,
+    ) -> GraphSearch {
+        let mut distance = vec![None; self.port_count()];
+        let mut parent = vec![None; self.port_count()];
+        let mut visited_factors = FixedBitSet::with_capacity(self.factor_count());
+
+        // 0-1 BFS: nets cost nothing and go to the front, components go to the back
+        let mut queue = VecDeque::new();
+        for &start in starts {
+            if distance[start.0 as usize].is_none() {
+                distance[start.0 as usize] = Some(0);
+                queue.push_back(start);
+            }
+        }
+
+        while let Some(p) = queue.pop_front() {
+            let d = distance[p.0 as usize].expect("queued ports have a distance");
+            for fid in self.port_factors(p) {
+                if visited_factors.contains(fid.0 as usize) {
+                    continue;
+                }
+                let cost = match self.factor_type(fid) {
+                    FactorType::Net(_) => 0,
+                    FactorType::Component(_) => {
+                        if max_components.is_some_and(|max| d + 1 > max) || !traversable(fid) {
+                            continue;
+                        }
+                        1
+                    }
+                };
+                visited_factors.insert(fid.0 as usize);
+
+                for &q in self.factor_ports(fid) {
+                    let qi = q.0 as usize;
+                    if distance[qi].is_none_or(|dq| d + cost < dq) {
+                        distance[qi] = Some(d + cost);
+                        parent[qi] = Some((p, fid));
+                        if cost == 0 {
+                            queue.push_front(q);
+                        } else {
+                            queue.push_back(q);
+                        }
+                    }
+                }
+            }
+        }
+
+        GraphSearch {
+            distance,
+            parent,
+            visited_factors,
+        }
+    }
+
+    /// Components that every path from `starts` to `goals` passes through, in the order
+    /// they appear on the shortest path. Empty if the goals are unreachable.
+    pub fn cut_components(
+        &self,
+        starts: &[PortId],
+        goals: &[PortId],
+        mut traversable: impl FnMut(FactorId) -> bool,
+    ) -> Vec<FactorId> {
+        let search = self.search(starts, &mut traversable, None);
+        let Some((_, factors)) = search.nearest(goals).and_then(|goal| search.path_to(goal)) else {
+            return Vec::new();
+        };
+
+        // Only components on one path can be on all of them; a component is a cut if the
+        // goals become unreachable without it
+        factors
+            .into_iter()
+            .filter(|&fid| matches!(self.factor_type(fid), FactorType::Component(_)))
+            .filter(|&cut| {
+                let without = self.search(starts, |fid| fid != cut && traversable(fid), None);
+                without.nearest(goals).is_none()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    /// VIN -(F1)- VBUS -(C1, C2)- GND, with R1 from VBUS to OUT and R2 from OUT to GND
+    fn power_graph() -> CircuitGraph {
+        let net_to_ports = HashMap::from([
+            ("VIN".to_string(), vec![("F1", "1").into()]),
+            (
+                "VBUS".to_string(),
+                vec![
+                    ("F1", "2").into(),
+                    ("C1", "1").into(),
+                    ("C2", "1").into(),
+                    ("R1", "1").into(),
+                ],
+            ),
+            (
+                "OUT".to_string(),
+                vec![("R1", "2").into(), ("R2", "1").into()],
+            ),
+            (
+                "GND".to_string(),
+                vec![("C1", "2").into(), ("C2", "2").into(), ("R2", "2").into()],
+            ),
+        ]);
+        let two_pins = || vec!["1".to_string(), "2".to_string()];
+        let component_pins = HashMap::from([
+            ("F1".into(), two_pins()),
+            ("C1".into(), two_pins()),
+            ("C2".into(), two_pins()),
+            ("R1".into(), two_pins()),
+            ("R2".into(), two_pins()),
+        ]);
+        CircuitGraph::new(net_to_ports, component_pins, HashSet::new()).unwrap()
+    }
+
+    fn names(graph: &CircuitGraph, factors: &[FactorId]) -> Vec<String> {
+        factors
+            .iter()
+            .map(|&fid| graph.factor_name(fid).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn shortest_path_minimizes_components() {
+        let graph = power_graph();
+        let search = graph.search(graph.net_ports("VBUS").unwrap(), |_| true, None);
+        let gnd = graph.net_ports("GND").unwrap();
+
+        let goal = search.nearest(gnd).unwrap();
+        assert_eq!(search.distance(goal), Some(1));
+        let (ports, factors) = search.path_to(goal).unwrap();
+        assert_eq!(ports.len(), factors.len() + 1);
+        let components: Vec<String> = names(&graph, &factors)
+            .into_iter()
+            .filter(|name| name.starts_with('C'))
+            .collect();
+        assert_eq!(components.len(), 1);
+    }
+
+    #[test]
+    fn search_respects_filters_and_limits() {
+        let graph = power_graph();
+        let vbus = graph.net_ports("VBUS").unwrap();
+        let gnd = graph.net_ports("GND").unwrap();
+        let r1 = graph.factor_id("R1").unwrap();
+        let r2 = graph.factor_id("R2").unwrap();
+        let resistors_only = |fid: FactorId| fid == r1 || fid == r2;
+
+        let search = graph.search(vbus, resistors_only, None);
+        let goal = search.nearest(gnd).unwrap();
+        assert_eq!(search.distance(goal), Some(2));
+
+        let search = graph.search(vbus, resistors_only, Some(1));
+        assert_eq!(search.nearest(gnd), None);
+
+        let vin = graph.net_ports("VIN").unwrap();
+        let search = graph.search(vin, resistors_only, None);
+        assert!(!search.reached(graph.net_ports("VBUS").unwrap()[0]));
+    }
+
+    #[test]
+    fn cut_components_are_on_every_path() {
+        let graph = power_graph();
+        let vin = graph.net_ports("VIN").unwrap();
+        let gnd = graph.net_ports("GND").unwrap();
+        let out = graph.net_ports("OUT").unwrap();
+
+        // Both capacitors and the divider bridge VBUS to GND, so only the fuse is a cut
+        let cuts = graph.cut_components(vin, gnd, |_| true);
+        assert_eq!(names(&graph, &cuts), ["F1"]);
+
+        let cuts = graph.cut_components(vin, out, |_| true);
+        assert_eq!(names(&graph, &cuts), ["F1"]);
+
+        let r2 = graph.factor_id("R2").unwrap();
+        let cuts = graph.cut_components(vin, out, |fid| fid != r2);
+        assert_eq!(names(&graph, &cuts), ["F1", "R1"]);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::graph::CircuitGraph;
+use crate::graph::{CircuitGraph, FactorId, FactorType};
 use crate::{downcast_frozen_module, lang::module::FrozenModuleValue};
 use allocative::Allocative;
+use fixedbitset::FixedBitSet;
 use starlark::{
     eval::{Arguments, Evaluator},
     starlark_complex_value,
@@ -33,6 +34,49 @@ pub struct PathsCallableGen<V: ValueLifetimeless> {
 
 starlark_complex_value!(pub PathsCallable);
 
+/// ModuleGraph methods answered by a single search of the graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Allocative, Freeze)]
+pub enum GraphQuery {
+    ShortestPath,
+    Reachable,
+    Subgraph,
+    Cuts,
+}
+
+impl GraphQuery {
+    fn name(self) -> &'static str {
+        match self {
+            GraphQuery::ShortestPath => "shortest_path",
+            GraphQuery::Reachable => "reachable",
+            GraphQuery::Subgraph => "subgraph",
+            GraphQuery::Cuts => "cuts",
+        }
+    }
+}
+
+/// GraphQueryCallable for the ModuleGraph search methods
+#[derive(Clone, Debug, Coerce, Trace, ProvidesStaticType, NoSerialize, Allocative, Freeze)]
+#[repr(C)]
+pub struct GraphQueryCallableGen<V: ValueLifetimeless> {
+    pub module: V,
+    #[freeze(identity)]
+    pub graph: Arc<CircuitGraph>,
+    pub query: GraphQuery,
+}
+
+starlark_complex_value!(pub GraphQueryCallable);
+
+/// The ports, components and nets reachable from a starting point
+#[derive(Clone, Debug, Coerce, Trace, ProvidesStaticType, NoSerialize, Allocative, Freeze)]
+#[repr(C)]
+pub struct SubgraphValueGen<V: ValueLifetimeless> {
+    pub ports: Vec<V>,      // List of port tuples
+    pub components: Vec<V>, // List of component objects
+    pub nets: Vec<V>,       // List of net names
+}
+
+starlark_complex_value!(pub SubgraphValue);
+
 /// Path object representing a circuit path with pre-computed data
 #[derive(Clone, Debug, Coerce, Trace, ProvidesStaticType, NoSerialize, Allocative, Freeze)]
 #[repr(C)]
@@ -139,6 +183,205 @@ where
                 };
                 Some(heap.alloc_complex(callable))
             }
+            "shortest_path" => Some(self.create_query_callable(heap, GraphQuery::ShortestPath)),
+            "reachable" => Some(self.create_query_callable(heap, GraphQuery::Reachable)),
+            "subgraph" => Some(self.create_query_callable(heap, GraphQuery::Subgraph)),
+            "cuts" => Some(self.create_query_callable(heap, GraphQuery::Cuts)),
+            _ => None,
+        }
+    }
+}
+
+impl<'v, V: ValueLike<'v>> ModuleGraphValueGen<V> {
+    fn create_query_callable(&self, heap: &'v Heap, query: GraphQuery) -> Value<'v> {
+        let callable = GraphQueryCallableGen {
+            module: self.module.to_value(),
+            graph: self.graph.clone(),
+            query,
+        };
+        heap.alloc_complex(callable)
+    }
+}
+
+// GraphQueryCallable implementation
+impl<V: ValueLifetimeless> std::fmt::Display for GraphQueryCallableGen<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.query.name())
+    }
+}
+
+impl<'v, V: ValueLike<'v>> GraphQueryCallableGen<V> {
+    /// The component factors `through` matches, or None to allow every component
+    fn traversable_components(
+        &self,
+        through: Option<Value<'v>>,
+        components: &starlark::collections::SmallMap<String, Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> Option<FixedBitSet> {
+        let matcher = through?;
+        let mut allowed = FixedBitSet::with_capacity(self.graph.factor_count());
+        for fid in (0..self.graph.factor_count()).map(|i| FactorId(i as u32)) {
+            if let FactorType::Component(comp_path) = self.graph.factor_type(fid) {
+                // A component is traversable if the matcher succeeds (no error) on it
+                let matched = components.get(comp_path).is_some_and(|component| {
+                    eval.eval_function(matcher, &[*component], &[]).is_ok()
+                });
+                allowed.set(fid.0 as usize, matched);
+            }
+        }
+        Some(allowed)
+    }
+}
+
+#[starlark_value(type = "builtin_function_or_method")]
+impl<'v, V: ValueLike<'v>> StarlarkValue<'v> for GraphQueryCallableGen<V>
+where
+    Self: ProvidesStaticType<'v>,
+{
+    fn invoke(
+        &self,
+        _me: Value<'v>,
+        args: &Arguments<'v, '_>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> starlark::Result<Value<'v>> {
+        let heap = eval.heap();
+        let method = self.query.name();
+
+        // Extract arguments from named parameters
+        let args_map = args.names_map()?;
+        let arg = |name: &str| args_map.get(&heap.alloc_str(name)).copied();
+        let start = arg("start").ok_or_else(|| {
+            starlark::Error::new_other(anyhow::anyhow!("{}() requires 'start' argument", method))
+        })?;
+        let through = arg("through").filter(|v| !v.is_none());
+        let max_components = arg("max_components")
+            .and_then(|v| v.unpack_i32())
+            .map(|n| n.max(0) as u32);
+
+        // Resolve labels to the ports the search starts and ends at
+        let starts = self.graph.resolve_label_to_ports(start, heap)?;
+        let goals = match self.query {
+            GraphQuery::Subgraph => Vec::new(),
+            _ => {
+                let end = arg("end").ok_or_else(|| {
+                    starlark::Error::new_other(anyhow::anyhow!(
+                        "{}() requires 'end' argument",
+                        method
+                    ))
+                })?;
+                self.graph.resolve_label_to_ports(end, heap)?
+            }
+        };
+
+        let module_ref = downcast_frozen_module!(self.module);
+        let components = module_ref.collect_components("");
+        let allowed = self.traversable_components(through, &components, eval);
+        let traversable =
+            |fid: FactorId| allowed.as_ref().is_none_or(|a| a.contains(fid.0 as usize));
+
+        match self.query {
+            GraphQuery::ShortestPath => {
+                let search = self.graph.search(&starts, traversable, max_components);
+                match search.nearest(&goals).and_then(|goal| search.path_to(goal)) {
+                    Some((ports, factors)) => {
+                        self.graph
+                            .create_path_value(&ports, &factors, &components, heap)
+                    }
+                    None => Ok(Value::new_none()),
+                }
+            }
+            GraphQuery::Reachable => {
+                let search = self.graph.search(&starts, traversable, max_components);
+                Ok(heap.alloc(search.nearest(&goals).is_some()))
+            }
+            GraphQuery::Subgraph => {
+                let search = self.graph.search(&starts, traversable, max_components);
+
+                let mut port_paths: Vec<_> = search
+                    .reached_ports()
+                    .filter_map(|port| self.graph.port_path(port))
+                    .filter(|path| path.component.as_str() != "<external>")
+                    .map(|path| (path.component.to_string(), path.pin.clone()))
+                    .collect();
+                port_paths.sort();
+                let ports = port_paths
+                    .iter()
+                    .map(|(component, pin)| {
+                        heap.alloc((heap.alloc_str(component), heap.alloc_str(pin)))
+                    })
+                    .collect();
+
+                let mut component_paths = Vec::new();
+                let mut net_names = Vec::new();
+                for fid in search.visited_factors() {
+                    match self.graph.factor_type(fid) {
+                        FactorType::Component(comp_path) => component_paths.push(comp_path),
+                        FactorType::Net(net_name) => net_names.push(net_name),
+                    }
+                }
+                component_paths.sort();
+                net_names.sort();
+
+                let subgraph = SubgraphValueGen {
+                    ports,
+                    components: component_paths
+                        .into_iter()
+                        .filter_map(|comp_path| components.get(comp_path).copied())
+                        .collect(),
+                    nets: net_names
+                        .into_iter()
+                        .map(|net_name| heap.alloc_str(net_name).to_value())
+                        .collect(),
+                };
+                Ok(heap.alloc_complex(subgraph))
+            }
+            GraphQuery::Cuts => {
+                let cuts: Vec<Value> = self
+                    .graph
+                    .cut_components(&starts, &goals, traversable)
+                    .into_iter()
+                    .filter_map(|fid| match self.graph.factor_type(fid) {
+                        FactorType::Component(comp_path) => components.get(comp_path).copied(),
+                        FactorType::Net(_) => None,
+                    })
+                    .collect();
+                Ok(heap.alloc(cuts))
+            }
+        }
+    }
+}
+
+// SubgraphValue implementation
+impl<V: ValueLifetimeless> std::fmt::Display for SubgraphValueGen<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Subgraph({} components, {} nets)",
+            self.components.len(),
+            self.nets.len()
+        )
+    }
+}
+
+#[starlark_value(type = "Subgraph")]
+impl<'v, V: ValueLike<'v>> StarlarkValue<'v> for SubgraphValueGen<V>
+where
+    Self: ProvidesStaticType<'v>,
+{
+    fn get_attr(&self, attr: &str, heap: &'v Heap) -> Option<Value<'v>> {
+        match attr {
+            "ports" => {
+                Some(heap.alloc(self.ports.iter().map(|v| v.to_value()).collect::<Vec<_>>()))
+            }
+            "components" => Some(
+                heap.alloc(
+                    self.components
+                        .iter()
+                        .map(|v| v.to_value())
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            "nets" => Some(heap.alloc(self.nets.iter().map(|v| v.to_value()).collect::<Vec<_>>())),
             _ => None,
         }
     }
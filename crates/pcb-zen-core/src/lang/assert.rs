@@ -3,6 +3,7 @@ use starlark::eval::Evaluator;
 use starlark::starlark_module;
 use starlark::values::Value;
 
+use crate::lang::error::CheckFailedError;
 use crate::lang::input::InputValue;
 
 /// Miscellaneous built-in Starlark helpers used by Diode.
@@ -27,7 +28,7 @@ pub(crate) fn assert_globals(builder: &mut GlobalsBuilder) {
         if cond {
             Ok(Value::new_none())
         } else {
-            Err(anyhow::anyhow!(msg))
+            Err(CheckFailedError { message: msg }.into())
         }
     }
 
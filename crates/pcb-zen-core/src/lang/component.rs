@@ -15,7 +15,12 @@ use starlark::{
 };
 
 use crate::{
-    lang::{evaluator_ext::EvaluatorExt, spice_model::SpiceModelValue},
+    lang::{
+        error::UnknownPinError,
+        evaluator_ext::EvaluatorExt,
+        requirements::{parse_requirements, Requirement},
+        spice_model::SpiceModelValue,
+    },
     FrozenSpiceModelValue,
 };
 
@@ -37,6 +42,11 @@ pub struct ComponentValueGen<V> {
     source_path: String,
     symbol: V,
     spice_model: Option<V>,
+    /// Datasheet requirements checked against every design the component is part of
+    #[allocative(skip)]
+    #[trace(unsafe_ignore)]
+    #[freeze(identity)]
+    requirements: Vec<Requirement>,
 }
 
 impl<V: std::fmt::Debug> std::fmt::Debug for ComponentValueGen<V> {
@@ -205,6 +215,35 @@ impl<'v, V: ValueLike<'v>> std::fmt::Display for ComponentValueGen<V> {
 }
 
 impl<'v, V: ValueLike<'v>> ComponentValueGen<V> {
+    /// A component without a SPICE model or requirements, e.g. one restored from the
+    /// module cache.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        name: String,
+        mpn: Option<String>,
+        ctype: Option<String>,
+        footprint: String,
+        prefix: String,
+        connections: SmallMap<String, V>,
+        properties: SmallMap<String, V>,
+        source_path: String,
+        symbol: V,
+    ) -> Self {
+        Self {
+            name,
+            mpn,
+            ctype,
+            footprint,
+            prefix,
+            connections,
+            properties,
+            source_path,
+            symbol,
+            spice_model: None,
+            requirements: Vec::new(),
+        }
+    }
+
     pub fn mpn(&self) -> Option<&str> {
         self.mpn.as_deref()
     }
@@ -232,6 +271,14 @@ impl<'v, V: ValueLike<'v>> ComponentValueGen<V> {
         &self.connections
     }
 
+    pub(crate) fn properties_mut(&mut self) -> &mut SmallMap<String, V> {
+        &mut self.properties
+    }
+
+    pub(crate) fn connections_mut(&mut self) -> &mut SmallMap<String, V> {
+        &mut self.connections
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -247,6 +294,33 @@ impl<'v, V: ValueLike<'v>> ComponentValueGen<V> {
     pub fn spice_model(&self) -> Option<&V> {
         self.spice_model.as_ref()
     }
+
+    pub fn requirements(&self) -> &[Requirement] {
+        &self.requirements
+    }
+}
+
+/// Parse the `requirements` of a component, checking they name pins it has
+fn component_requirements<'v>(
+    value: Option<Value<'v>>,
+    connections: &SmallMap<String, Value<'v>>,
+    eval: &Evaluator<'v, '_, '_>,
+) -> starlark::Result<Vec<Requirement>> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+    let requirements = parse_requirements(value, eval).map_err(starlark::Error::new_other)?;
+    if let Some(requirement) = requirements
+        .iter()
+        .find(|requirement| !connections.contains_key(&requirement.pin))
+    {
+        return Err(starlark::Error::new_other(anyhow!(format!(
+            "Requirement on unknown pin '{}' (expected one of: {})",
+            requirement.pin,
+            connections.keys().cloned().collect::<Vec<_>>().join(", ")
+        ))));
+    }
+    Ok(requirements)
 }
 
 /// ComponentFactory is a value that represents a factory for a component.
@@ -285,6 +359,7 @@ where
                 ("type", ParametersSpecParam::<Value<'_>>::Optional),
                 ("properties", ParametersSpecParam::<Value<'_>>::Optional),
                 ("spice_model", ParametersSpecParam::<Value<'_>>::Optional),
+                ("requirements", ParametersSpecParam::<Value<'_>>::Optional),
             ],
         );
 
@@ -341,6 +416,7 @@ where
             let ctype: Option<Value> = param_parser.next_opt()?;
             let properties_val: Value = param_parser.next_opt()?.unwrap_or_default();
             let spice_model_val: Option<Value> = param_parser.next_opt()?;
+            let requirements_val: Option<Value> = param_parser.next_opt()?;
 
             // Get a SymbolValue from the pin_defs or symbol_val
             let final_symbol: SymbolValue = if let Some(pin_defs) = pin_defs_val {
@@ -381,6 +457,7 @@ where
                             pad_to_signal, // Use pin mappings from pin_defs
                             source_path: symbol_value.source_path.clone(),
                             raw_sexp: symbol_value.raw_sexp.clone(),
+                            units: SmallMap::new(), // pin_defs replace the symbol's signals
                         }
                     } else {
                         // symbol is not a Symbol type, just use pin_defs
@@ -389,6 +466,7 @@ where
                             pad_to_signal,
                             source_path: None,
                             raw_sexp: None,
+                            units: SmallMap::new(),
                         }
                     }
                 } else {
@@ -398,6 +476,7 @@ where
                         pad_to_signal,
                         source_path: None,
                         raw_sexp: None,
+                        units: SmallMap::new(),
                     }
                 }
             } else if let Some(symbol) = &symbol_val {
@@ -432,11 +511,12 @@ where
                     .to_owned();
 
                 if !final_symbol.signal_names().any(|n| n == signal_name) {
-                    return Err(starlark::Error::new_other(anyhow!(format!(
-                        "Unknown pin name '{}' (expected one of: {})",
-                        signal_name,
-                        final_symbol.signal_names().collect::<Vec<_>>().join(", ")
-                    ))));
+                    return Err(starlark::Error::new_other(anyhow::Error::new(
+                        UnknownPinError {
+                            pin: signal_name,
+                            expected: final_symbol.signal_names().map(str::to_owned).collect(),
+                        },
+                    )));
                 }
 
                 if v_val.get_type() != "Net" {
@@ -508,6 +588,8 @@ where
                 }
             }
 
+            let requirements = component_requirements(requirements_val, &connections, eval_ctx)?;
+
             let component = eval_ctx.heap().alloc_complex(ComponentValue {
                 name,
                 mpn: mpn.and_then(|v| v.unpack_str().map(|s| s.to_owned())),
@@ -519,6 +601,7 @@ where
                 source_path: eval_ctx.source_path().unwrap_or_default(),
                 symbol: eval_ctx.heap().alloc_complex(final_symbol),
                 spice_model: spice_model_val,
+                requirements,
             });
 
             Ok(component)
@@ -633,6 +716,7 @@ where
                 ("type", ParametersSpecParam::<Value<'_>>::Optional),
                 ("properties", ParametersSpecParam::<Value<'_>>::Optional),
                 ("spice_model", ParametersSpecParam::<Value<'_>>::Optional),
+                ("requirements", ParametersSpecParam::<Value<'_>>::Optional),
             ],
         );
 
@@ -661,11 +745,12 @@ where
                     .to_owned();
 
                 if !self.symbol.signal_names().any(|n| n == pin_name) {
-                    return Err(starlark::Error::new_other(anyhow!(format!(
-                        "Unknown pin name '{}' (expected one of: {})",
-                        pin_name,
-                        self.symbol.signal_names().collect::<Vec<_>>().join(", ")
-                    ))));
+                    return Err(starlark::Error::new_other(anyhow::Error::new(
+                        UnknownPinError {
+                            pin: pin_name,
+                            expected: self.symbol.signal_names().map(str::to_owned).collect(),
+                        },
+                    )));
                 }
 
                 if v_val.get_type() != "Net" {
@@ -758,6 +843,7 @@ where
 
             let properties_val: Value = param_parser.next_opt()?.unwrap_or_default();
             let spice_model_val: Option<Value> = param_parser.next_opt()?;
+            let requirements_val: Option<Value> = param_parser.next_opt()?;
             let mut properties_map: SmallMap<String, Value<'v>> = SmallMap::new();
 
             // Start with default_properties from factory.
@@ -791,6 +877,8 @@ where
                 }
             }
 
+            let requirements = component_requirements(requirements_val, &connections, eval_ctx)?;
+
             let component = eval_ctx.heap().alloc_complex(ComponentValue {
                 name,
                 mpn: final_mpn,
@@ -802,6 +890,7 @@ where
                 source_path: eval_ctx.source_path().unwrap_or_default(),
                 symbol: eval_ctx.heap().alloc_complex(self.symbol.clone()),
                 spice_model: spice_model_val,
+                requirements,
             });
 
             Ok(component)
@@ -0,0 +1,257 @@
+//! Coverage of `.zen` files collected while evaluating: which lines ran, how often each
+//! module file was instantiated and which values each `config()` and `io()` took.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use starlark::codemap::{CodeMap, FileSpan, FileSpanRef, Span};
+use starlark::eval::{BeforeStmtFuncDyn, Evaluator};
+use starlark::syntax::ast::{AstLiteral, AstStmt, ExprP, StmtP};
+use starlark::syntax::AstModule;
+use starlark::values::{Heap, Value};
+
+use crate::lang::debugger::collect_top_level;
+use crate::lang::type_info::TypeInfo;
+
+/// How a file is being evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EvalKind {
+    /// Through `load()`
+    Load,
+    /// As a module instance, including the root file
+    Instance,
+    /// The placeholder evaluation that introspects a module's signature; its statements
+    /// do not count as covered
+    Introspection,
+}
+
+/// Coverage of one file
+#[derive(Debug, Default, Clone)]
+pub struct FileCoverage {
+    /// Hits per executable line (1-based)
+    pub lines: BTreeMap<u32, u64>,
+    /// Whether the file was used as a module
+    pub is_module: bool,
+    /// Times the file was instantiated as a module
+    pub instances: u64,
+    /// Values taken by each `config()` and `io()`, by parameter name
+    pub params: BTreeMap<String, ParamCoverage>,
+}
+
+impl FileCoverage {
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+}
+
+/// Values taken by one `config()` or `io()`
+#[derive(Debug, Default, Clone)]
+pub struct ParamCoverage {
+    /// Line of the `config()`/`io()` call (1-based)
+    pub line: u32,
+    /// Every value an enum or bool parameter can take
+    pub domain: Vec<String>,
+    /// Times each value was taken
+    pub taken: BTreeMap<String, u64>,
+}
+
+impl ParamCoverage {
+    /// Values of the domain that were never taken
+    pub fn missed(&self) -> Vec<&str> {
+        self.domain
+            .iter()
+            .filter(|value| !self.taken.contains_key(*value))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Coverage shared by an evaluation and every nested one, possibly across threads.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    files: Mutex<BTreeMap<String, FileCoverage>>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the executable lines of `ast` and, unless introspecting, count the
+    /// statements `eval` runs
+    pub(crate) fn attach(
+        self: &Arc<Self>,
+        eval: &mut Evaluator,
+        path: &Path,
+        ast: &AstModule,
+        kind: EvalKind,
+    ) {
+        let file = path.to_string_lossy().into_owned();
+        let mut lines = BTreeSet::new();
+        collect_lines(ast.codemap(), ast.statement(), &mut lines);
+        {
+            let mut files = self.files.lock().unwrap();
+            let coverage = files.entry(file.clone()).or_default();
+            for line in lines {
+                coverage.lines.entry(line).or_default();
+            }
+            coverage.is_module |= kind != EvalKind::Load;
+            if kind == EvalKind::Instance {
+                coverage.instances += 1;
+            }
+        }
+        if kind == EvalKind::Introspection {
+            return;
+        }
+
+        let mut top_level = HashSet::new();
+        collect_top_level(ast.statement(), &mut top_level);
+        let hook: Box<dyn BeforeStmtFuncDyn> = Box::new(StatementHook {
+            coverage: self.clone(),
+            file,
+            top_level,
+            last: None,
+        });
+        eval.before_stmt_for_dap(hook.into());
+    }
+
+    /// Record that the `config()`/`io()` called `name` at `file:line` took `value`
+    pub(crate) fn record_param<'v>(
+        &self,
+        file: &str,
+        line: u32,
+        name: &str,
+        typ: Value<'v>,
+        value: Value<'v>,
+        heap: &'v Heap,
+    ) {
+        let (domain, label) = match TypeInfo::from_value(typ) {
+            TypeInfo::Enum { variants, .. } => {
+                // Enum values are labelled by their variant, like the domain
+                let label = value
+                    .get_attr("value", heap)
+                    .ok()
+                    .flatten()
+                    .and_then(|v| v.unpack_str().map(str::to_string))
+                    .unwrap_or_else(|| value.to_repr());
+                (variants, label)
+            }
+            TypeInfo::Bool => (vec!["False".into(), "True".into()], value.to_repr()),
+            _ => (Vec::new(), value.to_repr()),
+        };
+
+        let mut files = self.files.lock().unwrap();
+        let param = files
+            .entry(file.to_string())
+            .or_default()
+            .params
+            .entry(name.to_string())
+            .or_default();
+        param.line = line;
+        param.domain = domain;
+        *param.taken.entry(label).or_default() += 1;
+    }
+
+    fn hit(&self, file: &str, line: u32) {
+        let mut files = self.files.lock().unwrap();
+        *files
+            .entry(file.to_string())
+            .or_default()
+            .lines
+            .entry(line)
+            .or_default() += 1;
+    }
+
+    /// The coverage collected so far, by file path
+    pub fn files(&self) -> BTreeMap<String, FileCoverage> {
+        self.files.lock().unwrap().clone()
+    }
+
+    /// Render the coverage as an LCOV tracefile. Module instantiations are reported as
+    /// a function named after the file, and each value of an enum or bool parameter as
+    /// a branch of its `config()`/`io()` line.
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+        for (path, file) in self.files() {
+            let _ = writeln!(out, "TN:");
+            let _ = writeln!(out, "SF:{path}");
+
+            if file.is_module {
+                let name = Path::new(&path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let _ = writeln!(out, "FN:1,{name}");
+                let _ = writeln!(out, "FNDA:{},{name}", file.instances);
+                let _ = writeln!(out, "FNF:1");
+                let _ = writeln!(out, "FNH:{}", u8::from(file.instances > 0));
+            }
+
+            let (mut found, mut hit) = (0, 0);
+            for param in file.params.values().filter(|p| !p.domain.is_empty()) {
+                for (branch, value) in param.domain.iter().enumerate() {
+                    let taken = param.taken.get(value).copied().unwrap_or(0);
+                    let _ = writeln!(out, "BRDA:{},0,{branch},{taken}", param.line);
+                    found += 1;
+                    hit += usize::from(taken > 0);
+                }
+            }
+            if found > 0 {
+                let _ = writeln!(out, "BRF:{found}");
+                let _ = writeln!(out, "BRH:{hit}");
+            }
+
+            for (line, hits) in &file.lines {
+                let _ = writeln!(out, "DA:{line},{hits}");
+            }
+            let _ = writeln!(out, "LF:{}", file.lines.len());
+            let _ = writeln!(out, "LH:{}", file.lines_hit());
+            let _ = writeln!(out, "end_of_record");
+        }
+        out
+    }
+}
+
+/// Lines (1-based) that start a statement Starlark runs. Loads, `pass` and docstrings
+/// never reach the statement hook, so they are not counted.
+fn collect_lines(codemap: &CodeMap, stmt: &AstStmt, lines: &mut BTreeSet<u32>) {
+    match &stmt.node {
+        StmtP::Statements(_) | StmtP::Load(_) | StmtP::Pass => {}
+        StmtP::Expression(expr) if matches!(expr.node, ExprP::Literal(AstLiteral::String(_))) => {}
+        _ => {
+            lines.insert(codemap.resolve_span(stmt.span).begin.line as u32 + 1);
+        }
+    }
+    stmt.node
+        .visit_stmt(|child| collect_lines(codemap, child, lines));
+}
+
+struct StatementHook {
+    coverage: Arc<Coverage>,
+    file: String,
+    top_level: HashSet<Span>,
+    last: Option<FileSpan>,
+}
+
+impl<'a, 'e: 'a> BeforeStmtFuncDyn<'a, 'e> for StatementHook {
+    fn call<'v>(
+        &mut self,
+        span: FileSpanRef,
+        eval: &mut Evaluator<'v, 'a, 'e>,
+    ) -> starlark::Result<()> {
+        // Top-level statements reach the hook twice in a row; count them once
+        let location = span.to_file_span();
+        let repeated = span.filename() == self.file
+            && self.top_level.contains(&span.span)
+            && eval.call_stack_count() == 0
+            && self.last.as_ref() == Some(&location);
+        self.last = Some(location);
+        if !repeated {
+            let line = span.resolve_span().begin.line as u32 + 1;
+            self.coverage.hit(span.filename(), line);
+        }
+        Ok(())
+    }
+}
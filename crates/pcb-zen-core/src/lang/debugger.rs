@@ -0,0 +1,724 @@
+//! Statement-level debugger shared by every evaluator of a Zen evaluation.
+//!
+//! Starlark's own DAP hook can only be attached to a single `Evaluator`, but a Zen design is
+//! evaluated by a tree of them: one per `load()`ed file and one per `Module()` instance. A
+//! [`Debugger`] is installed on an [`EvalContext`](crate::EvalContext) and propagated to every
+//! child context, so breakpoints and stepping work across all of them.
+//!
+//! Evaluation runs on its own thread. When a statement hits a breakpoint (or a step finishes)
+//! the evaluation thread blocks and serves requests from the debug adapter (stack traces,
+//! variables, expression evaluation) until it is told to resume.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use debugserver_types::{Scope, Source, StackFrame, Variable};
+use starlark::codemap::{FileSpan, FileSpanRef, Span};
+use starlark::debug::{InspectVariableInfo, PathSegment, StepKind};
+use starlark::eval::{BeforeStmtFuncDyn, Evaluator};
+use starlark::syntax::ast::{AstStmt, StmtP};
+use starlark::syntax::{AstModule, Dialect};
+use starlark::values::{Heap, Value};
+
+use crate::lang::component::ComponentValue;
+use crate::lang::evaluator_ext::EvaluatorExt;
+use crate::lang::module::ModuleValue;
+
+/// Variables reference of the local variables scope.
+const LOCALS_REF: i64 = 1;
+/// Variables reference of the current module's `io()`/`config()` values.
+const INPUTS_REF: i64 = 2;
+/// Variables reference of the nets introduced by the current module.
+const NETS_REF: i64 = 3;
+/// Variables reference of the components and submodules of the current module.
+const COMPONENTS_REF: i64 = 4;
+/// First variables reference handed out for expandable values.
+const FIRST_DYNAMIC_REF: i64 = 1000;
+
+/// Why the evaluation stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+impl StopReason {
+    /// The `reason` string of the DAP `stopped` event.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopReason::Entry => "entry",
+            StopReason::Breakpoint => "breakpoint",
+            StopReason::Step => "step",
+        }
+    }
+}
+
+/// Receives notifications from the evaluation thread.
+pub trait DebugClient: Send + Sync {
+    /// Evaluation is paused and ready to serve requests.
+    fn stopped(&self, reason: StopReason);
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Next {
+    Continue,
+    RemainPaused,
+    Step(StepKind),
+}
+
+type Command = Box<dyn FnOnce(FileSpanRef, &mut Evaluator, &Debugger) -> Next + Send>;
+
+/// A pending step request, recorded at the position it was issued from.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    kind: StepKind,
+    depth: usize,
+    stack: usize,
+}
+
+impl Step {
+    fn should_stop(&self, depth: usize, stack: usize) -> bool {
+        let here = (depth, stack);
+        let from = (self.depth, self.stack);
+        match self.kind {
+            StepKind::Into => true,
+            StepKind::Over => here <= from,
+            StepKind::Out => here < from,
+        }
+    }
+}
+
+/// One evaluator in the stack of nested module/file evaluations.
+struct EvaluatorFrame {
+    name: String,
+    /// The statement this evaluator last executed; for enclosing evaluators this is the
+    /// `load()` or `Module()` call that started the nested evaluation.
+    location: Option<FileSpan>,
+    /// Statements that run once at module level. Starlark calls the statement hook twice
+    /// for each of them (once for a possible GC), so the second call must be ignored.
+    top_level: HashSet<Span>,
+}
+
+/// Root of an expandable variable; resolved against the paused evaluator on demand.
+#[derive(Debug, Clone)]
+enum VariableRoot {
+    Local(String),
+    Input(String),
+    Child(usize),
+    Expr(String),
+}
+
+#[derive(Debug, Clone)]
+struct VariablePath {
+    root: VariableRoot,
+    path: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone)]
+struct Breakpoint {
+    condition: Option<String>,
+}
+
+pub struct Debugger {
+    client: Box<dyn DebugClient>,
+    /// Breakpoints keyed by canonical file path and 0-based line.
+    breakpoints: Mutex<HashMap<PathBuf, HashMap<usize, Breakpoint>>>,
+    /// Canonical paths of the filenames seen in code maps.
+    canonical_paths: Mutex<HashMap<String, PathBuf>>,
+    sender: Mutex<Sender<Command>>,
+    receiver: Mutex<Receiver<Command>>,
+    step: Mutex<Option<Step>>,
+    frames: Mutex<Vec<EvaluatorFrame>>,
+    stop_on_entry: AtomicBool,
+    paused: AtomicBool,
+    /// Set while evaluating expressions on behalf of the client (>= 1 disables breakpoints).
+    evaluating: AtomicUsize,
+    variables: Mutex<Vec<VariablePath>>,
+}
+
+impl std::fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Debugger").finish_non_exhaustive()
+    }
+}
+
+impl Debugger {
+    pub fn new(client: Box<dyn DebugClient>) -> Arc<Self> {
+        let (sender, receiver) = channel();
+        Arc::new(Self {
+            client,
+            breakpoints: Default::default(),
+            canonical_paths: Default::default(),
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+            step: Mutex::new(None),
+            frames: Default::default(),
+            stop_on_entry: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            evaluating: AtomicUsize::new(0),
+            variables: Default::default(),
+        })
+    }
+
+    /// Pause on the first statement that is executed.
+    pub fn stop_on_entry(&self) {
+        self.stop_on_entry.store(true, Ordering::SeqCst);
+    }
+
+    /// Replace the breakpoints of `path`, whose source is `contents`. `lines` are 1-based.
+    ///
+    /// Returns, for each requested line, whether a statement starts on it.
+    pub fn set_breakpoints(
+        &self,
+        path: &Path,
+        contents: &str,
+        lines: &[(usize, Option<String>)],
+    ) -> Vec<bool> {
+        let statement_lines: Vec<usize> =
+            AstModule::parse(&path.to_string_lossy(), contents.to_owned(), &dialect())
+                .map(|ast| {
+                    ast.stmt_locations()
+                        .iter()
+                        .map(|span| span.resolve_span().begin.line)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+        let mut resolved = HashMap::new();
+        let verified = lines
+            .iter()
+            .map(|(line, condition)| {
+                let line = line.saturating_sub(1);
+                let ok = statement_lines.contains(&line);
+                if ok {
+                    resolved.insert(
+                        line,
+                        Breakpoint {
+                            condition: condition.clone(),
+                        },
+                    );
+                }
+                ok
+            })
+            .collect();
+
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+        if resolved.is_empty() {
+            breakpoints.remove(path);
+        } else {
+            breakpoints.insert(path.to_path_buf(), resolved);
+        }
+        verified
+    }
+
+    /// Resume evaluation.
+    pub fn continue_(&self) -> anyhow::Result<()> {
+        self.resume(Next::Continue)
+    }
+
+    /// Resume evaluation until the step `kind` completes.
+    pub fn step(&self, kind: StepKind) -> anyhow::Result<()> {
+        self.resume(Next::Step(kind))
+    }
+
+    /// Stack frames of the paused evaluation, innermost first. Enclosing module evaluations
+    /// appear as frames located at the statement that started the nested evaluation.
+    pub fn stack_trace(&self) -> anyhow::Result<Vec<StackFrame>> {
+        self.with_paused(|span, eval, debugger| {
+            let frames = debugger.frames.lock().unwrap();
+            let mut result = Vec::new();
+
+            // Starlark records where each function was *called* from; walk them from the top
+            // so every frame is reported at the location it is currently executing.
+            let mut location = Some(span.to_file_span());
+            for frame in eval.call_stack().into_frames().iter().rev() {
+                result.push(stack_frame(result.len(), &frame.name, location.as_ref()));
+                location = frame.location.clone();
+            }
+            let module_name = frames.last().map(|f| f.name.as_str()).unwrap_or("<root>");
+            result.push(stack_frame(result.len(), module_name, location.as_ref()));
+
+            for frame in frames.iter().rev().skip(1) {
+                result.push(stack_frame(
+                    result.len(),
+                    &frame.name,
+                    frame.location.as_ref(),
+                ));
+            }
+            result
+        })
+    }
+
+    /// Variable scopes of the paused evaluation.
+    pub fn scopes(&self) -> anyhow::Result<Vec<Scope>> {
+        self.with_paused(|_, eval, _| {
+            let inputs = module_inputs(eval).len();
+            let (nets, children) = eval
+                .context_value()
+                .map(|ctx| {
+                    let module = ctx.module();
+                    (module.introduced_nets().len(), module.children().len())
+                })
+                .unwrap_or_default();
+            vec![
+                scope("Locals", LOCALS_REF, eval.local_variables().len()),
+                scope("Inputs", INPUTS_REF, inputs),
+                scope("Nets", NETS_REF, nets),
+                scope("Components", COMPONENTS_REF, children),
+            ]
+        })
+    }
+
+    /// Children of the scope or expandable variable identified by `reference`.
+    pub fn variables(&self, reference: i64) -> anyhow::Result<Vec<Variable>> {
+        let target = match reference {
+            LOCALS_REF | INPUTS_REF | NETS_REF | COMPONENTS_REF => None,
+            _ => Some(
+                usize::try_from(reference - FIRST_DYNAMIC_REF)
+                    .ok()
+                    .and_then(|index| self.variables.lock().unwrap().get(index).cloned())
+                    .ok_or_else(|| anyhow::anyhow!("Unknown variables reference {reference}"))?,
+            ),
+        };
+
+        self.with_paused(move |_, eval, debugger| -> anyhow::Result<Vec<Variable>> {
+            match (reference, target) {
+                (LOCALS_REF, _) => Ok(eval
+                    .local_variables()
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let root = VariableRoot::Local(name.clone());
+                        debugger.variable(name, value, root, Vec::new())
+                    })
+                    .collect()),
+                (INPUTS_REF, _) => Ok(module_inputs(eval)
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let root = VariableRoot::Input(name.clone());
+                        debugger.variable(name, value, root, Vec::new())
+                    })
+                    .collect()),
+                (NETS_REF, _) => Ok(module_nets(eval)
+                    .into_iter()
+                    .map(|name| Variable {
+                        name: name.clone(),
+                        value: format!("Net({name:?})"),
+                        type_: Some("Net".to_owned()),
+                        evaluate_name: None,
+                        indexed_variables: None,
+                        named_variables: None,
+                        presentation_hint: None,
+                        variables_reference: 0,
+                    })
+                    .collect()),
+                (COMPONENTS_REF, _) => Ok(module_children(eval)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, value)| {
+                        let name = child_name(value).unwrap_or_else(|| index.to_string());
+                        debugger.variable(name, value, VariableRoot::Child(index), Vec::new())
+                    })
+                    .collect()),
+                (_, Some(target)) => {
+                    let value = debugger.resolve(eval, &target)?;
+                    let heap = eval.heap();
+                    let info = InspectVariableInfo::try_from_value(value, heap)
+                        .map_err(|e| anyhow::anyhow!("{e}"))?;
+                    Ok(info
+                        .sub_values
+                        .into_iter()
+                        .map(|sub| {
+                            let child =
+                                sub_value(value, &sub.name, heap).unwrap_or_else(Value::new_none);
+                            let mut path = target.path.clone();
+                            path.push(sub.name.clone());
+                            debugger.variable(
+                                sub.name.to_string(),
+                                child,
+                                target.root.clone(),
+                                path,
+                            )
+                        })
+                        .collect())
+                }
+                _ => Ok(Vec::new()),
+            }
+        })?
+    }
+
+    /// Evaluate `expression` in the paused frame.
+    pub fn evaluate(&self, expression: &str) -> anyhow::Result<Variable> {
+        let expression = expression.to_owned();
+        self.with_paused(move |_, eval, debugger| -> anyhow::Result<Variable> {
+            let value = debugger.evaluate_expr(eval, &expression)?;
+            let root = VariableRoot::Expr(expression.clone());
+            Ok(debugger.variable(expression, value, root, Vec::new()))
+        })?
+    }
+
+    /// Attach this debugger to `eval`, which evaluates `ast` as a file or module instance
+    /// called `name`. The returned guard must be kept alive for as long as `eval` runs.
+    pub(crate) fn attach(
+        self: &Arc<Self>,
+        eval: &mut Evaluator,
+        name: impl Into<String>,
+        ast: &AstModule,
+    ) -> AttachGuard {
+        let mut top_level = HashSet::new();
+        collect_top_level(ast.statement(), &mut top_level);
+        let depth = {
+            let mut frames = self.frames.lock().unwrap();
+            frames.push(EvaluatorFrame {
+                name: name.into(),
+                location: None,
+                top_level,
+            });
+            frames.len() - 1
+        };
+        let hook: Box<dyn BeforeStmtFuncDyn> = Box::new(StatementHook {
+            debugger: self.clone(),
+            depth,
+        });
+        eval.before_stmt_for_dap(hook.into());
+        AttachGuard {
+            debugger: self.clone(),
+        }
+    }
+
+    fn resume(&self, next: Next) -> anyhow::Result<()> {
+        self.send(Box::new(move |_, _, _| next))
+    }
+
+    fn send(&self, command: Command) -> anyhow::Result<()> {
+        if !self.paused.load(Ordering::SeqCst) {
+            anyhow::bail!("Evaluation is not paused");
+        }
+        self.sender
+            .lock()
+            .unwrap()
+            .send(command)
+            .map_err(|_| anyhow::anyhow!("Evaluation has finished"))
+    }
+
+    fn with_paused<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(FileSpanRef, &mut Evaluator, &Debugger) -> T + Send + 'static,
+    ) -> anyhow::Result<T> {
+        let (sender, receiver) = channel();
+        self.send(Box::new(move |span, eval, debugger| {
+            let _ = sender.send(f(span, eval, debugger));
+            Next::RemainPaused
+        }))?;
+        receiver
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Evaluation has finished"))
+    }
+
+    fn variable(
+        &self,
+        name: String,
+        value: Value,
+        root: VariableRoot,
+        path: Vec<PathSegment>,
+    ) -> Variable {
+        let mut variable = starlark::debug::Variable::from_value(PathSegment::Attr(name), value);
+        let has_children = variable.has_children;
+        if matches!(root, VariableRoot::Child(_)) && path.is_empty() {
+            variable.value = child_summary(value).unwrap_or(variable.value);
+        }
+        let mut dap = variable.to_dap();
+        if has_children {
+            let mut variables = self.variables.lock().unwrap();
+            variables.push(VariablePath { root, path });
+            dap.variables_reference = FIRST_DYNAMIC_REF + variables.len() as i64 - 1;
+        }
+        dap
+    }
+
+    fn resolve<'v>(
+        &self,
+        eval: &mut Evaluator<'v, '_, '_>,
+        target: &VariablePath,
+    ) -> anyhow::Result<Value<'v>> {
+        let mut value = match &target.root {
+            VariableRoot::Local(name) => eval.local_variables().get(name).copied(),
+            VariableRoot::Input(name) => module_inputs(eval)
+                .into_iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v),
+            VariableRoot::Child(index) => module_children(eval).get(*index).copied(),
+            VariableRoot::Expr(expr) => Some(self.evaluate_expr(eval, expr)?),
+        }
+        .ok_or_else(|| anyhow::anyhow!("Variable is no longer available"))?;
+
+        for segment in &target.path {
+            value = sub_value(value, segment, eval.heap())
+                .ok_or_else(|| anyhow::anyhow!("Variable is no longer available"))?;
+        }
+        Ok(value)
+    }
+
+    fn evaluate_expr<'v>(
+        &self,
+        eval: &mut Evaluator<'v, '_, '_>,
+        expression: &str,
+    ) -> anyhow::Result<Value<'v>> {
+        // Breakpoints must not fire while evaluating on behalf of the client.
+        self.evaluating.fetch_add(1, Ordering::SeqCst);
+        let result = AstModule::parse("<debug>", expression.to_owned(), &dialect())
+            .and_then(|ast| eval.eval_statements(ast))
+            .map_err(|e| anyhow::anyhow!("{e}"));
+        self.evaluating.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    fn canonical_path(&self, filename: &str) -> PathBuf {
+        self.canonical_paths
+            .lock()
+            .unwrap()
+            .entry(filename.to_owned())
+            .or_insert_with(|| {
+                std::fs::canonicalize(filename).unwrap_or_else(|_| PathBuf::from(filename))
+            })
+            .clone()
+    }
+
+    fn breakpoint_at(&self, span: FileSpanRef) -> Option<Breakpoint> {
+        let breakpoints = self.breakpoints.lock().unwrap();
+        if breakpoints.is_empty() {
+            return None;
+        }
+        let file_breakpoints = breakpoints.get(&self.canonical_path(span.filename()))?;
+        let line = span.resolve_span().begin.line;
+        file_breakpoints.get(&line).cloned()
+    }
+
+    fn before_stmt(&self, depth: usize, span: FileSpanRef, eval: &mut Evaluator) {
+        if let Some(frame) = self.frames.lock().unwrap().get_mut(depth) {
+            let location = span.to_file_span();
+            let repeated = frame.top_level.contains(&span.span)
+                && eval.call_stack_count() == 0
+                && frame.location.as_ref() == Some(&location);
+            frame.location = Some(location);
+            if repeated {
+                return;
+            }
+        }
+        if self.evaluating.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+
+        let stack = eval.call_stack_count();
+        let stepped = self
+            .step
+            .lock()
+            .unwrap()
+            .is_some_and(|step| step.should_stop(depth, stack));
+        let reason = if self.stop_on_entry.swap(false, Ordering::SeqCst) {
+            Some(StopReason::Entry)
+        } else if stepped {
+            Some(StopReason::Step)
+        } else {
+            match self.breakpoint_at(span) {
+                Some(Breakpoint {
+                    condition: Some(condition),
+                }) => {
+                    // Stop if the condition holds or cannot be evaluated.
+                    let hit = self
+                        .evaluate_expr(eval, &condition)
+                        .map(|v| v.to_bool())
+                        .unwrap_or(true);
+                    hit.then_some(StopReason::Breakpoint)
+                }
+                Some(_) => Some(StopReason::Breakpoint),
+                None => None,
+            }
+        };
+        let Some(reason) = reason else {
+            return;
+        };
+
+        *self.step.lock().unwrap() = None;
+        self.paused.store(true, Ordering::SeqCst);
+        self.client.stopped(reason);
+
+        let receiver = self.receiver.lock().unwrap();
+        loop {
+            let Ok(command) = receiver.recv() else {
+                // The adapter went away; run to completion.
+                break;
+            };
+            match command(span, eval, self) {
+                Next::RemainPaused => continue,
+                Next::Continue => break,
+                Next::Step(kind) => {
+                    *self.step.lock().unwrap() = Some(Step { kind, depth, stack });
+                    break;
+                }
+            }
+        }
+        self.paused.store(false, Ordering::SeqCst);
+        self.variables.lock().unwrap().clear();
+    }
+}
+
+/// Removes the evaluator frame pushed by [`Debugger::attach`] when its evaluation ends.
+pub(crate) struct AttachGuard {
+    debugger: Arc<Debugger>,
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        self.debugger.frames.lock().unwrap().pop();
+    }
+}
+
+struct StatementHook {
+    debugger: Arc<Debugger>,
+    depth: usize,
+}
+
+impl<'a, 'e: 'a> BeforeStmtFuncDyn<'a, 'e> for StatementHook {
+    fn call<'v>(
+        &mut self,
+        span: FileSpanRef,
+        eval: &mut Evaluator<'v, 'a, 'e>,
+    ) -> starlark::Result<()> {
+        self.debugger.before_stmt(self.depth, span, eval);
+        Ok(())
+    }
+}
+
+/// Collect the spans of the statements Starlark evaluates as top-level statements: those at
+/// module level, including inside module-level `if` blocks, but not inside loops.
+pub(crate) fn collect_top_level(stmt: &AstStmt, spans: &mut HashSet<Span>) {
+    match &stmt.node {
+        StmtP::Statements(stmts) => {
+            for stmt in stmts {
+                collect_top_level(stmt, spans);
+            }
+        }
+        StmtP::If(_, then_block) => {
+            spans.insert(stmt.span);
+            collect_top_level(then_block, spans);
+        }
+        StmtP::IfElse(_, branches) => {
+            spans.insert(stmt.span);
+            collect_top_level(&branches.0, spans);
+            collect_top_level(&branches.1, spans);
+        }
+        _ => {
+            spans.insert(stmt.span);
+        }
+    }
+}
+
+fn dialect() -> Dialect {
+    let mut dialect = Dialect::Extended;
+    dialect.enable_f_strings = true;
+    dialect
+}
+
+fn scope(name: &str, reference: i64, count: usize) -> Scope {
+    Scope {
+        name: name.to_owned(),
+        named_variables: Some(count as i64),
+        variables_reference: reference,
+        expensive: false,
+        column: None,
+        end_column: None,
+        end_line: None,
+        indexed_variables: None,
+        line: None,
+        source: None,
+    }
+}
+
+fn stack_frame(id: usize, name: &str, location: Option<&FileSpan>) -> StackFrame {
+    let mut frame = StackFrame {
+        id: id as i64,
+        name: name.to_owned(),
+        column: 0,
+        line: 0,
+        end_column: None,
+        end_line: None,
+        module_id: None,
+        presentation_hint: None,
+        source: None,
+    };
+    if let Some(location) = location {
+        let span = location.resolve_span();
+        frame.line = span.begin.line as i64 + 1;
+        frame.column = span.begin.column as i64 + 1;
+        frame.end_line = Some(span.end.line as i64 + 1);
+        frame.end_column = Some(span.end.column as i64 + 1);
+        frame.source = Some(Source {
+            path: Some(location.filename().to_owned()),
+            ..Source::default()
+        });
+    }
+    frame
+}
+
+/// Values returned by the `io()` and `config()` calls executed so far.
+fn module_inputs<'v>(eval: &Evaluator<'v, '_, '_>) -> Vec<(String, Value<'v>)> {
+    eval.context_value()
+        .map(|ctx| {
+            ctx.module()
+                .signature()
+                .iter()
+                .filter_map(|param| Some((param.name.clone(), param.actual_value?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn module_nets(eval: &Evaluator) -> Vec<String> {
+    eval.context_value()
+        .map(|ctx| ctx.module().introduced_nets().values().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn module_children<'v>(eval: &Evaluator<'v, '_, '_>) -> Vec<Value<'v>> {
+    eval.context_value()
+        .map(|ctx| ctx.module().children().clone())
+        .unwrap_or_default()
+}
+
+fn child_name(value: Value) -> Option<String> {
+    if let Some(component) = ComponentValue::from_value(value) {
+        Some(component.name().to_owned())
+    } else {
+        ModuleValue::from_value(value).map(|module| module.name().to_owned())
+    }
+}
+
+fn child_summary(value: Value) -> Option<String> {
+    if let Some(component) = ComponentValue::from_value(value) {
+        let mut summary = format!("Component {}", component.prefix());
+        if let Some(mpn) = component.mpn() {
+            summary.push_str(&format!(" ({mpn})"));
+        }
+        Some(summary)
+    } else {
+        ModuleValue::from_value(value).map(|module| {
+            let file = Path::new(module.source_path())
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            format!("Module {file}")
+        })
+    }
+}
+
+fn sub_value<'v>(value: Value<'v>, segment: &PathSegment, heap: &'v Heap) -> Option<Value<'v>> {
+    match segment {
+        PathSegment::Index(i) => value.at(heap.alloc(*i), heap).ok(),
+        PathSegment::Attr(name) => value.get_attr(name, heap).ok().flatten(),
+        PathSegment::Key(key) => value.at(heap.alloc(key.as_str()), heap).ok(),
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -27,6 +28,47 @@ pub struct UnstableRefError {
     pub remote_ref: crate::RemoteRef,
 }
 
+/// Keyword arguments passed to a module that it does not declare via io()/config().
+#[derive(Debug, Error, Clone)]
+#[error("Unknown argument(s) provided to module {module}: {}", unknown.join(", "))]
+pub struct UnknownArgumentsError {
+    /// The name of the module being instantiated
+    pub module: String,
+
+    /// The keyword arguments the module does not accept
+    pub unknown: Vec<String>,
+
+    /// The inputs the module does declare
+    pub known: Vec<String>,
+}
+
+/// A module was instantiated without one of its required io()/config() inputs.
+#[derive(Debug, Error, Clone)]
+#[error("Input '{name}' is required but was not provided and no default value was given")]
+pub struct MissingInputError {
+    /// The name of the missing input
+    pub name: String,
+}
+
+/// A `check()` whose condition did not hold.
+#[derive(Debug, Error, Clone)]
+#[error("{message}")]
+pub struct CheckFailedError {
+    /// The message passed to `check()`
+    pub message: String,
+}
+
+/// A component's `pins` used a name that is not one of its symbol's signals.
+#[derive(Debug, Error, Clone)]
+#[error("Unknown pin name '{pin}' (expected one of: {})", expected.join(", "))]
+pub struct UnknownPinError {
+    /// The pin name that was used
+    pub pin: String,
+
+    /// The signals of the symbol
+    pub expected: Vec<String>,
+}
+
 /// Container for diagnostics that were suppressed during aggregation
 #[derive(Debug, Error, Clone)]
 #[error("Suppressed similar diagnostics")]
@@ -36,7 +78,7 @@ pub struct SuppressedDiagnostics {
 }
 
 /// Structured information about a test result from a TestBench check function
-#[derive(Debug, Error, Clone)]
+#[derive(Debug, Error, Clone, serde::Serialize, serde::Deserialize)]
 #[error("Test result")]
 pub struct BenchTestResult {
     /// The name of the TestBench
@@ -53,4 +95,127 @@ pub struct BenchTestResult {
 
     /// Whether the test passed or failed
     pub passed: bool,
+
+    /// Where the check failed, or the `TestBench()` call for passing checks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+
+    /// Why the check failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// The inputs of the test case, rendered as Starlark values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub case_params: BTreeMap<String, String>,
+
+    /// How long the check function ran, in seconds; 0 when replayed from a cache
+    #[serde(default)]
+    pub duration_secs: f64,
+}
+
+/// The outcome of re-running a TestBench's checks against one mutation of a test
+/// case's evaluated module
+#[derive(Debug, Error, Clone)]
+#[error("Mutation: {mutation}")]
+pub struct MutationResult {
+    /// The name of the TestBench
+    pub test_bench_name: String,
+
+    /// The name of the test case the mutated module was evaluated for
+    pub case_name: Option<String>,
+
+    /// The file path where the TestBench was defined
+    pub file_path: String,
+
+    /// The kind of mutation, e.g. `drop-component`
+    pub kind: String,
+
+    /// What was mutated, e.g. `drop R1`
+    pub mutation: String,
+
+    /// The first check whose `check()` failed on the mutant; `None` if no check did
+    pub killed_by: Option<String>,
+
+    /// The error a check raised on the mutant other than a failing `check()`, e.g. a
+    /// type error. Such a mutation is neither killed nor survived.
+    pub error: Option<String>,
+}
+
+/// A violation of a named design-rule lint
+#[derive(Debug, Error, Clone, serde::Serialize, serde::Deserialize)]
+#[error("{message}")]
+pub struct LintViolation {
+    /// The name of the lint, as used with `-D <lint>` and `# pcb: allow(<lint>)`
+    pub lint: String,
+
+    /// The module the violation was found in
+    pub module: String,
+
+    /// What is wrong
+    pub message: String,
+}
+
+/// Records that a design enables a lint, so `-D <lint>` can be checked against the lints
+/// a build actually knows about. Reported as advice, which is never rendered.
+#[derive(Debug, Error, Clone, serde::Serialize, serde::Deserialize)]
+#[error("lint '{lint}' is enabled")]
+pub struct LintEnabled {
+    pub lint: String,
+}
+
+/// A datasheet requirement declared on a component or module that the design does not meet
+#[derive(Debug, Error, Clone, serde::Serialize, serde::Deserialize)]
+#[error("{message}")]
+pub struct RequirementViolation {
+    /// The path of the component or module the requirement is declared on
+    pub instance: String,
+
+    /// The requirement, e.g. `EN: pull-up to VIN 10k-100k`
+    pub requirement: String,
+
+    /// Where the requirement comes from, e.g. a datasheet section
+    pub source: Option<String>,
+
+    /// What is wrong
+    pub message: String,
+}
+
+/// A netlist snapshot that is missing or differs from its checked-in `.snap` file,
+/// carrying the contents `pcb test --update-snapshots` writes to it
+#[derive(Debug, Error, Clone)]
+#[error("Netlist snapshot mismatch: {}", path.display())]
+pub struct SnapshotMismatch {
+    /// The `.snap` file the snapshot is compared against
+    pub path: PathBuf,
+
+    /// The snapshot of the evaluated module
+    pub contents: String,
+}
+
+/// A span in a source file, with 1-based lines and columns.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SourceSpan {
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl SourceSpan {
+    pub fn new(path: &str, span: &starlark::codemap::ResolvedSpan) -> Self {
+        Self {
+            path: path.to_string(),
+            line: span.begin.line as u32 + 1,
+            column: span.begin.column as u32 + 1,
+            end_line: span.end.line as u32 + 1,
+            end_column: span.end.column as u32 + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
 }
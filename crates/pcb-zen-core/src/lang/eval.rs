@@ -33,7 +33,7 @@ use crate::lang::{
 use crate::{Diagnostic, WithDiagnostics};
 
 /// Evaluation mode determines which features are active during evaluation
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EvalMode {
     /// Build mode: ignores testbench() calls, focuses on artifact generation
     Build,
@@ -55,8 +55,13 @@ fn default_file_provider() -> Arc<dyn crate::FileProvider> {
 
 use super::{
     context::{ContextValue, FrozenContextValue},
+    coverage::EvalKind,
     interface::interface_globals,
+    lint::lint_globals,
     module::{module_globals, FrozenModuleValue, ModuleLoader},
+    requirements::requirements_globals,
+    snapshot::snapshot_globals,
+    sweep::sweep_globals,
     test_bench::test_bench_globals,
 };
 
@@ -166,12 +171,24 @@ struct EvalContextState {
     /// surface rich information without additional parsing.
     symbol_meta: HashMap<PathBuf, HashMap<String, crate::SymbolInfo>>,
 
+    /// Per-file mapping of `symbol → net names` for globals bound to a `Net`, so that
+    /// editors can show the final (deduplicated) name next to the requested one.
+    net_names: HashMap<PathBuf, HashMap<String, crate::NetNameInfo>>,
+
+    /// Signatures of modules instantiated through `Module()`, keyed by canonical path.
+    /// Lets tooling show defaults for omitted parameters without re-evaluating.
+    module_signatures: HashMap<PathBuf, Vec<ParameterInfo>>,
+
     /// Cache of previously loaded modules keyed by their canonical absolute path. This
     /// ensures that repeated `load()` calls for the same file return the *same* frozen
     /// module instance so that type identities remain consistent across the evaluation
     /// graph (e.g. record types defined in that module).
     load_cache: HashMap<PathBuf, starlark::environment::FrozenModule>,
 
+    /// Map of a file in `load_cache` → the files that `load()`ed it. Lets long-running
+    /// hosts drop a changed file together with every cached module built on top of it.
+    load_dependents: HashMap<PathBuf, HashSet<PathBuf>>,
+
     /// Map of `module.zen` → set of files referenced via `load()`. Used by the LSP to
     /// propagate diagnostics when a dependency changes.
     module_deps: HashMap<PathBuf, HashSet<PathBuf>>,
@@ -287,6 +304,22 @@ pub struct EvalContext {
     /// Load resolver for resolving load() paths
     pub(crate) load_resolver: Option<Arc<dyn crate::LoadResolver>>,
 
+    /// Persistent cache for parsed symbol libraries
+    pub(crate) cache: Option<Arc<dyn crate::EvalCache>>,
+
+    /// Debugger attached to this evaluation and every nested one
+    debugger: Option<Arc<crate::Debugger>>,
+
+    /// Coverage collected for this evaluation and every nested one
+    coverage: Option<Arc<crate::Coverage>>,
+
+    /// Whether this evaluation (or one it is nested in) only introspects a module's
+    /// signature, so its statements do not count towards coverage
+    introspecting: bool,
+
+    /// Whether `TestBench()` re-runs its checks against mutations of each case's module
+    pub(crate) mutate: bool,
+
     /// Index to track which load statement we're currently processing (for span resolution)
     current_load_index: RefCell<usize>,
 
@@ -297,6 +330,20 @@ pub struct EvalContext {
     pub(crate) eval_mode: EvalMode,
 }
 
+/// The caches and configuration an [`EvalContext`] shares with its children, in a form
+/// that can be sent to another thread and turned back into a context there with
+/// [`EvalContext::from_shared`].
+#[derive(Clone)]
+pub struct SharedEvalState {
+    state: Arc<Mutex<EvalContextState>>,
+    builtin_docs: HashMap<String, String>,
+    eager: bool,
+    file_provider: Option<Arc<dyn crate::FileProvider>>,
+    load_resolver: Option<Arc<dyn crate::LoadResolver>>,
+    cache: Option<Arc<dyn crate::EvalCache>>,
+    eval_mode: EvalMode,
+}
+
 impl Default for EvalContext {
     fn default() -> Self {
         Self::new()
@@ -331,6 +378,11 @@ impl EvalContext {
             diagnostics: RefCell::new(Vec::new()),
             file_provider: None,
             load_resolver: None,
+            cache: None,
+            debugger: None,
+            coverage: None,
+            introspecting: false,
+            mutate: false,
             current_load_index: RefCell::new(0),
             current_module_index: RefCell::new(0),
             eval_mode: EvalMode::Build,
@@ -356,6 +408,51 @@ impl EvalContext {
         self
     }
 
+    /// Set the persistent cache used to skip re-parsing unchanged inputs
+    pub fn set_cache(mut self, cache: Arc<dyn crate::EvalCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Attach a debugger; it is shared with every `load()` and `Module()` evaluation
+    pub fn set_debugger(mut self, debugger: Arc<crate::Debugger>) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    /// Evaluate without the debugger, e.g. for the placeholder evaluation that
+    /// introspects a module's signature.
+    pub(crate) fn without_debugger(mut self) -> Self {
+        self.debugger = None;
+        self
+    }
+
+    /// Collect line, module and `config()`/`io()` coverage; it is shared with every
+    /// `load()` and `Module()` evaluation
+    pub fn set_coverage(mut self, coverage: Arc<crate::Coverage>) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Mutation test every `TestBench()`: after a case passes, re-run its checks against
+    /// structured mutations of the evaluated module and report which ones survive
+    pub fn set_mutate(mut self, enabled: bool) -> Self {
+        self.mutate = enabled;
+        self
+    }
+
+    /// Mark this as the placeholder evaluation that introspects a module's signature,
+    /// whose module instances and statements do not count towards coverage.
+    pub(crate) fn introspecting(mut self) -> Self {
+        self.introspecting = true;
+        self
+    }
+
+    /// The coverage to record into, unless disabled or introspecting
+    pub(crate) fn coverage(&self) -> Option<&Arc<crate::Coverage>> {
+        self.coverage.as_ref().filter(|_| !self.introspecting)
+    }
+
     /// Enable or disable strict IO/config placeholder checking for subsequent evaluations.
     pub fn set_strict_io_config(mut self, enabled: bool) -> Self {
         self.strict_io_config = enabled;
@@ -390,12 +487,57 @@ impl EvalContext {
             diagnostics: RefCell::new(Vec::new()),
             file_provider: self.file_provider.clone(),
             load_resolver: self.load_resolver.clone(),
+            cache: self.cache.clone(),
+            debugger: self.debugger.clone(),
+            coverage: self.coverage.clone(),
+            introspecting: self.introspecting,
+            mutate: self.mutate,
             current_load_index: RefCell::new(0),
             current_module_index: RefCell::new(0),
             eval_mode: self.eval_mode,
         }
     }
 
+    /// Capture the state shared with child contexts so it can be moved to another thread.
+    pub fn shared(&self) -> SharedEvalState {
+        SharedEvalState {
+            state: self.state.clone(),
+            builtin_docs: self.builtin_docs.clone(),
+            eager: self.eager,
+            file_provider: self.file_provider.clone(),
+            load_resolver: self.load_resolver.clone(),
+            cache: self.cache.clone(),
+            eval_mode: self.eval_mode,
+        }
+    }
+
+    /// Create a context that shares caches with the one `shared` was taken from.
+    pub fn from_shared(shared: SharedEvalState) -> Self {
+        Self {
+            module: starlark::environment::Module::new(),
+            state: shared.state,
+            builtin_docs: shared.builtin_docs,
+            strict_io_config: false,
+            eager: shared.eager,
+            source_path: None,
+            contents: None,
+            name: None,
+            inputs: None,
+            properties: None,
+            diagnostics: RefCell::new(Vec::new()),
+            file_provider: shared.file_provider,
+            load_resolver: shared.load_resolver,
+            cache: shared.cache,
+            debugger: None,
+            coverage: None,
+            introspecting: false,
+            mutate: false,
+            current_load_index: RefCell::new(0),
+            current_module_index: RefCell::new(0),
+            eval_mode: shared.eval_mode,
+        }
+    }
+
     fn dialect(&self) -> Dialect {
         let mut dialect = Dialect::Extended;
         dialect.enable_f_strings = true;
@@ -404,7 +546,7 @@ impl EvalContext {
 
     /// Construct the `Globals` used when evaluating modules. Kept in one place so the
     /// configuration stays consistent between the main evaluator and nested `load()`s.
-    fn build_globals() -> starlark::environment::Globals {
+    pub(crate) fn build_globals() -> starlark::environment::Globals {
         GlobalsBuilder::extended_by(&[
             LibraryExtension::RecordType,
             LibraryExtension::EnumType,
@@ -423,9 +565,22 @@ impl EvalContext {
         .with(file_globals)
         .with(model_globals)
         .with(test_bench_globals)
+        .with(sweep_globals)
+        .with(snapshot_globals)
+        .with(lint_globals)
+        .with(requirements_globals)
         .build()
     }
 
+    /// The persistent cache for `Module()` instances. Not used while debugging, collecting
+    /// coverage, introspecting or mutating, which all need module bodies to run.
+    pub(crate) fn module_cache(&self) -> Option<&Arc<dyn crate::EvalCache>> {
+        if self.debugger.is_some() || self.coverage.is_some() || self.introspecting || self.mutate {
+            return None;
+        }
+        self.cache.as_ref()
+    }
+
     /// Record that `from` references `to` via a `Module()` call.
     pub(crate) fn record_module_dependency(&self, from: &Path, to: &Path) {
         if let Ok(mut state) = self.state.lock() {
@@ -434,6 +589,27 @@ impl EvalContext {
         }
     }
 
+    /// Record the signature of a module instantiated from `path`.
+    pub(crate) fn record_module_signature(&self, path: &Path, signature: Vec<ParameterInfo>) {
+        let path = self
+            .file_provider
+            .as_ref()
+            .and_then(|fp| fp.canonicalize(path).ok())
+            .unwrap_or_else(|| path.to_path_buf());
+        if let Ok(mut state) = self.state.lock() {
+            state.module_signatures.insert(path, signature);
+        }
+    }
+
+    /// Get the signature of a module from its most recent instantiation, if any.
+    pub fn get_module_signature(&self, path: &Path) -> Option<Vec<ParameterInfo>> {
+        if let Ok(state) = self.state.lock() {
+            state.module_signatures.get(path).cloned()
+        } else {
+            None
+        }
+    }
+
     /// Check if there is a module dependency between two files
     pub fn module_dep_exists(&self, from: &Path, to: &Path) -> bool {
         if let Ok(state) = self.state.lock() {
@@ -586,6 +762,25 @@ impl EvalContext {
             eval.set_loader(&self);
             eval.set_print_handler(&print_handler);
 
+            let _debug_guard = self.debugger.as_ref().map(|debugger| {
+                let name = self
+                    .name
+                    .clone()
+                    .or_else(|| Some(source_path.file_name()?.to_string_lossy().into_owned()))
+                    .unwrap_or_default();
+                debugger.attach(&mut eval, name, &ast)
+            });
+
+            if let Some(coverage) = &self.coverage {
+                // Loads run once and are cached, so they count even while introspecting
+                let kind = match (&self.inputs, self.introspecting) {
+                    (None, _) => EvalKind::Load,
+                    (Some(_), false) => EvalKind::Instance,
+                    (Some(_), true) => EvalKind::Introspection,
+                };
+                coverage.attach(&mut eval, source_path, &ast, kind);
+            }
+
             // Attach a `ContextValue` so user code can access evaluation context.
             self.module
                 .set_extra_value(eval.heap().alloc_complex(ContextValue::from_context(&self)));
@@ -697,6 +892,15 @@ impl EvalContext {
         }
     }
 
+    /// Get the final and requested names of nets bound to globals in a file
+    pub fn get_net_names(&self, path: &Path) -> Option<HashMap<String, crate::NetNameInfo>> {
+        if let Ok(state) = self.state.lock() {
+            state.net_names.get(path).cloned()
+        } else {
+            None
+        }
+    }
+
     /// Get the symbol index for a file (symbol name -> target path)
     pub fn get_symbol_index(&self, path: &Path) -> Option<HashMap<String, PathBuf>> {
         if let Ok(state) = self.state.lock() {
@@ -706,6 +910,82 @@ impl EvalContext {
         }
     }
 
+    /// The files whose `load()` results are currently cached.
+    pub fn loaded_files(&self) -> Vec<PathBuf> {
+        self.state
+            .lock()
+            .unwrap()
+            .load_cache
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// The files `path` `load()`ed, directly or through other loads.
+    pub(crate) fn loads_of(&self, path: &Path) -> HashSet<PathBuf> {
+        let state = self.state.lock().unwrap();
+        let mut loads = HashSet::new();
+        let mut queue = vec![path.to_path_buf()];
+        while let Some(loader) = queue.pop() {
+            for (loaded, loaders) in &state.load_dependents {
+                if loaders.contains(&loader) && loads.insert(loaded.clone()) {
+                    queue.push(loaded.clone());
+                }
+            }
+        }
+        loads
+    }
+
+    /// The frozen modules of the cached `load()` results, e.g. to find the record types
+    /// they define.
+    pub(crate) fn loaded_modules(&self) -> Vec<starlark::environment::FrozenModule> {
+        self.state
+            .lock()
+            .unwrap()
+            .load_cache
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Forget the cached `load()` results of `changed` files and of every cached module
+    /// that (transitively) loaded one of them, so the next evaluation reads them again.
+    pub fn invalidate_loads<I, P>(&self, changed: I)
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let mut queue: Vec<PathBuf> = changed
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect();
+        let mut dirty = HashSet::new();
+        while let Some(path) = queue.pop() {
+            if let Some(loaders) = state.load_dependents.get(&path) {
+                queue.extend(loaders.iter().filter(|p| !dirty.contains(*p)).cloned());
+            }
+            dirty.insert(path);
+        }
+        state.load_cache.retain(|path, _| !dirty.contains(path));
+        state
+            .load_dependents
+            .retain(|path, _| !dirty.contains(path));
+    }
+
+    /// Record that the current file `load()`ed `loaded`.
+    fn record_load_dependent(&self, loaded: &Path) {
+        if let Some(loader) = &self.source_path {
+            self.state
+                .lock()
+                .unwrap()
+                .load_dependents
+                .entry(loaded.to_path_buf())
+                .or_default()
+                .insert(loader.clone());
+        }
+    }
+
     /// Get module dependencies for a file
     pub fn get_module_dependencies(&self, path: &Path) -> Option<HashSet<PathBuf>> {
         if let Ok(state) = self.state.lock() {
@@ -715,12 +995,12 @@ impl EvalContext {
         }
     }
 
-    /// Parse and analyze a file, updating the symbol index and metadata
+    /// Parse and evaluate a file, updating the symbol index and metadata
     pub fn parse_and_analyze_file(
         &self,
         path: PathBuf,
         contents: String,
-    ) -> WithDiagnostics<Option<AstModule>> {
+    ) -> WithDiagnostics<EvalOutput> {
         // Update the in-memory file contents
         self.set_file_contents(path.clone(), contents.clone());
 
@@ -737,6 +1017,7 @@ impl EvalContext {
             let mut symbol_index: HashMap<String, PathBuf> = HashMap::new();
             let mut symbol_params: HashMap<String, Vec<String>> = HashMap::new();
             let mut symbol_meta: HashMap<String, crate::SymbolInfo> = HashMap::new();
+            let mut net_names: HashMap<String, crate::NetNameInfo> = HashMap::new();
 
             let names = output.star_module.names().collect::<Vec<_>>();
 
@@ -787,6 +1068,16 @@ impl EvalContext {
                         };
                         symbol_meta.insert(name_str.to_string(), info);
                     } else {
+                        if let Some(net) = value.downcast_ref::<crate::FrozenNetValue>() {
+                            net_names.insert(
+                                name_str.to_string(),
+                                crate::NetNameInfo {
+                                    name: net.name().to_string(),
+                                    original_name: net.original_name().to_string(),
+                                },
+                            );
+                        }
+
                         // Build SymbolInfo for other types
                         let typ = value.get_type();
                         let kind = match typ {
@@ -826,10 +1117,12 @@ impl EvalContext {
                 if !symbol_meta.is_empty() {
                     state.symbol_meta.insert(path.clone(), symbol_meta);
                 }
+
+                state.net_names.insert(path.clone(), net_names);
             }
         }
 
-        result.map(|output| Some(output.ast))
+        result
     }
 
     /// Get the frozen module for a file if it has been evaluated
@@ -1012,6 +1305,28 @@ impl EvalContext {
         }
     }
 
+    /// Resolve the span of the name in the top-level `def` of `name` in the current module
+    pub fn resolve_span_for_def(&self, name: &str) -> Option<ResolvedSpan> {
+        use starlark::syntax::ast::{AstStmt, StmtP};
+
+        fn find_def(stmt: &AstStmt, name: &str) -> Option<starlark::codemap::Span> {
+            match &stmt.node {
+                StmtP::Def(def) if def.name.ident == name => Some(def.name.span),
+                StmtP::Statements(stmts) => stmts.iter().find_map(|stmt| find_def(stmt, name)),
+                StmtP::If(_, body) => find_def(body, name),
+                StmtP::IfElse(_, branches) => {
+                    find_def(&branches.0, name).or_else(|| find_def(&branches.1, name))
+                }
+                _ => None,
+            }
+        }
+
+        let ast = self.parse_current_ast()?;
+        let span = find_def(ast.statement(), name)?;
+        self.get_codemap()
+            .map(|codemap| codemap.file_span(span).resolve_span())
+    }
+
     /// Resolve the span for the current load statement being processed (using the load index)
     pub fn resolve_span_for_current_load(&self, path: &str) -> Option<ResolvedSpan> {
         let current_index = *self.current_load_index.borrow();
@@ -1103,6 +1418,13 @@ impl EvalContext {
         self.source_path.as_deref()
     }
 
+    /// Get the file provider, falling back to the default one
+    pub fn get_file_provider(&self) -> Arc<dyn crate::FileProvider> {
+        self.file_provider
+            .clone()
+            .unwrap_or_else(|| default_file_provider())
+    }
+
     /// Get the load resolver if available
     pub fn get_load_resolver(&self) -> Option<&Arc<dyn crate::LoadResolver>> {
         self.load_resolver.as_ref()
@@ -1175,8 +1497,16 @@ impl FileLoader for EvalContext {
         // Fast path: if we've already loaded (and frozen) this module once
         // within the current evaluation context, simply return the cached
         // instance so that callers share the same definitions.
-        if let Some(frozen) = self.state.lock().unwrap().load_cache.get(&canonical_path) {
-            return Ok(frozen.clone());
+        let cached = self
+            .state
+            .lock()
+            .unwrap()
+            .load_cache
+            .get(&canonical_path)
+            .cloned();
+        if let Some(frozen) = cached {
+            self.record_load_dependent(&canonical_path);
+            return Ok(frozen);
         }
 
         if file_provider.is_directory(&canonical_path) {
@@ -1247,6 +1577,7 @@ impl FileLoader for EvalContext {
         // Cache the result if successful
         if let Some(output) = result.output {
             let frozen = output.star_module;
+            self.record_load_dependent(&canonical_path);
             self.state
                 .lock()
                 .unwrap()
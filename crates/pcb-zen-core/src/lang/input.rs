@@ -438,4 +438,8 @@ impl InputMap {
     pub fn get(&self, name: &str) -> Option<&InputValue> {
         self.inner.get(name)
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &InputValue)> {
+        self.inner.iter()
+    }
 }
@@ -0,0 +1,702 @@
+#![allow(clippy::needless_lifetimes)]
+
+//! Design-rule lints: named rules, written in Zen with `Lint()` or in Rust as a
+//! [`LintRule`], that run over every module of an evaluated design.
+//!
+//! A lint is enabled by assigning it (or a list of lints) to a global of the root file.
+//! Loaded symbols are private, so lints from a package are enabled with an assignment such
+//! as `LINTS = PACKAGE_LINTS`. Each module is checked only for what it owns, the components
+//! it creates and the nets it introduces, so a violation is reported once. Violations are
+//! warnings, promoted to errors with `-D <lint>`, and a module can suppress a lint with a
+//! `# pcb: allow(<lint>)` comment anywhere in its source, on a line of its own or after
+//! code.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use starlark::environment::{GlobalsBuilder, Module};
+use starlark::errors::EvalSeverity;
+use starlark::{
+    any::ProvidesStaticType,
+    eval::Evaluator,
+    starlark_complex_value, starlark_module,
+    values::{
+        list::ListRef, starlark_value, tuple::TupleRef, Coerce, Freeze, FreezeResult, FrozenValue,
+        Heap, NoSerialize, StarlarkValue, Trace, Value, ValueLifetimeless, ValueLike,
+    },
+};
+
+use crate::lang::context::ContextValue;
+use crate::lang::error::{LintEnabled, LintViolation};
+use crate::lang::evaluator_ext::EvaluatorExt;
+use crate::lang::module::FrozenModuleValue;
+use crate::lang::requirements::REQUIREMENTS_LINT;
+use crate::lang::symbol::SymbolValue;
+use crate::{Diagnostic, EvalContext, EvalOutput, FrozenComponentValue, FrozenNetValue, NetId};
+
+/// A design rule implemented in Rust, available in Zen through `builtin_lint(name)`
+pub trait LintRule: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Violations of the rule in what `module` owns. `design` is the connectivity of the
+    /// whole design, for parts placed by other modules on the same nets.
+    fn check(&self, module: &FrozenModuleValue, design: &Connectivity) -> Vec<String>;
+}
+
+/// The Rust rules that ship with the compiler
+pub fn builtin_rules() -> Vec<Arc<dyn LintRule>> {
+    vec![
+        Arc::new(FloatingNet),
+        Arc::new(FloatingInput),
+        Arc::new(MissingDecoupling),
+        Arc::new(OpenDrainPullUp),
+        Arc::new(I2cPullUps),
+    ]
+}
+
+/// How the components of a whole design connect
+pub struct Connectivity {
+    /// The ports on each net, as an index into `components` and a pin name
+    ports: HashMap<NetId, Vec<(usize, String)>>,
+    names: HashMap<NetId, String>,
+    components: Vec<ConnectedComponent>,
+}
+
+struct ConnectedComponent {
+    prefix: String,
+    nets: Vec<NetId>,
+}
+
+impl Connectivity {
+    pub fn new(root: &FrozenModuleValue) -> Self {
+        let mut design = Self {
+            ports: HashMap::new(),
+            names: HashMap::new(),
+            components: Vec::new(),
+        };
+        for comp_val in root.collect_components("").values() {
+            let Some(component) = comp_val.downcast_ref::<FrozenComponentValue>() else {
+                continue;
+            };
+            let index = design.components.len();
+            let mut nets = Vec::new();
+            for (pin_name, net_val) in component.connections().iter() {
+                if let Some(net) = net_val.downcast_ref::<FrozenNetValue>() {
+                    design
+                        .ports
+                        .entry(net.id())
+                        .or_default()
+                        .push((index, pin_name.clone()));
+                    design
+                        .names
+                        .entry(net.id())
+                        .or_insert_with(|| net.name().to_string());
+                    nets.push(net.id());
+                }
+            }
+            design.components.push(ConnectedComponent {
+                prefix: component.prefix().to_string(),
+                nets,
+            });
+        }
+        design
+    }
+
+    /// The number of pins connected to `net`
+    pub fn pin_count(&self, net: NetId) -> usize {
+        self.ports.get(&net).map_or(0, Vec::len)
+    }
+
+    /// Whether `net` is a ground net, by name (`GND`, `AGND`, `VSS`, ...)
+    pub fn is_ground(&self, net: NetId) -> bool {
+        self.names.get(&net).is_some_and(|name| {
+            let name = name.to_ascii_uppercase();
+            name.contains("GND") || name.contains("VSS")
+        })
+    }
+
+    /// Whether a capacitor connects `net` to ground
+    pub fn has_decoupling(&self, net: NetId) -> bool {
+        self.bridges(net, "C", |other| self.is_ground(other))
+    }
+
+    /// Whether a resistor connects `net` to a net other than ground
+    pub fn has_pull_up(&self, net: NetId) -> bool {
+        self.bridges(net, "R", |other| !self.is_ground(other))
+    }
+
+    /// Whether a component with reference designator prefix `prefix` connects `net` to
+    /// another net that matches `other`
+    fn bridges(&self, net: NetId, prefix: &str, other: impl Fn(NetId) -> bool) -> bool {
+        self.ports
+            .get(&net)
+            .into_iter()
+            .flatten()
+            .any(|(index, _)| {
+                let component = &self.components[*index];
+                component.prefix == prefix
+                    && component
+                        .nets
+                        .iter()
+                        .any(|&other_net| other_net != net && other(other_net))
+            })
+    }
+}
+
+/// The components `module` creates itself, not those of its submodules
+fn own_components(module: &FrozenModuleValue) -> Vec<&FrozenComponentValue> {
+    module
+        .children()
+        .iter()
+        .filter_map(|child| child.downcast_ref::<FrozenComponentValue>())
+        .collect()
+}
+
+/// The electrical type of each pin of `component` (`power_in`, `input`, `open_collector`,
+/// ...) from its KiCad symbol. Symbols defined inline carry no types.
+fn pin_types(component: &FrozenComponentValue) -> HashMap<String, String> {
+    let Some(symbol) = component.symbol().downcast_ref::<SymbolValue>() else {
+        return HashMap::new();
+    };
+    let Some(raw_sexp) = symbol.raw_sexp() else {
+        return HashMap::new();
+    };
+    let Ok(eda_symbol) =
+        pcb_eda::Symbol::from_string(&format!("(kicad_symbol_lib {raw_sexp})"), "kicad_sym")
+    else {
+        return HashMap::new();
+    };
+    eda_symbol
+        .pins
+        .iter()
+        .filter_map(|pin| {
+            let signal = symbol.pad_to_signal().get(&pin.number)?;
+            Some((signal.clone(), pin.electrical_type.clone()))
+        })
+        .collect()
+}
+
+/// A pin of a component that a module creates
+struct OwnPin<'a> {
+    /// `<component>.<pin>`
+    path: String,
+    name: &'a str,
+    component: &'a FrozenComponentValue,
+    /// The electrical type from the component's symbol, if it has one
+    pin_type: Option<String>,
+    net: &'a FrozenNetValue,
+}
+
+/// The pins of the components `module` creates itself
+fn own_pins(module: &FrozenModuleValue) -> Vec<OwnPin<'_>> {
+    let mut pins = Vec::new();
+    for component in own_components(module) {
+        let types = pin_types(component);
+        for (pin_name, net_val) in component.connections().iter() {
+            if let Some(net) = net_val.downcast_ref::<FrozenNetValue>() {
+                pins.push(OwnPin {
+                    path: format!("{}.{}", component.name(), pin_name),
+                    name: pin_name,
+                    component,
+                    pin_type: types.get(pin_name).cloned(),
+                    net,
+                });
+            }
+        }
+    }
+    pins
+}
+
+/// Nets introduced by a module that connect to a single pin
+struct FloatingNet;
+
+impl LintRule for FloatingNet {
+    fn name(&self) -> &'static str {
+        "floating-net"
+    }
+
+    fn description(&self) -> &'static str {
+        "a net introduced by the module is connected to only one pin"
+    }
+
+    fn check(&self, module: &FrozenModuleValue, _design: &Connectivity) -> Vec<String> {
+        // Nets passed in through io() may be connected elsewhere
+        let mut io_nets = HashSet::new();
+        for param in module.signature().iter().filter(|param| !param.is_config) {
+            if let Some(actual_value) = &param.actual_value {
+                io_nets.extend(FrozenModuleValue::extract_nets_from_value(
+                    actual_value.to_value(),
+                ));
+            }
+        }
+
+        let mut ports: HashMap<_, Vec<String>> = HashMap::new();
+        for (comp_path, comp_val) in module.collect_components("").iter() {
+            let Some(component) = comp_val.downcast_ref::<FrozenComponentValue>() else {
+                continue;
+            };
+            for (pin_name, net_val) in component.connections().iter() {
+                if let Some(net) = net_val.downcast_ref::<FrozenNetValue>() {
+                    ports
+                        .entry(net.id())
+                        .or_default()
+                        .push(format!("{comp_path}.{pin_name}"));
+                }
+            }
+        }
+
+        module
+            .introduced_nets()
+            .iter()
+            .filter(|(_, name)| !io_nets.contains(name.as_str()))
+            .filter_map(|(id, name)| match ports.get(id).map(Vec::as_slice) {
+                Some([port]) => Some(format!("net '{name}' only connects to {port}")),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Input pins that nothing else connects to
+struct FloatingInput;
+
+impl LintRule for FloatingInput {
+    fn name(&self) -> &'static str {
+        "floating-input"
+    }
+
+    fn description(&self) -> &'static str {
+        "an input pin is not connected to any other pin"
+    }
+
+    fn check(&self, module: &FrozenModuleValue, design: &Connectivity) -> Vec<String> {
+        own_pins(module)
+            .into_iter()
+            .filter(|pin| {
+                pin.pin_type.as_deref() == Some("input") && design.pin_count(pin.net.id()) == 1
+            })
+            .map(|pin| {
+                format!(
+                    "input {} on net '{}' is not connected to anything else",
+                    pin.path,
+                    pin.net.name()
+                )
+            })
+            .collect()
+    }
+}
+
+/// Power pins of ICs without a capacitor to ground. Pins are found by their `power_in`
+/// type, or by name (VCC, VDD, VIN, ...) for symbols without types.
+struct MissingDecoupling;
+
+/// Name prefixes of IC power pins
+const POWER_PIN_PREFIXES: &[&str] = &["VCC", "VDD", "AVDD", "DVDD", "VIN", "VBAT", "VIO"];
+
+impl LintRule for MissingDecoupling {
+    fn name(&self) -> &'static str {
+        "missing-decoupling"
+    }
+
+    fn description(&self) -> &'static str {
+        "an IC power pin has no capacitor to ground"
+    }
+
+    fn check(&self, module: &FrozenModuleValue, design: &Connectivity) -> Vec<String> {
+        let mut reported = HashSet::new();
+        own_pins(module)
+            .into_iter()
+            .filter(|pin| {
+                let is_power = match &pin.pin_type {
+                    Some(pin_type) => pin_type == "power_in",
+                    None => {
+                        let name = pin.name.to_ascii_uppercase();
+                        POWER_PIN_PREFIXES
+                            .iter()
+                            .any(|prefix| name.starts_with(prefix))
+                    }
+                };
+                let net = pin.net.id();
+                pin.component.prefix() == "U"
+                    && is_power
+                    && !design.is_ground(net)
+                    && !design.has_decoupling(net)
+                    && reported.insert((pin.component.name().to_string(), net))
+            })
+            .map(|pin| {
+                format!(
+                    "{} on net '{}' has no decoupling capacitor to ground",
+                    pin.path,
+                    pin.net.name()
+                )
+            })
+            .collect()
+    }
+}
+
+/// Open-drain outputs without a pull-up resistor
+struct OpenDrainPullUp;
+
+impl LintRule for OpenDrainPullUp {
+    fn name(&self) -> &'static str {
+        "open-drain-pull-up"
+    }
+
+    fn description(&self) -> &'static str {
+        "an open-drain or open-collector output has no pull-up resistor"
+    }
+
+    fn check(&self, module: &FrozenModuleValue, design: &Connectivity) -> Vec<String> {
+        own_pins(module)
+            .into_iter()
+            .filter(|pin| {
+                pin.pin_type.as_deref() == Some("open_collector")
+                    && !design.has_pull_up(pin.net.id())
+            })
+            .map(|pin| {
+                format!(
+                    "open-drain {} on net '{}' has no pull-up resistor",
+                    pin.path,
+                    pin.net.name()
+                )
+            })
+            .collect()
+    }
+}
+
+/// I2C nets, by an `SDA` or `SCL` segment in their name, without a pull-up resistor
+struct I2cPullUps;
+
+impl LintRule for I2cPullUps {
+    fn name(&self) -> &'static str {
+        "i2c-pull-ups"
+    }
+
+    fn description(&self) -> &'static str {
+        "an I2C SDA or SCL net introduced by the module has no pull-up resistor"
+    }
+
+    fn check(&self, module: &FrozenModuleValue, design: &Connectivity) -> Vec<String> {
+        module
+            .introduced_nets()
+            .iter()
+            .filter(|(id, name)| {
+                let is_i2c = name
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .any(|segment| {
+                        segment.eq_ignore_ascii_case("SDA") || segment.eq_ignore_ascii_case("SCL")
+                    });
+                is_i2c && !design.has_pull_up(**id)
+            })
+            .map(|(_, name)| format!("I2C net '{name}' has no pull-up resistor"))
+            .collect()
+    }
+}
+
+/// A lint rule: a Zen `check(module)` function, or a builtin Rust rule
+#[derive(Clone, Coerce, Trace, ProvidesStaticType, NoSerialize, Allocative, Freeze)]
+#[repr(C)]
+pub struct LintValueGen<V: ValueLifetimeless> {
+    name: String,
+    description: String,
+    check: Option<V>,
+    #[allocative(skip)]
+    #[trace(unsafe_ignore)]
+    #[freeze(identity)]
+    rule: Option<Arc<dyn LintRule>>,
+}
+
+starlark_complex_value!(pub LintValue);
+
+#[starlark_value(type = "Lint")]
+impl<'v, V: ValueLike<'v>> StarlarkValue<'v> for LintValueGen<V> where Self: ProvidesStaticType<'v> {}
+
+impl<'v, V: ValueLike<'v>> std::fmt::Display for LintValueGen<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Lint({})", self.name)
+    }
+}
+
+impl<'v, V: ValueLike<'v>> std::fmt::Debug for LintValueGen<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lint")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("builtin", &self.rule.is_some())
+            .finish()
+    }
+}
+
+impl<'v, V: ValueLike<'v>> LintValueGen<V> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// Lints suppressed by `# pcb: allow(<lint>, ...)` comments in `source`, either on a
+/// line of their own or after code
+fn suppressed_lints(source: &str) -> HashSet<String> {
+    source
+        .lines()
+        .flat_map(|line| line.match_indices('#').map(move |(i, _)| &line[i + 1..]))
+        .filter_map(|comment| comment.trim().strip_prefix("pcb:"))
+        .filter_map(|directive| directive.trim().strip_prefix("allow("))
+        .filter_map(|args| args.split_once(')').map(|(args, _)| args))
+        .flat_map(|args| args.split(',').map(|lint| lint.trim().to_string()))
+        .filter(|lint| !lint.is_empty())
+        .collect()
+}
+
+/// The lints enabled in the root file: globals that are a `Lint` or a list of them
+fn enabled_lints(output: &EvalOutput) -> BTreeMap<String, FrozenLintValue> {
+    fn collect(value: Value, lints: &mut BTreeMap<String, FrozenLintValue>) {
+        if let Some(lint) = value.downcast_ref::<FrozenLintValue>() {
+            lints
+                .entry(lint.name.clone())
+                .or_insert_with(|| lint.clone());
+        } else if let Some(list) = ListRef::from_value(value) {
+            list.iter().for_each(|item| collect(item, lints));
+        } else if let Some(tuple) = TupleRef::from_value(value) {
+            tuple.iter().for_each(|item| collect(item, lints));
+        }
+    }
+
+    let mut lints = BTreeMap::new();
+    for name in output.star_module.names() {
+        if let Ok(Some(owned_val)) = output.star_module.get_option(name.as_str()) {
+            collect(owned_val.value(), &mut lints);
+        }
+    }
+    lints
+}
+
+/// Messages reported by a Zen lint function: None, a string or a list of strings. A
+/// failing `check()` inside the function is reported as a violation too. The function runs
+/// with a context for `module`, so context builtins work, and diagnostics they report are
+/// added to `diagnostics`.
+fn run_zen_check(
+    check: FrozenValue,
+    module: &FrozenModuleValue,
+    output: &EvalOutput,
+    ctx: &EvalContext,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Vec<String>, String> {
+    let ctx = ctx
+        .child_context()
+        .set_source_path(PathBuf::from(module.source_path()))
+        .set_module_name(module.name());
+    let env = Module::new();
+    let mut eval = Evaluator::new(&env);
+    env.set_extra_value(eval.heap().alloc_complex(ContextValue::from_context(&ctx)));
+    eval.frozen_heap()
+        .add_reference(output.star_module.frozen_heap());
+    // The check only sees what the module owns; submodules are checked on their own
+    let module_value = eval
+        .frozen_heap()
+        .alloc(module.without_submodules())
+        .to_value();
+
+    let result = eval.eval_function(check.to_value(), &[module_value], &[]);
+    if let Some(context) = eval.context_value() {
+        diagnostics.extend(context.diagnostics().iter().cloned());
+    }
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => return Ok(vec![e.kind().to_string()]),
+    };
+
+    if result.is_none() {
+        Ok(Vec::new())
+    } else if let Some(message) = result.unpack_str() {
+        Ok(vec![message.to_string()])
+    } else if let Some(list) = ListRef::from_value(result) {
+        list.iter()
+            .map(|item| item.unpack_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| "lint returned a list that is not all strings".to_string())
+    } else {
+        Err(format!(
+            "lint must return None, a string or a list of strings, got {}",
+            result.get_type()
+        ))
+    }
+}
+
+/// An advice diagnostic per lint enabled in the root file of `output`, which the CLI uses
+/// to reject `-D` names that no design knows
+pub fn enabled_lint_diagnostics(output: &EvalOutput) -> Vec<Diagnostic> {
+    enabled_lints(output)
+        .into_keys()
+        .map(|lint| {
+            let enabled = LintEnabled { lint };
+            Diagnostic {
+                path: output.sch_module.source_path().to_string(),
+                span: None,
+                severity: EvalSeverity::Advice,
+                body: enabled.to_string(),
+                call_stack: None,
+                child: None,
+                source_error: Some(Arc::new(enabled.into())),
+            }
+        })
+        .collect()
+}
+
+/// The names in `deny` that are neither a compiler lint nor a lint enabled by a design,
+/// going by the [`enabled_lint_diagnostics`] in `diagnostics`
+pub fn unknown_lints<'a, 'd>(
+    deny: &'a [String],
+    diagnostics: impl IntoIterator<Item = &'d Diagnostic>,
+) -> Vec<&'a str> {
+    let mut known: HashSet<String> = ["warnings", "unstable-refs", REQUIREMENTS_LINT]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    known.extend(builtin_rules().iter().map(|rule| rule.name().to_string()));
+    for diag in diagnostics {
+        if let Some(enabled) = diag.downcast_error_ref::<LintEnabled>() {
+            known.insert(enabled.lint.clone());
+        }
+    }
+    deny.iter()
+        .map(String::as_str)
+        .filter(|lint| !known.contains(*lint))
+        .collect()
+}
+
+/// Run the lints enabled in the root file of `output` over every module of the design,
+/// returning a warning per violation that is not suppressed in the module's source.
+/// Zen lints run in a child context of `ctx`.
+pub fn run_lints(output: &EvalOutput, ctx: &EvalContext) -> Vec<Diagnostic> {
+    let lints = enabled_lints(output);
+    if lints.is_empty() {
+        return Vec::new();
+    }
+
+    let design = Connectivity::new(&output.sch_module);
+    let file_provider = ctx.get_file_provider();
+    let mut modules = vec![output.sch_module.clone()];
+    let mut diagnostics = Vec::new();
+    let mut suppressions: HashMap<String, HashSet<String>> = HashMap::new();
+
+    while let Some(module) = modules.pop() {
+        for child in module.children().iter() {
+            if let Some(submodule) = child.downcast_ref::<FrozenModuleValue>() {
+                modules.push(submodule.clone());
+            }
+        }
+
+        let source_path = module.source_path().to_string();
+        let suppressed = suppressions.entry(source_path.clone()).or_insert_with(|| {
+            file_provider
+                .read_file(std::path::Path::new(&source_path))
+                .map(|source| suppressed_lints(&source))
+                .unwrap_or_default()
+        });
+
+        for (name, lint) in &lints {
+            if suppressed.contains(name) {
+                continue;
+            }
+
+            let outcome = match (&lint.rule, lint.check) {
+                (Some(rule), _) => Ok(rule.check(&module, &design)),
+                (None, Some(check)) => run_zen_check(check, &module, output, ctx, &mut diagnostics),
+                (None, None) => Ok(Vec::new()),
+            };
+
+            let (severity, messages) = match outcome {
+                Ok(messages) => (EvalSeverity::Warning, messages),
+                Err(error) => (EvalSeverity::Error, vec![error]),
+            };
+            for message in messages {
+                let violation = LintViolation {
+                    lint: name.clone(),
+                    module: module.name().to_string(),
+                    message: message.clone(),
+                };
+                diagnostics.push(Diagnostic {
+                    path: source_path.clone(),
+                    span: None,
+                    severity,
+                    body: format!("[{}] {}", name, message),
+                    call_stack: None,
+                    child: None,
+                    source_error: Some(Arc::new(violation.into())),
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[starlark_module]
+pub(crate) fn lint_globals(builder: &mut GlobalsBuilder) {
+    /// Define a design-rule lint called `name` (kebab-case, e.g. "missing-decoupling").
+    /// `check(module)` runs on every module of a design that enables the lint and returns
+    /// None, a violation message or a list of them; a failing `check()` is a violation.
+    /// `module` holds only the components the module creates itself, not its submodules,
+    /// which are checked on their own. Enable lints by assigning them, or a list of them,
+    /// to globals of the root file.
+    fn Lint<'v>(
+        #[starlark(require = named)] name: String,
+        #[starlark(require = named)] check: Value<'v>,
+        #[starlark(require = named, default = String::new())] description: String,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(anyhow::anyhow!(
+                "lint name '{}' must be kebab-case, e.g. 'missing-decoupling'",
+                name
+            ));
+        }
+        if check.get_type() != "function" && check.get_type() != "native_function" {
+            return Err(anyhow::anyhow!(
+                "lint check must be a function, got {}",
+                check.get_type()
+            ));
+        }
+        Ok(heap.alloc(LintValue {
+            name,
+            description,
+            check: Some(check),
+            rule: None,
+        }))
+    }
+
+    /// Enable a lint implemented in Rust: "floating-net", "floating-input",
+    /// "missing-decoupling", "open-drain-pull-up" or "i2c-pull-ups".
+    fn builtin_lint<'v>(
+        #[starlark(require = pos)] name: String,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        let rules = builtin_rules();
+        let Some(rule) = rules.iter().find(|rule| rule.name() == name) else {
+            let names: Vec<&str> = rules.iter().map(|rule| rule.name()).collect();
+            return Err(anyhow::anyhow!(
+                "unknown builtin lint '{}', expected one of: {}",
+                name,
+                names.join(", ")
+            ));
+        };
+        Ok(heap.alloc(LintValue {
+            name,
+            description: rule.description().to_string(),
+            check: None,
+            rule: Some(rule.clone()),
+        }))
+    }
+}
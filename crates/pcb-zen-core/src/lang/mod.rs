@@ -1,13 +1,21 @@
 pub mod component;
 pub(crate) mod context;
+pub mod coverage;
+pub mod debugger;
 pub mod eval;
 pub(crate) mod evaluator_ext;
 pub mod input;
 pub(crate) mod interface;
 pub(crate) mod interface_validation;
+pub mod lint;
 pub mod module;
+pub(crate) mod module_cache;
+pub(crate) mod mutation;
 pub mod net;
+pub mod requirements;
+pub mod snapshot;
 pub mod spice_model;
+pub mod sweep;
 pub mod symbol;
 pub mod test_bench;
 pub mod type_info;
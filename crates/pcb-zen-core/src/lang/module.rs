@@ -27,7 +27,9 @@ use crate::lang::context::ContextValue;
 use crate::lang::eval::EvalContext;
 use crate::lang::evaluator_ext::EvaluatorExt;
 use crate::lang::input::InputMap;
-use crate::{Diagnostic, InputValue};
+use crate::lang::module_cache::{ModuleCache, ModuleInstance};
+use crate::lang::requirements::Requirement;
+use crate::{Diagnostic, Diagnostics, InputValue};
 use starlark::values::dict::{AllocDict, DictRef};
 
 /// Helper macro for frozen module downcasting to reduce repetition
@@ -47,18 +49,12 @@ macro_rules! downcast_frozen_module {
 
 use super::net::{generate_net_id, NetValue};
 use crate::lang::context::FrozenContextValue;
+use crate::lang::error::MissingInputError;
 use crate::lang::net::NetId;
 use crate::{FrozenComponentValue, FrozenNetValue};
 use starlark::errors::{EvalMessage, EvalSeverity};
 use std::collections::HashMap;
 use std::sync::Arc;
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-#[error("Input '{name}' is required but was not provided and no default value was given")]
-pub struct MissingInputError {
-    name: String,
-}
 
 /// Metadata for a module parameter (from io() or config() calls)
 #[derive(Clone, Debug, Trace, ProvidesStaticType, NoSerialize, Allocative, Freeze)]
@@ -134,6 +130,11 @@ pub struct ModuleValueGen<V: ValueLifetimeless> {
     introduced_nets: starlark::collections::SmallMap<NetId, String>,
     /// Local name → net id, to enforce uniqueness of names within a module.
     net_name_to_id: starlark::collections::SmallMap<String, NetId>,
+    /// Datasheet requirements declared with `requirements()`, naming pins as `Component.pin`.
+    #[allocative(skip)]
+    #[trace(unsafe_ignore)]
+    #[freeze(identity)]
+    requirements: Vec<Requirement>,
 }
 
 starlark_complex_value!(pub ModuleValue);
@@ -373,6 +374,28 @@ impl<'v, V: ValueLike<'v>> ModuleValueGen<V> {
         self.properties.insert(name, value);
     }
 
+    pub(crate) fn set_children(&mut self, children: Vec<V>) {
+        self.children = children;
+    }
+
+    /// A copy of the module without its submodules, keeping the components it creates
+    /// itself
+    pub(crate) fn without_submodules(&self) -> Self {
+        let mut module = self.clone();
+        module
+            .children
+            .retain(|child| child.downcast_ref::<FrozenModuleValue>().is_none());
+        module
+    }
+
+    pub(crate) fn add_requirements(&mut self, requirements: Vec<Requirement>) {
+        self.requirements.extend(requirements);
+    }
+
+    pub fn requirements(&self) -> &[Requirement] {
+        &self.requirements
+    }
+
     pub fn new(name: String, source_path: &Path) -> Self {
         let source_path = source_path.to_string_lossy().into_owned();
         ModuleValueGen {
@@ -384,6 +407,7 @@ impl<'v, V: ValueLike<'v>> ModuleValueGen<V> {
             signature: Vec::new(),
             introduced_nets: SmallMap::new(),
             net_name_to_id: SmallMap::new(),
+            requirements: Vec::new(),
         }
     }
 
@@ -689,25 +713,60 @@ where
             self.name.clone()
         };
 
-        // Evaluate the module file with the given inputs
-        let ctx = eval
-            .eval_context()
-            .expect("expected eval context")
-            .child_context()
-            .set_strict_io_config(true);
+        // Leaf modules instantiated with the same inputs before are restored from the
+        // persistent cache instead of being evaluated again.
+        let module_cache = eval.eval_context().and_then(|ctx| {
+            ModuleCache::new(
+                ctx,
+                Path::new(&self.source_path),
+                &final_name,
+                &input_map,
+                properties_override.as_ref(),
+            )
+        });
+        let cached = module_cache
+            .as_ref()
+            .and_then(|cache| cache.lookup(&final_name));
 
-        let ctx = if let Some(props_map) = properties_override.clone() {
-            ctx.set_properties(props_map)
-        } else {
-            ctx
-        };
+        let (output, diagnostics) = match cached {
+            Some(instance) => (Some(instance), Diagnostics::default()),
+            None => {
+                // Evaluate the module file with the given inputs
+                let ctx = eval
+                    .eval_context()
+                    .expect("expected eval context")
+                    .child_context()
+                    .set_strict_io_config(true);
+
+                let ctx = if let Some(props_map) = properties_override.clone() {
+                    ctx.set_properties(props_map)
+                } else {
+                    ctx
+                };
 
-        let (output, diagnostics) = ctx
-            .set_source_path(std::path::PathBuf::from(&self.source_path))
-            .set_module_name(final_name.clone())
-            .set_inputs(input_map)
-            .eval()
-            .unpack();
+                let ctx = match &module_cache {
+                    Some(cache) => ctx.set_file_provider(cache.file_provider()),
+                    None => ctx,
+                };
+
+                let (output, diagnostics) = ctx
+                    .set_source_path(std::path::PathBuf::from(&self.source_path))
+                    .set_module_name(final_name.clone())
+                    .set_inputs(input_map)
+                    .eval()
+                    .unpack();
+                let output = output.map(ModuleInstance::from);
+
+                if let (Some(cache), Some(instance), Some(ctx)) =
+                    (&module_cache, &output, eval.eval_context())
+                {
+                    if diagnostics.is_empty() {
+                        cache.store(ctx, instance);
+                    }
+                }
+                (output, diagnostics)
+            }
+        };
 
         let context = eval
             .module()
@@ -763,49 +822,56 @@ where
 
         match output {
             Some(output) => {
+                if let Some(ctx) = eval.eval_context() {
+                    ctx.record_module_signature(
+                        Path::new(&self.source_path),
+                        output.signature.clone(),
+                    );
+                }
+
                 // Add a reference to the dependent module's frozen heap so it stays alive.
                 eval.frozen_heap()
                     .add_reference(output.star_module.frozen_heap());
 
+                let used_inputs: HashSet<String> = output
+                    .module
+                    .signature()
+                    .iter()
+                    .map(|param| param.name.clone())
+                    .collect();
+
                 // Add the evaluated module as a child on the *current* evaluation context so that
                 // it shows up in the final schematic.
-                context.add_child(eval.frozen_heap().alloc(output.sch_module).to_value());
-
-                let used_inputs: HashSet<String> = output
-                    .star_module
-                    .extra_value()
-                    .and_then(|extra| extra.downcast_ref::<FrozenContextValue>())
-                    .map(|fctx| {
-                        fctx.module
-                            .signature()
-                            .iter()
-                            .map(|param| param.name.clone())
-                            .collect()
-                    })
-                    .unwrap_or_default();
+                context.add_child(eval.frozen_heap().alloc(output.module).to_value());
 
                 // Remove any potential `name` override from the unused-check set.
-                let unused: Vec<String> =
+                let mut unused: Vec<String> =
                     provided_names.difference(&used_inputs).cloned().collect();
 
                 if !unused.is_empty() {
-                    let msg = format!(
-                        "Unknown argument(s) provided to module {}: {}",
-                        self.name,
-                        unused.join(", ")
-                    );
+                    unused.sort();
+                    let mut known: Vec<String> = used_inputs.into_iter().collect();
+                    known.sort();
+                    let error = crate::UnknownArgumentsError {
+                        module: self.name.clone(),
+                        unknown: unused,
+                        known,
+                    };
 
-                    if let Some(cs) = &call_site {
-                        let mut unused_diag =
-                            EvalMessage::from_any_error(Path::new(cs.filename()), &msg);
-                        unused_diag.span = Some(cs.resolve_span());
-                        context.add_diagnostic(unused_diag);
-                    } else {
-                        context.add_diagnostic(EvalMessage::from_any_error(
+                    let unused_diag = match &call_site {
+                        Some(cs) => Diagnostic::new(
+                            error.to_string(),
+                            EvalSeverity::Error,
+                            Path::new(cs.filename()),
+                        )
+                        .with_span(cs.resolve_span()),
+                        None => Diagnostic::new(
+                            error.to_string(),
+                            EvalSeverity::Error,
                             Path::new(&self.source_path),
-                            &msg,
-                        ));
-                    }
+                        ),
+                    };
+                    context.add_diagnostic(unused_diag.with_source_error(Some(error)));
                     // Continue execution without raising an error.
                 }
 
@@ -1584,6 +1650,7 @@ pub fn module_globals(builder: &mut GlobalsBuilder) {
                 Some(result_value),
             );
         }
+        record_param_coverage(eval, &name, typ, result_value);
 
         Ok(result_value)
     }
@@ -1660,6 +1727,7 @@ pub fn module_globals(builder: &mut GlobalsBuilder) {
                 Some(result_value),
             );
         }
+        record_param_coverage(eval, &name, typ, result_value);
 
         Ok(result_value)
     }
@@ -1675,6 +1743,22 @@ pub fn module_globals(builder: &mut GlobalsBuilder) {
     }
 }
 
+/// Record the value a `config()`/`io()` call took when coverage is being collected
+fn record_param_coverage<'v>(
+    eval: &Evaluator<'v, '_, '_>,
+    name: &str,
+    typ: Value<'v>,
+    value: Value<'v>,
+) {
+    let Some(coverage) = eval.eval_context().and_then(|ctx| ctx.coverage()) else {
+        return;
+    };
+    if let Some(location) = eval.call_stack_top_location() {
+        let line = location.resolve_span().begin.line as u32 + 1;
+        coverage.record_param(location.filename(), line, name, typ, value, eval.heap());
+    }
+}
+
 /// Construct a `ModuleLoader` for the Starlark file at `path` by performing a
 /// lightweight introspection pass (empty `InputMap`) so that we can populate
 /// the placeholder parameter list ahead of time.
@@ -1690,6 +1774,8 @@ pub fn build_module_loader_from_path(path: &Path, parent_ctx: &EvalContext) -> M
     // and cache the frozen module for later attribute look-ups.
     let result = parent_ctx
         .child_context()
+        .without_debugger()
+        .introspecting()
         .set_source_path(path.to_path_buf())
         .set_module_name(name.clone())
         .set_inputs(InputMap::new())
@@ -0,0 +1,644 @@
+//! Persistent cache of leaf module instances.
+//!
+//! Instantiating a module through its `ModuleLoader` evaluates the module file with the
+//! caller's inputs. For a leaf module, one that only creates components, the result
+//! depends on nothing but that file, the files its evaluation read and the inputs, so it
+//! is kept in the [`EvalCache`] and restored on later instantiations instead of running
+//! the module body again.
+//!
+//! Entries are keyed by the module's path and contents, the instance name, its inputs and
+//! properties, and the evaluation mode. Nets passed in are keyed by the order they first
+//! appear in rather than by id. Each entry records the content hash of every file the
+//! evaluation read or loaded, so it is only reused while the resolved load graph is
+//! unchanged.
+//!
+//! Only instances made of plain data are cached. A module is evaluated every time when it
+//! instantiates other modules, declares requirements, attaches SPICE models or net
+//! symbols, connects nets it neither created nor received, takes or records values other
+//! than strings, numbers, booleans, lists, dicts and nets, or reports any diagnostic.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use starlark::collections::SmallMap;
+use starlark::environment::{FrozenModule, Globals, Module};
+use starlark::eval::Evaluator;
+use starlark::values::{FrozenValue, Value};
+
+use crate::lang::component::ComponentValue;
+use crate::lang::eval::{EvalContext, EvalOutput};
+use crate::lang::input::{InputMap, InputValue};
+use crate::lang::module::{FrozenModuleValue, ModuleValue};
+use crate::lang::net::{generate_net_id, NetId, NetValue};
+use crate::lang::symbol::SymbolValue;
+use crate::lang::type_info::{ParameterInfo, TypeInfo};
+use crate::{
+    EvalCache, FileProvider, FileProviderError, FrozenComponentValue, FrozenNetValue, LoadResolver,
+};
+
+/// [`EvalCache`] namespace holding module instances
+const MODULE_CACHE_NAMESPACE: &str = "modules";
+
+/// Globals the types of restored parameters are taken from. They live as long as the
+/// process, so restored modules can refer to them without keeping a heap alive.
+static GLOBALS: Lazy<Globals> = Lazy::new(EvalContext::build_globals);
+
+/// The result of instantiating a module, evaluated or restored from the cache
+pub(crate) struct ModuleInstance {
+    pub star_module: FrozenModule,
+    pub module: FrozenModuleValue,
+    pub signature: Vec<ParameterInfo>,
+}
+
+impl From<EvalOutput> for ModuleInstance {
+    fn from(output: EvalOutput) -> Self {
+        Self {
+            star_module: output.star_module,
+            module: output.sch_module,
+            signature: output.signature,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedModule {
+    /// Every file the evaluation read or loaded, with the hash of its contents
+    files: Vec<(PathBuf, String)>,
+    nets: Vec<CachedNet>,
+    /// Nets created with `Net()`, as indices into `nets`, with their local names
+    introduced_nets: Vec<(usize, String)>,
+    properties: SmallMap<String, InputValue>,
+    parameters: Vec<CachedParameter>,
+    components: Vec<CachedComponent>,
+    signature: Vec<ParameterInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedNet {
+    /// Position among the nets passed in, or `None` for a net the module created
+    input: Option<usize>,
+    name: String,
+    original_name: Option<String>,
+    properties: SmallMap<String, InputValue>,
+}
+
+/// Plain data, or an index into [`CachedModule::nets`]
+#[derive(Serialize, Deserialize)]
+enum CachedValue {
+    Plain(InputValue),
+    Net(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedParameter {
+    name: String,
+    /// The global the parameter's type is bound to, e.g. `str` or `Net`
+    type_name: String,
+    optional: bool,
+    default_value: Option<CachedValue>,
+    is_config: bool,
+    help: Option<String>,
+    actual_value: Option<CachedValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedComponent {
+    name: String,
+    mpn: Option<String>,
+    ctype: Option<String>,
+    footprint: String,
+    prefix: String,
+    /// Pin name → index into [`CachedModule::nets`]
+    connections: SmallMap<String, usize>,
+    properties: SmallMap<String, InputValue>,
+    source_path: String,
+    symbol: CachedSymbol,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedSymbol {
+    name: Option<String>,
+    pad_to_signal: SmallMap<String, String>,
+    source_path: Option<String>,
+    raw_sexp: Option<String>,
+    units: SmallMap<String, Vec<String>>,
+}
+
+/// The cache entry of one `ModuleLoader` call
+pub(crate) struct ModuleCache {
+    cache: Arc<dyn EvalCache>,
+    file_provider: Arc<dyn FileProvider>,
+    load_resolver: Option<Arc<dyn LoadResolver>>,
+    source_path: PathBuf,
+    key: String,
+    /// The nets passed in, in the order they first appear in the inputs
+    input_nets: Vec<InputValue>,
+    reads: Arc<ReadRecorder>,
+}
+
+impl ModuleCache {
+    /// The entry for instantiating `source_path` as `name`, or `None` if `ctx` has no
+    /// module cache or the inputs are not plain data.
+    pub(crate) fn new(
+        ctx: &EvalContext,
+        source_path: &Path,
+        name: &str,
+        inputs: &InputMap,
+        properties: Option<&SmallMap<String, InputValue>>,
+    ) -> Option<Self> {
+        let cache = ctx.module_cache()?.clone();
+        let file_provider = ctx.file_provider.clone()?;
+        let contents = file_provider.read_file(source_path).ok()?;
+
+        let mut input_nets = Vec::new();
+        let mut normalized = SmallMap::new();
+        for (input, value) in inputs.iter() {
+            normalized.insert(input.clone(), normalize(value, &mut input_nets)?);
+        }
+        if !properties.is_none_or(|properties| properties.values().all(is_plain)) {
+            return None;
+        }
+
+        let key = crate::cache_key([
+            source_path.to_string_lossy().as_bytes().to_vec(),
+            contents.into_bytes(),
+            name.as_bytes().to_vec(),
+            serde_json::to_vec(&normalized).ok()?,
+            serde_json::to_vec(&properties).ok()?,
+            format!("{:?}", ctx.eval_mode).into_bytes(),
+        ]);
+        Some(Self {
+            cache,
+            reads: Arc::new(ReadRecorder::new(file_provider.clone())),
+            file_provider,
+            load_resolver: ctx.load_resolver.clone(),
+            source_path: source_path.to_path_buf(),
+            key,
+            input_nets,
+        })
+    }
+
+    /// File provider for evaluating the module, recording what it reads for [`Self::store`]
+    pub(crate) fn file_provider(&self) -> Arc<dyn FileProvider> {
+        self.reads.clone()
+    }
+
+    /// Restore the cached instance named `name`, if there is one and none of the files
+    /// it was evaluated from changed.
+    pub(crate) fn lookup(&self, name: &str) -> Option<ModuleInstance> {
+        let bytes = self.cache.get(MODULE_CACHE_NAMESPACE, &self.key)?;
+        let entry = serde_json::from_slice::<CachedModule>(&bytes).ok()?;
+        if !entry.files.iter().all(|(path, hash)| {
+            content_hash(self.file_provider.read_file(path).ok().as_deref()) == *hash
+        }) {
+            return None;
+        }
+
+        match self.restore(&entry, name) {
+            Ok((star_module, module)) => {
+                if let Some(load_resolver) = &self.load_resolver {
+                    load_resolver.track_file(&self.source_path);
+                }
+                Some(ModuleInstance {
+                    star_module,
+                    module,
+                    signature: entry.signature,
+                })
+            }
+            Err(e) => {
+                log::debug!(
+                    "Failed to restore cached module {}: {e}",
+                    self.source_path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Store `instance`, evaluated without diagnostics, unless it is not a leaf module
+    /// made of plain data.
+    pub(crate) fn store(&self, ctx: &EvalContext, instance: &ModuleInstance) {
+        let Some(mut entry) = self.capture(&instance.module) else {
+            return;
+        };
+        entry.signature = instance.signature.clone();
+
+        let mut files: HashMap<PathBuf, String> = self.reads.take();
+        for path in ctx.loads_of(&self.source_path) {
+            files.entry(path).or_insert_with_key(|path| {
+                content_hash(self.file_provider.read_file(path).ok().as_deref())
+            });
+        }
+        entry.files = files.into_iter().collect();
+        entry.files.sort();
+
+        if let Ok(bytes) = serde_json::to_vec(&entry) {
+            self.cache.put(MODULE_CACHE_NAMESPACE, &self.key, &bytes);
+        }
+    }
+
+    fn capture(&self, module: &FrozenModuleValue) -> Option<CachedModule> {
+        if !module.requirements().is_empty() {
+            return None;
+        }
+
+        let mut capture = Capture {
+            input_ids: self
+                .input_nets
+                .iter()
+                .enumerate()
+                .filter_map(|(ordinal, net)| match net {
+                    InputValue::Net { id, .. } => Some((*id, ordinal)),
+                    _ => None,
+                })
+                .collect(),
+            introduced: module.introduced_nets(),
+            index: HashMap::new(),
+            nets: Vec::new(),
+        };
+
+        let mut components = Vec::new();
+        for child in module.children() {
+            let component = child.downcast_ref::<FrozenComponentValue>()?;
+            if component.spice_model().is_some() || !component.requirements().is_empty() {
+                return None;
+            }
+            let symbol = component.symbol().downcast_ref::<SymbolValue>()?;
+            let mut connections = SmallMap::new();
+            for (pin, net) in component.connections().iter() {
+                connections.insert(pin.clone(), capture.net(net.to_value(), false)?);
+            }
+            components.push(CachedComponent {
+                name: component.name().to_owned(),
+                mpn: component.mpn().map(str::to_owned),
+                ctype: component.ctype().map(str::to_owned),
+                footprint: component.footprint().to_owned(),
+                prefix: component.prefix().to_owned(),
+                connections,
+                properties: plain_map(component.properties())?,
+                source_path: component.source_path().to_owned(),
+                symbol: CachedSymbol {
+                    name: symbol.name.clone(),
+                    pad_to_signal: symbol.pad_to_signal.clone(),
+                    source_path: symbol.source_path.clone(),
+                    raw_sexp: symbol.raw_sexp.clone(),
+                    units: symbol.units.clone(),
+                },
+            });
+        }
+
+        let mut parameters = Vec::new();
+        for param in module.signature() {
+            parameters.push(CachedParameter {
+                name: param.name.clone(),
+                type_name: type_name(param.type_value.to_value())?.to_owned(),
+                optional: param.optional,
+                default_value: None,
+                is_config: param.is_config,
+                help: param.help.clone(),
+                actual_value: match param.actual_value {
+                    Some(value) => Some(capture.value(value.to_value(), false)?),
+                    None => None,
+                },
+            });
+        }
+        // Defaults last, so that a net only counts as metadata if nothing else uses it
+        for (parameter, param) in parameters.iter_mut().zip(module.signature()) {
+            parameter.default_value = match param.default_value {
+                Some(value) => Some(capture.value(value.to_value(), true)?),
+                None => None,
+            };
+        }
+
+        let introduced_nets = module
+            .introduced_nets()
+            .iter()
+            .map(|(id, name)| (capture.introduced_net(*id, name), name.clone()))
+            .collect();
+
+        Some(CachedModule {
+            files: Vec::new(),
+            nets: capture.nets,
+            introduced_nets,
+            properties: plain_map(module.properties())?,
+            parameters,
+            components,
+            signature: Vec::new(),
+        })
+    }
+
+    fn restore(
+        &self,
+        entry: &CachedModule,
+        name: &str,
+    ) -> anyhow::Result<(FrozenModule, FrozenModuleValue)> {
+        let star_module = Module::new();
+        {
+            let mut eval = Evaluator::new(&star_module);
+
+            let mut nets = Vec::with_capacity(entry.nets.len());
+            for net in &entry.nets {
+                let value = match net.input {
+                    Some(ordinal) => self
+                        .input_nets
+                        .get(ordinal)
+                        .ok_or_else(|| anyhow!("input net {ordinal} is missing"))?
+                        .to_value(&mut eval, None)?,
+                    None => {
+                        let mut properties = SmallMap::new();
+                        for (key, value) in net.properties.iter() {
+                            properties.insert(key.clone(), value.to_value(&mut eval, None)?);
+                        }
+                        let mut value = NetValue::new(
+                            generate_net_id(),
+                            net.name.clone(),
+                            properties,
+                            Value::new_none(),
+                        );
+                        value.original_name = net.original_name.clone();
+                        eval.heap().alloc(value)
+                    }
+                };
+                nets.push(value);
+            }
+            let net = |index: usize| {
+                nets.get(index)
+                    .copied()
+                    .ok_or_else(|| anyhow!("net {index} is missing"))
+            };
+
+            let mut module = ModuleValue::new(name.to_owned(), &self.source_path);
+            for (key, value) in entry.properties.iter() {
+                module.add_property(key.clone(), value.to_value(&mut eval, None)?);
+            }
+            for (index, local_name) in &entry.introduced_nets {
+                let id = net(*index)?
+                    .downcast_ref::<NetValue>()
+                    .map(NetValue::id)
+                    .ok_or_else(|| anyhow!("net {index} is not a net"))?;
+                module.register_net(id, local_name.clone())?;
+            }
+
+            for param in &entry.parameters {
+                let type_value = GLOBALS
+                    .get_frozen(&param.type_name)
+                    .ok_or_else(|| anyhow!("unknown parameter type `{}`", param.type_name))?;
+                let mut restore = |value: &Option<CachedValue>| -> anyhow::Result<_> {
+                    Ok(match value {
+                        Some(CachedValue::Plain(value)) => Some(value.to_value(&mut eval, None)?),
+                        Some(CachedValue::Net(index)) => Some(net(*index)?),
+                        None => None,
+                    })
+                };
+                let default_value = restore(&param.default_value)?;
+                let actual_value = restore(&param.actual_value)?;
+                module.add_parameter_metadata(
+                    param.name.clone(),
+                    type_value.to_value(),
+                    param.optional,
+                    default_value,
+                    param.is_config,
+                    param.help.clone(),
+                    actual_value,
+                );
+            }
+
+            for component in &entry.components {
+                let mut connections = SmallMap::new();
+                for (pin, index) in component.connections.iter() {
+                    connections.insert(pin.clone(), net(*index)?);
+                }
+                let mut properties = SmallMap::new();
+                for (key, value) in component.properties.iter() {
+                    properties.insert(key.clone(), value.to_value(&mut eval, None)?);
+                }
+                let symbol = eval.heap().alloc_complex(SymbolValue {
+                    name: component.symbol.name.clone(),
+                    pad_to_signal: component.symbol.pad_to_signal.clone(),
+                    source_path: component.symbol.source_path.clone(),
+                    raw_sexp: component.symbol.raw_sexp.clone(),
+                    units: component.symbol.units.clone(),
+                });
+                let component = eval.heap().alloc_complex(ComponentValue::new(
+                    component.name.clone(),
+                    component.mpn.clone(),
+                    component.ctype.clone(),
+                    component.footprint.clone(),
+                    component.prefix.clone(),
+                    connections,
+                    properties,
+                    component.source_path.clone(),
+                    symbol,
+                ));
+                module.add_child(component);
+            }
+
+            star_module.set_extra_value(eval.heap().alloc_complex(module));
+        }
+
+        let star_module = star_module
+            .freeze()
+            .map_err(|e| anyhow!("failed to freeze cached module: {e:?}"))?;
+        let module = star_module
+            .extra_value()
+            .and_then(|extra| extra.downcast_ref::<FrozenModuleValue>())
+            .cloned()
+            .ok_or_else(|| anyhow!("cached module has no module value"))?;
+        Ok((star_module, module))
+    }
+}
+
+/// Numbers the nets of a module being stored
+struct Capture<'a> {
+    input_ids: HashMap<NetId, usize>,
+    introduced: &'a SmallMap<NetId, String>,
+    index: HashMap<NetId, usize>,
+    nets: Vec<CachedNet>,
+}
+
+impl Capture<'_> {
+    /// Index of the net `value`, or `None` if it is not a net the module may refer to.
+    /// Nets that only serve as a parameter's recorded default (`metadata`) never take
+    /// part in the design, so those may be any net.
+    fn net(&mut self, value: Value, metadata: bool) -> Option<usize> {
+        let net = value.downcast_ref::<FrozenNetValue>()?;
+        if let Some(index) = self.index.get(&net.id()) {
+            return Some(*index);
+        }
+        let input = self.input_ids.get(&net.id()).copied();
+        if input.is_none() && !metadata && !self.introduced.contains_key(&net.id()) {
+            return None;
+        }
+        if !net.symbol().to_value().is_none() {
+            return None;
+        }
+        let cached = CachedNet {
+            input,
+            name: net.name().to_owned(),
+            original_name: net.original_name.clone(),
+            properties: plain_map(net.properties())?,
+        };
+        Some(self.push(net.id(), cached))
+    }
+
+    /// Index of a net registered with `Net()`, which need not be connected to anything.
+    fn introduced_net(&mut self, id: NetId, name: &str) -> usize {
+        match self.index.get(&id) {
+            Some(index) => *index,
+            None => self.push(
+                id,
+                CachedNet {
+                    input: None,
+                    name: name.to_owned(),
+                    original_name: None,
+                    properties: SmallMap::new(),
+                },
+            ),
+        }
+    }
+
+    fn value(&mut self, value: Value, metadata: bool) -> Option<CachedValue> {
+        if value.downcast_ref::<FrozenNetValue>().is_some() {
+            return self.net(value, metadata).map(CachedValue::Net);
+        }
+        plain(value).map(CachedValue::Plain)
+    }
+
+    fn push(&mut self, id: NetId, net: CachedNet) -> usize {
+        self.index.insert(id, self.nets.len());
+        self.nets.push(net);
+        self.nets.len() - 1
+    }
+}
+
+/// Records the hash of every file read through it
+struct ReadRecorder {
+    inner: Arc<dyn FileProvider>,
+    reads: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl ReadRecorder {
+    fn new(inner: Arc<dyn FileProvider>) -> Self {
+        Self {
+            inner,
+            reads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn take(&self) -> HashMap<PathBuf, String> {
+        std::mem::take(&mut *self.reads.lock().unwrap())
+    }
+}
+
+impl FileProvider for ReadRecorder {
+    fn read_file(&self, path: &Path) -> Result<String, FileProviderError> {
+        let contents = self.inner.read_file(path);
+        self.reads.lock().unwrap().insert(
+            path.to_path_buf(),
+            content_hash(contents.as_ref().ok().map(String::as_str)),
+        );
+        contents
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.inner.is_directory(path)
+    }
+
+    fn list_directory(&self, path: &Path) -> Result<Vec<PathBuf>, FileProviderError> {
+        self.inner.list_directory(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, FileProviderError> {
+        self.inner.canonicalize(path)
+    }
+}
+
+/// Hash of a file's contents, or an empty string if it could not be read
+fn content_hash(contents: Option<&str>) -> String {
+    contents
+        .map(|contents| crate::cache_key([contents.as_bytes()]))
+        .unwrap_or_default()
+}
+
+/// `value` with the ids of its nets replaced by their position in `nets`, or `None` if
+/// it holds anything but plain data and nets
+fn normalize(value: &InputValue, nets: &mut Vec<InputValue>) -> Option<InputValue> {
+    match value {
+        InputValue::Net {
+            id,
+            name,
+            properties,
+        } => {
+            if !properties.values().all(is_plain) {
+                return None;
+            }
+            let ordinal = nets
+                .iter()
+                .position(|net| matches!(net, InputValue::Net { id: other, .. } if other == id))
+                .unwrap_or_else(|| {
+                    nets.push(value.clone());
+                    nets.len() - 1
+                });
+            Some(InputValue::Net {
+                id: ordinal as NetId,
+                name: name.clone(),
+                properties: properties.clone(),
+            })
+        }
+        InputValue::List(items) => items
+            .iter()
+            .map(|item| normalize(item, nets))
+            .collect::<Option<_>>()
+            .map(InputValue::List),
+        InputValue::Dict(map) => map
+            .iter()
+            .map(|(key, value)| Some((key.clone(), normalize(value, nets)?)))
+            .collect::<Option<_>>()
+            .map(InputValue::Dict),
+        value => is_plain(value).then(|| value.clone()),
+    }
+}
+
+fn is_plain(value: &InputValue) -> bool {
+    match value {
+        InputValue::None
+        | InputValue::Bool(_)
+        | InputValue::Int(_)
+        | InputValue::String(_)
+        | InputValue::Float(_) => true,
+        InputValue::List(items) => items.iter().all(is_plain),
+        InputValue::Dict(map) => map.values().all(is_plain),
+        _ => false,
+    }
+}
+
+fn plain(value: Value) -> Option<InputValue> {
+    let value = InputValue::from_value(value);
+    is_plain(&value).then_some(value)
+}
+
+fn plain_map(map: &SmallMap<String, FrozenValue>) -> Option<SmallMap<String, InputValue>> {
+    map.iter()
+        .map(|(key, value)| Some((key.clone(), plain(value.to_value())?)))
+        .collect()
+}
+
+/// The global a parameter type is bound to, for the types a cached module may use
+fn type_name(type_value: Value) -> Option<&'static str> {
+    match TypeInfo::from_value(type_value) {
+        TypeInfo::String => Some("str"),
+        TypeInfo::Int => Some("int"),
+        TypeInfo::Float => Some("float"),
+        TypeInfo::Bool => Some("bool"),
+        TypeInfo::Net => Some("Net"),
+        _ => None,
+    }
+}
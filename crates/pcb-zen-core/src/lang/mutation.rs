@@ -0,0 +1,399 @@
+//! Structured mutations of an evaluated module, used by `pcb test --mutate` to find out
+//! whether a TestBench's checks would catch a broken design.
+//!
+//! A mutant is a copy of the module with one change applied: a component dropped, two
+//! neighbouring pins of a component swapped, the nets on two neighbouring pins shorted
+//! (like a solder bridge) or a passive's value scaled by ten. Checks see the mutant
+//! exactly like the original, through `components()`, `nets()` and `graph()`.
+//!
+//! A physical value record is scaled by rebuilding it with its own record type, so it
+//! still compares and converts like the original. Only records whose type is defined
+//! in a `load()`ed file or used as a `config()` type can be rebuilt; other records are
+//! not mutated.
+
+use std::collections::BTreeSet;
+
+use starlark::environment::{FrozenModule, Module};
+use starlark::eval::Evaluator;
+use starlark::values::float::StarlarkFloat;
+use starlark::values::record::{FrozenRecord, FrozenRecordType};
+use starlark::values::typing::TypeCompiled;
+use starlark::values::{FrozenHeap, FrozenValue, Value, ValueLike};
+
+use crate::lang::module::FrozenModuleValue;
+use crate::{FrozenComponentValue, FrozenNetValue, NetId};
+
+/// Properties holding the value of a passive component
+const VALUE_PROPERTIES: &[&str] = &[
+    "value",
+    "__resistance__",
+    "__capacitance__",
+    "__inductance__",
+];
+
+/// One change to apply to an evaluated module
+#[derive(Debug, Clone)]
+pub(crate) enum Mutation {
+    /// Remove the component at this path
+    DropComponent { component: String },
+    /// Exchange the nets connected to two pins of a component
+    SwapPins {
+        component: String,
+        pins: (String, String),
+    },
+    /// Connect every port of the second net to the first instead. Nets are identified by
+    /// id, as every unnamed net has the same empty name; `names` are for display.
+    ShortNets {
+        nets: (NetId, NetId),
+        names: (String, String),
+    },
+    /// Multiply the value of a passive component by ten, by replacing each of its value
+    /// properties with the scaled value
+    ChangeValue {
+        component: String,
+        values: Vec<(String, FrozenValue)>,
+    },
+}
+
+impl Mutation {
+    /// Stable name of the kind of mutation
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Mutation::DropComponent { .. } => "drop-component",
+            Mutation::SwapPins { .. } => "swap-pins",
+            Mutation::ShortNets { .. } => "short-nets",
+            Mutation::ChangeValue { .. } => "change-value",
+        }
+    }
+}
+
+impl std::fmt::Display for Mutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mutation::DropComponent { component } => write!(f, "drop {component}"),
+            Mutation::SwapPins {
+                component,
+                pins: (a, b),
+            } => write!(f, "swap pins {a} and {b} of {component}"),
+            Mutation::ShortNets { names: (a, b), .. } => {
+                write!(f, "short {} to {}", net_label(a), net_label(b))
+            }
+            Mutation::ChangeValue { component, .. } => {
+                write!(f, "scale the value of {component} x10")
+            }
+        }
+    }
+}
+
+/// Every mutation of `module`, in component order. Scaled values are allocated on
+/// `heap`, rebuilding records with one of `record_types`.
+pub(crate) fn mutations(
+    module: &FrozenModuleValue,
+    heap: &FrozenHeap,
+    record_types: &[FrozenValue],
+) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+    let mut shorts = BTreeSet::new();
+    for (path, value) in module.collect_components("").iter() {
+        let Some(component) = value.downcast_ref::<FrozenComponentValue>() else {
+            continue;
+        };
+        mutations.push(Mutation::DropComponent {
+            component: path.clone(),
+        });
+
+        // Neighbouring pins on different nets; swapping or bridging pins on the same
+        // net would not change the circuit
+        let pins: Vec<(&String, &FrozenNetValue)> = component
+            .connections()
+            .iter()
+            .filter_map(|(pin, net)| net.downcast_ref::<FrozenNetValue>().map(|net| (pin, net)))
+            .collect();
+        for pair in pins.windows(2) {
+            let [(pin_a, net_a), (pin_b, net_b)] = pair else {
+                continue;
+            };
+            if net_a.id() == net_b.id() {
+                continue;
+            }
+            mutations.push(Mutation::SwapPins {
+                component: path.clone(),
+                pins: ((*pin_a).clone(), (*pin_b).clone()),
+            });
+            let (net_a, net_b) = if net_a.id() < net_b.id() {
+                (net_a, net_b)
+            } else {
+                (net_b, net_a)
+            };
+            if shorts.insert((net_a.id(), net_b.id())) {
+                mutations.push(Mutation::ShortNets {
+                    nets: (net_a.id(), net_b.id()),
+                    names: (net_a.name().to_string(), net_b.name().to_string()),
+                });
+            }
+        }
+
+        let values: Vec<(String, FrozenValue)> = VALUE_PROPERTIES
+            .iter()
+            .filter_map(|key| {
+                let value = component.properties().get(*key)?;
+                Some((key.to_string(), scale(*value, heap, record_types)?))
+            })
+            .collect();
+        if !values.is_empty() {
+            mutations.push(Mutation::ChangeValue {
+                component: path.clone(),
+                values,
+            });
+        }
+    }
+    mutations
+}
+
+/// A copy of `module` with `mutation` applied, allocated on `heap`
+pub(crate) fn apply(
+    module: &FrozenModuleValue,
+    mutation: &Mutation,
+    heap: &FrozenHeap,
+) -> FrozenModuleValue {
+    match mutation {
+        Mutation::DropComponent { component } => rebuild(module, "", heap, &mut |path, c| {
+            (path != component.as_str()).then(|| c.clone())
+        }),
+        Mutation::SwapPins {
+            component,
+            pins: (a, b),
+        } => rebuild(module, "", heap, &mut |path, c| {
+            let mut c = c.clone();
+            if path == component.as_str() {
+                let connections = c.connections_mut();
+                if let (Some(net_a), Some(net_b)) =
+                    (connections.get(a).copied(), connections.get(b).copied())
+                {
+                    connections.insert(a.clone(), net_b);
+                    connections.insert(b.clone(), net_a);
+                }
+            }
+            Some(c)
+        }),
+        Mutation::ShortNets { nets: (a, b), .. } => {
+            let Some(target) = find_net(module, *a) else {
+                return module.clone();
+            };
+            rebuild(module, "", heap, &mut |_, c| {
+                let mut c = c.clone();
+                for net in c.connections_mut().values_mut() {
+                    if net_id(*net) == Some(*b) {
+                        *net = target;
+                    }
+                }
+                Some(c)
+            })
+        }
+        Mutation::ChangeValue { component, values } => rebuild(module, "", heap, &mut |path, c| {
+            let mut c = c.clone();
+            if path == component.as_str() {
+                for (key, scaled) in values {
+                    c.properties_mut().insert(key.clone(), *scaled);
+                }
+            }
+            Some(c)
+        }),
+    }
+}
+
+/// Rebuild `module` with every component replaced by `f(path, component)`, dropping
+/// components for which it returns `None`
+fn rebuild(
+    module: &FrozenModuleValue,
+    prefix: &str,
+    heap: &FrozenHeap,
+    f: &mut dyn FnMut(&str, &FrozenComponentValue) -> Option<FrozenComponentValue>,
+) -> FrozenModuleValue {
+    let join = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}.{name}")
+        }
+    };
+
+    let mut children = Vec::with_capacity(module.children().len());
+    for child in module.children() {
+        if let Some(component) = child.downcast_ref::<FrozenComponentValue>() {
+            if let Some(mutated) = f(&join(component.name()), component) {
+                children.push(heap.alloc(mutated));
+            }
+        } else if let Some(submodule) = child.downcast_ref::<FrozenModuleValue>() {
+            let mutated = rebuild(submodule, &join(submodule.name()), heap, f);
+            children.push(heap.alloc(mutated));
+        } else {
+            children.push(*child);
+        }
+    }
+
+    let mut mutated = module.clone();
+    mutated.set_children(children);
+    mutated
+}
+
+fn net_id(value: FrozenValue) -> Option<NetId> {
+    value.downcast_ref::<FrozenNetValue>().map(|net| net.id())
+}
+
+/// The name of a net for display
+fn net_label(name: &str) -> &str {
+    if name.is_empty() {
+        "an unnamed net"
+    } else {
+        name
+    }
+}
+
+/// The net value with id `id`, as connected to some component of `module`
+fn find_net(module: &FrozenModuleValue, id: NetId) -> Option<FrozenValue> {
+    module
+        .collect_components("")
+        .values()
+        .filter_map(|value| value.downcast_ref::<FrozenComponentValue>())
+        .flat_map(|component| component.connections().values().copied())
+        .find(|net| net_id(*net) == Some(id))
+}
+
+/// The record types physical values in `module` can be rebuilt with: those defined by
+/// the files in `loaded` and the `config()` types of `module` and its submodules. `heap`
+/// keeps the loaded modules alive.
+pub(crate) fn record_types(
+    loaded: &[FrozenModule],
+    module: &FrozenModuleValue,
+    heap: &FrozenHeap,
+) -> Vec<FrozenValue> {
+    let is_record_type = |value: &FrozenValue| value.downcast_ref::<FrozenRecordType>().is_some();
+
+    let mut types = Vec::new();
+    for loaded in loaded {
+        heap.add_reference(loaded.frozen_heap());
+        for name in loaded.names() {
+            if let Ok(Some(owned)) = loaded.get_option(name.as_str()) {
+                // SAFETY: `heap` keeps the loaded module alive through the reference above
+                let value = unsafe { owned.unchecked_frozen_value() };
+                if is_record_type(&value) {
+                    types.push(value);
+                }
+            }
+        }
+    }
+
+    let mut modules = vec![module];
+    while let Some(module) = modules.pop() {
+        types.extend(
+            module
+                .signature()
+                .iter()
+                .map(|param| param.type_value)
+                .filter(is_record_type),
+        );
+        modules.extend(
+            module
+                .children()
+                .iter()
+                .filter_map(|child| child.downcast_ref::<FrozenModuleValue>()),
+        );
+    }
+    types
+}
+
+/// `value` multiplied by ten, allocated on `heap`: the leading number of a string like
+/// `4.7kOhm`, or the numeric `value` field of a physical value record. A record is
+/// rebuilt as an instance of the one of `record_types` it belongs to; `None` if it
+/// belongs to none of them or has no numeric `value` field.
+fn scale(
+    value: FrozenValue,
+    heap: &FrozenHeap,
+    record_types: &[FrozenValue],
+) -> Option<FrozenValue> {
+    if let Some(s) = value.unpack_str() {
+        return scale_str(s).map(|scaled| heap.alloc(scaled.as_str()));
+    }
+
+    let record = value.downcast_ref::<FrozenRecord>()?;
+    let env = Module::new();
+    {
+        let fields: Vec<(&str, Value)> = record
+            .iter()
+            .map(|(name, field)| {
+                if name != crate::attrs::record_fields::VALUE {
+                    return Some((name, field.to_value()));
+                }
+                let scaled = match (field.downcast_ref::<StarlarkFloat>(), field.unpack_i32()) {
+                    (Some(f), _) => env.heap().alloc(f.0 * 10.0),
+                    (None, Some(i)) => env.heap().alloc(i.checked_mul(10)?),
+                    (None, None) => return None,
+                };
+                Some((name, scaled))
+            })
+            .collect::<Option<_>>()?;
+        if !fields
+            .iter()
+            .any(|(name, _)| *name == crate::attrs::record_fields::VALUE)
+        {
+            return None;
+        }
+
+        let record_type = record_types.iter().find(|typ| {
+            TypeCompiled::new(typ.to_value(), env.heap())
+                .is_ok_and(|compiled| compiled.matches(value.to_value()))
+        })?;
+        let mut eval = Evaluator::new(&env);
+        let scaled = eval
+            .eval_function(record_type.to_value(), &[], &fields)
+            .ok()?;
+        env.set("scaled", scaled);
+    }
+
+    let frozen = env.freeze().ok()?;
+    let scaled = frozen.get_option("scaled").ok()??;
+    heap.add_reference(frozen.frozen_heap());
+    // SAFETY: `heap` keeps the frozen module alive through the reference added above
+    Some(unsafe { scaled.unchecked_frozen_value() })
+}
+
+/// Multiply the number `s` starts with by ten, by moving its decimal point so the
+/// result is exact: `4.7kOhm` becomes `47kOhm` and `100nF` becomes `1000nF`.
+fn scale_str(s: &str) -> Option<String> {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(end);
+    number.parse::<f64>().ok()?;
+
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    let (shifted, rest) = match frac.chars().next() {
+        Some(digit) => (format!("{int}{digit}"), &frac[1..]),
+        None => (format!("{int}0"), ""),
+    };
+    let shifted = match shifted.trim_start_matches('0') {
+        "" => "0",
+        digits => digits,
+    };
+    Some(if rest.is_empty() {
+        format!("{shifted}{unit}")
+    } else {
+        format!("{shifted}.{rest}{unit}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_the_leading_number_exactly() {
+        assert_eq!(scale_str("4.7kOhm").as_deref(), Some("47kOhm"));
+        assert_eq!(scale_str("100nF").as_deref(), Some("1000nF"));
+        assert_eq!(scale_str("0.1uF").as_deref(), Some("1uF"));
+        assert_eq!(scale_str("2.25V").as_deref(), Some("22.5V"));
+        assert_eq!(scale_str("DNP"), None);
+        assert_eq!(scale_str(""), None);
+    }
+}
@@ -220,6 +220,7 @@ where
         let names_map = args.names_map()?;
 
         let mut symbol_val: Option<Value<'v>> = None;
+        let mut rail_properties: Vec<(&str, Value<'v>)> = Vec::new();
 
         for (key, value) in names_map.iter() {
             match key.as_str() {
@@ -246,10 +247,16 @@ where
                     }
                     symbol_val = Some(*value);
                 }
+                "voltage" => {
+                    rail_properties.push((crate::attrs::VOLTAGE, *value));
+                }
+                "max_current" => {
+                    rail_properties.push((crate::attrs::MAX_CURRENT, *value));
+                }
                 _ => {
                     // No other kwargs accepted
                     return Err(starlark::Error::new_other(anyhow::anyhow!(
-                        "Net() does not accept keyword argument '{}'. Only 'name' and 'symbol' are allowed.",
+                        "Net() does not accept keyword argument '{}'. Only 'name', 'symbol', 'voltage' and 'max_current' are allowed.",
                         key.as_str()
                     )));
                 }
@@ -265,8 +272,11 @@ where
         let original_name = name_pos.or(name_kwarg);
         let net_name = original_name.clone().unwrap_or_default();
 
-        // Initialize with empty properties map
+        // Seed properties with any power rail annotations
         let mut properties = SmallMap::new();
+        for (key, value) in rail_properties {
+            properties.insert(key.to_string(), value);
+        }
 
         // Register this net with the current module context so its local name is
         // recorded at creation time. This ensures explicit names like "EN"
@@ -0,0 +1,844 @@
+//! Datasheet requirements: declarative rules such as "VIN needs at least 10uF of
+//! decoupling to GND" or "EN needs a 10k-100k pull-up to VIN", attached to a component
+//! with `Component(requirements = ...)` or to a module with `requirements(...)`.
+//!
+//! Requirements are written as Zen dicts or as `[[requirement]]` tables in a TOML file,
+//! and are compiled into searches of the [`CircuitGraph`] of every design the component
+//! or module is instantiated in:
+//!
+//! ```toml
+//! [[requirement]]
+//! pin = "EN"
+//! rule = "pull_up"
+//! to = "VIN"
+//! min = "10k"
+//! max = "100k"
+//! source = "Datasheet §7.3.2"
+//! ```
+//!
+//! A capacitor or resistor satisfies a rule when it lies on a path from the pin to `to`
+//! of at most `within` components, itself included (1 unless given). The rest of the
+//! path may only go through series parts, two-pin components such as ferrite beads or
+//! inductors, so `within = 2` accepts a capacitor behind a ferrite bead.
+//!
+//! Net names in a requirement resolve within the instance it is declared in, so two
+//! instances of the same module each check their own `VIN`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use starlark::codemap::ResolvedSpan;
+use starlark::environment::GlobalsBuilder;
+use starlark::errors::EvalSeverity;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::values::dict::DictRef;
+use starlark::values::float::StarlarkFloat;
+use starlark::values::list::ListRef;
+use starlark::values::none::NoneType;
+use starlark::values::record::FrozenRecord;
+use starlark::values::{Value, ValueLike};
+
+use crate::graph::{CircuitGraph, ComponentPath, FactorId, FactorType, PortPath};
+use crate::lang::error::RequirementViolation;
+use crate::lang::evaluator_ext::EvaluatorExt;
+use crate::lang::interface::FrozenInterfaceValue;
+use crate::lang::module::FrozenModuleValue;
+use crate::{Diagnostic, EvalOutput, FrozenComponentValue, FrozenNetValue, NetId};
+
+/// Name under which requirement violations are reported and promoted with `-D`
+pub const REQUIREMENTS_LINT: &str = "datasheet-requirements";
+
+const FIELDS: &[&str] = &["pin", "rule", "to", "min", "max", "within", "source"];
+
+/// A quantity as written in a requirement, e.g. `10uF`, with its value in base units
+#[derive(Debug, Clone)]
+pub struct Quantity {
+    pub text: String,
+    pub value: f64,
+}
+
+/// Bounds on a resistor value; either end may be open
+#[derive(Debug, Clone)]
+pub struct Range {
+    pub min: Option<Quantity>,
+    pub max: Option<Quantity>,
+}
+
+impl Range {
+    fn contains(&self, value: f64) -> bool {
+        self.min
+            .as_ref()
+            .is_none_or(|min| value >= min.value * (1.0 - 1e-9))
+            && self
+                .max
+                .as_ref()
+                .is_none_or(|max| value <= max.value * (1.0 + 1e-9))
+    }
+}
+
+impl std::fmt::Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) => write!(f, "{}-{}", min.text, max.text),
+            (Some(min), None) => write!(f, ">= {}", min.text),
+            (None, Some(max)) => write!(f, "<= {}", max.text),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// At least `min` of capacitance between the pin and `to`
+    Decoupling { to: String, min: Quantity },
+    /// A resistor between the pin and `to`, within `range`
+    PullUp { to: String, range: Range },
+    /// A resistor between the pin and `to` (GND unless given), within `range`
+    PullDown { to: String, range: Range },
+}
+
+/// One datasheet rule about a pin, and where it was declared
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    /// The constrained pin; `Component.pin` for requirements declared on a module
+    pub pin: String,
+    pub rule: Rule,
+    /// The most components on a path from the pin to `to` through a part that satisfies
+    /// the rule, counting the part itself
+    pub within: u32,
+    /// Where the rule comes from, e.g. a datasheet section
+    pub source: Option<String>,
+    /// The file declaring the requirement
+    pub path: String,
+    /// The declaration in `path`, when known
+    pub span: Option<ResolvedSpan>,
+}
+
+impl std::fmt::Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, to, range) = match &self.rule {
+            Rule::Decoupling { to, min } => {
+                write!(f, "{}: decoupling >= {} to {}", self.pin, min.text, to)?;
+                return self.fmt_within(f);
+            }
+            Rule::PullUp { to, range } => ("pull-up", to, range),
+            Rule::PullDown { to, range } => ("pull-down", to, range),
+        };
+        write!(f, "{}: {} to {}", self.pin, kind, to)?;
+        if range.min.is_some() || range.max.is_some() {
+            write!(f, " {range}")?;
+        }
+        self.fmt_within(f)
+    }
+}
+
+impl Requirement {
+    fn fmt_within(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.within != 1 {
+            write!(f, " within {}", self.within)?;
+        }
+        Ok(())
+    }
+
+    fn from_fields(
+        fields: BTreeMap<String, String>,
+        path: &str,
+        span: Option<ResolvedSpan>,
+    ) -> anyhow::Result<Self> {
+        if let Some(unknown) = fields.keys().find(|key| !FIELDS.contains(&key.as_str())) {
+            bail!(
+                "unknown requirement field '{}' (expected one of: {})",
+                unknown,
+                FIELDS.join(", ")
+            );
+        }
+
+        let pin = fields
+            .get("pin")
+            .cloned()
+            .ok_or_else(|| anyhow!("requirement is missing `pin`"))?;
+        let rule = fields
+            .get("rule")
+            .ok_or_else(|| anyhow!("requirement on {pin} is missing `rule`"))?;
+        let quantity = |name: &str| -> anyhow::Result<Option<Quantity>> {
+            fields
+                .get(name)
+                .map(|text| {
+                    parse_quantity(text)
+                        .map(|value| Quantity {
+                            text: text.clone(),
+                            value,
+                        })
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "requirement `{}` must be a quantity such as \"10uF\" or \"4.7k\", got '{}'",
+                                name,
+                                text
+                            )
+                        })
+                })
+                .transpose()
+        };
+        let to = |default: Option<&str>| {
+            fields
+                .get("to")
+                .cloned()
+                .or_else(|| default.map(str::to_string))
+                .ok_or_else(|| anyhow!("{rule} requirement on {pin} needs `to`"))
+        };
+        let range = || -> anyhow::Result<Range> {
+            Ok(Range {
+                min: quantity("min")?,
+                max: quantity("max")?,
+            })
+        };
+
+        let rule = match rule.as_str() {
+            "decoupling" => {
+                if fields.contains_key("max") {
+                    bail!("decoupling requirement on {pin} takes `min`, not `max`");
+                }
+                Rule::Decoupling {
+                    to: to(Some("GND"))?,
+                    min: quantity("min")?
+                        .ok_or_else(|| anyhow!("decoupling requirement on {pin} needs `min`"))?,
+                }
+            }
+            "pull_up" => Rule::PullUp {
+                to: to(None)?,
+                range: range()?,
+            },
+            "pull_down" => Rule::PullDown {
+                to: to(Some("GND"))?,
+                range: range()?,
+            },
+            other => bail!(
+                "unknown requirement rule '{}' (expected one of: decoupling, pull_up, pull_down)",
+                other
+            ),
+        };
+
+        let within = match fields.get("within") {
+            Some(text) => text
+                .parse::<u32>()
+                .ok()
+                .filter(|within| *within >= 1)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "requirement `within` must be a number of components of at least 1, got '{}'",
+                        text
+                    )
+                })?,
+            None => 1,
+        };
+
+        Ok(Requirement {
+            pin,
+            rule,
+            within,
+            source: fields.get("source").cloned(),
+            path: path.to_string(),
+            span,
+        })
+    }
+}
+
+/// Requirements given to `Component(requirements = ...)` or `requirements()`: a list of
+/// dicts, or the path of a TOML file with `[[requirement]]` tables, resolved like `load()`
+pub(crate) fn parse_requirements<'v>(
+    value: Value<'v>,
+    eval: &Evaluator<'v, '_, '_>,
+) -> anyhow::Result<Vec<Requirement>> {
+    if let Some(file) = value.unpack_str() {
+        return load_requirements_file(file, eval);
+    }
+
+    let list = ListRef::from_value(value).ok_or_else(|| {
+        anyhow!(
+            "`requirements` must be a list of dicts or the path of a TOML file, got {}",
+            value.get_type()
+        )
+    })?;
+    let path = eval.source_path().unwrap_or_default();
+    let span = eval
+        .call_stack_top_location()
+        .map(|location| location.resolve_span());
+
+    list.iter()
+        .map(|item| {
+            let dict = DictRef::from_value(item).ok_or_else(|| {
+                anyhow!("each requirement must be a dict, got {}", item.get_type())
+            })?;
+            let mut fields = BTreeMap::new();
+            for (key, value) in dict.iter() {
+                let key = key
+                    .unpack_str()
+                    .ok_or_else(|| anyhow!("requirement keys must be strings"))?;
+                let text = if let Some(s) = value.unpack_str() {
+                    s.to_string()
+                } else if let Some(n) = value.unpack_i32() {
+                    n.to_string()
+                } else if let Some(f) = value.downcast_ref::<StarlarkFloat>() {
+                    f.0.to_string()
+                } else {
+                    bail!(
+                        "requirement `{}` must be a string or a number, got {}",
+                        key,
+                        value.get_type()
+                    );
+                };
+                fields.insert(key.to_string(), text);
+            }
+            Requirement::from_fields(fields, &path, span)
+        })
+        .collect()
+}
+
+fn load_requirements_file(file: &str, eval: &Evaluator) -> anyhow::Result<Vec<Requirement>> {
+    let eval_context = eval
+        .eval_context()
+        .ok_or_else(|| anyhow!("No evaluation context available"))?;
+    let load_resolver = eval_context
+        .get_load_resolver()
+        .ok_or_else(|| anyhow!("No load resolver available"))?;
+    let current_file = eval_context
+        .get_source_path()
+        .ok_or_else(|| anyhow!("No source path available"))?;
+
+    let resolved_path = load_resolver
+        .resolve_path(file, current_file)
+        .map_err(|e| anyhow!("Failed to resolve requirements file '{}': {}", file, e))?;
+    let contents = eval_context
+        .get_file_provider()
+        .read_file(&resolved_path)
+        .map_err(|e| {
+            anyhow!(
+                "Failed to read requirements file '{}': {}",
+                resolved_path.display(),
+                e
+            )
+        })?;
+    parse_requirements_toml(&contents, &resolved_path.to_string_lossy())
+}
+
+/// Requirements from the `[[requirement]]` tables of a TOML file at `path`
+fn parse_requirements_toml(contents: &str, path: &str) -> anyhow::Result<Vec<Requirement>> {
+    let table: toml::Table =
+        toml::from_str(contents).map_err(|e| anyhow!("Failed to parse {}: {}", path, e))?;
+    if let Some(key) = table.keys().find(|key| *key != "requirement") {
+        bail!("unexpected key '{key}' in {path}, expected [[requirement]] tables");
+    }
+    let Some(entries) = table.get("requirement") else {
+        return Ok(Vec::new());
+    };
+    let entries = entries
+        .as_array()
+        .ok_or_else(|| anyhow!("`requirement` in {path} must be [[requirement]] tables"))?;
+
+    entries
+        .iter()
+        .map(|entry| {
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| anyhow!("`requirement` in {path} must be [[requirement]] tables"))?;
+            let mut fields = BTreeMap::new();
+            for (key, value) in entry {
+                let text = match value {
+                    toml::Value::String(s) => s.clone(),
+                    toml::Value::Integer(n) => n.to_string(),
+                    toml::Value::Float(f) => f.to_string(),
+                    other => bail!(
+                        "requirement `{}` in {} must be a string or a number, got {}",
+                        key,
+                        path,
+                        other.type_str()
+                    ),
+                };
+                fields.insert(key.clone(), text);
+            }
+            Requirement::from_fields(fields, path, None)
+        })
+        .collect()
+}
+
+/// The value of a quantity such as `10uF`, `4.7kOhm` or `100n`, in base units
+fn parse_quantity(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let text = ["Ohms", "Ohm", "Ω", "F", "H"]
+        .iter()
+        .find_map(|unit| text.strip_suffix(unit))
+        .unwrap_or(text)
+        .trim_end();
+
+    let mut chars = text.chars();
+    let scale = match chars.next_back()? {
+        'p' => 1e-12,
+        'n' => 1e-9,
+        'u' | 'µ' => 1e-6,
+        'm' => 1e-3,
+        'k' => 1e3,
+        'M' => 1e6,
+        'G' => 1e9,
+        _ => return text.parse().ok(),
+    };
+    chars
+        .as_str()
+        .trim_end()
+        .parse::<f64>()
+        .ok()
+        .map(|n| n * scale)
+}
+
+/// `value` with an SI prefix, e.g. `4.7uF`
+fn format_quantity(value: f64, unit: &str) -> String {
+    const PREFIXES: &[(f64, &str)] = &[
+        (1e9, "G"),
+        (1e6, "M"),
+        (1e3, "k"),
+        (1.0, ""),
+        (1e-3, "m"),
+        (1e-6, "u"),
+        (1e-9, "n"),
+        (1e-12, "p"),
+    ];
+    let (scale, prefix) = PREFIXES
+        .iter()
+        .copied()
+        .find(|(scale, _)| value.abs() >= scale * (1.0 - 1e-9))
+        .unwrap_or((1e-12, "p"));
+    let scaled = (value / scale * 1000.0).round() / 1000.0;
+    format!("{scaled}{prefix}{unit}")
+}
+
+/// The value of a passive: its `property` (e.g. `__capacitance__`), or its `value`
+/// property when its reference designator prefix is `prefix`
+fn passive_value(component: &FrozenComponentValue, property: &str, prefix: &str) -> Option<f64> {
+    let properties = component.properties();
+    let value = properties.get(property).or_else(|| {
+        properties
+            .get("value")
+            .filter(|_| component.prefix() == prefix)
+    })?;
+
+    let value = value.to_value();
+    if let Some(s) = value.unpack_str() {
+        parse_quantity(s)
+    } else if let Some(n) = value.unpack_i32() {
+        Some(n as f64)
+    } else if let Some(f) = value.downcast_ref::<StarlarkFloat>() {
+        Some(f.0)
+    } else {
+        value
+            .downcast_ref::<FrozenRecord>()?
+            .iter()
+            .find(|(name, _)| *name == crate::attrs::record_fields::VALUE)
+            .and_then(|(_, field)| field.downcast_ref::<StarlarkFloat>().map(|f| f.0))
+    }
+}
+
+/// A component of the design, as far as requirements are concerned
+struct Part {
+    path: String,
+    pins: HashMap<String, NetId>,
+    capacitance: Option<f64>,
+    resistance: Option<f64>,
+}
+
+/// The connectivity of an evaluated design
+struct Design {
+    parts: Vec<Part>,
+    index: HashMap<String, usize>,
+    /// Nets are named by [`net_factor`], so that nets with the same name stay apart
+    graph: CircuitGraph,
+    /// The nets each module instance can name, by instance path ("" for the root): its
+    /// io() nets and the nets it introduces
+    scopes: HashMap<String, HashMap<String, NetId>>,
+}
+
+impl Design {
+    fn new(root: &FrozenModuleValue) -> anyhow::Result<Self> {
+        let mut parts = Vec::new();
+        let mut index = HashMap::new();
+        let mut net_to_ports: HashMap<String, Vec<PortPath>> = HashMap::new();
+        let mut component_pins: HashMap<ComponentPath, Vec<String>> = HashMap::new();
+        for (path, value) in root.collect_components("").iter() {
+            let Some(component) = value.downcast_ref::<FrozenComponentValue>() else {
+                continue;
+            };
+            let mut pins = HashMap::new();
+            for (pin, net) in component.connections().iter() {
+                if let Some(net) = net.downcast_ref::<FrozenNetValue>() {
+                    pins.insert(pin.clone(), net.id());
+                    net_to_ports
+                        .entry(net_factor(net.id()))
+                        .or_default()
+                        .push(PortPath::new(path.as_str(), pin.as_str()));
+                }
+            }
+            if !pins.is_empty() {
+                component_pins.insert(path.as_str().into(), pins.keys().cloned().collect());
+            }
+            index.insert(path.clone(), parts.len());
+            parts.push(Part {
+                path: path.clone(),
+                pins,
+                capacitance: passive_value(component, "__capacitance__", "C"),
+                resistance: passive_value(component, "__resistance__", "R"),
+            });
+        }
+        let graph = CircuitGraph::new(net_to_ports, component_pins, Default::default())?;
+        let mut design = Design {
+            parts,
+            index,
+            graph,
+            scopes: HashMap::new(),
+        };
+
+        let mut modules = vec![(String::new(), root)];
+        while let Some((path, module)) = modules.pop() {
+            for child in module.children().iter() {
+                if let Some(submodule) = child.downcast_ref::<FrozenModuleValue>() {
+                    modules.push((join(&path, submodule.name()), submodule));
+                }
+            }
+
+            let mut names = HashMap::new();
+            for param in module.signature().iter().filter(|param| !param.is_config) {
+                let Some(actual_value) = &param.actual_value else {
+                    continue;
+                };
+                // A net passed to `io("GND", Net)` is GND here, whatever the caller named it
+                if let Some(net) = actual_value.downcast_ref::<FrozenNetValue>() {
+                    names.insert(param.name.clone(), net.id());
+                }
+                collect_nets(actual_value.to_value(), &mut names);
+            }
+            for (id, name) in module.introduced_nets().iter() {
+                names.insert(name.clone(), *id);
+            }
+            design.scopes.insert(path, names);
+        }
+        Ok(design)
+    }
+
+    fn pin_net(&self, component: &str, pin: &str) -> Option<NetId> {
+        let part = &self.parts[*self.index.get(component)?];
+        part.pins.get(pin).copied()
+    }
+
+    /// The net of `name`: a pin of `owner`, a `Component.pin` below `scope`, or a net
+    /// named in the module instance `scope`
+    fn net(&self, scope: &str, owner: Option<&str>, name: &str) -> Option<NetId> {
+        owner
+            .and_then(|owner| self.pin_net(owner, name))
+            .or_else(|| {
+                let (component, pin) = name.rsplit_once('.')?;
+                self.pin_net(&join(scope, component), pin)
+            })
+            .or_else(|| self.scopes.get(scope)?.get(name).copied())
+    }
+
+    /// The part a factor of the graph stands for, if it is a component
+    fn part(&self, factor: FactorId) -> Option<&Part> {
+        match self.graph.factor_type(factor) {
+            FactorType::Component(path) => Some(&self.parts[*self.index.get(path)?]),
+            FactorType::Net(_) => None,
+        }
+    }
+
+    /// Two-pin parts for which `candidate` holds that lie on a path from net `a` to net
+    /// `b` of at most `within` components, the part included. The rest of the path may
+    /// only pass through series parts: two-pin parts that are not candidates.
+    fn between(
+        &self,
+        a: NetId,
+        b: NetId,
+        within: u32,
+        candidate: impl Fn(&Part) -> bool,
+    ) -> Vec<&Part> {
+        let net_ports = |net: NetId| {
+            self.graph
+                .factor_id(&net_factor(net))
+                .map(|factor| self.graph.factor_ports(factor))
+        };
+        let (Some(a_ports), Some(b_ports)) = (net_ports(a), net_ports(b)) else {
+            return Vec::new();
+        };
+        let series = |factor: FactorId| {
+            self.part(factor)
+                .is_some_and(|part| part.pins.len() == 2 && !candidate(part))
+        };
+        let from_a = self.graph.search(a_ports, &series, Some(within - 1));
+        let from_b = self.graph.search(b_ports, &series, Some(within - 1));
+
+        self.parts
+            .iter()
+            .filter(|part| part.pins.len() == 2 && candidate(part))
+            .filter(|part| {
+                let ports: Vec<_> = part
+                    .pins
+                    .keys()
+                    .filter_map(|pin| {
+                        self.graph
+                            .port_id(&PortPath::new(part.path.as_str(), pin.as_str()))
+                    })
+                    .collect();
+                let [p, q] = ports[..] else {
+                    return false;
+                };
+                let hops = |x, y| Some(from_a.distance(x)? + 1 + from_b.distance(y)?);
+                [hops(p, q), hops(q, p)]
+                    .into_iter()
+                    .flatten()
+                    .any(|hops| hops <= within)
+            })
+            .collect()
+    }
+
+    /// What is wrong with the design with respect to `requirement`, if anything
+    fn check(&self, requirement: &Requirement, scope: &str, owner: Option<&str>) -> Option<String> {
+        let subject = join(owner.unwrap_or(scope), &requirement.pin);
+        let Some(pin) = self.net(scope, owner, &requirement.pin) else {
+            return Some(format!("{subject} is not a pin or net of the design"));
+        };
+        let (Rule::Decoupling { to, .. } | Rule::PullUp { to, .. } | Rule::PullDown { to, .. }) =
+            &requirement.rule;
+        let Some(target) = self.net(scope, owner, to) else {
+            return Some(format!("{subject}: no pin or net named '{to}'"));
+        };
+        if pin == target {
+            return Some(format!("{subject} is connected directly to {to}"));
+        }
+
+        let (kind, range) = match &requirement.rule {
+            Rule::Decoupling { min, .. } => {
+                let total: f64 = self
+                    .between(pin, target, requirement.within, |part| {
+                        part.capacitance.is_some()
+                    })
+                    .into_iter()
+                    .filter_map(|part| part.capacitance)
+                    .sum();
+                return if total == 0.0 {
+                    Some(format!(
+                        "{subject} has no decoupling capacitor to {to}, needs at least {}",
+                        min.text
+                    ))
+                } else if total < min.value * (1.0 - 1e-9) {
+                    Some(format!(
+                        "{subject} has {} of decoupling to {to}, needs at least {}",
+                        format_quantity(total, "F"),
+                        min.text
+                    ))
+                } else {
+                    None
+                };
+            }
+            Rule::PullUp { range, .. } => ("pull-up", range),
+            Rule::PullDown { range, .. } => ("pull-down", range),
+        };
+
+        let resistors: Vec<(&str, f64)> = self
+            .between(pin, target, requirement.within, |part| {
+                part.resistance.is_some()
+            })
+            .into_iter()
+            .filter_map(|part| Some((part.path.as_str(), part.resistance?)))
+            .collect();
+        match resistors.as_slice() {
+            [] => Some(format!("{subject} has no {kind} resistor to {to}")),
+            _ if resistors.iter().any(|(_, value)| range.contains(*value)) => None,
+            [(path, value), ..] => Some(format!(
+                "{subject} {kind} {path} is {}, expected {range}",
+                format_quantity(*value, "Ohm")
+            )),
+        }
+    }
+}
+
+/// Add the nets in `value`, a net or an interface of nets, to `names`
+fn collect_nets(value: Value, names: &mut HashMap<String, NetId>) {
+    if let Some(net) = value.downcast_ref::<FrozenNetValue>() {
+        names.insert(net.name().to_string(), net.id());
+    } else if let Some(iface) = value.downcast_ref::<FrozenInterfaceValue>() {
+        for field in iface.fields().values() {
+            collect_nets(field.to_value(), names);
+        }
+    }
+}
+
+/// The name of a net in [`Design::graph`]
+fn net_factor(net: NetId) -> String {
+    format!("#{net}")
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}.{name}")
+    }
+}
+
+fn violation(requirement: &Requirement, instance: String, message: String) -> Diagnostic {
+    let body = match &requirement.source {
+        Some(source) => format!("[{REQUIREMENTS_LINT}] {message} ({source})"),
+        None => format!("[{REQUIREMENTS_LINT}] {message}"),
+    };
+    let violation = RequirementViolation {
+        instance,
+        requirement: requirement.to_string(),
+        source: requirement.source.clone(),
+        message,
+    };
+    Diagnostic {
+        path: requirement.path.clone(),
+        span: requirement.span,
+        severity: EvalSeverity::Warning,
+        body,
+        call_stack: None,
+        child: None,
+        source_error: Some(Arc::new(violation.into())),
+    }
+}
+
+/// Check the requirements declared on every component and module instantiated in the
+/// design of `output`, returning a warning, at the declaration, per requirement the
+/// design does not meet.
+pub fn check_requirements(output: &EvalOutput) -> Vec<Diagnostic> {
+    let root = &output.sch_module;
+    let design = match Design::new(root) {
+        Ok(design) => design,
+        Err(e) => {
+            let message = format!("Failed to build the circuit graph for requirements: {e}");
+            return vec![Diagnostic::new(
+                message,
+                EvalSeverity::Error,
+                Path::new(root.source_path()),
+            )];
+        }
+    };
+    let mut diagnostics = Vec::new();
+
+    for (path, value) in root.collect_components("").iter() {
+        let Some(component) = value.downcast_ref::<FrozenComponentValue>() else {
+            continue;
+        };
+        // Names resolve in the module instance that created the component
+        let scope = path.rsplit_once('.').map_or("", |(scope, _)| scope);
+        for requirement in component.requirements() {
+            if let Some(message) = design.check(requirement, scope, Some(path.as_str())) {
+                diagnostics.push(violation(requirement, path.clone(), message));
+            }
+        }
+    }
+
+    let mut modules = vec![(String::new(), root)];
+    while let Some((path, module)) = modules.pop() {
+        for child in module.children().iter() {
+            if let Some(submodule) = child.downcast_ref::<FrozenModuleValue>() {
+                modules.push((join(&path, submodule.name()), submodule));
+            }
+        }
+        for requirement in module.requirements() {
+            if let Some(message) = design.check(requirement, &path, None) {
+                let instance = if path.is_empty() {
+                    module.name().to_string()
+                } else {
+                    path.clone()
+                };
+                diagnostics.push(violation(requirement, instance, message));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[starlark_module]
+pub(crate) fn requirements_globals(builder: &mut GlobalsBuilder) {
+    /// Declare datasheet requirements on the current module: a list of dicts, or the path
+    /// of a TOML file with `[[requirement]]` tables. Pins are named `Component.pin`
+    /// relative to the module, e.g. `{"pin": "U1.EN", "rule": "pull_up", "to": "U1.VIN"}`.
+    fn requirements<'v>(
+        #[starlark(require = pos)] rules: Value<'v>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<NoneType> {
+        let requirements = parse_requirements(rules, eval)?;
+        if let Some(mut module) = eval.module_value_mut() {
+            module.add_requirements(requirements);
+        }
+        Ok(NoneType)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quantities_with_prefixes_and_units() {
+        assert_eq!(parse_quantity("10uF"), Some(10.0 * 1e-6));
+        assert_eq!(parse_quantity("4.7kOhm"), Some(4700.0));
+        assert_eq!(parse_quantity("100n"), Some(100.0 * 1e-9));
+        assert_eq!(parse_quantity("1M"), Some(1e6));
+        assert_eq!(parse_quantity("22"), Some(22.0));
+        assert_eq!(parse_quantity("DNP"), None);
+        assert_eq!(format_quantity(4.7e-6, "F"), "4.7uF");
+        assert_eq!(format_quantity(100e3, "Ohm"), "100kOhm");
+    }
+
+    #[test]
+    fn parses_requirement_tables() {
+        let requirements = parse_requirements_toml(
+            r#"
+[[requirement]]
+pin = "VIN"
+rule = "decoupling"
+min = "10uF"
+source = "Datasheet §8.2.1"
+
+[[requirement]]
+pin = "EN"
+rule = "pull_up"
+to = "VIN"
+min = "10k"
+max = "100k"
+within = 2
+"#,
+            "/p/part.toml",
+        )
+        .unwrap();
+
+        let rendered: Vec<String> = requirements.iter().map(|r| r.to_string()).collect();
+        assert_eq!(
+            rendered,
+            [
+                "VIN: decoupling >= 10uF to GND",
+                "EN: pull-up to VIN 10k-100k within 2"
+            ]
+        );
+        assert_eq!(requirements[0].source.as_deref(), Some("Datasheet §8.2.1"));
+        assert_eq!(requirements[1].path, "/p/part.toml");
+
+        let error = parse_requirements_toml(
+            "[[requirement]]\npin = \"EN\"\nrule = \"pull_up\"\n",
+            "/p/part.toml",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "pull_up requirement on EN needs `to`");
+
+        let error = parse_requirements_toml(
+            "[[requirement]]\npin = \"VIN\"\nrule = \"decoupling\"\nmin = \"1uF\"\nwithin = 0\n",
+            "/p/part.toml",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "requirement `within` must be a number of components of at least 1, got '0'"
+        );
+    }
+}
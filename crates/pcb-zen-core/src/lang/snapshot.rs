@@ -0,0 +1,185 @@
+//! Golden netlist snapshots: a canonical view of the connectivity of an evaluated module
+//! that TestBench checks compare against checked-in `.snap` files.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use starlark::environment::GlobalsBuilder;
+use starlark::errors::EvalSeverity;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::values::Value;
+
+use crate::lang::error::SnapshotMismatch;
+use crate::lang::evaluator_ext::EvaluatorExt;
+use crate::lang::module::FrozenModuleValue;
+use crate::{Diagnostic, FrozenComponentValue, FrozenNetValue, NetId};
+
+/// Directory next to the evaluated file that holds its netlist snapshots
+pub const SNAPSHOT_DIR: &str = "__snapshots__";
+
+/// Render the connectivity of `module` as one `NET: port, port` line per net, with
+/// ports named `component.pin`. Nets and ports are sorted so the result does not depend
+/// on evaluation order or net IDs; unnamed nets are labelled `~` plus their first port.
+pub fn netlist_snapshot(module: &FrozenModuleValue) -> String {
+    let mut nets: HashMap<NetId, (String, Vec<String>)> = HashMap::new();
+    for (comp_path, comp_val) in module.collect_components("").iter() {
+        let Some(component) = comp_val.downcast_ref::<FrozenComponentValue>() else {
+            continue;
+        };
+        for (pin_name, net_val) in component.connections().iter() {
+            if let Some(net) = net_val.downcast_ref::<FrozenNetValue>() {
+                nets.entry(net.id())
+                    .or_insert_with(|| (net.name().to_string(), Vec::new()))
+                    .1
+                    .push(format!("{comp_path}.{pin_name}"));
+            }
+        }
+    }
+
+    let mut lines: Vec<String> = nets
+        .into_values()
+        .map(|(name, mut ports)| {
+            ports.sort();
+            let label = if name.is_empty() {
+                format!("~{}", ports[0])
+            } else {
+                name
+            };
+            format!("{label}: {}", ports.join(", "))
+        })
+        .collect();
+    lines.sort();
+
+    let mut snapshot = lines.join("\n");
+    snapshot.push('\n');
+    snapshot
+}
+
+/// The net of each port in a snapshot
+fn port_nets(snapshot: &str) -> BTreeMap<&str, &str> {
+    snapshot
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .flat_map(|(net, ports)| ports.split(", ").map(move |port| (port, net)))
+        .collect()
+}
+
+/// Describe how connectivity changed from `expected` to `actual`, one line per port that
+/// moved to another net, was added or was removed.
+pub fn diff_snapshots(expected: &str, actual: &str) -> Vec<String> {
+    let before = port_nets(expected);
+    let after = port_nets(actual);
+
+    let mut changes = Vec::new();
+    for (port, old_net) in &before {
+        match after.get(port) {
+            Some(new_net) if new_net != old_net => {
+                changes.push(format!("{port}: {old_net} -> {new_net}"));
+            }
+            Some(_) => {}
+            None => changes.push(format!("{port}: removed from {old_net}")),
+        }
+    }
+    for (port, new_net) in &after {
+        if !before.contains_key(port) {
+            changes.push(format!("{port}: added to {new_net}"));
+        }
+    }
+    changes.sort();
+    changes
+}
+
+/// File name for the snapshot called `name`, with characters that are awkward in paths
+/// replaced by `_`
+fn snapshot_file_name(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{stem}.snap")
+}
+
+#[starlark_module]
+pub(crate) fn snapshot_globals(builder: &mut GlobalsBuilder) {
+    /// Compare the netlist of an evaluated `module` with `__snapshots__/<name>.snap` next to
+    /// the current file, failing with the ports that moved between nets when they differ.
+    /// `name` defaults to the module name, which for TestBench cases is `<bench>__<case>`.
+    /// Run `pcb test --update-snapshots` to record new or changed snapshots.
+    fn snapshot<'v>(
+        #[starlark(require = pos)] module: Value<'v>,
+        #[starlark(require = named)] name: Option<String>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<Value<'v>> {
+        let module = module
+            .downcast_ref::<FrozenModuleValue>()
+            .ok_or_else(|| anyhow::anyhow!("snapshot() expects an evaluated module"))?;
+        let name = name.unwrap_or_else(|| module.name().to_string());
+
+        let eval_context = eval
+            .eval_context()
+            .ok_or_else(|| anyhow::anyhow!("No evaluation context available"))?;
+        let current_file = eval_context
+            .get_source_path()
+            .ok_or_else(|| anyhow::anyhow!("No source path available"))?;
+        let path: PathBuf = current_file
+            .parent()
+            .unwrap_or(current_file)
+            .join(SNAPSHOT_DIR)
+            .join(snapshot_file_name(&name));
+
+        let actual = netlist_snapshot(module);
+        let file_provider = eval_context.get_file_provider();
+        let expected = file_provider
+            .exists(&path)
+            .then(|| file_provider.read_file(&path).ok())
+            .flatten()
+            .map(|contents| contents.replace("\r\n", "\n"));
+
+        let message = match &expected {
+            Some(expected) if *expected == actual => return Ok(Value::new_none()),
+            Some(expected) => {
+                let changes = diff_snapshots(expected, &actual);
+                format!(
+                    "netlist snapshot '{}' does not match {}:\n  {}\nrun `pcb test --update-snapshots` to accept the change",
+                    name,
+                    path.display(),
+                    changes.join("\n  ")
+                )
+            }
+            None => format!(
+                "netlist snapshot '{}' has not been recorded; run `pcb test --update-snapshots` to create {}",
+                name,
+                path.display()
+            ),
+        };
+
+        // Hand the new snapshot to `pcb test --update-snapshots`
+        if let (Some(ctx), Some(location)) = (eval.context_value(), eval.call_stack_top_location())
+        {
+            ctx.add_diagnostic(Diagnostic {
+                path: location.filename().to_string(),
+                span: Some(location.resolve_span()),
+                severity: EvalSeverity::Advice,
+                body: format!("netlist snapshot '{}' needs updating", name),
+                call_stack: Some(eval.call_stack().clone()),
+                child: None,
+                source_error: Some(std::sync::Arc::new(
+                    SnapshotMismatch {
+                        path,
+                        contents: actual,
+                    }
+                    .into(),
+                )),
+            });
+        }
+
+        Err(anyhow::anyhow!(message))
+    }
+}
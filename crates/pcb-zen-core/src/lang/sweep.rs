@@ -0,0 +1,318 @@
+#![allow(clippy::needless_lifetimes)]
+
+//! Generated TestBench cases: the cartesian product of a set of input axes, or a
+//! reproducible random sample of it, plus shrinking of failing combinations.
+//!
+//! Only the listed values of each axis are ever run: sampling picks among their
+//! combinations, and shrinking moves axes to values listed earlier. A sweep has at most
+//! [`MAX_CASES`] cases.
+
+use std::collections::BTreeSet;
+
+use allocative::Allocative;
+use starlark::environment::GlobalsBuilder;
+use starlark::values::enumeration::{EnumType, FrozenEnumType};
+use starlark::{
+    any::ProvidesStaticType,
+    collections::SmallMap,
+    eval::Evaluator,
+    starlark_complex_value, starlark_module,
+    values::{
+        dict::DictRef, float::StarlarkFloat, list::AllocList, list::ListRef, starlark_value,
+        Coerce, Freeze, FreezeResult, Heap, NoSerialize, StarlarkValue, Trace, Value,
+        ValueLifetimeless, ValueLike,
+    },
+};
+
+/// The most cases a sweep may run; larger sweeps must set `samples`.
+pub const MAX_CASES: usize = 10_000;
+
+/// Inputs swept by a TestBench: every combination of the values of `axes`, or `samples`
+/// of them drawn with `seed`, each merged with the `fixed` inputs.
+#[derive(Clone, Coerce, Trace, ProvidesStaticType, NoSerialize, Allocative, Freeze)]
+#[repr(C)]
+pub struct SweepValueGen<V: ValueLifetimeless> {
+    /// Values of each swept input, in declaration order
+    axes: SmallMap<String, Vec<V>>,
+    /// Inputs passed unchanged to every case
+    fixed: SmallMap<String, V>,
+    /// Number of combinations to run instead of all of them
+    samples: Option<u32>,
+    seed: u64,
+    /// Whether to search for a simpler failing combination after a failure
+    shrink: bool,
+}
+
+starlark_complex_value!(pub SweepValue);
+
+#[starlark_value(type = "Sweep")]
+impl<'v, V: ValueLike<'v>> StarlarkValue<'v> for SweepValueGen<V> where Self: ProvidesStaticType<'v> {}
+
+impl<'v, V: ValueLike<'v>> std::fmt::Display for SweepValueGen<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let axes: Vec<&str> = self.axes.keys().map(|k| k.as_str()).collect();
+        write!(f, "Sweep({}: {} cases)", axes.join(", "), self.case_count())
+    }
+}
+
+impl<'v, V: ValueLike<'v>> std::fmt::Debug for SweepValueGen<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sweep")
+            .field("axes", &self.axes.keys().collect::<Vec<_>>())
+            .field("fixed", &self.fixed.keys().collect::<Vec<_>>())
+            .field("samples", &self.samples)
+            .field("seed", &self.seed)
+            .finish()
+    }
+}
+
+/// One generated test case.
+pub struct SweepCase<'v> {
+    /// `axis=value` pairs identifying the case
+    pub name: String,
+    /// Every input of the case, fixed ones first
+    pub params: Vec<(String, Value<'v>)>,
+    /// Index of the chosen value on each axis
+    point: Vec<usize>,
+}
+
+impl<'v, V: ValueLike<'v>> SweepValueGen<V> {
+    pub fn shrink(&self) -> bool {
+        self.shrink
+    }
+
+    /// Number of cases the sweep runs.
+    pub fn case_count(&self) -> usize {
+        let total = self.combinations();
+        self.samples.map_or(total, |n| total.min(n as usize))
+    }
+
+    fn combinations(&self) -> usize {
+        self.axes
+            .values()
+            .fold(1usize, |total, values| total.saturating_mul(values.len()))
+    }
+
+    /// The cases to run: all combinations with the last axis varying fastest, or a sorted
+    /// random sample of them that only depends on the seed. `Sweep()` ensures there are
+    /// at most [`MAX_CASES`].
+    pub fn cases(&self) -> Vec<SweepCase<'v>> {
+        let total = self.combinations();
+        let indices: Vec<usize> = match self.samples {
+            Some(n) if (n as usize) < total => {
+                let mut rng = SplitMix64(self.seed);
+                let mut chosen = BTreeSet::new();
+                while chosen.len() < n as usize {
+                    chosen.insert((rng.next() % total as u64) as usize);
+                }
+                chosen.into_iter().collect()
+            }
+            _ => (0..total).collect(),
+        };
+        indices
+            .into_iter()
+            .map(|index| self.case_at(self.point_of(index)))
+            .collect()
+    }
+
+    /// Search for a simpler case than `failing` for which `fails` still holds, by moving
+    /// one axis at a time to an earlier value until no such move fails.
+    ///
+    /// "Simpler" means earlier in each axis' order, so list the simplest values first: a
+    /// failure is never shrunk towards values listed after the failing ones.
+    pub fn shrink_case(
+        &self,
+        failing: SweepCase<'v>,
+        mut fails: impl FnMut(&SweepCase<'v>) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<SweepCase<'v>> {
+        let mut current = failing;
+        'search: loop {
+            for axis in 0..current.point.len() {
+                for index in 0..current.point[axis] {
+                    let mut point = current.point.clone();
+                    point[axis] = index;
+                    let candidate = self.case_at(point);
+                    if fails(&candidate)? {
+                        current = candidate;
+                        continue 'search;
+                    }
+                }
+            }
+            return Ok(current);
+        }
+    }
+
+    fn point_of(&self, mut index: usize) -> Vec<usize> {
+        let mut point = vec![0; self.axes.len()];
+        for (axis, values) in self.axes.values().enumerate().rev() {
+            point[axis] = index % values.len();
+            index /= values.len();
+        }
+        point
+    }
+
+    fn case_at(&self, point: Vec<usize>) -> SweepCase<'v> {
+        let mut params: Vec<(String, Value<'v>)> = self
+            .fixed
+            .iter()
+            .map(|(k, v)| (k.clone(), v.to_value()))
+            .collect();
+        let mut name = Vec::new();
+        for ((axis, values), &index) in self.axes.iter().zip(&point) {
+            let value = values[index].to_value();
+            name.push(format!("{axis}={value}"));
+            params.retain(|(k, _)| k != axis);
+            params.push((axis.clone(), value));
+        }
+        SweepCase {
+            name: name.join(","),
+            params,
+            point,
+        }
+    }
+}
+
+/// Small, seedable PRNG so sampled sweeps are reproducible on every platform.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// The values of one axis: the variants of an enum type, or the items of a list, tuple or
+/// `range()`.
+fn axis_values<'v>(values: Value<'v>, heap: &'v Heap) -> anyhow::Result<Vec<Value<'v>>> {
+    if let Some(enum_type) = values.downcast_ref::<EnumType>() {
+        let mut variants = Vec::new();
+        while let Ok(variant) = enum_type.at(heap.alloc(variants.len() as i32), heap) {
+            variants.push(variant.to_value());
+        }
+        return Ok(variants);
+    }
+
+    if let Some(frozen_enum_type) = values.downcast_ref::<FrozenEnumType>() {
+        let variants = frozen_enum_type
+            .get_attr("variants", heap)
+            .and_then(ListRef::from_value)
+            .ok_or_else(|| anyhow::anyhow!("expected enum type to have variants"))?;
+        return Ok(variants.iter().collect());
+    }
+
+    Ok(values
+        .iterate(heap)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "sweep values must be an enum type or iterable: {}",
+                e.into_anyhow()
+            )
+        })?
+        .collect())
+}
+
+fn as_f64(value: Value) -> Option<f64> {
+    value
+        .unpack_i32()
+        .map(f64::from)
+        .or_else(|| value.downcast_ref::<StarlarkFloat>().map(|f| f.0))
+}
+
+#[starlark_module]
+pub fn sweep_globals(builder: &mut GlobalsBuilder) {
+    /// Sweep TestBench inputs over every combination of the values in `axes`, a dict of
+    /// input name to an enum type, list or `range()`. `samples` runs that many
+    /// combinations drawn with `seed` instead, and is required above 10000 combinations.
+    /// Failing combinations are shrunk towards values listed earlier unless `shrink` is
+    /// False.
+    fn Sweep<'v>(
+        #[starlark(require = pos)] axes: Value<'v>,
+        #[starlark(require = named)] fixed: Option<Value<'v>>,
+        #[starlark(require = named)] samples: Option<i32>,
+        #[starlark(require = named, default = 0)] seed: i32,
+        #[starlark(require = named, default = true)] shrink: bool,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<Value<'v>> {
+        let axes_dict = DictRef::from_value(axes)
+            .ok_or_else(|| anyhow::anyhow!("'axes' must be a dictionary of input names"))?;
+        if axes_dict.is_empty() {
+            return Err(anyhow::anyhow!("'axes' cannot be empty"));
+        }
+
+        let mut swept = SmallMap::new();
+        for (key, values) in axes_dict.iter() {
+            let key = key
+                .unpack_str()
+                .ok_or_else(|| anyhow::anyhow!("sweep axes must be strings, got: {}", key))?;
+            let values = axis_values(values, eval.heap())?;
+            if values.is_empty() {
+                return Err(anyhow::anyhow!("sweep axis '{}' has no values", key));
+            }
+            swept.insert(key.to_string(), values);
+        }
+
+        let mut fixed_inputs = SmallMap::new();
+        if let Some(fixed) = fixed {
+            let fixed_dict = DictRef::from_value(fixed)
+                .ok_or_else(|| anyhow::anyhow!("'fixed' must be a dictionary"))?;
+            for (key, value) in fixed_dict.iter() {
+                let key = key
+                    .unpack_str()
+                    .ok_or_else(|| anyhow::anyhow!("input names must be strings, got: {}", key))?;
+                fixed_inputs.insert(key.to_string(), value);
+            }
+        }
+
+        let samples = match samples {
+            Some(n) if n <= 0 => return Err(anyhow::anyhow!("'samples' must be positive")),
+            Some(n) if n as usize > MAX_CASES => {
+                return Err(anyhow::anyhow!(
+                    "'samples' must be at most {}, got {}",
+                    MAX_CASES,
+                    n
+                ))
+            }
+            Some(n) => Some(n as u32),
+            None => None,
+        };
+
+        let sweep = SweepValueGen {
+            axes: swept,
+            fixed: fixed_inputs,
+            samples,
+            seed: seed as u64,
+            shrink,
+        };
+        if sweep.case_count() > MAX_CASES {
+            return Err(anyhow::anyhow!(
+                "sweep has {} combinations, more than the limit of {}; pass 'samples' to run a random subset",
+                sweep.combinations(),
+                MAX_CASES
+            ));
+        }
+        Ok(eval.heap().alloc(sweep))
+    }
+
+    /// `count` evenly spaced floats from `start` to `stop`, both included, to sweep a
+    /// numeric input.
+    fn linspace<'v>(
+        #[starlark(require = pos)] start: Value<'v>,
+        #[starlark(require = pos)] stop: Value<'v>,
+        #[starlark(require = pos)] count: i32,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        let (Some(start), Some(stop)) = (as_f64(start), as_f64(stop)) else {
+            return Err(anyhow::anyhow!("linspace() bounds must be numbers"));
+        };
+        if count < 2 {
+            return Err(anyhow::anyhow!("linspace() needs a count of at least 2"));
+        }
+        let step = (stop - start) / (count - 1) as f64;
+        let values = (0..count).map(|i| StarlarkFloat(start + step * i as f64));
+        Ok(heap.alloc(AllocList(values)))
+    }
+}
@@ -1,18 +1,18 @@
 #![allow(clippy::needless_lifetimes)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 
 use allocative::Allocative;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use starlark::{
     any::ProvidesStaticType,
     collections::SmallMap,
     eval::{Arguments, Evaluator, ParametersSpec, ParametersSpecParam},
     starlark_simple_value,
     values::{
-        list::ListRef, starlark_value, tuple::TupleRef, Freeze, FreezeResult, Heap, NoSerialize,
-        StarlarkValue, Trace, Value,
+        dict::AllocDict, list::ListRef, starlark_value, tuple::TupleRef, Freeze, FreezeResult,
+        Heap, NoSerialize, StarlarkValue, Trace, Value,
     },
 };
 
@@ -24,17 +24,30 @@ use anyhow::anyhow;
 use pcb_eda::kicad::symbol_library::KicadSymbolLibrary;
 use pcb_eda::Symbol as EdaSymbol;
 
-/// Cache for parsed symbol libraries with lazy extends resolution
-#[derive(Clone)]
+/// A symbol parsed from a KiCad library, together with its formatted s-expression.
+/// This is the unit stored in both the in-process and the persistent symbol caches.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct LibrarySymbol {
+    pub symbol: EdaSymbol,
+    pub raw_sexp: Option<String>,
+}
+
+/// [`crate::EvalCache`] namespace holding resolved symbols, keyed by the contents of
+/// their library and their name.
+const SYMBOL_CACHE_NAMESPACE: &str = "symbols";
+
+/// A symbol library read during evaluation. The library is only parsed once a symbol
+/// is missing from both caches, and its symbols are resolved one at a time on demand.
 struct CachedLibrary {
-    /// The unresolved library for lazy loading
-    kicad_library: Arc<KicadSymbolLibrary>,
-    /// Cache of already resolved symbols
-    resolved_symbols: Arc<Mutex<HashMap<String, EdaSymbol>>>,
+    contents: String,
+    /// Hash of `contents`, shared by the persistent keys of the library's symbols
+    content_key: String,
+    parsed: OnceCell<KicadSymbolLibrary>,
+    resolved: Mutex<HashMap<String, LibrarySymbol>>,
 }
 
-/// Global cache for symbol libraries
-static SYMBOL_LIBRARY_CACHE: Lazy<Mutex<HashMap<String, CachedLibrary>>> =
+/// Global cache for symbol libraries, keyed by the hash of their contents
+static SYMBOL_LIBRARY_CACHE: Lazy<Mutex<HashMap<String, Arc<CachedLibrary>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
 /// Symbol represents a schematic symbol definition with pins
@@ -45,6 +58,7 @@ pub struct SymbolValue {
     pub pad_to_signal: SmallMap<String, String>, // pad name -> signal name
     pub source_path: Option<String>, // Absolute path to the symbol library (if loaded from file)
     pub raw_sexp: Option<String>, // Raw s-expression of the symbol (if loaded from file, otherwise None)
+    pub units: SmallMap<String, Vec<String>>, // unit name -> signal names (empty for single-unit symbols)
 }
 
 impl std::fmt::Debug for SymbolValue {
@@ -61,6 +75,10 @@ impl std::fmt::Debug for SymbolValue {
             debug.field("pins", &pins_map);
         }
 
+        if !self.units.is_empty() {
+            debug.field("units", &self.units);
+        }
+
         debug.finish()
     }
 }
@@ -75,6 +93,36 @@ where
     fn provide(&'v self, demand: &mut starlark::values::Demand<'_, 'v>) {
         demand.provide_value::<&dyn DeepCopyToHeap>(self);
     }
+
+    fn get_attr(&self, attribute: &str, heap: &'v Heap) -> Option<Value<'v>> {
+        match attribute {
+            "name" => Some(
+                self.name
+                    .as_deref()
+                    .map(|name| heap.alloc_str(name).to_value())
+                    .unwrap_or_else(Value::new_none),
+            ),
+            "units" => {
+                let units: Vec<(Value<'v>, Value<'v>)> = self
+                    .units
+                    .iter()
+                    .map(|(unit, signals)| {
+                        (heap.alloc_str(unit).to_value(), heap.alloc(signals.clone()))
+                    })
+                    .collect();
+                Some(heap.alloc(AllocDict(units)))
+            }
+            _ => None,
+        }
+    }
+
+    fn has_attr(&self, attribute: &str, _heap: &'v Heap) -> bool {
+        matches!(attribute, "name" | "units")
+    }
+
+    fn dir_attr(&self) -> Vec<String> {
+        vec!["name".to_string(), "units".to_string()]
+    }
 }
 
 impl std::fmt::Display for SymbolValue {
@@ -177,6 +225,7 @@ impl<'v> SymbolValue {
                 pad_to_signal,
                 source_path: None,
                 raw_sexp: None,
+                units: SmallMap::new(),
             })
         }
         // Case 2: Load from library
@@ -201,62 +250,47 @@ impl<'v> SymbolValue {
                 .as_ref()
                 .ok_or_else(|| starlark::Error::new_other(anyhow!("No file provider available")))?;
 
-            // If we have a specific symbol name, use lazy loading
-            let selected_symbol = if let Some(name) = name {
-                // Load only the specific symbol we need
-                match load_symbol_from_library(&resolved_path, &name, file_provider.as_ref())? {
-                    Some(symbol) => symbol,
-                    None => {
-                        // If not found, we need to load all symbols to provide a helpful error
-                        let symbols =
-                            load_symbols_from_library(&resolved_path, file_provider.as_ref())?;
-                        return Err(starlark::Error::new_other(anyhow!(
-                            "Symbol '{}' not found in library '{}'. Available symbols: {}",
-                            name,
-                            resolved_path.display(),
-                            symbols
-                                .iter()
-                                .map(|s| s.name.as_str())
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        )));
+            let cache = eval_ctx.cache.as_deref();
+
+            let name = match name {
+                Some(name) => name,
+                None => {
+                    // No specific name provided, the library must have exactly one symbol
+                    let names = library_symbol_names(&resolved_path, file_provider.as_ref())?;
+                    match names.as_slice() {
+                        [name] => name.clone(),
+                        [] => {
+                            return Err(starlark::Error::new_other(anyhow!(
+                                "No symbols found in library '{}'",
+                                resolved_path.display()
+                            )))
+                        }
+                        _ => {
+                            return Err(starlark::Error::new_other(anyhow!(
+                                "Library '{}' contains {} symbols. Please specify which one with the 'name' parameter. Available symbols: {}",
+                                resolved_path.display(),
+                                names.len(),
+                                names.join(", ")
+                            )))
+                        }
                     }
                 }
-            } else {
-                // No specific name provided, need to check if library has exactly one symbol
-                let symbols = load_symbols_from_library(&resolved_path, file_provider.as_ref())?;
+            };
 
-                if symbols.len() == 1 {
-                    // Only one symbol, use it
-                    symbols.into_iter().next().unwrap()
-                } else if symbols.is_empty() {
-                    return Err(starlark::Error::new_other(anyhow!(
-                        "No symbols found in library '{}'",
-                        resolved_path.display()
-                    )));
-                } else {
-                    // Multiple symbols, need name parameter
-                    return Err(starlark::Error::new_other(anyhow!(
-                            "Library '{}' contains {} symbols. Please specify which one with the 'name' parameter. Available symbols: {}",
-                            resolved_path.display(),
-                            symbols.len(),
-                            symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
-                        )));
-                }
+            let Some(selected_symbol) =
+                load_symbol_from_library(&resolved_path, &name, file_provider.as_ref(), cache)?
+            else {
+                // If not found, list the library's symbols to provide a helpful error
+                let names = library_symbol_names(&resolved_path, file_provider.as_ref())?;
+                return Err(starlark::Error::new_other(anyhow!(
+                    "Symbol '{}' not found in library '{}'. Available symbols: {}",
+                    name,
+                    resolved_path.display(),
+                    names.join(", ")
+                )));
             };
 
-            // Convert EdaSymbol pins to our Symbol format
-            // Map pad number -> signal name (which is the pin name from the symbol)
-            let mut pad_to_signal: SmallMap<String, String> = SmallMap::new();
-            for pin in &selected_symbol.pins {
-                // If pin name is ~, use the pin number instead
-                let signal_name = if pin.name == "~" {
-                    &pin.number
-                } else {
-                    &pin.name
-                };
-                pad_to_signal.insert(pin.number.clone(), signal_name.to_owned());
-            }
+            let (pad_to_signal, units) = signals_from_eda_symbol(&selected_symbol.symbol);
 
             // Get the absolute path using file provider
             let absolute_path = file_provider
@@ -265,16 +299,12 @@ impl<'v> SymbolValue {
                 .to_string_lossy()
                 .into_owned();
 
-            // Store the raw s-expression if available
-            let sexpr = selected_symbol
-                .raw_sexp()
-                .map(|s| pcb_sexpr::format_sexpr(s, 0));
-
             Ok(SymbolValue {
-                name: Some(selected_symbol.name.clone()),
+                name: Some(selected_symbol.symbol.name),
                 pad_to_signal,
                 source_path: Some(absolute_path),
-                raw_sexp: sexpr,
+                raw_sexp: selected_symbol.raw_sexp,
+                units,
             })
         } else {
             Err(starlark::Error::new_other(anyhow!(
@@ -302,6 +332,85 @@ impl<'v> SymbolValue {
     pub fn signal_names(&self) -> impl Iterator<Item = &str> {
         self.pad_to_signal.values().map(|v| v.as_str())
     }
+
+    pub fn units(&self) -> &SmallMap<String, Vec<String>> {
+        &self.units
+    }
+}
+
+/// Map pad number -> signal name (which is the pin name from the symbol), and
+/// group the signals of multi-unit symbols by unit.
+///
+/// Single-unit symbols keep their pin names as-is, so repeated names such as
+/// several GND pins still share one signal. In multi-unit symbols a name that
+/// different units put on different pads (e.g. the `+`/`-` inputs of a quad
+/// op-amp) is qualified with the unit name (`A.+`, `B.+`, ...) so each unit can
+/// be wired independently instead of all of them being shorted together. Pins
+/// shared by every unit (unit 0), pins unique to one unit and pins that each unit
+/// repeats on the same pads keep their plain name.
+fn signals_from_eda_symbol(
+    symbol: &EdaSymbol,
+) -> (SmallMap<String, String>, SmallMap<String, Vec<String>>) {
+    // If pin name is ~, use the pin number instead
+    let base_name = |pin: &pcb_eda::Pin| {
+        if pin.name == "~" {
+            pin.number.clone()
+        } else {
+            pin.name.clone()
+        }
+    };
+
+    let mut pad_to_signal: SmallMap<String, String> = SmallMap::new();
+    if symbol.unit_count() <= 1 {
+        for pin in &symbol.pins {
+            pad_to_signal.insert(pin.number.clone(), base_name(pin));
+        }
+        return (pad_to_signal, SmallMap::new());
+    }
+
+    // name -> unit -> pads the unit puts that name on
+    let mut name_pads: HashMap<String, BTreeMap<u32, BTreeSet<&str>>> = HashMap::new();
+    for pin in symbol.pins.iter().filter(|pin| pin.unit != 0) {
+        name_pads
+            .entry(base_name(pin))
+            .or_default()
+            .entry(pin.unit)
+            .or_default()
+            .insert(pin.number.as_str());
+    }
+    let per_unit = |name: &str| {
+        name_pads.get(name).is_some_and(|units| {
+            let mut pads = units.values();
+            let first = pads.next();
+            pads.any(|other| Some(other) != first)
+        })
+    };
+
+    let mut units: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for pin in &symbol.pins {
+        let name = base_name(pin);
+        let signal_name = if pin.unit != 0 && per_unit(&name) {
+            format!("{}.{}", pcb_eda::unit_name(pin.unit), name)
+        } else {
+            name
+        };
+
+        if pin.unit != 0 {
+            let signals = units.entry(pin.unit).or_default();
+            if !signals.contains(&signal_name) {
+                signals.push(signal_name.clone());
+            }
+        }
+
+        pad_to_signal.insert(pin.number.clone(), signal_name);
+    }
+
+    let units = units
+        .into_iter()
+        .map(|(unit, signals)| (pcb_eda::unit_name(unit), signals))
+        .collect();
+
+    (pad_to_signal, units)
 }
 
 impl DeepCopyToHeap for SymbolValue {
@@ -416,60 +525,14 @@ where
     }
 }
 
-/// Parse all symbols from a KiCad symbol library with caching
-pub fn load_symbols_from_library(
+/// Read the library at `path` and return its in-process cache entry.
+///
+/// The file is read on every call so that edits are picked up and the read is seen by
+/// the file provider; only parsing and symbol resolution are cached.
+fn cached_library(
     path: &std::path::Path,
     file_provider: &dyn crate::FileProvider,
-) -> starlark::Result<Vec<EdaSymbol>> {
-    // Get the canonical path for cache key
-    let cache_key = file_provider
-        .canonicalize(path)
-        .unwrap_or_else(|_| path.to_path_buf())
-        .to_string_lossy()
-        .into_owned();
-
-    // Check cache first
-    {
-        let cache = SYMBOL_LIBRARY_CACHE
-            .lock()
-            .map_err(|e| starlark::Error::new_other(anyhow!("Failed to lock cache: {}", e)))?;
-        if let Some(cached_lib) = cache.get(&cache_key) {
-            // Library is cached, get all symbols lazily resolved
-            let kicad_lib = &cached_lib.kicad_library;
-            let mut result = Vec::new();
-
-            // Get all symbol names and resolve them lazily
-            for symbol_name in kicad_lib.symbol_names() {
-                // Check if already resolved
-                let mut resolved_cache = cached_lib.resolved_symbols.lock().map_err(|e| {
-                    starlark::Error::new_other(anyhow!("Failed to lock resolved cache: {}", e))
-                })?;
-
-                if let Some(resolved) = resolved_cache.get(symbol_name) {
-                    result.push(resolved.clone());
-                } else {
-                    // Resolve the symbol lazily
-                    if let Some(resolved_kicad) =
-                        kicad_lib.get_symbol_lazy(symbol_name).map_err(|e| {
-                            starlark::Error::new_other(anyhow!(
-                                "Failed to resolve symbol '{}': {}",
-                                symbol_name,
-                                e
-                            ))
-                        })?
-                    {
-                        let eda_symbol: EdaSymbol = resolved_kicad.into();
-                        resolved_cache.insert(symbol_name.to_string(), eda_symbol.clone());
-                        result.push(eda_symbol);
-                    }
-                }
-            }
-
-            return Ok(result);
-        }
-    }
-
-    // Not in cache, read and parse the file
+) -> starlark::Result<Arc<CachedLibrary>> {
     let contents = file_provider.read_file(path).map_err(|e| {
         starlark::Error::new_other(anyhow!(
             "Failed to read symbol library '{}': {}",
@@ -477,106 +540,127 @@ pub fn load_symbols_from_library(
             e
         ))
     })?;
+    let content_key = crate::cache_key([contents.as_bytes()]);
+
+    let mut cache = SYMBOL_LIBRARY_CACHE
+        .lock()
+        .map_err(|e| starlark::Error::new_other(anyhow!("Failed to lock cache: {}", e)))?;
+    Ok(cache
+        .entry(content_key.clone())
+        .or_insert_with(|| {
+            Arc::new(CachedLibrary {
+                contents,
+                content_key,
+                parsed: OnceCell::new(),
+                resolved: Mutex::new(HashMap::new()),
+            })
+        })
+        .clone())
+}
 
-    // Parse library without resolving extends
-    let kicad_library = KicadSymbolLibrary::from_string_lazy(&contents).map_err(|e| {
-        starlark::Error::new_other(anyhow!(
-            "Failed to parse symbol library {}: {}",
-            path.display(),
-            e
-        ))
-    })?;
+impl CachedLibrary {
+    /// The parsed library, without resolving `extends`
+    fn library(&self, path: &std::path::Path) -> starlark::Result<&KicadSymbolLibrary> {
+        self.parsed.get_or_try_init(|| {
+            KicadSymbolLibrary::from_string_lazy(&self.contents).map_err(|e| {
+                starlark::Error::new_other(anyhow!(
+                    "Failed to parse symbol library {}: {}",
+                    path.display(),
+                    e
+                ))
+            })
+        })
+    }
 
-    // Get all symbols and resolve them eagerly for now (to maintain compatibility)
-    let mut resolved_symbols = HashMap::new();
-    let mut result = Vec::new();
-
-    for symbol_name in kicad_library.symbol_names() {
-        if let Some(resolved_kicad) = kicad_library.get_symbol_lazy(symbol_name).map_err(|e| {
-            starlark::Error::new_other(anyhow!("Failed to resolve symbol '{}': {}", symbol_name, e))
-        })? {
-            let eda_symbol: EdaSymbol = resolved_kicad.into();
-            resolved_symbols.insert(symbol_name.to_string(), eda_symbol.clone());
-            result.push(eda_symbol);
+    /// Resolve `symbol_name`, from the in-process cache, then the persistent `cache`, and
+    /// only then by parsing the library.
+    fn symbol(
+        &self,
+        path: &std::path::Path,
+        symbol_name: &str,
+        cache: Option<&dyn crate::EvalCache>,
+    ) -> starlark::Result<Option<LibrarySymbol>> {
+        let lock_error =
+            |e: std::sync::PoisonError<_>| anyhow!("Failed to lock resolved cache: {}", e);
+        if let Some(symbol) = self
+            .resolved
+            .lock()
+            .map_err(|e| starlark::Error::new_other(lock_error(e)))?
+            .get(symbol_name)
+        {
+            return Ok(Some(symbol.clone()));
         }
-    }
 
-    // Store in cache
-    {
-        let mut cache = SYMBOL_LIBRARY_CACHE
+        let key = crate::cache_key([self.content_key.as_bytes(), symbol_name.as_bytes()]);
+        let persisted = cache
+            .and_then(|c| c.get(SYMBOL_CACHE_NAMESPACE, &key))
+            .and_then(|bytes| serde_json::from_slice::<LibrarySymbol>(&bytes).ok());
+
+        let symbol = match persisted {
+            Some(symbol) => symbol,
+            None => {
+                let Some(resolved_kicad) = self
+                    .library(path)?
+                    .get_symbol_lazy(symbol_name)
+                    .map_err(|e| {
+                        starlark::Error::new_other(anyhow!(
+                            "Failed to resolve symbol '{}': {}",
+                            symbol_name,
+                            e
+                        ))
+                    })?
+                else {
+                    return Ok(None);
+                };
+                let mut symbol: EdaSymbol = resolved_kicad.into();
+                let raw_sexp = symbol
+                    .raw_sexp
+                    .take()
+                    .map(|s| pcb_sexpr::format_sexpr(&s, 0));
+                let symbol = LibrarySymbol { symbol, raw_sexp };
+                if let Some(cache) = cache {
+                    if let Ok(bytes) = serde_json::to_vec(&symbol) {
+                        cache.put(SYMBOL_CACHE_NAMESPACE, &key, &bytes);
+                    }
+                }
+                symbol
+            }
+        };
+
+        self.resolved
             .lock()
-            .map_err(|e| starlark::Error::new_other(anyhow!("Failed to lock cache: {}", e)))?;
-        cache.insert(
-            cache_key,
-            CachedLibrary {
-                kicad_library: Arc::new(kicad_library),
-                resolved_symbols: Arc::new(Mutex::new(resolved_symbols)),
-            },
-        );
+            .map_err(|e| starlark::Error::new_other(lock_error(e)))?
+            .insert(symbol_name.to_string(), symbol.clone());
+        Ok(Some(symbol))
     }
+}
 
-    Ok(result)
+/// Names of the symbols in a KiCad symbol library
+pub fn library_symbol_names(
+    path: &std::path::Path,
+    file_provider: &dyn crate::FileProvider,
+) -> starlark::Result<Vec<String>> {
+    let library = cached_library(path, file_provider)?;
+    Ok(library
+        .library(path)?
+        .symbol_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect())
 }
 
-/// Load a specific symbol from a library with lazy resolution
+/// Load a specific symbol from a library with lazy resolution.
+///
+/// Symbols are cached in-process and, when `cache` is provided, persistently by the
+/// library's contents and the symbol's name, so a run that hits the persistent cache
+/// does not parse the library at all.
 pub fn load_symbol_from_library(
     path: &std::path::Path,
     symbol_name: &str,
     file_provider: &dyn crate::FileProvider,
-) -> starlark::Result<Option<EdaSymbol>> {
-    // Get the canonical path for cache key
-    let cache_key = file_provider
-        .canonicalize(path)
-        .unwrap_or_else(|_| path.to_path_buf())
-        .to_string_lossy()
-        .into_owned();
-
-    // Check cache first
-    {
-        let cache = SYMBOL_LIBRARY_CACHE
-            .lock()
-            .map_err(|e| starlark::Error::new_other(anyhow!("Failed to lock cache: {}", e)))?;
-        if let Some(cached_lib) = cache.get(&cache_key) {
-            // Check if already resolved
-            {
-                let resolved_cache = cached_lib.resolved_symbols.lock().map_err(|e| {
-                    starlark::Error::new_other(anyhow!("Failed to lock resolved cache: {}", e))
-                })?;
-
-                if let Some(resolved) = resolved_cache.get(symbol_name) {
-                    return Ok(Some(resolved.clone()));
-                }
-            }
-
-            // Not resolved yet, resolve it now
-            let kicad_lib = &cached_lib.kicad_library;
-            if let Some(resolved_kicad) = kicad_lib.get_symbol_lazy(symbol_name).map_err(|e| {
-                starlark::Error::new_other(anyhow!(
-                    "Failed to resolve symbol '{}': {}",
-                    symbol_name,
-                    e
-                ))
-            })? {
-                let eda_symbol: EdaSymbol = resolved_kicad.into();
-
-                // Cache the resolved symbol
-                let mut resolved_cache = cached_lib.resolved_symbols.lock().map_err(|e| {
-                    starlark::Error::new_other(anyhow!("Failed to lock resolved cache: {}", e))
-                })?;
-                resolved_cache.insert(symbol_name.to_string(), eda_symbol.clone());
-
-                return Ok(Some(eda_symbol));
-            }
-
-            return Ok(None);
-        }
-    }
-
-    // Not in cache, need to load the library first
-    load_symbols_from_library(path, file_provider)?;
-
-    // Now try again
-    load_symbol_from_library(path, symbol_name, file_provider)
+    cache: Option<&dyn crate::EvalCache>,
+) -> starlark::Result<Option<LibrarySymbol>> {
+    cached_library(path, file_provider)?.symbol(path, symbol_name, cache)
 }
 
 impl DeepCopyToHeap for SymbolType {
@@ -1,12 +1,20 @@
 #![allow(clippy::needless_lifetimes)]
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
+use crate::lang::error::{CheckFailedError, MutationResult, SourceSpan};
 use crate::lang::eval::EvalMode;
 use crate::lang::evaluator_ext::EvaluatorExt;
 use crate::lang::input::{InputMap, InputValue};
+use crate::lang::interface::{
+    get_promotion_key, unwrap_using, FrozenInterfaceValue, InterfaceValue,
+};
 use crate::lang::module::{FrozenModuleValue, ModuleLoader};
-use crate::Diagnostic;
+use crate::lang::mutation;
+use crate::lang::net::{FrozenNetValue, NetValue};
+use crate::lang::sweep::SweepValue;
+use crate::{Diagnostic, EvalOutput, WithDiagnostics};
 use allocative::Allocative;
 use starlark::environment::GlobalsBuilder;
 use starlark::errors::EvalSeverity;
@@ -20,8 +28,8 @@ use starlark::{
         list::ListRef,
         starlark_value,
         tuple::TupleRef,
-        Coerce, Freeze, FreezeResult, NoSerialize, StarlarkValue, Trace, Value, ValueLifetimeless,
-        ValueLike,
+        Coerce, Freeze, FreezeResult, Heap, NoSerialize, StarlarkValue, Trace, Value,
+        ValueLifetimeless, ValueLike,
     },
 };
 
@@ -140,24 +148,9 @@ impl ModuleLoader {
         inputs: InputMap,
         case_name: Option<&str>,
     ) -> anyhow::Result<Option<FrozenModuleValue>> {
-        // Create a child context with strict_io_config = true
-        let ctx = eval
-            .eval_context()
-            .expect("expected eval context")
-            .child_context()
-            .set_strict_io_config(true); // Strict mode - require all inputs
-
-        let module_name = match case_name {
-            Some(name) => format!("{}__{}", test_bench_name, name),
-            None => test_bench_name,
-        };
-
-        let ctx = ctx
-            .set_source_path(std::path::PathBuf::from(&self.source_path))
-            .set_module_name(module_name)
-            .set_inputs(inputs);
-
-        let (output, diagnostics) = ctx.eval().unpack();
+        let (output, diagnostics) = self
+            .eval_case(test_bench_name, eval, inputs, case_name)
+            .unpack();
 
         // Get the parent context for diagnostic propagation
         let parent_context = eval
@@ -237,22 +230,308 @@ impl ModuleLoader {
             }
         }
     }
+
+    /// Evaluate this module with `inputs` without reporting its diagnostics, returning
+    /// `None` if evaluation failed. Used to probe inputs while shrinking a sweep.
+    pub(crate) fn try_evaluate_with_inputs<'v>(
+        &self,
+        test_bench_name: &str,
+        eval: &mut Evaluator<'v, '_, '_>,
+        inputs: InputMap,
+    ) -> Option<FrozenModuleValue> {
+        let result = self.eval_case(test_bench_name.to_string(), eval, inputs, Some("shrink"));
+        if result.diagnostics.has_errors() {
+            return None;
+        }
+        let output = result.output?;
+        eval.frozen_heap()
+            .add_reference(output.star_module.frozen_heap());
+        Some(output.sch_module)
+    }
+
+    fn eval_case(
+        &self,
+        test_bench_name: String,
+        eval: &Evaluator<'_, '_, '_>,
+        inputs: InputMap,
+        case_name: Option<&str>,
+    ) -> WithDiagnostics<EvalOutput> {
+        // Create a child context with strict_io_config = true
+        let ctx = eval
+            .eval_context()
+            .expect("expected eval context")
+            .child_context()
+            .set_strict_io_config(true); // Strict mode - require all inputs
+
+        let module_name = match case_name {
+            Some(name) => format!("{}__{}", test_bench_name, name),
+            None => test_bench_name,
+        };
+
+        ctx.set_source_path(std::path::PathBuf::from(&self.source_path))
+            .set_module_name(module_name)
+            .set_inputs(inputs)
+            .eval()
+    }
+}
+
+/// Parse an explicit `test_cases` dictionary into named lists of inputs
+fn parse_test_cases<'v>(test_cases: Value<'v>) -> anyhow::Result<Vec<(String, Params<'v>)>> {
+    let test_cases_dict = DictRef::from_value(test_cases).ok_or_else(|| {
+        anyhow::anyhow!("'test_cases' parameter must be a dictionary or a Sweep()")
+    })?;
+
+    if test_cases_dict.is_empty() {
+        return Err(anyhow::anyhow!("'test_cases' cannot be empty"));
+    }
+
+    let mut cases = Vec::new();
+    for (case_name, case_value) in test_cases_dict.iter() {
+        let case_name_str = case_name.unpack_str().ok_or_else(|| {
+            anyhow::anyhow!("test case names must be strings, got: {}", case_name)
+        })?;
+
+        let case_dict = DictRef::from_value(case_value)
+            .ok_or_else(|| anyhow::anyhow!("test case '{}' must be a dictionary", case_name_str))?;
+
+        let mut params = Vec::new();
+        for (key, value) in case_dict.iter() {
+            let key_str = key
+                .unpack_str()
+                .ok_or_else(|| anyhow::anyhow!("test case keys must be strings, got: {}", key))?;
+            params.push((key_str.to_string(), value));
+        }
+        cases.push((case_name_str.to_string(), params));
+    }
+    Ok(cases)
 }
 
-/// Build an InputMap from a test case dictionary
-fn build_input_map<'v>(case_dict: &DictRef<'v>) -> anyhow::Result<InputMap> {
+/// Parse the `checks` list into check functions and their optional custom names
+fn parse_checks<'v>(checks: Value<'v>) -> anyhow::Result<Vec<(Value<'v>, Option<&'v str>)>> {
+    let checks_list = ListRef::from_value(checks)
+        .ok_or_else(|| anyhow::anyhow!("'checks' parameter must be a list of functions"))?;
+
+    let mut parsed = Vec::new();
+    for check_item in checks_list.iter() {
+        // Check if it's a tuple (name, function) or just a function
+        if let Some(tuple_ref) = TupleRef::from_value(check_item) {
+            if tuple_ref.len() != 2 {
+                return Err(anyhow::anyhow!(
+                    "Check tuple must have exactly 2 elements: (name, function)"
+                ));
+            }
+            let tuple_items: Vec<_> = tuple_ref.iter().collect();
+            let name = tuple_items[0].unpack_str().ok_or_else(|| {
+                anyhow::anyhow!("First element of check tuple must be a string name")
+            })?;
+            parsed.push((tuple_items[1], Some(name)));
+        } else {
+            parsed.push((check_item, None));
+        }
+    }
+    Ok(parsed)
+}
+
+/// The inputs of one test case, by name
+type Params<'v> = Vec<(String, Value<'v>)>;
+
+/// Build an InputMap from test case parameters
+fn build_input_map(params: &[(String, Value)]) -> InputMap {
     let mut inputs = InputMap::new();
+    for (key, value) in params {
+        inputs.insert(key.clone(), InputValue::from_value(*value));
+    }
+    inputs
+}
 
-    for (key, value) in case_dict.iter() {
-        let key_str = key
-            .unpack_str()
-            .ok_or_else(|| anyhow::anyhow!("test case keys must be strings, got: {}", key))?;
+/// Allocate test case parameters as the `inputs` dict passed to check functions
+fn params_dict<'v>(heap: &'v Heap, params: &[(String, Value<'v>)]) -> Value<'v> {
+    heap.alloc(AllocDict(
+        params
+            .iter()
+            .map(|(key, value)| (key.as_str(), *value))
+            .collect::<Vec<_>>(),
+    ))
+}
 
-        let input_value = InputValue::from_value(value);
-        inputs.insert(key_str.to_string(), input_value);
+/// Whether the module fails to evaluate with `params` or any check fails on it, without
+/// reporting any diagnostics.
+fn case_fails<'v>(
+    eval: &mut Evaluator<'v, '_, '_>,
+    loader: &ModuleLoader,
+    test_bench_name: &str,
+    params: &[(String, Value<'v>)],
+    checks: &[(Value<'v>, Option<&'v str>)],
+) -> bool {
+    let Some(module) =
+        loader.try_evaluate_with_inputs(test_bench_name, eval, build_input_map(params))
+    else {
+        return true;
+    };
+    let args = [
+        eval.frozen_heap().alloc(module).to_value(),
+        params_dict(eval.heap(), params),
+    ];
+    checks
+        .iter()
+        .any(|(check_func, _)| eval.eval_function(*check_func, &args, &[]).is_err())
+}
+
+/// Run `f` and return how long it took in seconds. Without the `native` feature (e.g. on
+/// wasm, where there is no monotonic clock) durations are reported as zero.
+fn timed<T>(f: impl FnOnce() -> T) -> (T, f64) {
+    #[cfg(feature = "native")]
+    {
+        let start = std::time::Instant::now();
+        let result = f();
+        (result, start.elapsed().as_secs_f64())
+    }
+    #[cfg(not(feature = "native"))]
+    {
+        (f(), 0.0)
+    }
+}
+
+/// The name of a check function
+fn function_name(check_func: Value) -> String {
+    let check_func_str = check_func.to_string();
+    check_func_str
+        .rsplit('.')
+        .next()
+        .unwrap_or("check")
+        .to_string()
+}
+
+/// The name a check is reported under: its custom name, or the function's name
+fn check_name(check_func: Value, custom_name: Option<&str>) -> String {
+    match custom_name {
+        Some(name) => name.to_string(),
+        None => function_name(check_func),
+    }
+}
+
+/// A case parameter as written in Zen: nets by name, e.g. `Net("GND")`, interfaces by
+/// their fields and anything else by its repr. Net ids are left out, so that reports are
+/// the same on every run.
+fn param_repr(value: Value) -> String {
+    let value = unwrap_using(value);
+    if let Some(net) = value.downcast_ref::<NetValue>() {
+        return format!("Net({:?})", net.name());
+    }
+    if let Some(net) = value.downcast_ref::<FrozenNetValue>() {
+        return format!("Net({:?})", net.name());
+    }
+
+    let fields: Vec<(String, Value)> =
+        if let Some(interface) = value.downcast_ref::<InterfaceValue>() {
+            interface
+                .fields()
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect()
+        } else if let Some(interface) = value.downcast_ref::<FrozenInterfaceValue>() {
+            interface
+                .fields()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_value()))
+                .collect()
+        } else {
+            return value.to_repr();
+        };
+    let type_name = get_promotion_key(value).unwrap_or_else(|_| "Interface".to_string());
+    let mut fields: Vec<String> = fields
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", name, param_repr(value)))
+        .collect();
+    fields.sort();
+    format!("{}({})", type_name, fields.join(", "))
+}
+
+/// Re-run `checks` against every mutation of `module`, the module evaluated for a passing
+/// case, and report each mutation as an advice diagnostic carrying a [`MutationResult`].
+/// A mutation is killed when a `check()` in any check fails on it. A check raising any
+/// other error on a mutant is reported as an error, and the mutation is not scored.
+fn run_mutations<'v>(
+    eval: &mut Evaluator<'v, '_, '_>,
+    module: &FrozenModuleValue,
+    params: &[(String, Value<'v>)],
+    checks: &[(Value<'v>, Option<&'v str>)],
+    test_bench_name: &str,
+    case_name: &str,
+) {
+    let inputs = params_dict(eval.heap(), params);
+    let loaded = eval
+        .eval_context()
+        .map(|ctx| ctx.loaded_modules())
+        .unwrap_or_default();
+    let record_types = mutation::record_types(&loaded, module, eval.frozen_heap());
+    for mutation in mutation::mutations(module, eval.frozen_heap(), &record_types) {
+        let mutant = mutation::apply(module, &mutation, eval.frozen_heap());
+        let args = [eval.frozen_heap().alloc(mutant).to_value(), inputs];
+        let mut killed_by = None;
+        let mut error = None;
+        for (check_func, custom_name) in checks {
+            match eval.eval_function(*check_func, &args, &[]) {
+                Ok(_) => continue,
+                Err(e) if is_check_failure(&e) => {
+                    killed_by = Some(check_name(*check_func, *custom_name));
+                }
+                Err(e) => error = Some((check_name(*check_func, *custom_name), e)),
+            }
+            break;
+        }
+
+        let (Some(ctx), Some(location)) = (eval.context_value(), eval.call_stack_top_location())
+        else {
+            continue;
+        };
+        let (severity, outcome, child) = match (&killed_by, error) {
+            (Some(check), _) => (
+                EvalSeverity::Advice,
+                format!("killed by check '{}'", check),
+                None,
+            ),
+            (None, Some((check, e))) => (
+                EvalSeverity::Error,
+                format!("raised an error in check '{}'", check),
+                Some(Box::new(Diagnostic::from(e))),
+            ),
+            (None, None) => (EvalSeverity::Advice, "survived".to_string(), None),
+        };
+        let result = MutationResult {
+            test_bench_name: test_bench_name.to_string(),
+            case_name: Some(case_name.to_string()),
+            file_path: location.filename().to_string(),
+            kind: mutation.kind().to_string(),
+            mutation: mutation.to_string(),
+            killed_by,
+            error: child.as_ref().map(|child| child.innermost().body.clone()),
+        };
+        ctx.add_diagnostic(Diagnostic {
+            path: location.filename().to_string(),
+            span: Some(location.resolve_span()),
+            severity,
+            body: format!(
+                "TestBench '{}' case '{}' mutation '{}' {}",
+                test_bench_name, case_name, mutation, outcome
+            ),
+            call_stack: Some(eval.call_stack().clone()),
+            child,
+            source_error: Some(Arc::new(result.into())),
+        });
     }
+}
 
-    Ok(inputs)
+/// Whether `error` is a failing `check()`, as opposed to e.g. a type error
+fn is_check_failure(error: &starlark::Error) -> bool {
+    let mut current = error.kind().source();
+    while let Some(source) = current {
+        if source.is::<CheckFailedError>() {
+            return true;
+        }
+        current = source.source();
+    }
+    false
 }
 
 /// Execute a single check function and handle the result
@@ -262,18 +541,10 @@ fn execute_check<'v>(
     args: &[Value<'v>],
     test_bench_name: &str,
     case_name: Option<&str>,
+    case_params: &BTreeMap<String, String>,
     custom_name: Option<&str>,
 ) -> anyhow::Result<(Value<'v>, bool)> {
-    let check_name = if let Some(name) = custom_name {
-        name.to_string()
-    } else {
-        let check_func_str = check_func.to_string();
-        check_func_str
-            .rsplit('.')
-            .next()
-            .unwrap_or("check")
-            .to_string()
-    };
+    let check_name = check_name(check_func, custom_name);
 
     let case_suffix = case_name
         .map(|n| format!(" case '{}'", n))
@@ -288,12 +559,19 @@ fn execute_check<'v>(
         ));
     }
 
-    match eval.eval_function(check_func, args, &[]) {
+    let (outcome, duration_secs) = timed(|| eval.eval_function(check_func, args, &[]));
+    match outcome {
         Ok(result) => {
             // Only add diagnostics if context is available
             if let (Some(ctx), Some(test_bench_location)) =
                 (eval.context_value(), eval.call_stack_top_location())
             {
+                // Point at the check's `def` when it is defined next to the TestBench
+                let definition_span = ctx
+                    .parent_context()
+                    .resolve_span_for_def(&function_name(check_func))
+                    .unwrap_or_else(|| test_bench_location.resolve_span());
+
                 // Create structured test result for tracking
                 let test_result = crate::lang::error::BenchTestResult {
                     test_bench_name: test_bench_name.to_string(),
@@ -301,6 +579,13 @@ fn execute_check<'v>(
                     check_name: check_name.clone(),
                     file_path: test_bench_location.filename().to_string(),
                     passed: true,
+                    span: Some(SourceSpan::new(
+                        test_bench_location.filename(),
+                        &definition_span,
+                    )),
+                    message: None,
+                    case_params: case_params.clone(),
+                    duration_secs,
                 };
 
                 // Add as a non-error diagnostic for collection purposes
@@ -327,7 +612,16 @@ fn execute_check<'v>(
             {
                 // Convert error to diagnostic - this will handle DiagnosticError chains properly
                 let child_diagnostic = Diagnostic::from(e);
-                let child = Some(Box::new(child_diagnostic));
+
+                // Point at the innermost frame of the failure, e.g. the failing `check()`
+                let failure = child_diagnostic.innermost();
+                let span = match &failure.span {
+                    Some(span) if !failure.path.is_empty() => SourceSpan::new(&failure.path, span),
+                    _ => SourceSpan::new(
+                        test_bench_location.filename(),
+                        &test_bench_location.resolve_span(),
+                    ),
+                };
 
                 // Create structured test result for tracking
                 let test_result = crate::lang::error::BenchTestResult {
@@ -336,7 +630,12 @@ fn execute_check<'v>(
                     check_name: check_name.clone(),
                     file_path: test_bench_location.filename().to_string(),
                     passed: false,
+                    span: Some(span),
+                    message: Some(failure.body.clone()),
+                    case_params: case_params.clone(),
+                    duration_secs,
                 };
+                let child = Some(Box::new(child_diagnostic));
 
                 // Parent diagnostic for TestBench context
                 ctx.add_diagnostic(Diagnostic {
@@ -381,84 +680,58 @@ pub fn test_bench_globals(builder: &mut GlobalsBuilder) {
             anyhow::anyhow!("'module' parameter must be a ModuleLoader (created with Module())")
         })?;
 
-        // Parse test_cases dict
-        let test_cases_dict = DictRef::from_value(test_cases)
-            .ok_or_else(|| anyhow::anyhow!("'test_cases' parameter must be a dictionary"))?;
-
-        if test_cases_dict.is_empty() {
-            return Err(anyhow::anyhow!("'test_cases' cannot be empty"));
-        }
+        // Parse explicit test cases, or generate them from a Sweep()
+        let sweep = SweepValue::from_value(test_cases);
+        let sweep_cases = sweep.map(|sweep| sweep.cases());
+        let case_specs = match &sweep_cases {
+            Some(generated) => generated
+                .iter()
+                .map(|case| (case.name.clone(), case.params.clone()))
+                .collect(),
+            None => parse_test_cases(test_cases)?,
+        };
+        let check_fns = checks.map(parse_checks).transpose()?;
 
         let mut cases = Vec::new();
         let mut total_checks = 0;
         let mut total_failed_checks = 0;
 
         // Process each test case
-        for (case_name, case_value) in test_cases_dict.iter() {
-            let case_name_str = case_name.unpack_str().ok_or_else(|| {
-                anyhow::anyhow!("test case names must be strings, got: {}", case_name)
-            })?;
-
-            let case_dict = DictRef::from_value(case_value).ok_or_else(|| {
-                anyhow::anyhow!("test case '{}' must be a dictionary", case_name_str)
-            })?;
-
-            // Build InputMap from case parameters
-            let inputs = build_input_map(&case_dict)?;
-
+        for (case_name_str, case_params) in &case_specs {
             // Evaluate the module with this test case
-            let evaluated_module =
-                loader.evaluate_with_inputs(name.clone(), eval, inputs, Some(case_name_str))?;
+            let evaluated_module = loader.evaluate_with_inputs(
+                name.clone(),
+                eval,
+                build_input_map(case_params),
+                Some(case_name_str.as_str()),
+            )?;
 
             // Execute check functions for this case
             let mut case_check_results = Vec::new();
             let mut case_failed_count = 0;
 
-            if let (Some(checks_value), Some(ref module)) = (&checks, &evaluated_module) {
-                let checks_list = ListRef::from_value(*checks_value).ok_or_else(|| {
-                    anyhow::anyhow!("'checks' parameter must be a list of functions")
-                })?;
-
+            if let (Some(check_fns), Some(ref module)) = (&check_fns, &evaluated_module) {
                 // Use frozen_heap to allocate the FrozenModuleValue
                 let module_value = eval.frozen_heap().alloc(module.clone()).to_value();
 
                 // Convert test case parameters to Starlark dict
-                let inputs_dict = eval
-                    .heap()
-                    .alloc(AllocDict(case_dict.iter().collect::<Vec<_>>()))
-                    .to_value();
+                let inputs_dict = params_dict(eval.heap(), case_params);
 
                 let args = [module_value, inputs_dict];
+                let rendered_params: BTreeMap<String, String> = case_params
+                    .iter()
+                    .map(|(key, value)| (key.clone(), param_repr(*value)))
+                    .collect();
 
-                for check_item in checks_list.iter() {
-                    // Check if it's a tuple (name, function) or just a function
-                    let (check_func, custom_name) =
-                        if let Some(tuple_ref) = TupleRef::from_value(check_item) {
-                            if tuple_ref.len() == 2 {
-                                let tuple_items: Vec<_> = tuple_ref.iter().collect();
-                                let name = tuple_items[0].unpack_str().ok_or_else(|| {
-                                    anyhow::anyhow!(
-                                        "First element of check tuple must be a string name"
-                                    )
-                                })?;
-                                let func = tuple_items[1];
-                                (func, Some(name))
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "Check tuple must have exactly 2 elements: (name, function)"
-                                ));
-                            }
-                        } else {
-                            (check_item, None)
-                        };
-
+                for (check_func, custom_name) in check_fns {
                     let (result, failed) = execute_check(
                         eval,
-                        check_func,
+                        *check_func,
                         &args,
                         &name,
-                        Some(case_name_str),
-                        custom_name,
+                        Some(case_name_str.as_str()),
+                        &rendered_params,
+                        *custom_name,
                     )?;
                     case_check_results.push(result);
                     total_checks += 1;
@@ -469,14 +742,19 @@ pub fn test_bench_globals(builder: &mut GlobalsBuilder) {
                 }
             }
 
-            // Store case parameters for introspection
-            let mut params = SmallMap::new();
-            for (key, value) in case_dict.iter() {
-                if let Some(key_str) = key.unpack_str() {
-                    params.insert(key_str.to_string(), value);
+            // Check that the checks would catch a broken version of this case's module
+            let mutate = eval.eval_context().is_some_and(|ctx| ctx.mutate);
+            if let (true, 0, Some(check_fns), Some(module)) =
+                (mutate, case_failed_count, &check_fns, &evaluated_module)
+            {
+                if !check_fns.is_empty() {
+                    run_mutations(eval, module, case_params, check_fns, &name, case_name_str);
                 }
             }
 
+            // Store case parameters for introspection
+            let params: SmallMap<String, Value> = case_params.iter().cloned().collect();
+
             cases.push(TestCaseResultGen {
                 params,
                 evaluated: evaluated_module,
@@ -485,6 +763,38 @@ pub fn test_bench_globals(builder: &mut GlobalsBuilder) {
             });
         }
 
+        // Shrink the first failing combination of a sweep to a simpler one that still fails
+        let mut shrunk_case = None;
+        if let (Some(sweep), Some(generated)) = (sweep, sweep_cases) {
+            let first_failure = cases
+                .iter()
+                .position(|case| case.evaluated.is_none() || case.failed_checks > 0);
+            if let (true, Some(index)) = (sweep.shrink(), first_failure) {
+                let failing = generated.into_iter().nth(index).expect("one case per spec");
+                let check_fns = check_fns.as_deref().unwrap_or_default();
+                let shrunk = sweep.shrink_case(failing, |case| {
+                    Ok(case_fails(eval, loader, &name, &case.params, check_fns))
+                })?;
+                if let (Some(ctx), Some(location)) =
+                    (eval.context_value(), eval.call_stack_top_location())
+                {
+                    ctx.add_diagnostic(Diagnostic {
+                        path: location.filename().to_string(),
+                        span: Some(location.resolve_span()),
+                        severity: EvalSeverity::Warning,
+                        body: format!(
+                            "TestBench '{}' sweep: smallest failing case is {}",
+                            name, shrunk.name
+                        ),
+                        call_stack: Some(eval.call_stack().clone()),
+                        child: None,
+                        source_error: None,
+                    });
+                }
+                shrunk_case = Some(params_dict(eval.heap(), &shrunk.params));
+            }
+        }
+
         // Build summary
         let mut summary = SmallMap::new();
         summary.insert(
@@ -499,6 +809,9 @@ pub fn test_bench_globals(builder: &mut GlobalsBuilder) {
             "total_failed_checks".to_string(),
             eval.heap().alloc(total_failed_checks).to_value(),
         );
+        if let Some(shrunk_case) = shrunk_case {
+            summary.insert("shrunk_case".to_string(), shrunk_case);
+        }
 
         // Log and print results
         log::info!(
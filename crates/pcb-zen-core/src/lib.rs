@@ -31,6 +31,14 @@ pub mod attrs {
     pub const SYMBOL_VALUE: &str = "__symbol_value";
     pub const PADS: &str = "pads";
 
+    // Power budget annotations, shared by nets and components. Defined next to the
+    // analysis in `pcb_sch::power`, which can't depend on this crate.
+    pub const VOLTAGE: &str = pcb_sch::power::ATTR_VOLTAGE;
+    pub const MAX_CURRENT: &str = pcb_sch::power::ATTR_MAX_CURRENT;
+    pub const LOAD_CURRENT: &str = pcb_sch::power::ATTR_LOAD_CURRENT;
+    pub const SUPPLY_CURRENT: &str = pcb_sch::power::ATTR_SUPPLY_CURRENT;
+    pub const SUPPLY_PINS: &str = pcb_sch::power::ATTR_SUPPLY_PINS;
+
     pub mod net {
         pub mod kind {
             pub const GROUND: &str = "ground";
@@ -50,8 +58,14 @@ pub use config::{BoardConfig, ModuleConfig, PcbToml, WorkspaceConfig};
 pub use diagnostics::{
     Diagnostic, DiagnosticError, Diagnostics, DiagnosticsPass, LoadError, WithDiagnostics,
 };
-pub use lang::error::{SuppressedDiagnostics, UnstableRefError};
-pub use lang::eval::{EvalContext, EvalMode, EvalOutput};
+pub use lang::coverage::Coverage;
+pub use lang::debugger::{DebugClient, Debugger, StopReason};
+pub use lang::error::{
+    CheckFailedError, LintEnabled, LintViolation, MissingInputError, MutationResult,
+    RequirementViolation, SuppressedDiagnostics, UnknownArgumentsError, UnknownPinError,
+    UnstableRefError,
+};
+pub use lang::eval::{EvalContext, EvalMode, EvalOutput, SharedEvalState};
 pub use lang::input::{InputMap, InputValue};
 pub use load_spec::LoadSpec;
 pub use passes::{AggregatePass, FilterHiddenPass, LspFilterPass, PromoteDeniedPass, SortPass};
@@ -109,6 +123,15 @@ pub struct SymbolInfo {
     pub documentation: Option<String>,
 }
 
+/// The final and originally requested name of a net bound to a global symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetNameInfo {
+    /// Name after deduplication within the module (e.g. `GND_2`)
+    pub name: String,
+    /// Name passed to `Net()` (e.g. `GND`)
+    pub original_name: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SymbolKind {
     Function,
@@ -229,6 +252,42 @@ impl RemoteFetcher for NoopRemoteFetcher {
     }
 }
 
+/// Persistent key/value store for results that are expensive to recompute, such as
+/// parsed symbol libraries. Keys are derived from content hashes, so entries never
+/// need to be invalidated; implementors are free to drop entries at any time.
+pub trait EvalCache: Send + Sync {
+    /// Return the value stored under `key` in `namespace`, if any.
+    fn get(&self, namespace: &str, key: &str) -> Option<Vec<u8>>;
+
+    /// Store `value` under `key` in `namespace`. Failures are not reported; a cache
+    /// that cannot be written simply behaves like an empty one.
+    fn put(&self, namespace: &str, key: &str, value: &[u8]);
+}
+
+/// Hex-encoded SHA-256 over `parts`, used to build [`EvalCache`] keys. The crate
+/// version is mixed in so that entries written by a different release are ignored.
+pub fn cache_key<I, B>(parts: I) -> String
+where
+    I: IntoIterator<Item = B>,
+    B: AsRef<[u8]>,
+{
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    for part in parts {
+        let part = part.as_ref();
+        // Length-prefix each part so that ("ab", "c") and ("a", "bc") differ.
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
 /// Abstraction for resolving load() paths to file contents
 /// Kind of a resolved Git reference after fetching
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -535,6 +535,35 @@ impl LoadSpec {
         )
     }
 
+    /// Return a copy of this LoadSpec pinned to `rev` (a tag or commit).
+    ///
+    /// Local paths have no revision, so `None` is returned for them.
+    pub fn with_rev(&self, rev: &str) -> Option<LoadSpec> {
+        match self {
+            LoadSpec::Github {
+                user, repo, path, ..
+            } => Some(LoadSpec::Github {
+                user: user.clone(),
+                repo: repo.clone(),
+                rev: rev.to_string(),
+                path: path.clone(),
+            }),
+            LoadSpec::Gitlab {
+                project_path, path, ..
+            } => Some(LoadSpec::Gitlab {
+                project_path: project_path.clone(),
+                rev: rev.to_string(),
+                path: path.clone(),
+            }),
+            LoadSpec::Package { package, path, .. } => Some(LoadSpec::Package {
+                package: package.clone(),
+                tag: rev.to_string(),
+                path: path.clone(),
+            }),
+            LoadSpec::Path { .. } => None,
+        }
+    }
+
     /// Drop the path from the LoadSpec for all variants
     pub fn without_path(&self) -> Self {
         self.with_path(PathBuf::new())
@@ -633,6 +662,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_with_rev_pins_remote_specs() {
+        let spec = LoadSpec::parse("@stdlib/interfaces.zen").unwrap();
+        assert_eq!(
+            spec.with_rev("v0.2.8").unwrap().to_load_string(),
+            "@stdlib:v0.2.8/interfaces.zen"
+        );
+
+        let spec = LoadSpec::parse("@github/foo/bar:main/x.zen").unwrap();
+        assert_eq!(
+            spec.with_rev("v1.0.0").unwrap().to_load_string(),
+            "@github/foo/bar:v1.0.0/x.zen"
+        );
+
+        assert!(LoadSpec::parse("./x.zen").unwrap().with_rev("v1").is_none());
+    }
+
     #[test]
     fn test_parse_load_spec_github_no_rev() {
         let spec = LoadSpec::parse("@github/foo/bar/scripts/build.zen");
@@ -1,31 +1,62 @@
-use crate::{Diagnostic, Diagnostics, DiagnosticsPass, SuppressedDiagnostics};
+use crate::lang::requirements::REQUIREMENTS_LINT;
+use crate::{
+    Diagnostic, Diagnostics, DiagnosticsPass, LintViolation, RequirementViolation,
+    SuppressedDiagnostics, UnstableRefError,
+};
 use starlark::errors::EvalSeverity;
 use std::path::Path;
 use std::sync::Arc;
 
-/// A pass that promotes diagnostics based on deny rules
+/// A pass that promotes diagnostics based on deny rules: `warnings` denies every
+/// warning, any other name denies the warnings of that lint
 pub struct PromoteDeniedPass {
     deny_warnings: bool,
+    denied_lints: Vec<String>,
 }
 
 impl PromoteDeniedPass {
     pub fn new(deny: &[String]) -> Self {
         Self {
             deny_warnings: deny.contains(&"warnings".to_string()),
+            denied_lints: deny
+                .iter()
+                .filter(|lint| *lint != "warnings")
+                .cloned()
+                .collect(),
         }
     }
 }
 
 impl DiagnosticsPass for PromoteDeniedPass {
     fn apply(&self, diagnostics: &mut Diagnostics) {
-        if self.deny_warnings {
-            for diagnostic in &mut diagnostics.diagnostics {
+        for diagnostic in &mut diagnostics.diagnostics {
+            let denied = self.deny_warnings
+                || lint_name(diagnostic).is_some_and(|lint| self.denied_lints.contains(&lint));
+            if denied {
                 promote_diagnostic_to_error(diagnostic);
             }
         }
     }
 }
 
+/// The name of the lint that produced a diagnostic or one of its children, if any
+fn lint_name(diagnostic: &Diagnostic) -> Option<String> {
+    let mut current = Some(diagnostic);
+    while let Some(diag) = current {
+        if let Some(violation) = diag.downcast_error_ref::<LintViolation>() {
+            return Some(violation.lint.clone());
+        }
+        if diag.is_error_type::<UnstableRefError>() {
+            return Some("unstable-refs".to_string());
+        }
+        if diag.is_error_type::<RequirementViolation>() {
+            return Some(REQUIREMENTS_LINT.to_string());
+        }
+        current = diag.child.as_deref();
+    }
+    None
+}
+
 /// A pass that filters out hidden diagnostics (containing "<hidden>")
 pub struct FilterHiddenPass;
 
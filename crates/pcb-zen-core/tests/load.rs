@@ -66,3 +66,59 @@ snapshot_eval!(module_loader_attrs, {
         check(MyModule.TestExport == "test", "TestExport should be 'test'")
     "#
 });
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn invalidating_a_load_drops_its_dependents() {
+    use pcb_zen_core::{CoreLoadResolver, EvalContext, InputMap, NoopRemoteFetcher};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    let files = std::collections::HashMap::from([
+        ("b.zen".to_string(), "B = 1\n".to_string()),
+        (
+            "a.zen".to_string(),
+            "load(\"b.zen\", \"B\")\nA = B\n".to_string(),
+        ),
+        ("c.zen".to_string(), "C = 2\n".to_string()),
+        (
+            "main.zen".to_string(),
+            "load(\"a.zen\", \"A\")\nload(\"c.zen\", \"C\")\n".to_string(),
+        ),
+    ]);
+    let file_provider = Arc::new(common::InMemoryFileProvider::new(files));
+    let load_resolver = Arc::new(CoreLoadResolver::new(
+        file_provider.clone(),
+        Arc::new(NoopRemoteFetcher::default()),
+        PathBuf::from("/"),
+        true,
+    ));
+    let base = EvalContext::new()
+        .set_file_provider(file_provider)
+        .set_load_resolver(load_resolver);
+
+    let result = base
+        .child_context()
+        .set_source_path(PathBuf::from("/main.zen"))
+        .set_module_name("<root>")
+        .set_inputs(InputMap::new())
+        .eval();
+    assert!(result.is_success());
+
+    let loaded = |ctx: &EvalContext| {
+        let mut files = ctx.loaded_files();
+        files.sort();
+        files
+    };
+    assert_eq!(
+        loaded(&base),
+        vec![
+            PathBuf::from("/a.zen"),
+            PathBuf::from("/b.zen"),
+            PathBuf::from("/c.zen")
+        ]
+    );
+
+    base.invalidate_loads([PathBuf::from("/b.zen")]);
+    assert_eq!(loaded(&base), vec![PathBuf::from("/c.zen")]);
+}
@@ -131,3 +131,18 @@ snapshot_eval!(interface_net_template_naming, {
         check(power.NET.name == "POWER", "Single-net interface should use instance name directly")
     "#,
 });
+
+snapshot_eval!(net_with_rail_annotations, {
+    "test.zen" => r#"
+        # Annotate a power rail with its voltage and available current
+        vcc = Net("VCC_3V3", voltage = "3.3V", max_current = "500mA")
+
+        print("Rail:", vcc)
+    "#
+});
+
+snapshot_eval!(net_rejects_unknown_kwarg, {
+    "test.zen" => r#"
+        Net("VCC", current = "1A")
+    "#
+});
@@ -275,3 +275,32 @@ snapshot_eval!(symbol_positional_multiple_colons, {
         print("Symbol:", sym)
     "#
 });
+
+snapshot_eval!(symbol_multi_unit, {
+    "LM358.kicad_sym" => include_str!("../../pcb-eda/tests/resources/kicad/LM358/LM358.kicad_sym"),
+    "test.zen" => r#"
+        # Pins repeated across units are qualified with the unit name
+        sym = Symbol(library = "LM358.kicad_sym")
+        print(sym)
+        print(sym.units)
+
+        # Each unit can be wired on its own
+        fb_a = Net("FB_A")
+        fb_b = Net("FB_B")
+        Component(
+            name = "U1",
+            footprint = "SOIC-8",
+            symbol = sym,
+            pins = {
+                "A.+": Net("IN_A"),
+                "A.-": fb_a,
+                "1": fb_a,
+                "B.+": Net("IN_B"),
+                "B.-": fb_b,
+                "7": fb_b,
+                "V+": Net("VCC"),
+                "V-": Net("GND"),
+            },
+        )
+    "#
+});
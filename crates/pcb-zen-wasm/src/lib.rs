@@ -1,10 +1,12 @@
 use log::debug;
-use pcb_sch::generate_bom_entries;
+use pcb_sch::kicad_netlist::to_kicad_netlist;
+use pcb_sch::{generate_bom_entries, group_bom_entries, Schematic};
 use pcb_zen_core::config::find_workspace_root;
 use pcb_zen_core::convert::ToSchematic;
-use pcb_zen_core::{EvalContext, FileProvider, InputMap, InputValue};
+use pcb_zen_core::{EvalContext, EvalOutput, FileProvider, InputMap, InputValue, WithDiagnostics};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use wasm_bindgen::prelude::*;
@@ -219,11 +221,64 @@ impl FetchRequest {
 /// Custom file provider that wraps InMemoryFileProvider and adds JavaScript fallback
 struct WasmFileProvider {
     inner: Arc<Mutex<pcb_zen_core::InMemoryFileProvider>>,
+    /// Files deleted through `Module::deleteFile`, which must not be loaded from JavaScript again
+    deleted: Mutex<HashSet<PathBuf>>,
 }
 
 impl WasmFileProvider {
     fn new(inner: Arc<Mutex<pcb_zen_core::InMemoryFileProvider>>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            deleted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// The key `path` is stored under in the inner provider
+    fn normalize(&self, path: &Path) -> PathBuf {
+        let path = absolute_path(path);
+        let inner = self.inner.lock().unwrap();
+        inner.canonicalize(&path).unwrap_or(path)
+    }
+
+    fn is_deleted(&self, path: &Path) -> bool {
+        let path = self.normalize(path);
+        self.deleted.lock().unwrap().contains(&path)
+    }
+
+    /// Create or overwrite a file in memory
+    fn write_file(&self, path: &Path, content: String) {
+        let path = self.normalize(path);
+        self.deleted.lock().unwrap().remove(&path);
+        self.inner.lock().unwrap().add_file(path, content);
+    }
+
+    /// Remove a file from memory, returning whether it existed
+    fn delete_file(&self, path: &Path) -> bool {
+        let path = self.normalize(path);
+        let existed = {
+            let mut inner = self.inner.lock().unwrap();
+            let existed = inner.files().contains_key(&path);
+            inner.remove_file(path.clone());
+            existed
+        };
+        self.deleted.lock().unwrap().insert(path);
+        existed
+    }
+
+    /// All files currently held in memory, sorted by path
+    fn list_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.inner.lock().unwrap().files().keys().cloned().collect();
+        files.sort();
+        files
+    }
+}
+
+/// Make `path` absolute the same way `InMemoryFileProvider` stores it
+fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        PathBuf::from("/").join(path)
     }
 }
 
@@ -231,6 +286,12 @@ impl pcb_zen_core::FileProvider for WasmFileProvider {
     fn read_file(&self, path: &Path) -> Result<String, pcb_zen_core::FileProviderError> {
         let path_str = path.to_string_lossy();
 
+        if self.is_deleted(path) {
+            return Err(pcb_zen_core::FileProviderError::NotFound(
+                path.to_path_buf(),
+            ));
+        }
+
         // Try the inner provider first
         if let Ok(provider) = self.inner.lock() {
             match provider.read_file(path) {
@@ -267,6 +328,10 @@ impl pcb_zen_core::FileProvider for WasmFileProvider {
     }
 
     fn exists(&self, path: &Path) -> bool {
+        if self.is_deleted(path) {
+            return false;
+        }
+
         // Check the inner provider first
         if let Ok(provider) = self.inner.lock() {
             if provider.exists(path) {
@@ -361,7 +426,10 @@ pub struct Module {
     main_file: String,
     module_name: String,
     file_provider: Arc<WasmFileProvider>,
-    load_resolver: Arc<pcb_zen_core::CoreLoadResolver>,
+    remote_fetcher: Arc<dyn pcb_zen_core::RemoteFetcher>,
+    use_vendor_dir: bool,
+    /// Rebuilt whenever a `pcb.toml` changes, since it caches workspace aliases
+    load_resolver: RefCell<Arc<pcb_zen_core::CoreLoadResolver>>,
 }
 
 #[wasm_bindgen]
@@ -370,91 +438,168 @@ impl Module {
     #[wasm_bindgen(js_name = fromPath)]
     pub fn from_path(file_path: &str, options: JsValue) -> Result<Module, JsValue> {
         // Extract module name from the file path
-        let path = PathBuf::from(file_path);
-        let module_name = path
+        let module_name = Path::new(file_path)
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("module")
             .to_string();
 
-        // Generate unique ID
-        let id = format!("module_{}", uuid::Uuid::new_v4());
+        Self::with_files(file_path, module_name, HashMap::new(), options)
+    }
 
-        // Create shared inner provider
-        let inner_provider = Arc::new(Mutex::new(pcb_zen_core::InMemoryFileProvider::new(
-            HashMap::new(),
-        )));
+    /// Create a module from individual files
+    #[wasm_bindgen(js_name = fromFiles)]
+    pub fn from_files(
+        files_json: &str,
+        main_file: &str,
+        module_name: &str,
+        options: JsValue,
+    ) -> Result<Module, JsValue> {
+        let files: std::collections::HashMap<String, String> = serde_json::from_str(files_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse files JSON: {e}")))?;
 
-        // Create file provider and remote fetcher that share the same inner provider
-        let file_provider = Arc::new(WasmFileProvider::new(inner_provider.clone()));
-        // Parse options for resolver configuration
-        #[derive(Deserialize)]
-        struct ModuleOptions {
-            #[serde(rename = "useVendorDir")]
-            use_vendor_dir: Option<bool>,
-            offline: Option<bool>,
-        }
+        Self::with_files(main_file, module_name.to_string(), files, options)
+    }
 
-        let (use_vendor_dir, offline) = if !options.is_undefined() && !options.is_null() {
-            match serde_wasm_bindgen::from_value::<ModuleOptions>(options) {
-                Ok(opts) => (
-                    opts.use_vendor_dir.unwrap_or(true),
-                    opts.offline.unwrap_or(false),
-                ),
+    /// Evaluate the module with the given inputs
+    #[wasm_bindgen]
+    pub fn evaluate(&self, inputs_json: &str) -> Result<JsValue, JsValue> {
+        let result = self.eval(inputs_json)?;
+
+        // Extract schematic from the result
+        let schematic_opt = result
+            .output
+            .as_ref()
+            .and_then(|output| output.sch_module.to_schematic().ok());
+
+        let parameters = result
+            .output
+            .as_ref()
+            .map(|output| output.signature.clone());
+
+        // Generate BOM JSON if schematic is available
+        let bom_json = schematic_opt.as_ref().and_then(|schematic| {
+            let entries = generate_bom_entries(schematic);
+            match serde_json::to_string(&entries) {
+                Ok(json) => Some(json),
                 Err(e) => {
-                    return Err(JsValue::from_str(&format!(
-                        "Failed to parse module options: {e}"
-                    )));
+                    log::error!("Failed to serialize BOM to JSON: {e}");
+                    None
                 }
             }
-        } else {
-            (true, false)
-        };
+        });
 
-        let remote_fetcher: Arc<dyn pcb_zen_core::RemoteFetcher> = if offline {
-            Arc::new(pcb_zen_core::NoopRemoteFetcher)
-        } else {
-            Arc::new(WasmRemoteFetcher::new(inner_provider))
+        // Build evaluation result
+        let evaluation_result = EvaluationResult {
+            success: result.output.is_some(),
+            parameters,
+            schematic: schematic_opt.and_then(|s| match serde_json::to_string(&s) {
+                Ok(json) => Some(json),
+                Err(e) => {
+                    log::error!("Failed to serialize schematic to JSON: {e}");
+                    None
+                }
+            }),
+            bom: bom_json,
+            diagnostics: result
+                .diagnostics
+                .into_iter()
+                .map(|d| diagnostic_to_json(&d))
+                .collect(),
         };
 
-        // Determine workspace root using pcb.toml discovery
-        let workspace_root = find_workspace_root(file_provider.as_ref(), &path);
+        serde_wasm_bindgen::to_value(&evaluation_result)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize result: {e}")))
+    }
 
-        // Create load resolver
-        let load_resolver = Arc::new(pcb_zen_core::CoreLoadResolver::new(
-            file_provider.clone(),
-            remote_fetcher.clone(),
-            workspace_root,
-            use_vendor_dir,
-        ));
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
 
-        Ok(Module {
-            id,
-            main_file: file_path.to_string(),
-            module_name,
-            file_provider,
-            load_resolver,
+    /// Free the module from memory
+    #[wasm_bindgen]
+    pub fn free_module(&self) {
+        // TODO: Send a cleanup message to the worker
+        debug!("Freeing module {}", self.id);
+    }
+
+    /// Read a file from the module's file system
+    #[wasm_bindgen(js_name = readFile)]
+    pub fn read_file(&self, path: &str) -> Result<String, JsValue> {
+        self.file_provider
+            .read_file(Path::new(path))
+            .map_err(|e| JsValue::from_str(&format!("Failed to read {path}: {e}")))
+    }
+
+    /// Write a file to the module's file system. The next evaluation picks up the change.
+    #[wasm_bindgen(js_name = writeFile)]
+    pub fn write_file(&self, path: &str, content: &str) -> Result<(), JsValue> {
+        self.file_provider
+            .write_file(Path::new(path), content.to_string());
+        self.file_changed(path);
+        Ok(())
+    }
+
+    /// Delete a file from the module's file system
+    #[wasm_bindgen(js_name = deleteFile)]
+    pub fn delete_file(&self, path: &str) -> Result<(), JsValue> {
+        if !self.file_provider.delete_file(Path::new(path)) {
+            return Err(JsValue::from_str(&format!("File not found: {path}")));
+        }
+        self.file_changed(path);
+        Ok(())
+    }
+
+    /// List all files in the module's file system, as a JSON array of paths
+    #[wasm_bindgen(js_name = listFiles)]
+    pub fn list_files(&self) -> Result<String, JsValue> {
+        let files: Vec<String> = self
+            .file_provider
+            .list_files()
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        serde_json::to_string(&files)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize file list: {e}")))
+    }
+
+    /// Evaluate the module and return its schematic as JSON
+    #[wasm_bindgen(js_name = exportSchematic)]
+    pub fn export_schematic(&self, inputs_json: &str) -> Result<JsValue, JsValue> {
+        self.export(inputs_json, |schematic| {
+            serde_json::to_string(schematic).map_err(anyhow::Error::from)
         })
     }
 
-    /// Create a module from individual files
-    #[wasm_bindgen(js_name = fromFiles)]
-    pub fn from_files(
-        files_json: &str,
+    /// Evaluate the module and return its KiCad netlist
+    #[wasm_bindgen(js_name = exportNetlist)]
+    pub fn export_netlist(&self, inputs_json: &str) -> Result<JsValue, JsValue> {
+        self.export(inputs_json, |schematic| Ok(to_kicad_netlist(schematic)))
+    }
+
+    /// Evaluate the module and return its BOM as JSON, with identical parts grouped together
+    #[wasm_bindgen(js_name = exportBom)]
+    pub fn export_bom(&self, inputs_json: &str) -> Result<JsValue, JsValue> {
+        self.export(inputs_json, |schematic| {
+            let entries = group_bom_entries(generate_bom_entries(schematic));
+            serde_json::to_string(&entries).map_err(anyhow::Error::from)
+        })
+    }
+}
+
+impl Module {
+    fn with_files(
         main_file: &str,
-        module_name: &str,
+        module_name: String,
+        files: HashMap<String, String>,
         options: JsValue,
     ) -> Result<Module, JsValue> {
-        let files: std::collections::HashMap<String, String> = serde_json::from_str(files_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse files JSON: {e}")))?;
-
         // Generate unique ID
         let id = format!("module_{}", uuid::Uuid::new_v4());
 
         // Create shared inner provider with the provided files
-        let inner_provider = Arc::new(Mutex::new(pcb_zen_core::InMemoryFileProvider::new(
-            files.clone(),
-        )));
+        let inner_provider = Arc::new(Mutex::new(pcb_zen_core::InMemoryFileProvider::new(files)));
 
         // Create file provider and remote fetcher that share the same inner provider
         let file_provider = Arc::new(WasmFileProvider::new(inner_provider.clone()));
@@ -488,38 +633,35 @@ impl Module {
             Arc::new(WasmRemoteFetcher::new(inner_provider))
         };
 
-        // Determine workspace root using pcb.toml discovery
-        let main_path = PathBuf::from(main_file);
-        let workspace_root = find_workspace_root(file_provider.as_ref(), &main_path);
-
-        // Create load resolver
-        let load_resolver = Arc::new(pcb_zen_core::CoreLoadResolver::new(
-            file_provider.clone(),
-            remote_fetcher.clone(),
-            workspace_root,
+        let load_resolver = new_load_resolver(
+            &file_provider,
+            &remote_fetcher,
+            Path::new(main_file),
             use_vendor_dir,
-        ));
+        );
 
         Ok(Module {
             id,
             main_file: main_file.to_string(),
-            module_name: module_name.to_string(),
+            module_name,
             file_provider,
-            load_resolver,
+            remote_fetcher,
+            use_vendor_dir,
+            load_resolver: RefCell::new(load_resolver),
         })
     }
 
-    /// Evaluate the module with the given inputs
-    #[wasm_bindgen]
-    pub fn evaluate(&self, inputs_json: &str) -> Result<JsValue, JsValue> {
+    /// Evaluate the module against the current contents of its file system
+    fn eval(&self, inputs_json: &str) -> Result<WithDiagnostics<EvalOutput>, JsValue> {
         // Parse inputs
         let inputs: HashMap<String, serde_json::Value> = serde_json::from_str(inputs_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse inputs JSON: {e}")))?;
 
-        // Create evaluation context using the stored providers
+        // Create evaluation context using the stored providers. Every evaluation starts
+        // from a fresh context so that edits made through `writeFile` are picked up.
         let ctx = EvalContext::new()
             .set_file_provider(self.file_provider.clone())
-            .set_load_resolver(self.load_resolver.clone());
+            .set_load_resolver(self.load_resolver.borrow().clone());
 
         // Convert inputs to InputMap
         let mut input_map = InputMap::new();
@@ -531,97 +673,85 @@ impl Module {
 
         // Evaluate the module
         let main_path = PathBuf::from(&self.main_file);
-        let result = ctx
+        Ok(ctx
             .set_source_path(main_path)
             .set_module_name(self.module_name.clone())
             .set_inputs(input_map)
-            .eval();
-
-        // Extract schematic from the result
-        let schematic_opt = result
-            .output
-            .as_ref()
-            .and_then(|output| output.sch_module.to_schematic().ok());
-
-        let parameters = result
-            .output
-            .as_ref()
-            .map(|output| output.signature.clone());
+            .eval())
+    }
 
-        // Generate BOM JSON if schematic is available
-        let bom_json = schematic_opt.as_ref().and_then(|schematic| {
-            let entries = generate_bom_entries(&mut schematic.clone());
-            match serde_json::to_string(&entries) {
-                Ok(json) => Some(json),
+    /// Evaluate the module and render its schematic with `render`
+    fn export(
+        &self,
+        inputs_json: &str,
+        render: impl FnOnce(&Schematic) -> anyhow::Result<String>,
+    ) -> Result<JsValue, JsValue> {
+        let (output, mut diagnostics) = self.eval(inputs_json)?.unpack();
+        let content = match output.map(|output| output.sch_module.to_schematic()) {
+            Some(Ok(schematic)) if !diagnostics.has_errors() => match render(&schematic) {
+                Ok(content) => Some(content),
                 Err(e) => {
-                    log::error!("Failed to serialize BOM to JSON: {e}");
+                    diagnostics.push(export_error(&self.main_file, e));
                     None
                 }
+            },
+            Some(Err(e)) => {
+                diagnostics.push(export_error(&self.main_file, e));
+                None
             }
-        });
+            _ => None,
+        };
 
-        // Build evaluation result
-        let evaluation_result = EvaluationResult {
-            success: result.output.is_some(),
-            parameters,
-            schematic: schematic_opt.and_then(|s| match serde_json::to_string(&s) {
-                Ok(json) => Some(json),
-                Err(e) => {
-                    log::error!("Failed to serialize schematic to JSON: {e}");
-                    None
-                }
-            }),
-            bom: bom_json,
-            diagnostics: result
-                .diagnostics
+        let export_result = ExportResult {
+            success: content.is_some(),
+            content,
+            diagnostics: diagnostics
                 .into_iter()
                 .map(|d| diagnostic_to_json(&d))
                 .collect(),
         };
-
-        serde_wasm_bindgen::to_value(&evaluation_result)
+        serde_wasm_bindgen::to_value(&export_result)
             .map_err(|e| JsValue::from_str(&format!("Failed to serialize result: {e}")))
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn id(&self) -> String {
-        self.id.clone()
-    }
-
-    /// Free the module from memory
-    #[wasm_bindgen]
-    pub fn free_module(&self) {
-        // TODO: Send a cleanup message to the worker
-        debug!("Freeing module {}", self.id);
-    }
-
-    /// Read a file from the module's file system
-    #[wasm_bindgen(js_name = readFile)]
-    pub fn read_file(&self, _path: &str) -> Result<String, JsValue> {
-        // TODO: Implement file reading through worker
-        Err(JsValue::from_str("File reading not yet implemented"))
-    }
-
-    /// Write a file to the module's file system
-    #[wasm_bindgen(js_name = writeFile)]
-    pub fn write_file(&self, _path: &str, _content: &str) -> Result<(), JsValue> {
-        // TODO: Implement file writing through worker
-        Err(JsValue::from_str("File writing not yet implemented"))
+    /// Invalidate state derived from `path` after it was written or deleted
+    fn file_changed(&self, path: &str) {
+        if Path::new(path).file_name() == Some(std::ffi::OsStr::new("pcb.toml")) {
+            *self.load_resolver.borrow_mut() = new_load_resolver(
+                &self.file_provider,
+                &self.remote_fetcher,
+                Path::new(&self.main_file),
+                self.use_vendor_dir,
+            );
+        }
     }
+}
 
-    /// Delete a file from the module's file system
-    #[wasm_bindgen(js_name = deleteFile)]
-    pub fn delete_file(&self, _path: &str) -> Result<(), JsValue> {
-        // TODO: Implement file deletion through worker
-        Err(JsValue::from_str("File deletion not yet implemented"))
-    }
+/// Create a load resolver rooted at the workspace containing `main_file`
+fn new_load_resolver(
+    file_provider: &Arc<WasmFileProvider>,
+    remote_fetcher: &Arc<dyn pcb_zen_core::RemoteFetcher>,
+    main_file: &Path,
+    use_vendor_dir: bool,
+) -> Arc<pcb_zen_core::CoreLoadResolver> {
+    // Determine workspace root using pcb.toml discovery
+    let workspace_root = find_workspace_root(file_provider.as_ref(), main_file);
+
+    Arc::new(pcb_zen_core::CoreLoadResolver::new(
+        file_provider.clone(),
+        remote_fetcher.clone(),
+        workspace_root,
+        use_vendor_dir,
+    ))
+}
 
-    /// List all files in the module's file system
-    #[wasm_bindgen(js_name = listFiles)]
-    pub fn list_files(&self) -> Result<String, JsValue> {
-        // TODO: Implement file listing through worker
-        Ok("[]".to_string())
-    }
+/// A diagnostic for a failure to export an evaluated module
+fn export_error(path: &str, error: anyhow::Error) -> pcb_zen_core::Diagnostic {
+    pcb_zen_core::Diagnostic::new(
+        format!("Failed to export: {error}"),
+        starlark::errors::EvalSeverity::Error,
+        Path::new(path),
+    )
 }
 
 // Data structures for serialization
@@ -635,6 +765,14 @@ pub struct DiagnosticInfo {
     pub child: Option<Box<DiagnosticInfo>>,
 }
 
+/// The result of one of the `export*` entry points
+#[derive(Serialize, Deserialize)]
+pub struct ExportResult {
+    pub success: bool,
+    pub content: Option<String>,
+    pub diagnostics: Vec<DiagnosticInfo>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct EvaluationResult {
     pub success: bool,
@@ -643,3 +781,108 @@ pub struct EvaluationResult {
     pub bom: Option<String>,
     pub diagnostics: Vec<DiagnosticInfo>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen(inline_js = r#"
+export function install_js_stubs() {
+    globalThis.__zen = {
+        loadFile: (path) => "ERROR: not found: " + path,
+        fetchRemoteFile: (request) => "ERROR: offline",
+    };
+}
+"#)]
+    extern "C" {
+        fn install_js_stubs();
+    }
+
+    fn resistor(name: &str, value: &str) -> String {
+        format!(
+            r#"
+Component(
+    name = "{name}",
+    prefix = "R",
+    footprint = "SMD:0402",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {{"1": Net("A"), "2": Net("B")}},
+    properties = {{"value": "{value}"}},
+)
+"#
+        )
+    }
+
+    fn module(board: &str) -> Module {
+        install_js_stubs();
+        let files = serde_json::json!({ "/board.zen": board }).to_string();
+        Module::from_files(&files, "/board.zen", "board", JsValue::UNDEFINED).unwrap()
+    }
+
+    fn export(result: Result<JsValue, JsValue>) -> ExportResult {
+        serde_wasm_bindgen::from_value(result.unwrap()).unwrap()
+    }
+
+    fn files(module: &Module) -> Vec<String> {
+        serde_json::from_str(&module.list_files().unwrap()).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn files_can_be_written_read_listed_and_deleted() {
+        let module = module(&resistor("R1", "10k"));
+
+        module.write_file("lib/part.zen", "X = 1\n").unwrap();
+        assert_eq!(module.read_file("/lib/part.zen").unwrap(), "X = 1\n");
+        assert_eq!(files(&module), ["/board.zen", "/lib/part.zen"]);
+
+        module.write_file("/lib/part.zen", "X = 2\n").unwrap();
+        assert_eq!(module.read_file("lib/part.zen").unwrap(), "X = 2\n");
+
+        module.delete_file("/lib/part.zen").unwrap();
+        assert_eq!(files(&module), ["/board.zen"]);
+        assert!(module.read_file("/lib/part.zen").is_err());
+        assert!(module.delete_file("/lib/part.zen").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn exports_render_the_evaluated_board() {
+        let module = module(&resistor("R1", "10k"));
+
+        let netlist = export(module.export_netlist("{}"));
+        assert!(netlist.success, "{:?}", netlist.content);
+        assert!(netlist.content.unwrap().contains("(export"));
+
+        let schematic = export(module.export_schematic("{}"));
+        assert!(schematic.success);
+        let schematic: serde_json::Value =
+            serde_json::from_str(&schematic.content.unwrap()).unwrap();
+        assert!(schematic.get("instances").is_some());
+
+        let bom = export(module.export_bom("{}"));
+        assert!(bom.success);
+        let bom: Vec<serde_json::Value> = serde_json::from_str(&bom.content.unwrap()).unwrap();
+        assert_eq!(bom.len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn exports_pick_up_written_files() {
+        let module = module(&resistor("R1", "10k"));
+        let board = resistor("R1", "10k") + &resistor("R2", "4.7k");
+        module.write_file("/board.zen", &board).unwrap();
+
+        let bom = export(module.export_bom("{}"));
+        let bom: Vec<serde_json::Value> = serde_json::from_str(&bom.content.unwrap()).unwrap();
+        assert_eq!(bom.len(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn failed_exports_report_diagnostics() {
+        let module = module("Component(\n");
+
+        let netlist = export(module.export_netlist("{}"));
+        assert!(!netlist.success);
+        assert!(netlist.content.is_none());
+        assert!(netlist.diagnostics.iter().any(|diag| diag.level == "error"));
+    }
+}
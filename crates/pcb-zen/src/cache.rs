@@ -0,0 +1,481 @@
+//! Persistent evaluation cache stored under `<workspace>/.pcb/eval-cache`.
+//!
+//! The cache has its own root rather than living under `<workspace>/.pcb/cache`, because
+//! every entry of that directory is the symlink of a load alias: `@name/...` loads are
+//! exposed as `.pcb/cache/name`, and aliases are user-defined in `pcb.toml`, so no
+//! subdirectory name there is safe from being claimed by an alias. `pcb clean` removes
+//! both, as they live under `.pcb`. Three namespaces live in the cache:
+//!
+//! * `symbols/` holds resolved KiCad symbols keyed by the contents of their library and
+//!   their name. These entries are written by `pcb-zen-core` through the [`EvalCache`]
+//!   trait.
+//! * `modules/` holds leaf module instances keyed by the module's contents and the inputs
+//!   of the `Module()` call, each with the hashes of the files it read and loaded. These
+//!   are also written by `pcb-zen-core`, and spare re-evaluating unchanged modules when
+//!   the root module or another part of the design changed.
+//! * `eval/` holds the schematic and diagnostics of a root module keyed by its path,
+//!   contents, evaluation mode and offline flag. Each entry also records every file the
+//!   evaluation read or probed and the commit every remote checkout it loaded from was
+//!   at, so it is only reused while the whole resolved load graph — loaded modules,
+//!   symbol libraries, footprints, remote revisions — is unchanged.
+//!
+//! Set `PCB_NO_CACHE` to bypass the evaluation cache entirely.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use pcb_sch::Schematic;
+use pcb_zen_core::lang::error::{
+    BenchTestResult, LintEnabled, LintViolation, RequirementViolation,
+};
+use pcb_zen_core::{
+    cache_key, CoreLoadResolver, Diagnostic, EvalCache, EvalMode, FileProvider, FileProviderError,
+    LoadSpec, WithDiagnostics,
+};
+use serde::{Deserialize, Serialize};
+use starlark::codemap::{ResolvedPos, ResolvedSpan};
+use starlark::errors::EvalSeverity;
+
+use crate::{git, load};
+
+/// Environment variable that disables the evaluation cache when set.
+pub const NO_CACHE_ENV: &str = "PCB_NO_CACHE";
+
+/// Whether the evaluation cache has been disabled through [`NO_CACHE_ENV`].
+pub fn disabled_by_env() -> bool {
+    std::env::var_os(NO_CACHE_ENV).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Namespace of cached root-module evaluations.
+const EVAL_NAMESPACE: &str = "eval";
+
+/// [`EvalCache`] backed by files under `<workspace>/.pcb/eval-cache/<namespace>/<key>`.
+///
+/// Entries are written to a temporary file and renamed into place, so concurrent
+/// readers and writers never observe partially written entries.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    root: PathBuf,
+}
+
+impl DiskCache {
+    pub fn for_workspace(workspace_root: &Path) -> Self {
+        Self {
+            root: workspace_root.join(".pcb").join("eval-cache"),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_path(&self, namespace: &str, key: &str) -> PathBuf {
+        self.root.join(namespace).join(key)
+    }
+}
+
+impl EvalCache for DiskCache {
+    fn get(&self, namespace: &str, key: &str) -> Option<Vec<u8>> {
+        fs::read(self.entry_path(namespace, key)).ok()
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) {
+        let path = self.entry_path(namespace, key);
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        let written = fs::create_dir_all(dir)
+            .and_then(|_| tempfile::NamedTempFile::new_in(dir))
+            .and_then(|mut tmp| {
+                tmp.write_all(value)?;
+                tmp.persist(&path).map_err(|e| e.error)?;
+                Ok(())
+            });
+        if let Err(e) = written {
+            log::debug!("Failed to write cache entry {}: {e}", path.display());
+        }
+    }
+}
+
+/// What an evaluation observed about a single path. Only the fields that were
+/// actually queried are recorded, and only those are compared on reuse.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Probe {
+    /// Content hash, or an empty string if the file could not be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    contents: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exists: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    is_dir: Option<bool>,
+    /// Hash of the sorted directory listing, or an empty string if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    listing: Option<String>,
+}
+
+impl Probe {
+    /// Re-observe `path`, querying the same facts that were recorded in `self`.
+    fn observe_again(&self, path: &Path) -> Probe {
+        Probe {
+            contents: self
+                .contents
+                .as_ref()
+                .map(|_| content_hash(fs::read_to_string(path).ok().as_deref())),
+            exists: self.exists.map(|_| path.exists()),
+            is_dir: self.is_dir.map(|_| path.is_dir()),
+            listing: self.listing.as_ref().map(|_| {
+                let entries = fs::read_dir(path)
+                    .ok()
+                    .map(|entries| entries.flatten().map(|e| e.path()).collect::<Vec<_>>());
+                listing_hash(entries.as_deref())
+            }),
+        }
+    }
+}
+
+fn content_hash(contents: Option<&str>) -> String {
+    contents
+        .map(|c| cache_key([c.as_bytes()]))
+        .unwrap_or_default()
+}
+
+fn listing_hash(entries: Option<&[PathBuf]>) -> String {
+    let Some(entries) = entries else {
+        return String::new();
+    };
+    let mut names: Vec<String> = entries
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    cache_key(names)
+}
+
+/// [`FileProvider`] that records every path an evaluation looks at.
+pub(crate) struct RecordingFileProvider {
+    inner: Arc<dyn FileProvider>,
+    probes: Mutex<BTreeMap<PathBuf, Probe>>,
+}
+
+impl RecordingFileProvider {
+    pub(crate) fn new(inner: Arc<dyn FileProvider>) -> Self {
+        Self {
+            inner,
+            probes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn record(&self, path: &Path, update: impl FnOnce(&mut Probe)) {
+        if let Ok(mut probes) = self.probes.lock() {
+            update(probes.entry(path.to_path_buf()).or_default());
+        }
+    }
+
+    fn probes(&self) -> BTreeMap<PathBuf, Probe> {
+        self.probes.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    /// Every path read or probed so far
+    pub(crate) fn paths(&self) -> Vec<PathBuf> {
+        self.probes
+            .lock()
+            .map(|p| p.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl FileProvider for RecordingFileProvider {
+    fn read_file(&self, path: &Path) -> Result<String, FileProviderError> {
+        let result = self.inner.read_file(path);
+        let hash = content_hash(result.as_deref().ok());
+        self.record(path, |p| p.contents = Some(hash));
+        result
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let exists = self.inner.exists(path);
+        self.record(path, |p| p.exists = Some(exists));
+        exists
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        let is_dir = self.inner.is_directory(path);
+        self.record(path, |p| p.is_dir = Some(is_dir));
+        is_dir
+    }
+
+    fn list_directory(&self, path: &Path) -> Result<Vec<PathBuf>, FileProviderError> {
+        let result = self.inner.list_directory(path);
+        let hash = listing_hash(result.as_deref().ok());
+        self.record(path, |p| p.listing = Some(hash));
+        result
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, FileProviderError> {
+        self.inner.canonicalize(path)
+    }
+}
+
+/// The structured errors that are preserved across a cache round-trip. Diagnostics
+/// carrying any other source error are not cached.
+#[derive(Serialize, Deserialize)]
+enum CachedError {
+    Test(BenchTestResult),
+    Lint(LintViolation),
+    LintEnabled(LintEnabled),
+    Requirement(RequirementViolation),
+}
+
+impl CachedError {
+    fn from_diagnostic(diag: &Diagnostic) -> Option<Option<Self>> {
+        if diag.source_error.is_none() {
+            return Some(None);
+        }
+        if let Some(test) = diag.downcast_error_ref::<BenchTestResult>() {
+            return Some(Some(Self::Test(test.clone())));
+        }
+        if let Some(lint) = diag.downcast_error_ref::<LintViolation>() {
+            return Some(Some(Self::Lint(lint.clone())));
+        }
+        if let Some(enabled) = diag.downcast_error_ref::<LintEnabled>() {
+            return Some(Some(Self::LintEnabled(enabled.clone())));
+        }
+        if let Some(violation) = diag.downcast_error_ref::<RequirementViolation>() {
+            return Some(Some(Self::Requirement(violation.clone())));
+        }
+        None
+    }
+
+    fn into_error(self) -> anyhow::Error {
+        match self {
+            // The checks did not run this time, so report no time for them
+            Self::Test(test) => BenchTestResult {
+                duration_secs: 0.0,
+                ..test
+            }
+            .into(),
+            Self::Lint(lint) => lint.into(),
+            Self::LintEnabled(enabled) => enabled.into(),
+            Self::Requirement(violation) => violation.into(),
+        }
+    }
+}
+
+/// A diagnostic of a clean evaluation. Call stacks are only rendered for errors, which
+/// are never cached, so they are not kept.
+#[derive(Serialize, Deserialize)]
+struct CachedDiagnostic {
+    path: String,
+    /// 0-based `[begin line, begin column, end line, end column]`
+    span: Option<[usize; 4]>,
+    severity: String,
+    body: String,
+    child: Option<Box<CachedDiagnostic>>,
+    error: Option<CachedError>,
+}
+
+impl CachedDiagnostic {
+    fn new(diag: &Diagnostic) -> Option<Self> {
+        let severity = match diag.severity {
+            EvalSeverity::Error => return None,
+            EvalSeverity::Warning => "warning",
+            EvalSeverity::Advice => "advice",
+            EvalSeverity::Disabled => "disabled",
+        };
+        let child = match &diag.child {
+            Some(child) => Some(Box::new(Self::new(child)?)),
+            None => None,
+        };
+        Some(Self {
+            path: diag.path.clone(),
+            span: diag.span.map(|span| {
+                [
+                    span.begin.line,
+                    span.begin.column,
+                    span.end.line,
+                    span.end.column,
+                ]
+            }),
+            severity: severity.to_string(),
+            body: diag.body.clone(),
+            child,
+            error: CachedError::from_diagnostic(diag)?,
+        })
+    }
+
+    fn into_diagnostic(self) -> Diagnostic {
+        let severity = match self.severity.as_str() {
+            "warning" => EvalSeverity::Warning,
+            "disabled" => EvalSeverity::Disabled,
+            _ => EvalSeverity::Advice,
+        };
+        Diagnostic {
+            path: self.path,
+            span: self
+                .span
+                .map(|[line, column, end_line, end_column]| ResolvedSpan {
+                    begin: ResolvedPos { line, column },
+                    end: ResolvedPos {
+                        line: end_line,
+                        column: end_column,
+                    },
+                }),
+            severity,
+            body: self.body,
+            call_stack: None,
+            child: self.child.map(|child| Box::new(child.into_diagnostic())),
+            source_error: self.error.map(|error| Arc::new(error.into_error())),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedEval {
+    probes: BTreeMap<PathBuf, Probe>,
+    /// Checkout directory of every remote the evaluation loaded from -> its commit
+    remotes: BTreeMap<PathBuf, String>,
+    schematic: String,
+    diagnostics: Vec<CachedDiagnostic>,
+}
+
+/// Cache key of the evaluation of `source_path` with `contents` in `mode`.
+pub(crate) fn eval_key(
+    source_path: &Path,
+    contents: &str,
+    mode: EvalMode,
+    offline: bool,
+) -> String {
+    cache_key([
+        source_path.to_string_lossy().as_bytes(),
+        contents.as_bytes(),
+        format!("{mode:?}").as_bytes(),
+        if offline { b"offline" } else { b"online" },
+    ])
+}
+
+/// The commit every remote checkout `load_resolver` resolved a load to is at.
+pub(crate) fn remote_revisions(load_resolver: &CoreLoadResolver) -> BTreeMap<PathBuf, String> {
+    load_resolver
+        .get_tracked_files()
+        .into_values()
+        .filter(|spec| matches!(spec, LoadSpec::Github { .. } | LoadSpec::Gitlab { .. }))
+        .filter_map(|spec| load::remote_cache_root(&spec).ok())
+        .map(|root| {
+            let commit = git::rev_parse_head(&root).unwrap_or_default();
+            (root, commit)
+        })
+        .collect()
+}
+
+/// Return the cached result for `key` if every file and remote revision it depended on
+/// is unchanged.
+pub(crate) fn lookup(cache: &DiskCache, key: &str) -> Option<WithDiagnostics<Schematic>> {
+    let bytes = cache.get(EVAL_NAMESPACE, key)?;
+    let entry: CachedEval = serde_json::from_slice(&bytes).ok()?;
+
+    if let Some((path, _)) = entry
+        .probes
+        .iter()
+        .find(|(path, probe)| probe.observe_again(path) != **probe)
+    {
+        log::debug!("Evaluation cache miss: {} changed", path.display());
+        return None;
+    }
+    if let Some((root, _)) = entry
+        .remotes
+        .iter()
+        .find(|(root, commit)| git::rev_parse_head(root).unwrap_or_default() != **commit)
+    {
+        log::debug!("Evaluation cache miss: {} moved", root.display());
+        return None;
+    }
+
+    let schematic = Schematic::from_json(&entry.schematic).ok()?;
+    let mut result = WithDiagnostics::success(schematic);
+    result.extend(
+        entry
+            .diagnostics
+            .into_iter()
+            .map(CachedDiagnostic::into_diagnostic),
+    );
+    Some(result)
+}
+
+/// Store `result` under `key` together with everything `recorder` observed and the
+/// `remotes` it was evaluated against.
+///
+/// Evaluations with errors are not stored, and neither are those with diagnostics whose
+/// structured source error would be lost on replay.
+pub(crate) fn store(
+    cache: &DiskCache,
+    key: &str,
+    result: &WithDiagnostics<Schematic>,
+    recorder: &RecordingFileProvider,
+    remotes: BTreeMap<PathBuf, String>,
+) {
+    let Some(schematic) = &result.output else {
+        return;
+    };
+    let Some(diagnostics) = result
+        .diagnostics
+        .iter()
+        .map(CachedDiagnostic::new)
+        .collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+
+    let Ok(schematic) = schematic.to_json() else {
+        return;
+    };
+    let entry = CachedEval {
+        probes: recorder.probes(),
+        remotes,
+        schematic,
+        diagnostics,
+    };
+    if let Ok(bytes) = serde_json::to_vec(&entry) {
+        cache.put(EVAL_NAMESPACE, key, &bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_zen_core::DefaultFileProvider;
+
+    #[test]
+    fn probe_detects_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.zen");
+        fs::write(&file, "x = 1\n").unwrap();
+
+        let recorder = RecordingFileProvider::new(Arc::new(DefaultFileProvider));
+        recorder.read_file(&file).unwrap();
+        recorder.exists(&dir.path().join("missing.zen"));
+
+        let probes = recorder.probes();
+        assert!(probes
+            .iter()
+            .all(|(p, probe)| probe.observe_again(p) == *probe));
+
+        fs::write(&file, "x = 2\n").unwrap();
+        assert!(probes
+            .iter()
+            .any(|(p, probe)| probe.observe_again(p) != *probe));
+    }
+
+    #[test]
+    fn disk_cache_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::for_workspace(dir.path());
+        assert_eq!(cache.root(), dir.path().join(".pcb").join("eval-cache"));
+        assert_eq!(cache.get("eval", "abc"), None);
+        cache.put("eval", "abc", b"value");
+        assert_eq!(cache.get("eval", "abc").as_deref(), Some(&b"value"[..]));
+        assert!(cache.root().join("eval").join("abc").exists());
+    }
+}
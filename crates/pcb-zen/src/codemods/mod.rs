@@ -0,0 +1,28 @@
+use anyhow::Result;
+use std::path::Path;
+
+pub mod remove_directory_loads;
+
+pub trait Codemod {
+    fn apply(&self, path: &Path, content: &str) -> Result<Option<String>>;
+}
+
+/// The codemods run by `pcb upgrade` and the LSP `source.fixAll` action, in order.
+pub fn all() -> Vec<Box<dyn Codemod>> {
+    vec![Box::new(remove_directory_loads::RemoveDirectoryLoads)]
+}
+
+/// Apply every codemod to `content` in sequence.
+///
+/// Returns `Ok(None)` when no codemod changed anything.
+pub fn apply_all(path: &Path, content: &str) -> Result<Option<String>> {
+    let mut current = content.to_string();
+    let mut changed = false;
+    for codemod in all() {
+        if let Some(updated) = codemod.apply(path, &current)? {
+            current = updated;
+            changed = true;
+        }
+    }
+    Ok((changed && current != content).then_some(current))
+}
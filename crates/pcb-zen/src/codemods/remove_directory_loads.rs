@@ -1,5 +1,5 @@
+use crate::load::DefaultRemoteFetcher;
 use anyhow::Result;
-use pcb_zen::load::DefaultRemoteFetcher;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -15,7 +15,12 @@
  * limitations under the License.
  */
 
-use std::path::Path;
+//! Debug Adapter Protocol server for Zen designs.
+//!
+//! A launched program is evaluated exactly like `pcb build` does, with a [`Debugger`]
+//! attached to the evaluation context so breakpoints and stepping reach every loaded file
+//! and instantiated module, including remote ones.
+
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -24,133 +29,170 @@ use std::thread;
 use debugserver_types::*;
 use dupe::Dupe;
 pub(crate) use library::*;
+use pcb_zen_core::config::find_workspace_root;
+use pcb_zen_core::DebugClient;
+use pcb_zen_core::Debugger;
+use pcb_zen_core::DefaultFileProvider;
+use pcb_zen_core::EvalMode;
+use pcb_zen_core::StopReason;
 use serde_json::Map;
 use serde_json::Value;
-use starlark::debug::dap_capabilities;
-use starlark::debug::prepare_dap_adapter;
-use starlark::debug::resolve_breakpoints;
-use starlark::debug::DapAdapter;
-use starlark::debug::DapAdapterClient;
-use starlark::debug::DapAdapterEvalHook;
-use starlark::environment::Globals;
-use starlark::environment::Module;
-use starlark::eval::Evaluator;
-use starlark::syntax::AstModule;
-use starlark::syntax::Dialect;
-use starlark::StarlarkResultExt;
+use starlark::debug::StepKind;
 
 mod library;
 
+/// Stack size of the evaluation thread; Zen evaluation recurses once per module instance.
+const EVAL_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+struct LaunchConfig {
+    program: PathBuf,
+    offline: bool,
+    mode: EvalMode,
+}
+
 #[derive(Debug)]
 struct Backend {
-    adapter: Arc<dyn DapAdapter>,
-    eval_wrapper: Mutex<Option<Box<dyn DapAdapterEvalHook>>>,
+    debugger: Arc<Debugger>,
     client: Client,
-    file: Mutex<Option<String>>,
-    dialect: Dialect,
-    globals: Globals,
+    launch: Mutex<Option<LaunchConfig>>,
 }
 
-impl DapAdapterClient for Client {
-    fn event_stopped(&self) -> starlark::Result<()> {
+impl DebugClient for Client {
+    fn stopped(&self, reason: StopReason) {
         self.event_stopped(StoppedEventBody {
-            reason: "breakpoint".to_owned(),
+            reason: reason.as_str().to_owned(),
             thread_id: Some(0),
-            description: Some("Hello".to_owned()),
+            description: None,
             all_threads_stopped: Some(true),
             preserve_focus_hint: None,
             text: None,
         });
-        Ok(())
     }
 }
 
 impl Backend {
-    fn execute(&self, path: &str) {
+    fn execute(&self, config: LaunchConfig) -> anyhow::Result<()> {
         let client = self.client.dupe();
-        let client2 = self.client.dupe();
-        let wrapper = self.eval_wrapper.lock().unwrap().take().unwrap();
-        let path = PathBuf::from(path);
-        let dialect = self.dialect.clone();
-        let globals = self.globals.dupe();
-
-        let go = move || -> anyhow::Result<String> {
-            client.log(&format!("EVALUATION PREPARE: {}", path.display()));
-            let ast = AstModule::parse_file(&path, &dialect).into_anyhow_result()?;
-            let module = Module::new();
-            let mut eval = Evaluator::new(&module);
-            wrapper.add_dap_hooks(&mut eval);
-
-            // No way to pass back success/failure to the caller
-            client.log(&format!("EVALUATION START: {}", path.display()));
-            let v = eval.eval_module(ast, &globals).into_anyhow_result()?;
-            let s = v.to_string();
-            client.log(&format!("EVALUATION FINISHED: {}", path.display()));
-            Ok(s)
-        };
+        let debugger = self.debugger.clone();
+        let program = config
+            .program
+            .canonicalize()
+            .map_err(|e| anyhow::anyhow!("Failed to resolve {}: {e}", config.program.display()))?;
 
-        thread::spawn(move || {
-            let res = go();
-            let output = match &res {
-                Err(e) => format!("{:#}", e),
-                Ok(v) => v.to_owned(),
-            };
-            client2.event_output(OutputEventBody {
-                output,
-                category: None,
-                column: None,
-                data: None,
-                line: None,
-                source: None,
-                variables_reference: None,
-            });
-            client2.event_exited(ExitedEventBody {
-                exit_code: if res.is_ok() { 0 } else { 1 },
-            });
-            client2.event_terminated(None);
-        });
-    }
+        thread::Builder::new()
+            .name("zen-debuggee".to_owned())
+            .stack_size(EVAL_STACK_SIZE)
+            .spawn(move || {
+                client.log(&format!("Evaluating {}", program.display()));
+                let workspace_root = find_workspace_root(&DefaultFileProvider, &program);
+                let ctx = crate::create_eval_context(&workspace_root, config.offline)
+                    .set_debugger(debugger);
+                let result = crate::eval_root(ctx, &program, config.mode);
 
-    fn get_ast(&self, source: &str) -> anyhow::Result<Arc<AstModule>> {
-        Ok(Arc::new(
-            AstModule::parse_file(Path::new(source), &self.dialect).into_anyhow_result()?,
-        ))
+                for diag in result.diagnostics.iter() {
+                    client.event_output(OutputEventBody {
+                        output: format!("{diag}\n"),
+                        category: Some(
+                            if diag.is_error() { "stderr" } else { "console" }.to_owned(),
+                        ),
+                        column: None,
+                        data: None,
+                        line: None,
+                        source: None,
+                        variables_reference: None,
+                    });
+                }
+                let success = result.output.is_some() && !result.diagnostics.has_errors();
+                client.event_exited(ExitedEventBody {
+                    exit_code: if success { 0 } else { 1 },
+                });
+                client.event_terminated(None);
+            })?;
+        Ok(())
     }
 }
 
 impl DebugServer for Backend {
     fn initialize(&self, _: InitializeRequestArguments) -> anyhow::Result<Option<Capabilities>> {
         self.client.event_initialized(None);
-        Ok(Some(dap_capabilities()))
+        Ok(Some(Capabilities {
+            supports_configuration_done_request: Some(true),
+            supports_evaluate_for_hovers: Some(true),
+            supports_conditional_breakpoints: Some(true),
+            ..Capabilities::default()
+        }))
     }
 
     fn set_breakpoints(
         &self,
         x: SetBreakpointsArguments,
     ) -> anyhow::Result<SetBreakpointsResponseBody> {
-        let source = x.source.path.as_ref().unwrap();
-        let resolved = resolve_breakpoints(&x, &*self.get_ast(source)?)?;
-        self.adapter.set_breakpoints(source, &resolved)?;
-        Ok(resolved.to_response())
+        let source = x
+            .source
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("setBreakpoints requires a source path"))?;
+        let path = PathBuf::from(source);
+        let path = path.canonicalize().unwrap_or(path);
+        let contents = std::fs::read_to_string(&path)?;
+
+        let requested = x.breakpoints.unwrap_or_default();
+        let lines: Vec<_> = requested
+            .iter()
+            .map(|bp| (bp.line as usize, bp.condition.clone()))
+            .collect();
+        let verified = self.debugger.set_breakpoints(&path, &contents, &lines);
+
+        Ok(SetBreakpointsResponseBody {
+            breakpoints: requested
+                .iter()
+                .zip(verified)
+                .map(|(bp, verified)| Breakpoint {
+                    column: None,
+                    end_column: None,
+                    end_line: None,
+                    id: None,
+                    line: Some(bp.line),
+                    message: (!verified).then(|| "No statement starts on this line".to_owned()),
+                    source: Some(x.source.clone()),
+                    verified,
+                })
+                .collect(),
+        })
     }
 
     fn set_exception_breakpoints(&self, _: SetExceptionBreakpointsArguments) -> anyhow::Result<()> {
-        // We just assume that break on error is always useful
+        // Evaluation errors are reported as diagnostics when the program exits.
         Ok(())
     }
 
     fn launch(&self, _: LaunchRequestArguments, args: Map<String, Value>) -> anyhow::Result<()> {
-        // Expecting program of type string
-        match args.get("program") {
-            Some(Value::String(path)) => {
-                *self.file.lock().unwrap() = Some(path.to_owned());
-                Ok(())
+        let program = match args.get("program") {
+            Some(Value::String(path)) => PathBuf::from(path),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Couldn't find a program to launch, got args {:?}",
+                    args
+                ))
             }
-            _ => Err(anyhow::anyhow!(
-                "Couldn't find a program to launch, got args {:?}",
-                args
-            )),
+        };
+        let flag = |name: &str| args.get(name).and_then(Value::as_bool).unwrap_or(false);
+        let mode = match args.get("mode").and_then(Value::as_str) {
+            None | Some("build") => EvalMode::Build,
+            Some("test") => EvalMode::Test,
+            Some(other) => return Err(anyhow::anyhow!("Unknown evaluation mode `{other}`")),
+        };
+
+        if flag("stopOnEntry") {
+            self.debugger.stop_on_entry();
         }
+        *self.launch.lock().unwrap() = Some(LaunchConfig {
+            program,
+            offline: flag("offline"),
+            mode,
+        });
+        Ok(())
     }
 
     fn threads(&self) -> anyhow::Result<ThreadsResponseBody> {
@@ -163,74 +205,81 @@ impl DebugServer for Backend {
     }
 
     fn configuration_done(&self) -> anyhow::Result<()> {
-        if let Some(path) = self.file.lock().unwrap().as_ref() {
-            self.execute(path);
+        if let Some(config) = self.launch.lock().unwrap().take() {
+            self.execute(config)?;
         }
         Ok(())
     }
 
-    fn stack_trace(&self, v: StackTraceArguments) -> anyhow::Result<StackTraceResponseBody> {
-        self.adapter.stack_trace(v)
+    fn stack_trace(&self, _: StackTraceArguments) -> anyhow::Result<StackTraceResponseBody> {
+        let stack_frames = self.debugger.stack_trace()?;
+        Ok(StackTraceResponseBody {
+            total_frames: Some(stack_frames.len() as i64),
+            stack_frames,
+        })
     }
 
     fn scopes(&self, _: ScopesArguments) -> anyhow::Result<ScopesResponseBody> {
-        let scopes_info = self.adapter.scopes()?;
         Ok(ScopesResponseBody {
-            scopes: vec![Scope {
-                name: "Locals".to_owned(),
-                named_variables: Some(scopes_info.num_locals as i64),
-                variables_reference: 2000,
-                expensive: false,
-                column: None,
-                end_column: None,
-                end_line: None,
-                indexed_variables: None,
-                line: None,
-                source: None,
-            }],
+            scopes: self.debugger.scopes()?,
         })
     }
 
-    fn variables(&self, _: VariablesArguments) -> anyhow::Result<VariablesResponseBody> {
-        let vars_info = self.adapter.variables()?;
+    fn variables(&self, x: VariablesArguments) -> anyhow::Result<VariablesResponseBody> {
         Ok(VariablesResponseBody {
-            variables: vars_info
-                .locals
-                .into_iter()
-                .map(|var| var.to_dap())
-                .collect(),
+            variables: self.debugger.variables(x.variables_reference)?,
         })
     }
 
     fn evaluate(&self, x: EvaluateArguments) -> anyhow::Result<EvaluateResponseBody> {
-        let expr_result = self.adapter.evaluate(&x.expression)?;
-
+        let result = self.debugger.evaluate(&x.expression)?;
         Ok(EvaluateResponseBody {
             indexed_variables: None,
             named_variables: None,
             presentation_hint: None,
-            result: expr_result.result,
-            type_: Some(expr_result.type_),
-            variables_reference: 0.0,
+            result: result.value,
+            type_: result.type_,
+            variables_reference: result.variables_reference as f64,
         })
     }
 
     fn continue_(&self, _: ContinueArguments) -> anyhow::Result<ContinueResponseBody> {
-        self.adapter.continue_()?;
-        Ok(ContinueResponseBody::default())
+        self.debugger.continue_()?;
+        Ok(ContinueResponseBody {
+            all_threads_continued: Some(true),
+        })
+    }
+
+    fn next(&self, _: NextArguments) -> anyhow::Result<()> {
+        self.debugger.step(StepKind::Over)
+    }
+
+    fn step_in(&self, _: StepInArguments) -> anyhow::Result<()> {
+        self.debugger.step(StepKind::Into)
+    }
+
+    fn step_out(&self, _: StepOutArguments) -> anyhow::Result<()> {
+        self.debugger.step(StepKind::Out)
     }
 }
 
-pub(crate) fn server(dialect: Dialect, globals: Globals) {
-    DapService::run(|client| {
-        let (adapter, wrapper) = prepare_dap_adapter(Box::new(client.dupe()));
-        Backend {
-            adapter: Arc::new(adapter),
-            eval_wrapper: Mutex::new(Some(Box::new(wrapper))),
-            client,
-            file: Default::default(),
-            dialect,
-            globals,
-        }
-    })
+fn create_backend(client: Client) -> Backend {
+    Backend {
+        debugger: Debugger::new(Box::new(client.dupe())),
+        client,
+        launch: Default::default(),
+    }
+}
+
+/// Serve the Debug Adapter Protocol over stdin/stdout until the client disconnects.
+pub fn server() -> anyhow::Result<()> {
+    DapService::run(create_backend)
+}
+
+/// Serve the Debug Adapter Protocol over `input` and `output`, e.g. a socket or a pipe.
+pub fn serve(
+    input: impl std::io::BufRead,
+    output: Box<dyn std::io::Write + Send>,
+) -> anyhow::Result<()> {
+    DapService::serve(input, output, create_backend)
 }
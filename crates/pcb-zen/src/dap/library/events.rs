@@ -15,74 +15,104 @@
  * limitations under the License.
  */
 
+//! Messages sent from the debug adapter to the client: responses and events.
+
+use std::io::Write;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
 use debugserver_types::*;
 use dupe::Dupe;
 use serde::Serialize;
+use serde_json::json;
 use serde_json::Value;
 
-use crate::dap::library::stream::log;
-use crate::dap::library::stream::send;
+use super::stream::write_message;
 
-#[derive(Debug, Clone, Dupe)]
+/// Handle for sending messages to the client; cheap to clone and usable from any thread.
+#[derive(Clone, Dupe)]
 pub(crate) struct Client {
-    _private: (),
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    seq: Arc<AtomicI64>,
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client").finish_non_exhaustive()
+    }
 }
 
 impl Client {
-    pub(crate) fn new() -> Self {
-        Self { _private: () }
+    pub(crate) fn new(output: Box<dyn Write + Send>) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            seq: Arc::new(AtomicI64::new(1)),
+        }
     }
 
-    pub(crate) fn log(&self, x: &str) {
-        log(x)
+    fn send(&self, mut message: Value) {
+        message["seq"] = self.seq.fetch_add(1, Ordering::SeqCst).into();
+        let mut output = self.output.lock().unwrap();
+        if let Err(e) = write_message(&mut **output, &message) {
+            log::debug!("Failed to write DAP message: {e}");
+        }
     }
 
-    fn event(&self, x: impl Serialize) {
-        send(serde_json::to_value(&x).unwrap())
+    pub(crate) fn respond(&self, request_seq: i64, command: &str, result: anyhow::Result<Value>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request_seq,
+            "command": command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => response["body"] = body,
+            Err(e) => response["message"] = format!("{e:#}").into(),
+        }
+        self.send(response);
     }
 
-    pub(crate) fn event_stopped(&self, body: StoppedEventBody) {
-        self.event(StoppedEvent {
-            type_: "event".to_owned(),
-            seq: 0,
-            event: "stopped".to_owned(),
-            body,
-        })
+    fn event(&self, event: &str, body: Option<impl Serialize>) {
+        let mut message = json!({ "type": "event", "event": event });
+        if let Some(body) = body.and_then(|b| serde_json::to_value(b).ok()) {
+            message["body"] = body;
+        }
+        self.send(message);
     }
 
     pub(crate) fn event_initialized(&self, body: Option<Value>) {
-        self.event(InitializedEvent {
-            type_: "event".to_owned(),
-            seq: 0,
-            event: "initialized".to_owned(),
-            body,
-        })
+        self.event("initialized", body)
+    }
+
+    pub(crate) fn event_stopped(&self, body: StoppedEventBody) {
+        self.event("stopped", Some(body))
+    }
+
+    pub(crate) fn event_output(&self, body: OutputEventBody) {
+        self.event("output", Some(body))
     }
 
     pub(crate) fn event_exited(&self, body: ExitedEventBody) {
-        self.event(ExitedEvent {
-            type_: "event".to_owned(),
-            seq: 0,
-            event: "exited".to_owned(),
-            body,
-        })
+        self.event("exited", Some(body))
     }
 
     pub(crate) fn event_terminated(&self, body: Option<TerminatedEventBody>) {
-        self.event(TerminatedEvent {
-            type_: "event".to_owned(),
-            seq: 0,
-            event: "terminated".to_owned(),
-            body,
-        })
+        self.event("terminated", body)
     }
 
-    pub(crate) fn event_output(&self, body: OutputEventBody) {
-        self.event(OutputEvent {
-            type_: "event".to_owned(),
-            seq: 0,
-            event: "output".to_owned(),
-            body,
-        })
+    /// Show `message` in the client's debug console.
+    pub(crate) fn log(&self, message: &str) {
+        self.event_output(OutputEventBody {
+            output: format!("{message}\n"),
+            category: Some("console".to_owned()),
+            column: None,
+            data: None,
+            line: None,
+            source: None,
+            variables_reference: None,
+        });
     }
 }
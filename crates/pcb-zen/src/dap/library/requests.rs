@@ -15,77 +15,94 @@
  * limitations under the License.
  */
 
+//! Requests received from the client and their dispatch to a [`DebugServer`].
+
 use debugserver_types::*;
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Map;
 use serde_json::Value;
 
+/// The requests a debug adapter serves. Unsupported requests fail with an error response.
 pub(crate) trait DebugServer {
     fn initialize(&self, x: InitializeRequestArguments) -> anyhow::Result<Option<Capabilities>>;
+
     fn set_breakpoints(
         &self,
         x: SetBreakpointsArguments,
     ) -> anyhow::Result<SetBreakpointsResponseBody>;
+
     fn set_exception_breakpoints(&self, x: SetExceptionBreakpointsArguments) -> anyhow::Result<()>;
+
     fn launch(&self, x: LaunchRequestArguments, args: Map<String, Value>) -> anyhow::Result<()>;
+
     fn threads(&self) -> anyhow::Result<ThreadsResponseBody>;
+
     fn configuration_done(&self) -> anyhow::Result<()>;
+
     fn stack_trace(&self, x: StackTraceArguments) -> anyhow::Result<StackTraceResponseBody>;
+
     fn scopes(&self, x: ScopesArguments) -> anyhow::Result<ScopesResponseBody>;
+
     fn variables(&self, x: VariablesArguments) -> anyhow::Result<VariablesResponseBody>;
-    fn continue_(&self, x: ContinueArguments) -> anyhow::Result<ContinueResponseBody>;
+
     fn evaluate(&self, x: EvaluateArguments) -> anyhow::Result<EvaluateResponseBody>;
-    fn disconnect(&self, _x: DisconnectArguments) -> anyhow::Result<()> {
-        Ok(())
-    }
-}
 
-pub(crate) fn dispatch(server: &impl DebugServer, r: &Request) -> Response {
-    fn arg<T: for<'a> Deserialize<'a>>(r: &Request) -> T {
-        serde_json::from_value(r.arguments.clone().unwrap()).unwrap()
-    }
+    fn continue_(&self, x: ContinueArguments) -> anyhow::Result<ContinueResponseBody>;
 
-    fn arg_extra(r: &Request) -> Map<String, Value> {
-        match &r.arguments {
-            Some(Value::Object(x)) => x.clone(),
-            _ => Default::default(),
-        }
-    }
+    fn next(&self, x: NextArguments) -> anyhow::Result<()>;
 
-    fn ret<T: Serialize>(r: &Request, v: anyhow::Result<Option<T>>) -> Response {
-        Response {
-            type_: "response".to_owned(),
-            command: r.command.clone(),
-            request_seq: r.seq,
-            seq: 0,
-            success: v.is_ok(),
-            message: v.as_ref().err().map(|e| format!("{:#}", e)),
-            body: v.unwrap_or(None).map(|v| serde_json::to_value(v).unwrap()),
-        }
-    }
+    fn step_in(&self, x: StepInArguments) -> anyhow::Result<()>;
 
-    fn ret_some<T: Serialize>(r: &Request, v: anyhow::Result<T>) -> Response {
-        ret(r, v.map(Some))
-    }
+    fn step_out(&self, x: StepOutArguments) -> anyhow::Result<()>;
 
-    fn ret_none(r: &Request, v: anyhow::Result<()>) -> Response {
-        ret::<()>(r, v.map(|_| None))
+    fn disconnect(&self, _: DisconnectArguments) -> anyhow::Result<()> {
+        Ok(())
     }
+}
+
+fn arguments<T: DeserializeOwned>(arguments: Value) -> anyhow::Result<T> {
+    // Some clients omit `arguments` entirely for requests whose fields are all optional.
+    let arguments = if arguments.is_null() {
+        Value::Object(Map::new())
+    } else {
+        arguments
+    };
+    Ok(serde_json::from_value(arguments)?)
+}
 
-    match r.command.as_str() {
-        "initialize" => ret(r, server.initialize(arg(r))),
-        "setBreakpoints" => ret_some(r, server.set_breakpoints(arg(r))),
-        "setExceptionBreakpoints" => ret_none(r, server.set_exception_breakpoints(arg(r))),
-        "launch" => ret_none(r, server.launch(arg(r), arg_extra(r))),
-        "threads" => ret_some(r, server.threads()),
-        "configurationDone" => ret_none(r, server.configuration_done()),
-        "stackTrace" => ret_some(r, server.stack_trace(arg(r))),
-        "scopes" => ret_some(r, server.scopes(arg(r))),
-        "variables" => ret_some(r, server.variables(arg(r))),
-        "continue" => ret_some(r, server.continue_(arg(r))),
-        "evaluate" => ret_some(r, server.evaluate(arg(r))),
-        "disconnect" => ret_none(r, server.disconnect(arg(r))),
-        _ => ret_none(r, Err(anyhow::anyhow!("Unknown command: {}", r.command))),
+fn body(x: impl Serialize) -> anyhow::Result<Value> {
+    Ok(serde_json::to_value(x)?)
+}
+
+/// Dispatch `command` to `server` and return the response body.
+pub(crate) fn dispatch(
+    server: &impl DebugServer,
+    command: &str,
+    args: Value,
+) -> anyhow::Result<Value> {
+    match command {
+        "initialize" => body(server.initialize(arguments(args)?)?),
+        "setBreakpoints" => body(server.set_breakpoints(arguments(args)?)?),
+        "setExceptionBreakpoints" => body(server.set_exception_breakpoints(arguments(args)?)?),
+        "launch" => {
+            let map = match &args {
+                Value::Object(map) => map.clone(),
+                _ => Map::new(),
+            };
+            body(server.launch(arguments(args)?, map)?)
+        }
+        "threads" => body(server.threads()?),
+        "configurationDone" => body(server.configuration_done()?),
+        "stackTrace" => body(server.stack_trace(arguments(args)?)?),
+        "scopes" => body(server.scopes(arguments(args)?)?),
+        "variables" => body(server.variables(arguments(args)?)?),
+        "evaluate" => body(server.evaluate(arguments(args)?)?),
+        "continue" => body(server.continue_(arguments(args)?)?),
+        "next" => body(server.next(arguments(args)?)?),
+        "stepIn" => body(server.step_in(arguments(args)?)?),
+        "stepOut" => body(server.step_out(arguments(args)?)?),
+        "disconnect" => body(server.disconnect(arguments(args)?)?),
+        _ => Err(anyhow::anyhow!("Unsupported request `{command}`")),
     }
 }
@@ -15,48 +15,57 @@
  * limitations under the License.
  */
 
-use debugserver_types as ds;
+//! The request loop of a debug adapter.
 
-use crate::dap::library::events::*;
-use crate::dap::library::requests::*;
-use crate::dap::library::stream::*;
+use std::io::BufRead;
+use std::io::Write;
 
-pub(crate) struct DapService {
-    _private: (),
-}
+use serde_json::Value;
+
+use super::events::Client;
+use super::requests::dispatch;
+use super::requests::DebugServer;
+use super::stream::read_message;
+
+pub(crate) struct DapService;
 
 impl DapService {
-    pub(crate) fn run<T: DebugServer>(f: impl FnOnce(Client) -> T) {
-        server(f(Client::new()));
+    /// Serve DAP over stdin/stdout until the client disconnects.
+    pub(crate) fn run<S: DebugServer>(create: impl FnOnce(Client) -> S) -> anyhow::Result<()> {
+        let stdin = std::io::stdin();
+        Self::serve(stdin.lock(), Box::new(std::io::stdout()), create)
     }
-}
 
-fn server(dap: impl DebugServer) {
-    log_begin();
-
-    // Because of the eval we're running in, we probably can't see panics.
-    // So mirror them to the log file.
-    let orig_hook = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |panic_info| {
-        if let Some(s) = panic_info.payload().downcast_ref::<&str>() {
-            log(&format!("Panic occurred: {:?}", s));
-        } else {
-            log("Panic occurred: Unknown message");
-        }
-        orig_hook(panic_info);
-    }));
-
-    log("DEBUG ADAPTER STARTING");
-    loop {
-        let recv = read();
-        let r: ds::Request = serde_json::from_value(recv).unwrap();
-        assert_eq!(r.type_, "request");
-        let resp = dispatch(&dap, &r);
-        send(serde_json::to_value(resp).unwrap());
-
-        if r.command == "disconnect" {
-            break;
+    /// Serve DAP requests read from `input`, writing responses and events to `output`.
+    pub(crate) fn serve<S: DebugServer>(
+        mut input: impl BufRead,
+        output: Box<dyn Write + Send>,
+        create: impl FnOnce(Client) -> S,
+    ) -> anyhow::Result<()> {
+        let client = Client::new(output);
+        let server = create(client.clone());
+
+        while let Some(message) = read_message(&mut input)? {
+            if message.get("type").and_then(Value::as_str) != Some("request") {
+                continue;
+            }
+            let seq = message
+                .get("seq")
+                .and_then(Value::as_i64)
+                .unwrap_or_default();
+            let command = message
+                .get("command")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned();
+            let args = message.get("arguments").cloned().unwrap_or(Value::Null);
+
+            let result = dispatch(&server, &command, args);
+            client.respond(seq, &command, result);
+            if command == "disconnect" {
+                break;
+            }
         }
+        Ok(())
     }
-    log("DEBUG ADAPTER STOPPING");
 }
@@ -15,57 +15,43 @@
  * limitations under the License.
  */
 
-//! Lowest level stream communication as JSON.
-//! Because DAP debugging is hard, we write everything we see to stdout (for the protocol)
-//! AND stderr (for debugging).
+//! Reading and writing DAP messages, which are JSON bodies framed by HTTP-style
+//! `Content-Length` headers.
 
-use std::env;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io;
-use std::io::Read;
+use std::io::BufRead;
 use std::io::Write;
-use std::path::PathBuf;
 
 use serde_json::Value;
 
-// Debugging anything through DAP is a nightmare, because VS Code doesn't surface any logs.
-// Therefore, do the hacky thing of putting logs next to the binary.
-fn log_file() -> PathBuf {
-    let mut res = env::current_exe().unwrap();
-    res.set_extension("dap.log");
-    res
+/// Read the next message from `reader`. Returns `None` at end of input.
+pub(crate) fn read_message(reader: &mut impl BufRead) -> anyhow::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+
+    let length =
+        content_length.ok_or_else(|| anyhow::anyhow!("DAP message without Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
 }
 
-pub(crate) fn log_begin() {
-    File::create(log_file()).unwrap();
-}
-
-pub(crate) fn log(x: &str) {
-    let mut file = OpenOptions::new().append(true).open(log_file()).unwrap();
-    file.write_all(format!("{}\n", x).as_bytes()).unwrap()
-}
-
-pub(crate) fn send(x: Value) {
-    let s = x.to_string();
-    log(&format!("SEND: {}", s));
-    print!("Content-Length: {}\r\n\r\n{}", s.len(), s);
-    io::stdout().flush().unwrap()
-}
-
-pub(crate) fn read() -> Value {
-    let mut s = String::new();
-    io::stdin().read_line(&mut s).unwrap();
-    let len: usize = s
-        .strip_prefix("Content-Length: ")
-        .unwrap()
-        .trim()
-        .parse()
-        .unwrap();
-    io::stdin().read_line(&mut s).unwrap();
-    let mut res = vec![0u8; len];
-    io::stdin().lock().read_exact(&mut res).unwrap();
-    let s = String::from_utf8_lossy(&res);
-    log(&format!("RECV: {}", s));
-    serde_json::from_str(&s).unwrap()
+/// Write `message` to `writer` with its `Content-Length` header.
+pub(crate) fn write_message(writer: &mut dyn Write, message: &Value) -> std::io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
 }
@@ -193,6 +193,30 @@ pub fn list_tags(repo_root: &Path, pattern: &str) -> anyhow::Result<Vec<String>>
     Ok(tags)
 }
 
+/// List the tags of a remote repository without cloning it
+pub fn ls_remote_tags(remote_url: &str) -> anyhow::Result<Vec<String>> {
+    let out = Command::new("git")
+        .arg("ls-remote")
+        .arg("--tags")
+        .arg("--refs")
+        .arg(remote_url)
+        .output()?;
+
+    if !out.status.success() {
+        return Err(anyhow::anyhow!("Git ls-remote failed for {remote_url}"));
+    }
+
+    let tags_output = String::from_utf8_lossy(&out.stdout);
+    let tags: Vec<String> = tags_output
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter_map(|r| r.strip_prefix("refs/tags/"))
+        .map(|tag| tag.to_string())
+        .collect();
+
+    Ok(tags)
+}
+
 /// Get the remote URL for origin
 pub fn get_remote_url(repo_root: &Path) -> anyhow::Result<String> {
     let out = Command::new("git")
@@ -209,3 +233,64 @@ pub fn get_remote_url(repo_root: &Path) -> anyhow::Result<String> {
 
     Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
 }
+
+/// Root of the working tree containing `path`
+pub fn show_toplevel(path: &Path) -> Option<std::path::PathBuf> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("rev-parse")
+        .arg("--show-toplevel")
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let s = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!s.is_empty()).then(|| s.into())
+}
+
+/// Check out `rev` into a new detached worktree at `dest_dir`
+pub fn add_worktree(repo_root: &Path, rev: &str, dest_dir: &Path) -> anyhow::Result<()> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .arg("worktree")
+        .arg("add")
+        .arg("--detach")
+        .arg("--quiet")
+        .arg(dest_dir)
+        .arg(rev)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Git worktree add failed for {rev}"))
+    }
+}
+
+/// Remove a worktree created by [`add_worktree`]
+pub fn remove_worktree(repo_root: &Path, worktree: &Path) -> anyhow::Result<()> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .arg("worktree")
+        .arg("remove")
+        .arg("--force")
+        .arg(worktree)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Git worktree remove failed for {}",
+            worktree.display()
+        ))
+    }
+}
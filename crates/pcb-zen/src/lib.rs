@@ -1,25 +1,31 @@
 //! Diode Star – evaluate .zen designs and return schematic data structures.
 
+pub mod cache;
+pub mod codemods;
+pub mod dap;
 pub mod diagnostics;
 pub mod git;
 pub mod load;
 pub mod lsp;
+pub mod serve;
 pub mod suppression;
 
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::cache::{DiskCache, RecordingFileProvider};
 use crate::load::DefaultRemoteFetcher;
 use pcb_sch::Schematic;
 use pcb_zen_core::config::find_workspace_root;
 use pcb_zen_core::convert::ToSchematic;
 use pcb_zen_core::{
-    CoreLoadResolver, DefaultFileProvider, EvalContext, InputMap, NoopRemoteFetcher,
+    CoreLoadResolver, DefaultFileProvider, EvalContext, FileProvider, InputMap, NoopRemoteFetcher,
 };
 use starlark::errors::EvalMessage;
 
 pub use pcb_zen_core::file_extensions;
-pub use pcb_zen_core::{Diagnostic, Diagnostics, EvalMode, WithDiagnostics};
+pub use pcb_zen_core::{Coverage, Diagnostic, Diagnostics, EvalMode, WithDiagnostics};
 pub use starlark::errors::EvalSeverity;
 
 /// Create an evaluation context with proper load resolver setup for a given workspace.
@@ -43,8 +49,18 @@ pub use starlark::errors::EvalSeverity;
 /// // Now Module() calls within evaluated files will support all import types
 /// ```
 pub fn create_eval_context(workspace_root: &Path, offline: bool) -> EvalContext {
-    let file_provider = Arc::new(DefaultFileProvider);
+    let file_provider: Arc<dyn FileProvider> = Arc::new(DefaultFileProvider);
+    let load_resolver = core_load_resolver(workspace_root, offline, file_provider.clone());
+    EvalContext::new()
+        .set_file_provider(file_provider)
+        .set_load_resolver(load_resolver)
+}
 
+fn core_load_resolver(
+    workspace_root: &Path,
+    offline: bool,
+    file_provider: Arc<dyn FileProvider>,
+) -> Arc<CoreLoadResolver> {
     // Choose remote fetcher based on offline mode
     let remote_fetcher: Arc<dyn pcb_zen_core::RemoteFetcher> = if offline {
         Arc::new(NoopRemoteFetcher)
@@ -52,16 +68,12 @@ pub fn create_eval_context(workspace_root: &Path, offline: bool) -> EvalContext
         Arc::new(DefaultRemoteFetcher::default())
     };
 
-    let load_resolver = Arc::new(CoreLoadResolver::new(
-        file_provider.clone(),
+    Arc::new(CoreLoadResolver::new(
+        file_provider,
         remote_fetcher,
         workspace_root.to_path_buf(),
         true,
-    ));
-
-    EvalContext::new()
-        .set_file_provider(file_provider)
-        .set_load_resolver(load_resolver)
+    ))
 }
 
 /// Evaluate `file` and return a [`Schematic`].
@@ -77,20 +89,185 @@ pub fn run(file: &Path, offline: bool, mode: EvalMode) -> WithDiagnostics<Schema
     let workspace_root = find_workspace_root(&file_provider, &abs_path);
 
     let ctx = create_eval_context(&workspace_root, offline);
+    eval_root(ctx, &abs_path, mode)
+}
+
+/// Evaluate `file` like [`run`], through the on-disk cache under
+/// `<workspace>/.pcb/eval-cache`.
+///
+/// When neither `file` nor anything its evaluation read has changed since the last
+/// evaluation without errors, the cached schematic and diagnostics are returned without
+/// re-evaluating. Otherwise the leaf modules it instantiates are restored from the cache
+/// while their files, loads and inputs are unchanged. Setting `PCB_NO_CACHE` makes this
+/// behave exactly like [`run`].
+pub fn run_cached(file: &Path, offline: bool, mode: EvalMode) -> WithDiagnostics<Schematic> {
+    let abs_path = file
+        .canonicalize()
+        .expect("failed to canonicalise input path");
+    eval_cached(&abs_path, None, offline, mode)
+}
+
+/// Evaluate the module at `abs_path` through the on-disk cache, using `contents` as its
+/// source instead of the file on disk when given (e.g. an unsaved editor buffer).
+pub(crate) fn eval_cached(
+    abs_path: &Path,
+    contents: Option<String>,
+    offline: bool,
+    mode: EvalMode,
+) -> WithDiagnostics<Schematic> {
+    let workspace_root = find_workspace_root(&DefaultFileProvider, abs_path);
+    if cache::disabled_by_env() {
+        let mut ctx = create_eval_context(&workspace_root, offline);
+        if let Some(contents) = contents {
+            ctx = ctx.set_source_contents(contents);
+        }
+        return eval_root(ctx, abs_path, mode);
+    }
+    let cache = DiskCache::for_workspace(&workspace_root);
+
+    let contents = contents.or_else(|| std::fs::read_to_string(abs_path).ok());
+    let key = contents
+        .as_deref()
+        .map(|contents| cache::eval_key(abs_path, contents, mode, offline));
+    if let Some(hit) = key.as_deref().and_then(|key| cache::lookup(&cache, key)) {
+        log::debug!("Using cached evaluation of {}", abs_path.display());
+        return hit;
+    }
+
+    let recorder = Arc::new(RecordingFileProvider::new(Arc::new(DefaultFileProvider)));
+    let load_resolver = core_load_resolver(&workspace_root, offline, recorder.clone());
+    let mut ctx = EvalContext::new()
+        .set_file_provider(recorder.clone())
+        .set_load_resolver(load_resolver.clone())
+        .set_cache(Arc::new(cache.clone()));
+    if let Some(contents) = contents {
+        ctx = ctx.set_source_contents(contents);
+    }
+    let result = eval_root(ctx, abs_path, mode);
+
+    if let Some(key) = key {
+        let remotes = cache::remote_revisions(&load_resolver);
+        cache::store(&cache, &key, &result, &recorder, remotes);
+    }
+    result
+}
 
+/// Evaluate `file` like [`run`], recording line, module and `config()`/`io()`
+/// coverage of every file it evaluates into `coverage`.
+pub fn run_with_coverage(
+    file: &Path,
+    offline: bool,
+    mode: EvalMode,
+    coverage: Arc<Coverage>,
+) -> WithDiagnostics<Schematic> {
+    let abs_path = file
+        .canonicalize()
+        .expect("failed to canonicalise input path");
+    let workspace_root = find_workspace_root(&DefaultFileProvider, &abs_path);
+
+    let ctx = create_eval_context(&workspace_root, offline).set_coverage(coverage);
+    eval_root(ctx, &abs_path, mode)
+}
+
+/// Evaluate `file` like [`run`], re-running the checks of every passing
+/// `TestBench()` case against mutations of its module. Each mutation is reported as an
+/// advice diagnostic carrying a [`pcb_zen_core::MutationResult`].
+pub fn run_with_mutations(
+    file: &Path,
+    offline: bool,
+    mode: EvalMode,
+) -> WithDiagnostics<Schematic> {
+    let abs_path = file
+        .canonicalize()
+        .expect("failed to canonicalise input path");
+    let workspace_root = find_workspace_root(&DefaultFileProvider, &abs_path);
+
+    let ctx = create_eval_context(&workspace_root, offline).set_mutate(true);
+    eval_root(ctx, &abs_path, mode)
+}
+
+/// Evaluate `file` like [`run`], also returning every file the evaluation
+/// depends on: `file` itself, every path the evaluation read or probed through its
+/// [`FileProvider`] (sources, symbol libraries, `.snap` files, requirements, ...), the
+/// files tracked by its load resolver and, transitively, the `Module()` dependencies
+/// recorded for them. If `file` no longer exists, the result is an error diagnostic.
+pub fn run_tracked(
+    file: &Path,
+    offline: bool,
+    mode: EvalMode,
+) -> (WithDiagnostics<Schematic>, BTreeSet<PathBuf>) {
+    let abs_path = match file.canonicalize() {
+        Ok(abs_path) => abs_path,
+        Err(e) => {
+            let diagnostic = Diagnostic::new(
+                format!("Failed to read {}: {e}", file.display()),
+                EvalSeverity::Error,
+                file,
+            );
+            return (diagnostic.into(), BTreeSet::from([file.to_path_buf()]));
+        }
+    };
+    let workspace_root = find_workspace_root(&DefaultFileProvider, &abs_path);
+
+    let recorder = Arc::new(RecordingFileProvider::new(Arc::new(DefaultFileProvider)));
+    let load_resolver = core_load_resolver(&workspace_root, offline, recorder.clone());
+    let ctx = EvalContext::new()
+        .set_file_provider(recorder.clone())
+        .set_load_resolver(load_resolver.clone());
+    // Shares its state with `ctx`, so module dependencies can be read after the eval
+    let deps_ctx = ctx.child_context();
+    let result = eval_root(ctx, &abs_path, mode);
+
+    let mut files: BTreeSet<PathBuf> = load_resolver.get_tracked_files().into_keys().collect();
+    files.extend(recorder.paths());
+    files.insert(abs_path);
+    let mut pending: Vec<PathBuf> = files.iter().cloned().collect();
+    while let Some(path) = pending.pop() {
+        for dep in deps_ctx.get_module_dependencies(&path).unwrap_or_default() {
+            if files.insert(dep.clone()) {
+                pending.push(dep);
+            }
+        }
+    }
+    (result, files)
+}
+
+pub(crate) fn eval_root(
+    ctx: EvalContext,
+    abs_path: &Path,
+    mode: EvalMode,
+) -> WithDiagnostics<Schematic> {
     // For now we don't inject any external inputs.
     let inputs = InputMap::new();
-    ctx.set_source_path(abs_path.clone())
+    let lint_ctx = ctx.child_context();
+    let mut result = ctx
+        .set_source_path(abs_path.to_path_buf())
         .set_module_name("<root>".to_string())
         .set_inputs(inputs)
         .set_eval_mode(mode)
-        .eval()
-        .try_map(|m| {
-            // Convert schematic conversion error into a Starlark diagnostic
-            m.sch_module
-                .to_schematic()
-                .map_err(|e| EvalMessage::from_error(abs_path.as_path(), &e.into()))
-        })
+        .eval();
+
+    // Run the design-rule lints enabled by the root file over every module
+    if let Some(output) = &result.output {
+        result.extend(pcb_zen_core::lang::lint::enabled_lint_diagnostics(output));
+        if mode == EvalMode::Build {
+            let violations = pcb_zen_core::lang::lint::run_lints(output, &lint_ctx);
+            result.extend(violations);
+        }
+    }
+
+    // Check the datasheet requirements of every instantiated component and module
+    if let Some(output) = &result.output {
+        let violations = pcb_zen_core::lang::requirements::check_requirements(output);
+        result.extend(violations);
+    }
+
+    result.try_map(|m| {
+        // Convert schematic conversion error into a Starlark diagnostic
+        m.sch_module
+            .to_schematic()
+            .map_err(|e| EvalMessage::from_error(abs_path, &e.into()))
+    })
 }
 
 pub fn lsp() -> anyhow::Result<()> {
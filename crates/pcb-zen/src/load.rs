@@ -1,8 +1,11 @@
 use log::debug;
+use once_cell::sync::Lazy;
 use pcb_zen_core::{LoadSpec, RefKind, RemoteRefMeta};
 use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 #[cfg(unix)]
 use std::os::unix::fs as unix_fs;
@@ -95,7 +98,14 @@ fn classify_remote(cache_root: &Path, spec: &LoadSpec) -> Option<RemoteRefMeta>
     })
 }
 
+/// Per-directory locks so concurrent fetches of the same remote within this process
+/// download it only once.
+static FETCH_LOCKS: Lazy<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = Lazy::new(Default::default);
+
 /// Ensure a cache directory exists atomically, downloading if necessary.
+///
+/// Threads of this process fetching the same `cache_root` wait for each other; other
+/// processes are kept consistent by downloading into a temp directory and renaming it.
 fn ensure_cached_atomically(
     cache_root: &Path,
     download_fn: impl FnOnce(&Path) -> anyhow::Result<()>,
@@ -104,6 +114,19 @@ fn ensure_cached_atomically(
         return Ok(());
     }
 
+    let lock = FETCH_LOCKS
+        .lock()
+        .unwrap()
+        .entry(cache_root.to_path_buf())
+        .or_default()
+        .clone();
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+
+    // Another thread may have finished the download while we were waiting
+    if cache_root.exists() {
+        return Ok(());
+    }
+
     // Ensure parent directory exists
     if let Some(parent) = cache_root.parent() {
         std::fs::create_dir_all(parent)?;
@@ -131,27 +154,34 @@ fn ensure_cached_atomically(
 /// Returns the directory containing the checked-out repository or unpacked package.
 /// Uses atomic directory creation to prevent race conditions when multiple tests run in parallel.
 pub fn ensure_remote_cached(spec: &LoadSpec) -> anyhow::Result<PathBuf> {
+    let cache_root = remote_cache_root(spec)?;
     match spec {
         LoadSpec::Github {
             user, repo, rev, ..
-        } => {
-            let cache_root = cache_dir()?.join("github").join(user).join(repo).join(rev);
-            ensure_cached_atomically(&cache_root, |temp_dir| {
-                download_and_unpack_github_repo(user, repo, rev, temp_dir)
-            })?;
-            Ok(cache_root)
-        }
+        } => ensure_cached_atomically(&cache_root, |temp_dir| {
+            download_and_unpack_github_repo(user, repo, rev, temp_dir)
+        })?,
         LoadSpec::Gitlab {
             project_path, rev, ..
-        } => {
-            let cache_root = cache_dir()?.join("gitlab").join(project_path).join(rev);
-            ensure_cached_atomically(&cache_root, |temp_dir| {
-                download_and_unpack_gitlab_repo(project_path, rev, temp_dir)
-            })?;
-            Ok(cache_root)
-        }
+        } => ensure_cached_atomically(&cache_root, |temp_dir| {
+            download_and_unpack_gitlab_repo(project_path, rev, temp_dir)
+        })?,
         _ => anyhow::bail!("ensure_remote_cached only handles remote specs"),
     }
+    Ok(cache_root)
+}
+
+/// The directory a remote spec is checked out to, whether or not it has been fetched.
+pub fn remote_cache_root(spec: &LoadSpec) -> anyhow::Result<PathBuf> {
+    match spec {
+        LoadSpec::Github {
+            user, repo, rev, ..
+        } => Ok(cache_dir()?.join("github").join(user).join(repo).join(rev)),
+        LoadSpec::Gitlab {
+            project_path, rev, ..
+        } => Ok(cache_dir()?.join("gitlab").join(project_path).join(rev)),
+        _ => anyhow::bail!("remote_cache_root only handles remote specs"),
+    }
 }
 
 pub fn cache_dir() -> anyhow::Result<PathBuf> {
@@ -647,6 +677,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn concurrent_fetches_download_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_root = dir
+            .path()
+            .join("github")
+            .join("user")
+            .join("repo")
+            .join("v1");
+        let downloads = std::sync::atomic::AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    ensure_cached_atomically(&cache_root, |tmp| {
+                        downloads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                        fs::write(tmp.join("pcb.toml"), "")?;
+                        Ok(())
+                    })
+                    .unwrap();
+                });
+            }
+        });
+
+        assert_eq!(downloads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(cache_root.join("pcb.toml").exists());
+    }
+
     #[test]
     fn default_package_aliases() {
         // Test that default aliases are available
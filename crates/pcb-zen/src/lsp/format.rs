@@ -0,0 +1,261 @@
+//! Document formatting and `source.fixAll` support for the Zen language server.
+//!
+//! Formatting is delegated to the bundled buildifier (the same formatter used by
+//! `pcb fmt`) and fix-all runs the `pcb upgrade` codemods. Both produce a new
+//! version of the whole buffer, which is turned into line-based `TextEdit`s so
+//! that range formatting can keep only the hunks touching the requested range.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use pcb_buildifier::Buildifier;
+
+use crate::codemods;
+
+/// Above this many (old × new) changed lines we skip the LCS and emit a single hunk.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Format `content` with buildifier and return the edits needed to get there.
+///
+/// When `range` is set, only hunks overlapping the given lines are returned.
+pub fn format_edits(path: &Path, content: &str, range: Option<Range>) -> Result<Vec<TextEdit>> {
+    let formatted = Buildifier::new()?.format_str(path, content)?;
+    let hunks = line_hunks(content, &formatted);
+    Ok(match range {
+        Some(range) => hunks
+            .into_iter()
+            .filter(|hunk| hunk.overlaps(range.start.line as usize, range.end.line as usize))
+            .map(Hunk::into_edit)
+            .collect(),
+        None => hunks.into_iter().map(Hunk::into_edit).collect(),
+    })
+}
+
+/// Build the `source.fixAll` code action for a buffer, if any codemod applies.
+pub fn fix_all_action(
+    uri: &Url,
+    path: &Path,
+    content: &str,
+) -> Result<Option<CodeActionOrCommand>> {
+    let Some(updated) = codemods::apply_all(path, content)? else {
+        return Ok(None);
+    };
+
+    // `pcb upgrade` formats files after rewriting them; do the same here, but
+    // keep the unformatted result if buildifier isn't usable.
+    let updated = Buildifier::new()
+        .and_then(|b| b.format_str(path, &updated))
+        .unwrap_or(updated);
+
+    let edits: Vec<TextEdit> = line_hunks(content, &updated)
+        .into_iter()
+        .map(Hunk::into_edit)
+        .collect();
+    if edits.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Apply `pcb upgrade` fixes".to_string(),
+        kind: Some(CodeActionKind::SOURCE_FIX_ALL),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    })))
+}
+
+/// Whether a `CodeActionContext.only` filter admits actions of `kind`.
+pub fn kind_requested(only: Option<&[CodeActionKind]>, kind: &CodeActionKind) -> bool {
+    let Some(only) = only else {
+        return true;
+    };
+    only.iter().any(|requested| {
+        let requested = requested.as_str();
+        kind.as_str() == requested
+            || kind
+                .as_str()
+                .strip_prefix(requested)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Whether a `CodeActionContext.only` filter names `kind` itself. Actions that read and
+/// format the whole document are only worth computing when the client asks for them, e.g.
+/// on save, not for every cursor move.
+pub fn kind_explicitly_requested(only: Option<&[CodeActionKind]>, kind: &CodeActionKind) -> bool {
+    only.is_some_and(|only| only.contains(kind))
+}
+
+/// A contiguous block of old lines `old.0..old.1` replaced by `new_text`.
+#[derive(Debug, PartialEq)]
+struct Hunk {
+    old: (usize, usize),
+    new_text: String,
+}
+
+impl Hunk {
+    fn overlaps(&self, first_line: usize, last_line: usize) -> bool {
+        let (start, end) = self.old;
+        if start == end {
+            // Pure insertion before line `start`.
+            start >= first_line && start <= last_line
+        } else {
+            start <= last_line && end > first_line
+        }
+    }
+
+    fn into_edit(self) -> TextEdit {
+        TextEdit {
+            range: Range {
+                start: Position::new(self.old.0 as u32, 0),
+                end: Position::new(self.old.1 as u32, 0),
+            },
+            new_text: self.new_text,
+        }
+    }
+}
+
+/// Compute line-level hunks turning `old` into `new`.
+fn line_hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    if a_mid.is_empty() && b_mid.is_empty() {
+        return Vec::new();
+    }
+    if a_mid.len() * b_mid.len() > MAX_DIFF_CELLS {
+        return vec![Hunk {
+            old: (prefix, prefix + a_mid.len()),
+            new_text: b_mid.concat(),
+        }];
+    }
+
+    // lcs[i][j] = length of the LCS of a_mid[i..] and b_mid[j..]
+    let (n, m) = (a_mid.len(), b_mid.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut pending: Option<(usize, usize)> = None; // (start of old, start of new)
+    let flush =
+        |pending: &mut Option<(usize, usize)>, i: usize, j: usize, hunks: &mut Vec<Hunk>| {
+            if let Some((oi, oj)) = pending.take() {
+                hunks.push(Hunk {
+                    old: (prefix + oi, prefix + i),
+                    new_text: b_mid[oj..j].concat(),
+                });
+            }
+        };
+    while i < n || j < m {
+        if i < n && j < m && a_mid[i] == b_mid[j] {
+            flush(&mut pending, i, j, &mut hunks);
+            i += 1;
+            j += 1;
+            continue;
+        }
+        pending.get_or_insert((i, j));
+        if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    flush(&mut pending, i, j, &mut hunks);
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(old: &str, hunks: Vec<Hunk>) -> String {
+        let mut lines: Vec<String> = old.split_inclusive('\n').map(str::to_string).collect();
+        for hunk in hunks.into_iter().rev() {
+            lines.splice(hunk.old.0..hunk.old.1, [hunk.new_text]);
+        }
+        lines.concat()
+    }
+
+    #[test]
+    fn identical_text_has_no_hunks() {
+        assert!(line_hunks("a\nb\n", "a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn hunks_reproduce_new_text() {
+        let old = "load(\"x\", \"y\")\nx=1\nkeep\ny  =  2\nz\n";
+        let new = "load(\"x\", \"y\")\n\nx = 1\nkeep\ny = 2\nz\n";
+        let hunks = line_hunks(old, new);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(apply(old, hunks), new);
+    }
+
+    #[test]
+    fn range_keeps_only_overlapping_hunks() {
+        let old = "a=1\nb\nc=3\n";
+        let new = "a = 1\nb\nc = 3\n";
+        let hunks: Vec<Hunk> = line_hunks(old, new)
+            .into_iter()
+            .filter(|h| h.overlaps(2, 2))
+            .collect();
+        assert_eq!(
+            hunks,
+            vec![Hunk {
+                old: (2, 3),
+                new_text: "c = 3\n".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn only_filter_matches_kind_prefixes() {
+        let fix_all = CodeActionKind::SOURCE_FIX_ALL;
+        assert!(kind_requested(None, &fix_all));
+        assert!(kind_requested(Some(&[CodeActionKind::SOURCE]), &fix_all));
+        assert!(kind_requested(Some(&[fix_all.clone()]), &fix_all));
+        assert!(!kind_requested(Some(&[CodeActionKind::QUICKFIX]), &fix_all));
+        assert!(!kind_requested(
+            Some(&[CodeActionKind::new("source.fix")]),
+            &fix_all
+        ));
+    }
+
+    #[test]
+    fn explicit_requests_name_the_kind() {
+        let fix_all = CodeActionKind::SOURCE_FIX_ALL;
+        assert!(!kind_explicitly_requested(None, &fix_all));
+        assert!(!kind_explicitly_requested(
+            Some(&[CodeActionKind::SOURCE]),
+            &fix_all
+        ));
+        assert!(kind_explicitly_requested(
+            Some(&[CodeActionKind::QUICKFIX, fix_all.clone()]),
+            &fix_all
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+//! Inlay hints for the Zen language server.
+//!
+//! Three kinds of hints are produced from the last evaluation of a file:
+//!
+//! * the final name of a net whose requested name was deduplicated,
+//! * the file a `Module("@...")` path resolves to, and
+//! * the default values of `config()` parameters omitted at a module call site.
+
+use std::path::{Path, PathBuf};
+
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintTooltip, Position, Range};
+use pcb_starlark_lsp::server::{LspContext, LspUrl};
+use pcb_zen_core::config::find_workspace_root;
+use pcb_zen_core::lang::type_info::ParameterInfo;
+use pcb_zen_core::InputValue;
+use starlark::syntax::ast::{ArgumentP, AstLiteral, ExprP, StmtP};
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use super::quickfix;
+use super::LspEvalContext;
+
+impl LspEvalContext {
+    /// Compute the inlay hints for `path` that fall inside `range`.
+    pub(super) fn inlay_hints(&self, path: &Path, range: Range) -> Vec<InlayHint> {
+        let Some(ast) = self
+            .read(path)
+            .and_then(|text| quickfix::parse(path, &text))
+        else {
+            return Vec::new();
+        };
+        let codemap = ast.codemap();
+        let mut hints = Vec::new();
+
+        // Nets whose final name differs from the one requested in the source.
+        let net_names = self.inner.get_net_names(path).unwrap_or_default();
+        for stmt in ast.top_level_stmts() {
+            let StmtP::Assign(assign) = &stmt.node else {
+                continue;
+            };
+            let mut names = Vec::new();
+            assign
+                .lhs
+                .visit_lvalue(|ident| names.push(ident.node.ident.clone()));
+            let [name] = names.as_slice() else {
+                continue;
+            };
+            let Some(net) = net_names.get(name) else {
+                continue;
+            };
+            if net.name == net.original_name {
+                continue;
+            }
+            hints.push(InlayHint {
+                position: quickfix::to_range(codemap.resolve_span(assign.rhs.span)).end,
+                label: InlayHintLabel::String(format!("→ {}", net.name)),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: Some(InlayHintTooltip::String(format!(
+                    "Net \"{}\" was renamed to \"{}\" to keep net names unique",
+                    net.original_name, net.name
+                ))),
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+        }
+
+        let symbols = self.inner.get_symbols_for_file(path).unwrap_or_default();
+        for expr in quickfix::all_exprs(&ast) {
+            let ExprP::Call(callee, args) = &expr.node else {
+                continue;
+            };
+            let ExprP::Identifier(callee) = &callee.node else {
+                continue;
+            };
+            let call_range = quickfix::to_range(codemap.resolve_span(expr.span));
+
+            // Where `Module("...")` paths resolve to.
+            if callee.node.ident == "Module" {
+                let Some(arg) = args.args.first() else {
+                    continue;
+                };
+                let ArgumentP::Positional(arg) = &arg.node else {
+                    continue;
+                };
+                let ExprP::Literal(AstLiteral::String(spec)) = &arg.node else {
+                    continue;
+                };
+                if let Some(label) = self.module_path_label(&spec.node, path) {
+                    hints.push(InlayHint {
+                        position: quickfix::to_range(codemap.resolve_span(arg.span)).end,
+                        label: InlayHintLabel::String(label),
+                        kind: None,
+                        text_edits: None,
+                        tooltip: None,
+                        padding_left: Some(true),
+                        padding_right: None,
+                        data: None,
+                    });
+                }
+                continue;
+            }
+
+            // Defaults of omitted `config()` parameters at module call sites.
+            let Some(source_path) = symbols
+                .get(&callee.node.ident)
+                .filter(|info| info.type_name == "ModuleLoader")
+                .and_then(|info| info.source_path.clone())
+            else {
+                continue;
+            };
+            let source_path = self
+                .file_provider
+                .canonicalize(&source_path)
+                .unwrap_or(source_path);
+            let Some(signature) = self.inner.get_module_signature(&source_path) else {
+                continue;
+            };
+            let provided: Vec<&str> = args
+                .args
+                .iter()
+                .filter_map(|arg| match &arg.node {
+                    ArgumentP::Named(name, _) => Some(name.node.as_str()),
+                    _ => None,
+                })
+                .collect();
+            if let Some(label) = config_defaults_label(&signature, &provided) {
+                hints.push(InlayHint {
+                    // Just inside the closing parenthesis.
+                    position: Position::new(
+                        call_range.end.line,
+                        call_range.end.character.saturating_sub(1),
+                    ),
+                    label: InlayHintLabel::String(label),
+                    kind: Some(InlayHintKind::PARAMETER),
+                    text_edits: None,
+                    tooltip: Some(InlayHintTooltip::String(
+                        "Default values of config() parameters not set here".to_string(),
+                    )),
+                    padding_left: Some(!provided.is_empty()),
+                    padding_right: None,
+                    data: None,
+                });
+            }
+        }
+
+        hints.retain(|hint| in_range(hint.position, range));
+        hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+        hints
+    }
+
+    /// The display path a `Module()` spec resolves to, relative to the workspace if possible.
+    fn module_path_label(&self, spec: &str, current_file: &Path) -> Option<String> {
+        if !spec.starts_with('@') {
+            return None;
+        }
+        let LspUrl::File(resolved) = self
+            .resolve_load(spec, &LspUrl::File(current_file.to_path_buf()), None)
+            .ok()?
+        else {
+            return None;
+        };
+        let workspace_root = find_workspace_root(self.file_provider.as_ref(), current_file);
+        Some(display_path(&resolved, &workspace_root, dirs::home_dir()))
+    }
+}
+
+/// Render `path` relative to the workspace, or with the home directory shortened to `~`.
+fn display_path(path: &Path, workspace_root: &Path, home: Option<PathBuf>) -> String {
+    if let Ok(relative) = path.strip_prefix(workspace_root) {
+        return format!("./{}", relative.display());
+    }
+    if let Some(relative) = home
+        .as_deref()
+        .and_then(|home| path.strip_prefix(home).ok())
+    {
+        return format!("~/{}", relative.display());
+    }
+    path.display().to_string()
+}
+
+/// The label listing default values of `config()` parameters not in `provided`.
+fn config_defaults_label(signature: &[ParameterInfo], provided: &[&str]) -> Option<String> {
+    let defaults: Vec<String> = signature
+        .iter()
+        .filter(|param| param.is_config() && !provided.contains(&param.name.as_str()))
+        .filter_map(|param| {
+            let value = param.default_value.as_ref()?;
+            Some(format!("{} = {}", param.name, render_default(value)))
+        })
+        .collect();
+    (!defaults.is_empty()).then(|| defaults.join(", "))
+}
+
+/// Render a default value the way it would be written in source.
+fn render_default(value: &InputValue) -> String {
+    match value {
+        InputValue::Enum { variant } => format!("\"{variant}\""),
+        other => other.to_string(),
+    }
+}
+
+fn in_range(position: Position, range: Range) -> bool {
+    let key = |p: Position| (p.line, p.character);
+    key(range.start) <= key(position) && key(position) <= key(range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_zen_core::lang::type_info::TypeInfo;
+
+    fn param(name: &str, type_info: TypeInfo, default_value: Option<InputValue>) -> ParameterInfo {
+        ParameterInfo {
+            name: name.to_string(),
+            type_info,
+            required: default_value.is_none(),
+            default_value,
+            help: None,
+        }
+    }
+
+    #[test]
+    fn lists_only_omitted_config_defaults() {
+        let signature = vec![
+            param("VCC", TypeInfo::Net, None),
+            param(
+                "value",
+                TypeInfo::String,
+                Some(InputValue::String("10k".into())),
+            ),
+            param("count", TypeInfo::Int, Some(InputValue::Int(2))),
+            param("name", TypeInfo::String, None),
+            param(
+                "package",
+                TypeInfo::String,
+                Some(InputValue::Enum {
+                    variant: "0402".into(),
+                }),
+            ),
+        ];
+        assert_eq!(
+            config_defaults_label(&signature, &["count"]).as_deref(),
+            Some("value = \"10k\", package = \"0402\"")
+        );
+        assert_eq!(
+            config_defaults_label(&signature, &["value", "count", "package"]),
+            None
+        );
+    }
+
+    #[test]
+    fn shortens_module_paths() {
+        let home = Some(PathBuf::from("/home/u"));
+        assert_eq!(
+            display_path(
+                Path::new("/ws/modules/R.zen"),
+                Path::new("/ws"),
+                home.clone()
+            ),
+            "./modules/R.zen"
+        );
+        assert_eq!(
+            display_path(
+                Path::new("/home/u/.pcb/cache/stdlib/v1/R.zen"),
+                Path::new("/ws"),
+                home
+            ),
+            "~/.pcb/cache/stdlib/v1/R.zen"
+        );
+    }
+}
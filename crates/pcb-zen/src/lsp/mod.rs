@@ -1,26 +1,40 @@
+pub mod format;
+pub mod inlay_hints;
+pub mod quickfix;
+pub mod semantic_tokens;
 pub mod signature;
+pub mod viewer;
 
 use lsp_server::ResponseError;
 use lsp_types::{
-    request::Request, Hover, HoverContents, MarkupContent, MarkupKind, ServerCapabilities,
-    SignatureHelpOptions, Url, WorkDoneProgressOptions,
+    request::{
+        CodeActionRequest, Formatting, InlayHintRequest, RangeFormatting, Request,
+        SemanticTokensFullRequest,
+    },
+    CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, DocumentFormattingParams, DocumentRangeFormattingParams, Hover,
+    HoverContents, InlayHintParams, MarkupContent, MarkupKind, OneOf, SemanticTokensParams,
+    SemanticTokensResult, ServerCapabilities, SignatureHelpOptions, TextEdit, Url,
+    WorkDoneProgressOptions,
 };
+use pcb_sch::Schematic;
 use pcb_starlark_lsp::server::{
     self, CompletionMeta, LspContext, LspEvalResult, LspUrl, Response, StringLiteralResult,
 };
 use pcb_zen_core::config::find_workspace_root;
 use pcb_zen_core::lang::type_info::ParameterInfo;
 use pcb_zen_core::{
-    CoreLoadResolver, DefaultFileProvider, EvalContext, FileProvider, InputMap, InputValue,
-    LoadResolver,
+    CoreLoadResolver, DefaultFileProvider, EvalContext, EvalOutput, FileProvider,
+    FileProviderError, InputMap, InputValue, LoadResolver, WithDiagnostics,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use starlark::docs::DocModule;
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 
+use crate::cache::DiskCache;
 use crate::load::DefaultRemoteFetcher;
 use pcb_zen_core::convert::ToSchematic;
 
@@ -28,7 +42,59 @@ use pcb_zen_core::convert::ToSchematic;
 pub struct LspEvalContext {
     inner: EvalContext,
     builtin_docs: HashMap<LspUrl, String>,
-    file_provider: Arc<dyn FileProvider>,
+    file_provider: Arc<DocumentFileProvider>,
+    viewer: viewer::ViewerStates,
+    /// Schematics of watched files from this context's latest evaluation of each
+    evaluated: Mutex<HashMap<PathBuf, Schematic>>,
+}
+
+/// Reads files from disk, except for documents the server has evaluated, whose latest
+/// contents are served instead. Modules instantiated by one document therefore see
+/// unsaved edits to another.
+#[derive(Default)]
+struct DocumentFileProvider {
+    documents: RwLock<HashMap<PathBuf, String>>,
+}
+
+impl DocumentFileProvider {
+    fn document(&self, path: &Path) -> Option<String> {
+        self.documents.read().unwrap().get(path).cloned()
+    }
+
+    /// Record the latest contents of `path`, returning whether they changed.
+    fn set_document(&self, path: &Path, contents: &str) -> bool {
+        let mut documents = self.documents.write().unwrap();
+        if documents.get(path).map(String::as_str) == Some(contents) {
+            return false;
+        }
+        documents.insert(path.to_path_buf(), contents.to_string());
+        true
+    }
+}
+
+impl FileProvider for DocumentFileProvider {
+    fn read_file(&self, path: &Path) -> Result<String, FileProviderError> {
+        match self.document(path) {
+            Some(contents) => Ok(contents),
+            None => DefaultFileProvider.read_file(path),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.documents.read().unwrap().contains_key(path) || DefaultFileProvider.exists(path)
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        DefaultFileProvider.is_directory(path)
+    }
+
+    fn list_directory(&self, path: &Path) -> Result<Vec<PathBuf>, FileProviderError> {
+        DefaultFileProvider.list_directory(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf, FileProviderError> {
+        DefaultFileProvider.canonicalize(path)
+    }
 }
 
 /// Helper function to create a standard load resolver with remote and workspace support
@@ -72,13 +138,15 @@ impl Default for LspEvalContext {
             }
         }
 
-        let file_provider = Arc::new(DefaultFileProvider);
+        let file_provider = Arc::new(DocumentFileProvider::default());
         let inner = EvalContext::with_file_provider(file_provider.clone());
 
         Self {
             inner,
             builtin_docs,
             file_provider,
+            viewer: Default::default(),
+            evaluated: Default::default(),
         }
     }
 }
@@ -89,6 +157,19 @@ impl LspEvalContext {
         self
     }
 
+    /// Evaluate `path` with `contents` as its latest contents
+    fn evaluate(&self, path: &Path, contents: String) -> WithDiagnostics<EvalOutput> {
+        if self.file_provider.set_document(path, &contents) {
+            // Modules that loaded the previous contents must read them again
+            self.inner.invalidate_loads([path]);
+        }
+        let load_resolver = create_standard_load_resolver(self.file_provider.clone(), path);
+        self.inner
+            .child_context()
+            .set_load_resolver(load_resolver)
+            .parse_and_analyze_file(path.to_path_buf(), contents)
+    }
+
     /// Create LSP-specific diagnostic passes
     fn create_lsp_diagnostic_passes(
         &self,
@@ -192,10 +273,18 @@ impl LspEvalContext {
         }
         let full_message = full_chain_lines.join("\n");
 
+        // Attach what the code action handler needs to offer a quick fix.
+        let quick_fix = quickfix::QuickFixData::classify(diag, |d| self.undefined_stdlib_symbol(d));
+        if let Some(quickfix::QuickFixData::UnstableRef { repo_url, .. }) = &quick_fix {
+            quickfix::prefetch_latest_tag(repo_url);
+        }
+
         lsp_types::Diagnostic {
             range,
             severity: Some(severity),
-            code: None,
+            code: quick_fix
+                .as_ref()
+                .map(|data| lsp_types::NumberOrString::String(data.code().to_string())),
             code_description: None,
             source: Some("diode-star".to_string()),
             message: full_message,
@@ -205,7 +294,7 @@ impl LspEvalContext {
                 Some(related)
             },
             tags: None,
-            data: None,
+            data: quick_fix.and_then(|data| serde_json::to_value(data).ok()),
         }
     }
 }
@@ -220,6 +309,17 @@ impl LspContext for LspEvalContext {
                     work_done_progress: None,
                 },
             }),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![
+                    CodeActionKind::QUICKFIX,
+                    CodeActionKind::SOURCE_FIX_ALL,
+                ]),
+                ..CodeActionOptions::default()
+            })),
+            semantic_tokens_provider: Some(semantic_tokens::capability()),
+            inlay_hint_provider: Some(OneOf::Left(true)),
             ..ServerCapabilities::default()
         }
     }
@@ -227,16 +327,10 @@ impl LspContext for LspEvalContext {
     fn parse_file_with_contents(&self, uri: &LspUrl, content: String) -> LspEvalResult {
         match uri {
             LspUrl::File(path) => {
-                // Create a load resolver for this file
-                let load_resolver =
-                    create_standard_load_resolver(self.file_provider.clone(), uri.path());
-
-                // Parse and analyze the file with the load resolver set
-                let mut result = self
-                    .inner
-                    .child_context()
-                    .set_load_resolver(load_resolver)
-                    .parse_and_analyze_file(path.clone(), content.clone());
+                let mut result = self.evaluate(path, content);
+                if let Some(output) = &result.output {
+                    self.record_evaluation(path, output);
+                }
 
                 // Apply LSP-specific diagnostic passes
                 let passes = self.create_lsp_diagnostic_passes(path);
@@ -251,7 +345,7 @@ impl LspContext for LspEvalContext {
 
                 LspEvalResult {
                     diagnostics,
-                    ast: result.output.flatten(),
+                    ast: result.output.map(|output| output.ast),
                 }
             }
             _ => {
@@ -338,8 +432,8 @@ impl LspContext for LspEvalContext {
     fn get_load_contents(&self, uri: &LspUrl) -> anyhow::Result<Option<String>> {
         match uri {
             LspUrl::File(path) => {
-                // First check in-memory contents
-                if let Some(contents) = self.inner.get_file_contents(path) {
+                // First check the editor's contents
+                if let Some(contents) = self.file_provider.document(path) {
                     return Ok(Some(contents));
                 }
                 // Then check file system
@@ -418,6 +512,43 @@ impl LspContext for LspEvalContext {
         }
     }
 
+    fn background_context(&self) -> Option<Box<dyn FnOnce() -> Self + Send>> {
+        // The worker gets its own `EvalContext` sharing our caches, so that symbols and
+        // diagnostics from background evaluation are visible to requests handled here.
+        let shared = self.inner.shared();
+        let builtin_docs = self.builtin_docs.clone();
+        let file_provider = self.file_provider.clone();
+        let viewer = self.viewer.clone();
+        Some(Box::new(move || Self {
+            inner: EvalContext::from_shared(shared),
+            builtin_docs,
+            file_provider,
+            viewer,
+            evaluated: Default::default(),
+        }))
+    }
+
+    fn did_evaluate(
+        &self,
+        uri: &LspUrl,
+        diagnostics: &[lsp_types::Diagnostic],
+    ) -> Vec<lsp_server::Notification> {
+        let LspUrl::File(path) = uri else {
+            return Vec::new();
+        };
+        let schematic = self.evaluated.lock().unwrap().remove(path);
+        if diagnostics
+            .iter()
+            .any(|d| d.severity == Some(lsp_types::DiagnosticSeverity::ERROR))
+        {
+            return Vec::new();
+        }
+        schematic
+            .and_then(|schematic| self.viewer_update(path, schematic))
+            .into_iter()
+            .collect()
+    }
+
     fn is_eager(&self) -> bool {
         self.inner.is_eager()
     }
@@ -433,6 +564,13 @@ impl LspContext for LspEvalContext {
         self.inner.module_dep_exists(from, to)
     }
 
+    fn get_module_dependencies(&self, path: &Path) -> Vec<PathBuf> {
+        self.inner
+            .get_module_dependencies(path)
+            .map(|deps| deps.into_iter().collect())
+            .unwrap_or_default()
+    }
+
     fn get_custom_hover_for_load(
         &self,
         load_path: &str,
@@ -555,39 +693,10 @@ impl LspContext for LspEvalContext {
         if req.method == ViewerGetStateRequest::METHOD {
             match serde_json::from_value::<ViewerGetStateParams>(req.params.clone()) {
                 Ok(params) => {
+                    // Evaluate the module, reusing the on-disk cache when possible. From
+                    // now on, changes to it are pushed through `viewer/didChangeState`.
                     let state_json: Option<JsonValue> = match &params.uri {
-                        LspUrl::File(path_buf) => {
-                            // Get contents from memory or disk
-                            let maybe_contents = self.get_load_contents(&params.uri).ok().flatten();
-
-                            // Evaluate the module
-                            let ctx = EvalContext::new()
-                                .set_file_provider(self.file_provider.clone())
-                                .set_load_resolver(create_standard_load_resolver(
-                                    self.file_provider.clone(),
-                                    path_buf,
-                                ));
-
-                            let eval_result = if let Some(contents) = maybe_contents {
-                                ctx.set_source_path(path_buf.clone())
-                                    .set_module_name("<root>".to_string())
-                                    .set_inputs(InputMap::new())
-                                    .set_source_contents(contents)
-                                    .eval()
-                            } else {
-                                ctx.set_source_path(path_buf.clone())
-                                    .set_module_name("<root>".to_string())
-                                    .set_inputs(InputMap::new())
-                                    .eval()
-                            };
-
-                            eval_result
-                                .output
-                                .and_then(|fmv| match fmv.sch_module.to_schematic() {
-                                    Ok(schematic) => serde_json::to_value(&schematic).ok(),
-                                    Err(_) => None,
-                                })
-                        }
+                        LspUrl::File(path_buf) => self.viewer_state(path_buf),
                         _ => None,
                     };
 
@@ -612,6 +721,26 @@ impl LspContext for LspEvalContext {
             }
         }
 
+        // Map a schematic instance back to the call that created it
+        if req.method == ViewerResolveInstanceRequest::METHOD {
+            return Some(
+                match serde_json::from_value::<ViewerResolveInstanceParams>(req.params.clone()) {
+                    Ok(params) => {
+                        let location = match &params.uri {
+                            LspUrl::File(path) => self.resolve_instance(path, &params.instance),
+                            _ => None,
+                        };
+                        Response {
+                            id: req.id.clone(),
+                            result: Some(serde_json::to_value(location).unwrap()),
+                            error: None,
+                        }
+                    }
+                    Err(e) => params_error(req, e),
+                },
+            );
+        }
+
         // Handle zener/evaluate requests
         if req.method == ZenerEvaluateRequest::METHOD {
             match serde_json::from_value::<ZenerEvaluateParams>(req.params.clone()) {
@@ -652,11 +781,171 @@ impl LspContext for LspEvalContext {
             }
         }
 
+        // Handle document and range formatting requests
+        if req.method == Formatting::METHOD {
+            return Some(
+                match serde_json::from_value::<DocumentFormattingParams>(req.params.clone()) {
+                    Ok(params) => self.format_response(req, params.text_document.uri, None),
+                    Err(e) => params_error(req, e),
+                },
+            );
+        }
+        if req.method == RangeFormatting::METHOD {
+            return Some(
+                match serde_json::from_value::<DocumentRangeFormattingParams>(req.params.clone()) {
+                    Ok(params) => {
+                        self.format_response(req, params.text_document.uri, Some(params.range))
+                    }
+                    Err(e) => params_error(req, e),
+                },
+            );
+        }
+
+        // Handle code action requests (quick fixes and `source.fixAll` via the upgrade codemods)
+        if req.method == CodeActionRequest::METHOD {
+            return Some(
+                match serde_json::from_value::<CodeActionParams>(req.params.clone()) {
+                    Ok(params) => {
+                        let actions = self.code_actions(&params);
+                        Response {
+                            id: req.id.clone(),
+                            result: Some(serde_json::to_value(actions).unwrap()),
+                            error: None,
+                        }
+                    }
+                    Err(e) => params_error(req, e),
+                },
+            );
+        }
+
+        // Handle semantic tokens and inlay hints, both computed from the last evaluation
+        if req.method == SemanticTokensFullRequest::METHOD {
+            return Some(
+                match serde_json::from_value::<SemanticTokensParams>(req.params.clone()) {
+                    Ok(params) => {
+                        let tokens = match LspUrl::try_from(params.text_document.uri) {
+                            Ok(LspUrl::File(path)) => self.semantic_tokens(&path),
+                            _ => Default::default(),
+                        };
+                        Response {
+                            id: req.id.clone(),
+                            result: Some(
+                                serde_json::to_value(SemanticTokensResult::Tokens(tokens)).unwrap(),
+                            ),
+                            error: None,
+                        }
+                    }
+                    Err(e) => params_error(req, e),
+                },
+            );
+        }
+        if req.method == InlayHintRequest::METHOD {
+            return Some(
+                match serde_json::from_value::<InlayHintParams>(req.params.clone()) {
+                    Ok(params) => {
+                        let hints = match LspUrl::try_from(params.text_document.uri) {
+                            Ok(LspUrl::File(path)) => self.inlay_hints(&path, params.range),
+                            _ => Vec::new(),
+                        };
+                        Response {
+                            id: req.id.clone(),
+                            result: Some(serde_json::to_value(hints).unwrap()),
+                            error: None,
+                        }
+                    }
+                    Err(e) => params_error(req, e),
+                },
+            );
+        }
+
         None
     }
 }
 
+/// Build the error response for a request whose params failed to deserialize.
+fn params_error(req: &server::Request, e: serde_json::Error) -> Response {
+    Response {
+        id: req.id.clone(),
+        result: None,
+        error: Some(ResponseError {
+            code: 0,
+            message: format!("Failed to parse params: {e}"),
+            data: None,
+        }),
+    }
+}
+
 impl LspEvalContext {
+    /// Format the current contents of `uri`, optionally restricted to `range`.
+    fn format_response(
+        &self,
+        req: &server::Request,
+        uri: Url,
+        range: Option<lsp_types::Range>,
+    ) -> Response {
+        let result =
+            LspUrl::try_from(uri)
+                .map_err(anyhow::Error::from)
+                .and_then(|uri| match &uri {
+                    LspUrl::File(path) => {
+                        let contents = self.get_load_contents(&uri)?.unwrap_or_default();
+                        format::format_edits(path, &contents, range)
+                    }
+                    _ => Ok(Vec::<TextEdit>::new()),
+                });
+
+        match result {
+            Ok(edits) => Response {
+                id: req.id.clone(),
+                result: Some(serde_json::to_value(edits).unwrap()),
+                error: None,
+            },
+            Err(e) => Response {
+                id: req.id.clone(),
+                result: None,
+                error: Some(ResponseError {
+                    code: 0,
+                    message: format!("Formatting failed: {e}"),
+                    data: None,
+                }),
+            },
+        }
+    }
+
+    /// Collect the code actions available for a document.
+    fn code_actions(&self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let mut actions = Vec::new();
+        let only = params.context.only.as_deref();
+
+        let Ok(uri) = LspUrl::try_from(params.text_document.uri.clone()) else {
+            return actions;
+        };
+        let LspUrl::File(path) = &uri else {
+            return actions;
+        };
+
+        if format::kind_requested(only, &CodeActionKind::QUICKFIX) {
+            for diagnostic in &params.context.diagnostics {
+                actions.extend(self.quick_fixes(diagnostic));
+            }
+        }
+
+        if format::kind_explicitly_requested(only, &CodeActionKind::SOURCE_FIX_ALL) {
+            let contents = self
+                .get_load_contents(&uri)
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            match format::fix_all_action(&params.text_document.uri, path, &contents) {
+                Ok(Some(action)) => actions.push(action),
+                Ok(None) => {}
+                Err(e) => log::debug!("source.fixAll failed for {}: {e}", path.display()),
+            }
+        }
+
+        actions
+    }
+
     fn evaluate_module(
         &self,
         params: ZenerEvaluateParams,
@@ -685,12 +974,14 @@ impl LspEvalContext {
         }
 
         // Create evaluation context
+        let workspace_root = find_workspace_root(self.file_provider.as_ref(), path_buf);
         let ctx = EvalContext::new()
             .set_file_provider(self.file_provider.clone())
             .set_load_resolver(create_standard_load_resolver(
                 self.file_provider.clone(),
                 path_buf,
             ))
+            .set_cache(Arc::new(DiskCache::for_workspace(&workspace_root)))
             .set_module_name(module_name)
             .set_inputs(input_map);
 
@@ -736,7 +1027,7 @@ impl LspEvalContext {
 }
 
 /// Convert serde_json::Value to InputValue
-fn json_to_input_value(json: &JsonValue) -> Option<InputValue> {
+pub(crate) fn json_to_input_value(json: &JsonValue) -> Option<InputValue> {
     match json {
         JsonValue::Null => Some(InputValue::None),
         JsonValue::Bool(b) => Some(InputValue::Bool(*b)),
@@ -767,7 +1058,7 @@ fn json_to_input_value(json: &JsonValue) -> Option<InputValue> {
 }
 
 /// Convert a Diagnostic to DiagnosticInfo
-fn diagnostic_to_info(diag: &pcb_zen_core::Diagnostic) -> DiagnosticInfo {
+pub(crate) fn diagnostic_to_info(diag: &pcb_zen_core::Diagnostic) -> DiagnosticInfo {
     let level = match diag.severity {
         starlark::errors::EvalSeverity::Error => "error",
         starlark::errors::EvalSeverity::Warning => "warning",
@@ -805,6 +1096,23 @@ struct ViewerGetStateResponse {
     state: Option<JsonValue>,
 }
 
+// Custom LSP request to find the source of an instance clicked in the viewer.
+struct ViewerResolveInstanceRequest;
+impl lsp_types::request::Request for ViewerResolveInstanceRequest {
+    type Params = ViewerResolveInstanceParams;
+    type Result = Option<lsp_types::Location>;
+    const METHOD: &'static str = "viewer/resolveInstance";
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ViewerResolveInstanceParams {
+    /// The file whose schematic the instance belongs to.
+    uri: LspUrl,
+    /// The instance reference, as serialized in the schematic's `instances`.
+    instance: String,
+}
+
 // Custom LSP request for zener/evaluate - evaluates a module with given inputs and returns a netlist
 struct ZenerEvaluateRequest;
 impl lsp_types::request::Request for ZenerEvaluateRequest {
@@ -832,7 +1140,7 @@ struct ZenerEvaluateResponse {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-struct DiagnosticInfo {
+pub(crate) struct DiagnosticInfo {
     level: String,
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
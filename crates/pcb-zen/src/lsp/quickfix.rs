@@ -0,0 +1,977 @@
+//! Quick fixes for common Zen diagnostics.
+//!
+//! When a diagnostic is converted for the editor, [`QuickFixData::classify`] recognises
+//! the cases we know how to repair and stores what the fix needs in the LSP
+//! diagnostic's `data` field. The client hands that diagnostic back in
+//! `textDocument/codeAction`, where [`LspEvalContext::quick_fixes`] turns it into edits.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use once_cell::sync::Lazy;
+use pcb_starlark_lsp::server::{LspContext, LspUrl};
+use pcb_zen_core::lang::type_info::{ParameterInfo, TypeInfo};
+use pcb_zen_core::{
+    Diagnostic, LoadSpec, MissingInputError, UnknownArgumentsError, UnknownPinError,
+    UnstableRefError,
+};
+use serde::{Deserialize, Serialize};
+use starlark::codemap::{CodeMap, ResolvedSpan};
+use starlark::syntax::ast::{ArgumentP, AstExpr, AstLiteral, ExprP, StmtP};
+use starlark::syntax::{AstModule, Dialect};
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use super::{LspEvalContext, ZenerEvaluateParams};
+
+/// The stdlib package, whose top-level files provide the symbols a missing `load()` is
+/// offered for.
+const STDLIB: &str = "@stdlib";
+
+/// Latest release tag per repository URL. Lookups run in the background, started when an
+/// unstable-ref diagnostic is published, so code action requests never wait on the network.
+static LATEST_TAGS: Lazy<Mutex<HashMap<String, TagLookup>>> = Lazy::new(Default::default);
+
+/// Symbols exported by the top-level files of a stdlib checkout, keyed by its root.
+static STDLIB_EXPORTS: Lazy<Mutex<HashMap<PathBuf, Arc<BTreeMap<String, String>>>>> =
+    Lazy::new(Default::default);
+
+enum TagLookup {
+    Pending,
+    Done(Option<String>),
+}
+
+/// A file location that a quick fix edits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixLocation {
+    pub path: PathBuf,
+    pub range: Range,
+}
+
+/// What a quick fix needs to know about the diagnostic it repairs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QuickFixData {
+    /// A remote load resolved to a branch or HEAD instead of a tag or commit.
+    UnstableRef {
+        location: FixLocation,
+        spec: LoadSpec,
+        repo_url: String,
+        /// Where the alias is declared in `pcb.toml`, for non-default aliases.
+        alias: Option<FixLocation>,
+    },
+    /// A module was instantiated without one of its required `io()` inputs.
+    MissingInput {
+        location: FixLocation,
+        module: PathBuf,
+        name: String,
+    },
+    /// A module was instantiated with keyword arguments it does not declare.
+    UnknownArguments {
+        location: FixLocation,
+        unknown: Vec<String>,
+        known: Vec<String>,
+    },
+    /// A `pins` dict used a name that is not one of the symbol's signals.
+    UnknownPin {
+        location: FixLocation,
+        name: String,
+        expected: Vec<String>,
+    },
+    /// An identifier is not defined but is exported by a stdlib module.
+    UndefinedSymbol { location: FixLocation, name: String },
+}
+
+impl QuickFixData {
+    /// The LSP diagnostic `code` for this kind of fix.
+    pub fn code(&self) -> &'static str {
+        match self {
+            QuickFixData::UnstableRef { .. } => "unstable-ref",
+            QuickFixData::MissingInput { .. } => "missing-input",
+            QuickFixData::UnknownArguments { .. } => "unknown-argument",
+            QuickFixData::UnknownPin { .. } => "unknown-pin",
+            QuickFixData::UndefinedSymbol { .. } => "undefined-symbol",
+        }
+    }
+
+    /// Walk a diagnostic chain and extract fix data for the first fixable entry.
+    /// `undefined_symbol` names the symbol an error reports as undefined, if a stdlib
+    /// module exports it.
+    pub fn classify(
+        diag: &Diagnostic,
+        undefined_symbol: impl Fn(&Diagnostic) -> Option<String>,
+    ) -> Option<Self> {
+        let mut parent: Option<&Diagnostic> = None;
+        let mut current = Some(diag);
+
+        while let Some(d) = current {
+            if let Some(err) = d.downcast_error_ref::<UnstableRefError>() {
+                // For aliases declared in pcb.toml the error is attached to the toml
+                // diagnostic, which is a child of the one at the load site.
+                let (location, alias) = if d.path.ends_with(".toml") {
+                    (location(parent?)?, location(d))
+                } else {
+                    (location(d)?, None)
+                };
+                return Some(QuickFixData::UnstableRef {
+                    location,
+                    spec: err.spec_chain.first()?.clone(),
+                    repo_url: err.remote_ref.repo_url()?,
+                    alias,
+                });
+            }
+
+            if let Some(err) = d.downcast_error_ref::<UnknownArgumentsError>() {
+                return Some(QuickFixData::UnknownArguments {
+                    location: location(d)?,
+                    unknown: err.unknown.clone(),
+                    known: err.known.clone(),
+                });
+            }
+
+            if let Some(err) = d.downcast_error_ref::<MissingInputError>() {
+                // The call to fix is the one that instantiated the module reporting the error.
+                return Some(QuickFixData::MissingInput {
+                    location: location(parent?)?,
+                    module: PathBuf::from(&d.path),
+                    name: err.name.clone(),
+                });
+            }
+
+            if let Some(err) = d.downcast_error_ref::<UnknownPinError>() {
+                return Some(QuickFixData::UnknownPin {
+                    location: location(d)?,
+                    name: err.pin.clone(),
+                    expected: err.expected.clone(),
+                });
+            }
+
+            if let Some(name) = undefined_symbol(d) {
+                return Some(QuickFixData::UndefinedSymbol {
+                    location: location(d)?,
+                    name,
+                });
+            }
+
+            parent = Some(d);
+            current = d.child.as_deref();
+        }
+
+        None
+    }
+}
+
+impl LspEvalContext {
+    /// Compute the quick fixes for a diagnostic previously published by this server.
+    pub(super) fn quick_fixes(
+        &self,
+        diagnostic: &lsp_types::Diagnostic,
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(data) = diagnostic
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<QuickFixData>(data).ok())
+        else {
+            return Vec::new();
+        };
+
+        let fixes = match &data {
+            QuickFixData::UnstableRef {
+                location,
+                spec,
+                repo_url,
+                alias,
+            } => self
+                .pin_to_latest_tag(location, spec, repo_url, alias.as_ref())
+                .into_iter()
+                .collect(),
+            QuickFixData::MissingInput {
+                location,
+                module,
+                name,
+            } => self
+                .add_missing_inputs(location, module, name)
+                .into_iter()
+                .collect(),
+            QuickFixData::UnknownArguments {
+                location,
+                unknown,
+                known,
+            } => self.rename_unknown_arguments(location, unknown, known),
+            QuickFixData::UnknownPin {
+                location,
+                name,
+                expected,
+            } => self
+                .rename_unknown_pin(location, name, expected)
+                .into_iter()
+                .collect(),
+            QuickFixData::UndefinedSymbol { location, name } => {
+                self.add_missing_load(location, name).into_iter().collect()
+            }
+        };
+
+        fixes
+            .into_iter()
+            .map(|fix| fix.into_action(diagnostic))
+            .collect()
+    }
+
+    pub(super) fn read(&self, path: &Path) -> Option<String> {
+        self.get_load_contents(&LspUrl::File(path.to_path_buf()))
+            .ok()
+            .flatten()
+    }
+
+    fn pin_to_latest_tag(
+        &self,
+        location: &FixLocation,
+        spec: &LoadSpec,
+        repo_url: &str,
+        alias: Option<&FixLocation>,
+    ) -> Option<QuickFix> {
+        let tag = latest_tag(repo_url)?;
+
+        // Non-default aliases are pinned where they are declared.
+        if let Some(alias) = alias {
+            let text = self.read(&alias.path)?;
+            let (range, value) = string_literals(&text, alias.range).into_iter().next()?;
+            let pinned = LoadSpec::parse(&value)?.with_rev(&tag)?;
+            return Some(QuickFix {
+                title: format!("Pin `{value}` to `{tag}`"),
+                path: alias.path.clone(),
+                edits: vec![TextEdit::new(range, pinned.to_load_string())],
+                preferred: true,
+            });
+        }
+
+        let text = self.read(&location.path)?;
+        let pinned = spec.with_rev(&tag)?.to_load_string();
+        let edits: Vec<TextEdit> = string_literals(&text, location.range)
+            .into_iter()
+            .filter(|(_, value)| LoadSpec::parse(value).as_ref() == Some(spec))
+            .map(|(range, _)| TextEdit::new(range, pinned.clone()))
+            .collect();
+        if edits.is_empty() {
+            return None;
+        }
+        Some(QuickFix {
+            title: format!("Pin to `{pinned}`"),
+            path: location.path.clone(),
+            edits,
+            preferred: true,
+        })
+    }
+
+    fn add_missing_inputs(
+        &self,
+        location: &FixLocation,
+        module: &Path,
+        name: &str,
+    ) -> Option<QuickFix> {
+        let text = self.read(&location.path)?;
+        let ast = parse(&location.path, &text)?;
+        let call = find_call(&ast, location.range)?;
+        let ExprP::Call(_, args) = &call.node else {
+            return None;
+        };
+        let provided: HashSet<&str> = args
+            .args
+            .iter()
+            .filter_map(|arg| match &arg.node {
+                ArgumentP::Named(name, _) => Some(name.node.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        // Prefer the module's full signature so every missing input is added at once.
+        let mut missing: Vec<(String, String)> = self
+            .module_signature(module)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|param| param.required && param.type_info.is_io_type())
+            .filter(|param| !provided.contains(param.name.as_str()))
+            .map(|param| {
+                let placeholder = placeholder_for(&param.name, &param.type_info);
+                (param.name, placeholder)
+            })
+            .collect();
+        if missing.is_empty() && !provided.contains(name) {
+            missing.push((name.to_string(), format!("Net(\"{name}\")")));
+        }
+        if missing.is_empty() {
+            return None;
+        }
+
+        let names: Vec<String> = missing.iter().map(|(n, _)| format!("`{n}`")).collect();
+        let kwargs: Vec<String> = missing
+            .iter()
+            .map(|(n, placeholder)| format!("{n} = {placeholder}"))
+            .collect();
+        Some(QuickFix {
+            title: if names.len() == 1 {
+                format!("Add missing input {}", names[0])
+            } else {
+                format!("Add missing inputs {}", names.join(", "))
+            },
+            path: location.path.clone(),
+            edits: insert_keyword_arguments(&text, ast.codemap(), call, &kwargs),
+            preferred: true,
+        })
+    }
+
+    fn rename_unknown_arguments(
+        &self,
+        location: &FixLocation,
+        unknown: &[String],
+        known: &[String],
+    ) -> Vec<QuickFix> {
+        let Some(text) = self.read(&location.path) else {
+            return Vec::new();
+        };
+        let Some(ast) = parse(&location.path, &text) else {
+            return Vec::new();
+        };
+        let Some(call) = find_call(&ast, location.range) else {
+            return Vec::new();
+        };
+        let ExprP::Call(_, args) = &call.node else {
+            return Vec::new();
+        };
+
+        let provided: HashSet<&str> = args
+            .args
+            .iter()
+            .filter_map(|arg| match &arg.node {
+                ArgumentP::Named(name, _) => Some(name.node.as_str()),
+                _ => None,
+            })
+            .collect();
+        let candidates: Vec<&str> = known
+            .iter()
+            .map(String::as_str)
+            .filter(|k| !provided.contains(k))
+            .collect();
+
+        args.args
+            .iter()
+            .filter_map(|arg| match &arg.node {
+                ArgumentP::Named(name, _) if unknown.contains(&name.node) => Some(name),
+                _ => None,
+            })
+            .filter_map(|name| {
+                let suggestion = closest(&name.node, candidates.iter().copied())?;
+                Some(QuickFix {
+                    title: format!("Rename argument `{}` to `{suggestion}`", name.node),
+                    path: location.path.clone(),
+                    edits: vec![TextEdit::new(
+                        to_range(ast.codemap().resolve_span(name.span)),
+                        suggestion.to_string(),
+                    )],
+                    preferred: true,
+                })
+            })
+            .collect()
+    }
+
+    fn rename_unknown_pin(
+        &self,
+        location: &FixLocation,
+        name: &str,
+        expected: &[String],
+    ) -> Option<QuickFix> {
+        let suggestion = closest(name, expected.iter().map(String::as_str))?;
+        let text = self.read(&location.path)?;
+        let ast = parse(&location.path, &text)?;
+
+        let edits: Vec<TextEdit> = all_exprs(&ast)
+            .into_iter()
+            .filter(|expr| {
+                matches!(&expr.node, ExprP::Literal(AstLiteral::String(s)) if s.node == name)
+            })
+            .map(|expr| to_range(ast.codemap().resolve_span(expr.span)))
+            .filter(|range| contains(location.range, *range))
+            .map(|range| TextEdit::new(range, format!("\"{suggestion}\"")))
+            .collect();
+        if edits.is_empty() {
+            return None;
+        }
+
+        Some(QuickFix {
+            title: format!("Change pin `{name}` to `{suggestion}`"),
+            path: location.path.clone(),
+            edits,
+            preferred: true,
+        })
+    }
+
+    /// The stdlib symbol an error reports as undefined: the error covers exactly an
+    /// identifier that its file neither defines nor loads, and a stdlib module exports it.
+    pub(super) fn undefined_stdlib_symbol(&self, diag: &Diagnostic) -> Option<String> {
+        if !diag.is_error() {
+            return None;
+        }
+        let location = location(diag)?;
+        let text = self.read(&location.path)?;
+        let ast = parse(&location.path, &text)?;
+        let name = all_exprs(&ast)
+            .into_iter()
+            .find_map(|expr| match &expr.node {
+                ExprP::Identifier(ident)
+                    if to_range(ast.codemap().resolve_span(expr.span)) == location.range =>
+                {
+                    Some(ident.node.ident.clone())
+                }
+                _ => None,
+            })?;
+        if top_level_names(&ast, true).contains(&name) {
+            return None;
+        }
+        self.stdlib_exports(&location.path)
+            .contains_key(&name)
+            .then_some(name)
+    }
+
+    /// Public top-level symbols of the stdlib files, mapped to the load path of the file
+    /// defining them. Empty if the stdlib cannot be resolved from `from`.
+    fn stdlib_exports(&self, from: &Path) -> Arc<BTreeMap<String, String>> {
+        let Ok(LspUrl::File(root)) = self.resolve_load(STDLIB, &LspUrl::File(from.into()), None)
+        else {
+            return Arc::default();
+        };
+        let mut cache = STDLIB_EXPORTS.lock().unwrap();
+        if let Some(exports) = cache.get(&root) {
+            return exports.clone();
+        }
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&root)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "zen"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+
+        let mut exports = BTreeMap::new();
+        for file in files {
+            let Some(ast) = std::fs::read_to_string(&file)
+                .ok()
+                .and_then(|text| parse(&file, &text))
+            else {
+                continue;
+            };
+            let Some(file_name) = file.file_name() else {
+                continue;
+            };
+            let module = format!("{STDLIB}/{}", file_name.to_string_lossy());
+            for name in top_level_names(&ast, false) {
+                if !name.starts_with('_') {
+                    exports.entry(name).or_insert_with(|| module.clone());
+                }
+            }
+        }
+
+        let exports = Arc::new(exports);
+        cache.insert(root, exports.clone());
+        exports
+    }
+
+    fn add_missing_load(&self, location: &FixLocation, name: &str) -> Option<QuickFix> {
+        let exports = self.stdlib_exports(&location.path);
+        let module = exports.get(name)?.as_str();
+        let text = self.read(&location.path)?;
+        let ast = parse(&location.path, &text)?;
+
+        let loads: Vec<_> =
+            starlark_syntax::syntax::top_level_stmts::top_level_stmts(ast.statement())
+                .into_iter()
+                .filter_map(|stmt| match &stmt.node {
+                    StmtP::Load(load) => Some((stmt, load)),
+                    _ => None,
+                })
+                .collect();
+
+        // Extend an existing load of the same module (at any tag) rather than adding another.
+        let existing = loads
+            .iter()
+            .find(|(_, load)| same_module(&load.module.node, module));
+        let edit = match (existing, loads.last()) {
+            (Some((stmt, _)), _) => {
+                let span = ast.codemap().resolve_span(stmt.span);
+                let close = Position::new(span.end.line as u32, span.end.column as u32 - 1);
+                TextEdit::new(Range::new(close, close), format!(", \"{name}\""))
+            }
+            (None, Some((stmt, _))) => {
+                let line = ast.codemap().resolve_span(stmt.span).end.line as u32 + 1;
+                let at = Position::new(line, 0);
+                TextEdit::new(
+                    Range::new(at, at),
+                    format!("load(\"{module}\", \"{name}\")\n"),
+                )
+            }
+            (None, None) => TextEdit::new(
+                Range::default(),
+                format!("load(\"{module}\", \"{name}\")\n\n"),
+            ),
+        };
+
+        Some(QuickFix {
+            title: format!("Load `{name}` from \"{module}\""),
+            path: location.path.clone(),
+            edits: vec![edit],
+            preferred: true,
+        })
+    }
+
+    /// Evaluate a module on its own to learn its declared inputs.
+    fn module_signature(&self, module: &Path) -> Option<Vec<ParameterInfo>> {
+        self.evaluate_module(ZenerEvaluateParams {
+            uri: LspUrl::File(module.to_path_buf()),
+            inputs: HashMap::new(),
+        })
+        .ok()?
+        .parameters
+    }
+}
+
+/// A set of edits to a single file that repairs a diagnostic.
+struct QuickFix {
+    title: String,
+    path: PathBuf,
+    edits: Vec<TextEdit>,
+    preferred: bool,
+}
+
+impl QuickFix {
+    fn into_action(self, diagnostic: &lsp_types::Diagnostic) -> CodeActionOrCommand {
+        let uri = Url::from_file_path(&self.path)
+            .unwrap_or_else(|_| Url::parse(&format!("file://{}", self.path.display())).unwrap());
+        CodeActionOrCommand::CodeAction(CodeAction {
+            title: self.title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(uri, self.edits)])),
+                ..WorkspaceEdit::default()
+            }),
+            is_preferred: Some(self.preferred),
+            ..CodeAction::default()
+        })
+    }
+}
+
+fn location(diag: &Diagnostic) -> Option<FixLocation> {
+    if diag.path.is_empty() {
+        return None;
+    }
+    Some(FixLocation {
+        path: PathBuf::from(&diag.path),
+        range: to_range(diag.span?),
+    })
+}
+
+pub(super) fn to_range(span: ResolvedSpan) -> Range {
+    Range::new(
+        Position::new(span.begin.line as u32, span.begin.column as u32),
+        Position::new(span.end.line as u32, span.end.column as u32),
+    )
+}
+
+fn contains(outer: Range, inner: Range) -> bool {
+    let key = |p: Position| (p.line, p.character);
+    key(outer.start) <= key(inner.start) && key(inner.end) <= key(outer.end)
+}
+
+/// Parse a buffer with the same dialect used for evaluation.
+pub(super) fn parse(path: &Path, text: &str) -> Option<AstModule> {
+    let mut dialect = Dialect::Extended;
+    dialect.enable_f_strings = true;
+    AstModule::parse(path.to_string_lossy().as_ref(), text.to_owned(), &dialect).ok()
+}
+
+/// Every expression in the module, outermost first.
+pub(super) fn all_exprs(ast: &AstModule) -> Vec<&AstExpr> {
+    fn walk<'a>(expr: &'a AstExpr, out: &mut Vec<&'a AstExpr>) {
+        out.push(expr);
+        expr.visit_expr(|e| walk(e, out));
+    }
+    let mut out = Vec::new();
+    ast.statement().visit_expr(|e| walk(e, &mut out));
+    out
+}
+
+/// Find the call expression a diagnostic was reported against.
+fn find_call(ast: &AstModule, range: Range) -> Option<&AstExpr> {
+    let calls: Vec<(&AstExpr, Range)> = all_exprs(ast)
+        .into_iter()
+        .filter(|expr| matches!(expr.node, ExprP::Call(..)))
+        .map(|expr| (expr, to_range(ast.codemap().resolve_span(expr.span))))
+        .collect();
+
+    calls
+        .iter()
+        .find(|(_, r)| *r == range)
+        .or_else(|| calls.iter().rev().find(|(_, r)| r.start == range.start))
+        .map(|(expr, _)| *expr)
+}
+
+/// Byte offset of an LSP position, treating `character` as a char index.
+fn offset(text: &str, pos: Position) -> Option<usize> {
+    let mut start = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        if i == pos.line as usize {
+            let col = line
+                .char_indices()
+                .nth(pos.character as usize)
+                .map(|(idx, _)| idx)
+                .unwrap_or(line.trim_end_matches('\n').len());
+            return Some(start + col);
+        }
+        start += line.len();
+    }
+    (pos.line as usize == text.split_inclusive('\n').count()).then_some(text.len())
+}
+
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    Position::new(line as u32, before[line_start..].chars().count() as u32)
+}
+
+/// The contents of quoted string literals inside `range`, with the range of each literal's
+/// contents (excluding quotes).
+fn string_literals(text: &str, range: Range) -> Vec<(Range, String)> {
+    let (Some(start), Some(end)) = (offset(text, range.start), offset(text, range.end)) else {
+        return Vec::new();
+    };
+    let slice = &text[start..end];
+
+    let mut out = Vec::new();
+    let mut chars = slice.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '"' && c != '\'' {
+            continue;
+        }
+        let Some((j, _)) = chars.by_ref().find(|(_, d)| *d == c) else {
+            break;
+        };
+        let (a, b) = (start + i + 1, start + j);
+        out.push((
+            Range::new(position(text, a), position(text, b)),
+            text[a..b].to_string(),
+        ));
+    }
+    out
+}
+
+/// Build the edits inserting `kwargs` (already rendered as `name = value`) into `call`.
+fn insert_keyword_arguments(
+    text: &str,
+    codemap: &CodeMap,
+    call: &AstExpr,
+    kwargs: &[String],
+) -> Vec<TextEdit> {
+    let ExprP::Call(_, args) = &call.node else {
+        return Vec::new();
+    };
+    let call_span = codemap.resolve_span(call.span);
+    let close = Position::new(
+        call_span.end.line as u32,
+        (call_span.end.column as u32).saturating_sub(1),
+    );
+    let last_end = args
+        .args
+        .last()
+        .map(|arg| codemap.resolve_span(arg.span).end)
+        .map(|end| Position::new(end.line as u32, end.column as u32));
+    let between = |from: Position| -> &str {
+        match (offset(text, from), offset(text, close)) {
+            (Some(a), Some(b)) if a <= b => &text[a..b],
+            _ => "",
+        }
+    };
+    let has_trailing_comma = last_end.is_some_and(|end| between(end).contains(','));
+
+    let close_line = text.lines().nth(close.line as usize).unwrap_or("");
+    let before_close: String = close_line.chars().take(close.character as usize).collect();
+
+    if call_span.begin.line != call_span.end.line && before_close.trim().is_empty() {
+        // Multi-line call with `)` on its own line: one argument per line.
+        let indent = match &last_end {
+            Some(end) => {
+                let line = text.lines().nth(end.line as usize).unwrap_or("");
+                line[..line.len() - line.trim_start().len()].to_string()
+            }
+            None => format!("{before_close}    "),
+        };
+        let mut edits = Vec::new();
+        if let (Some(end), false) = (last_end, has_trailing_comma) {
+            edits.push(TextEdit::new(Range::new(end, end), ",".to_string()));
+        }
+        let at = Position::new(close.line, 0);
+        edits.push(TextEdit::new(
+            Range::new(at, at),
+            kwargs.iter().map(|kw| format!("{indent}{kw},\n")).collect(),
+        ));
+        return edits;
+    }
+
+    let prefix = match last_end {
+        None => "",
+        Some(_) if has_trailing_comma => " ",
+        Some(_) => ", ",
+    };
+    vec![TextEdit::new(
+        Range::new(close, close),
+        format!("{prefix}{}", kwargs.join(", ")),
+    )]
+}
+
+/// Placeholder value for a required io() input of the given type.
+fn placeholder_for(name: &str, type_info: &TypeInfo) -> String {
+    match type_info {
+        TypeInfo::Interface { name: iface, .. } => format!("{iface}(\"{name}\")"),
+        _ => format!("Net(\"{name}\")"),
+    }
+}
+
+/// Names bound at the top level of a module by assignments and `def`s, and by `load()`s
+/// if `include_loads` is set (loaded symbols are private to the module).
+fn top_level_names(ast: &AstModule, include_loads: bool) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in starlark_syntax::syntax::top_level_stmts::top_level_stmts(ast.statement()) {
+        match &stmt.node {
+            StmtP::Assign(assign) => assign.lhs.visit_lvalue(|ident| {
+                names.insert(ident.node.ident.clone());
+            }),
+            StmtP::Def(def) => {
+                names.insert(def.name.ident.clone());
+            }
+            StmtP::Load(load) if include_loads => {
+                names.extend(load.args.iter().map(|arg| arg.local.ident.clone()));
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Whether two load paths refer to the same module, ignoring any pinned tag.
+fn same_module(a: &str, b: &str) -> bool {
+    match (LoadSpec::parse(a), LoadSpec::parse(b)) {
+        (Some(a), Some(b)) => a.with_rev("").unwrap_or(a) == b.with_rev("").unwrap_or(b),
+        _ => a == b,
+    }
+}
+
+/// Start looking up the newest semver tag of a remote repository in the background,
+/// unless it is known or already being looked up. Failed lookups are forgotten so that
+/// the next request retries them.
+pub(super) fn prefetch_latest_tag(repo_url: &str) {
+    let mut cache = LATEST_TAGS.lock().unwrap();
+    if cache.contains_key(repo_url) {
+        return;
+    }
+    cache.insert(repo_url.to_string(), TagLookup::Pending);
+
+    let repo_url = repo_url.to_string();
+    std::thread::spawn(move || {
+        let tags = crate::git::ls_remote_tags(&repo_url);
+        let mut cache = LATEST_TAGS.lock().unwrap();
+        match tags {
+            Ok(tags) => {
+                cache.insert(repo_url, TagLookup::Done(latest_version_tag(&tags)));
+            }
+            Err(e) => {
+                log::debug!("Failed to list tags of {repo_url}: {e}");
+                cache.remove(&repo_url);
+            }
+        }
+    });
+}
+
+/// The newest semver tag of a remote repository, if its lookup has finished.
+fn latest_tag(repo_url: &str) -> Option<String> {
+    let known = LATEST_TAGS
+        .lock()
+        .unwrap()
+        .get(repo_url)
+        .map(|lookup| match lookup {
+            TagLookup::Done(tag) => tag.clone(),
+            TagLookup::Pending => None,
+        });
+    known.unwrap_or_else(|| {
+        prefetch_latest_tag(repo_url);
+        None
+    })
+}
+
+/// The highest non-prerelease semver tag (with or without a leading `v`).
+fn latest_version_tag(tags: &[String]) -> Option<String> {
+    tags.iter()
+        .filter_map(|tag| {
+            let version = semver::Version::parse(tag.trim_start_matches('v')).ok()?;
+            version.pre.is_empty().then_some((version, tag))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, tag)| tag.clone())
+}
+
+/// The candidate closest to `name` by case-insensitive edit distance.
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    candidates
+        .into_iter()
+        .min_by_key(|candidate| edit_distance(&name, &candidate.to_lowercase()))
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(cur)
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starlark::codemap::ResolvedPos;
+    use starlark::errors::EvalSeverity;
+
+    fn diag(body: &str) -> Diagnostic {
+        Diagnostic::new(body, EvalSeverity::Error, Path::new("/ws/top.zen")).with_span(
+            ResolvedSpan {
+                begin: ResolvedPos { line: 3, column: 0 },
+                end: ResolvedPos {
+                    line: 3,
+                    column: 10,
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn classifies_unknown_pin() {
+        let d = diag("Unknown pin").with_source_error(Some(UnknownPinError {
+            pin: "VCC".to_string(),
+            expected: vec!["VDD".to_string(), "GND".to_string()],
+        }));
+        let Some(QuickFixData::UnknownPin { name, expected, .. }) =
+            QuickFixData::classify(&d, |_| None)
+        else {
+            panic!("expected unknown pin");
+        };
+        assert_eq!(name, "VCC");
+        assert_eq!(expected, vec!["VDD", "GND"]);
+    }
+
+    #[test]
+    fn classifies_missing_input_at_parent_call_site() {
+        let child = Diagnostic::new(
+            "Input 'VIN' is required",
+            EvalSeverity::Error,
+            Path::new("/ws/child.zen"),
+        )
+        .with_source_error(Some(MissingInputError {
+            name: "VIN".to_string(),
+        }));
+        let d = diag("Error instantiating `child`").with_child(child.boxed());
+        let Some(QuickFixData::MissingInput {
+            location,
+            module,
+            name,
+        }) = QuickFixData::classify(&d, |_| None)
+        else {
+            panic!("expected missing input");
+        };
+        assert_eq!(location.path, PathBuf::from("/ws/top.zen"));
+        assert_eq!(module, PathBuf::from("/ws/child.zen"));
+        assert_eq!(name, "VIN");
+    }
+
+    #[test]
+    fn messages_alone_are_not_fixable() {
+        let d = diag("Unknown pin name 'VCC' (expected one of: VDD, GND)");
+        assert!(QuickFixData::classify(&d, |_| None).is_none());
+        let Some(QuickFixData::UndefinedSymbol { name, .. }) =
+            QuickFixData::classify(&d, |_| Some("Power".to_string()))
+        else {
+            panic!("expected undefined symbol");
+        };
+        assert_eq!(name, "Power");
+    }
+
+    #[test]
+    fn top_level_names_exclude_loads_on_request() {
+        let text = "load(\"./a.zen\", \"A\")\nB = 1\n_c, d = 2, 3\ndef f(x):\n    y = x\n";
+        let ast = parse(Path::new("top.zen"), text).unwrap();
+        let mut exported: Vec<_> = top_level_names(&ast, false).into_iter().collect();
+        exported.sort();
+        assert_eq!(exported, ["B", "_c", "d", "f"]);
+        assert!(top_level_names(&ast, true).contains("A"));
+    }
+
+    #[test]
+    fn closest_prefers_smallest_edit_distance() {
+        assert_eq!(closest("VCC", ["GND", "VDD", "EN"]), Some("VDD"));
+        assert_eq!(closest("enable", ["EN", "ENABLE"]), Some("ENABLE"));
+        assert_eq!(closest("x", []), None);
+    }
+
+    #[test]
+    fn picks_latest_release_tag() {
+        let tags: Vec<String> = ["v0.2.2", "v0.2.10", "v0.3.0-rc1", "latest"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(latest_version_tag(&tags).as_deref(), Some("v0.2.10"));
+    }
+
+    #[test]
+    fn inserts_keyword_arguments_inline_and_multiline() {
+        let text = "Child(name = \"c\")\nChild(\n    name = \"d\",\n)\n";
+        let ast = parse(Path::new("top.zen"), text).unwrap();
+        let calls: Vec<&AstExpr> = all_exprs(&ast)
+            .into_iter()
+            .filter(|e| matches!(e.node, ExprP::Call(..)))
+            .collect();
+        let kwargs = vec!["VIN = Net(\"VIN\")".to_string()];
+
+        let inline = insert_keyword_arguments(text, ast.codemap(), calls[0], &kwargs);
+        assert_eq!(inline.len(), 1);
+        assert_eq!(inline[0].range.start, Position::new(0, 16));
+        assert_eq!(inline[0].new_text, ", VIN = Net(\"VIN\")");
+
+        let multiline = insert_keyword_arguments(text, ast.codemap(), calls[1], &kwargs);
+        assert_eq!(multiline.len(), 1);
+        assert_eq!(multiline[0].range.start, Position::new(3, 0));
+        assert_eq!(multiline[0].new_text, "    VIN = Net(\"VIN\"),\n");
+    }
+
+    #[test]
+    fn finds_string_literals_in_range() {
+        let text = "load(\"@stdlib/interfaces.zen\", \"Power\")\n";
+        let literals = string_literals(text, Range::new(Position::new(0, 0), Position::new(0, 40)));
+        assert_eq!(literals[0].1, "@stdlib/interfaces.zen");
+        assert_eq!(literals[0].0.start, Position::new(0, 6));
+        assert_eq!(literals[1].1, "Power");
+    }
+}
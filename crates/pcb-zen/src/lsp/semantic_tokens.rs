@@ -0,0 +1,320 @@
+//! Semantic tokens that tell Zen's domain objects apart from plain Starlark values.
+//!
+//! Identifiers are classified by the type of the global they refer to after the
+//! last evaluation of the file (see `EvalContext::get_symbols_for_file`), so a
+//! `VCC = Net("VCC")` binding is highlighted as a net everywhere it is used.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensServerCapabilities, WorkDoneProgressOptions,
+};
+use starlark::codemap::{CodeMap, Span};
+use starlark::syntax::ast::{AstNoPayload, DefP, ExprP, StmtP};
+use starlark::syntax::AstModule;
+use starlark_syntax::codemap::Spanned;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::uniplate::Visit;
+
+use super::quickfix;
+use super::LspEvalContext;
+
+/// Token types in legend order; the index is the value sent over the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZenTokenType {
+    Net = 0,
+    Interface = 1,
+    Module = 2,
+    Component = 3,
+}
+
+const MODIFIER_DECLARATION: u32 = 1 << 0;
+const MODIFIER_DEFAULT_LIBRARY: u32 = 1 << 1;
+
+/// The semantic tokens capability advertised by the Zen language server.
+pub fn capability() -> SemanticTokensServerCapabilities {
+    SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+        work_done_progress_options: WorkDoneProgressOptions::default(),
+        legend: SemanticTokensLegend {
+            token_types: vec![
+                SemanticTokenType::new("net"),
+                SemanticTokenType::new("interface"),
+                SemanticTokenType::new("module"),
+                SemanticTokenType::new("component"),
+            ],
+            token_modifiers: vec![
+                SemanticTokenModifier::DECLARATION,
+                SemanticTokenModifier::DEFAULT_LIBRARY,
+            ],
+        },
+        range: None,
+        full: Some(SemanticTokensFullOptions::Bool(true)),
+    })
+}
+
+/// Map the Starlark type name of a value to the token type used to highlight it.
+pub fn token_type_for(type_name: &str) -> Option<ZenTokenType> {
+    match type_name {
+        "Net" | "NetType" => Some(ZenTokenType::Net),
+        "InterfaceValue" | "InterfaceFactory" => Some(ZenTokenType::Interface),
+        "Module" | "ModuleLoader" => Some(ZenTokenType::Module),
+        "Component" | "ComponentFactory" | "ComponentType" => Some(ZenTokenType::Component),
+        _ => None,
+    }
+}
+
+/// Builtin constructors for the domain types.
+fn builtin_token_type(name: &str) -> Option<ZenTokenType> {
+    match name {
+        "Net" => Some(ZenTokenType::Net),
+        "interface" => Some(ZenTokenType::Interface),
+        "Module" => Some(ZenTokenType::Module),
+        "Component" => Some(ZenTokenType::Component),
+        _ => None,
+    }
+}
+
+/// A reference to (or declaration of) a name that resolves at global scope.
+#[derive(Debug, PartialEq)]
+struct GlobalName {
+    span: Span,
+    name: String,
+    declaration: bool,
+}
+
+impl LspEvalContext {
+    /// Compute the semantic tokens for the current contents of `path`.
+    pub(super) fn semantic_tokens(&self, path: &Path) -> SemanticTokens {
+        let Some(ast) = self
+            .read(path)
+            .and_then(|text| quickfix::parse(path, &text))
+        else {
+            return SemanticTokens::default();
+        };
+
+        let types: HashMap<String, ZenTokenType> = self
+            .inner
+            .get_symbols_for_file(path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(name, info)| Some((name, token_type_for(&info.type_name)?)))
+            .collect();
+
+        SemanticTokens {
+            result_id: None,
+            data: encode(ast.codemap(), &global_names(&ast), |name| {
+                match types.get(name) {
+                    Some(ty) => Some((*ty, 0)),
+                    None => builtin_token_type(name).map(|ty| (ty, MODIFIER_DEFAULT_LIBRARY)),
+                }
+            }),
+        }
+    }
+}
+
+/// Delta-encode the classified names as LSP semantic tokens.
+fn encode(
+    codemap: &CodeMap,
+    names: &[GlobalName],
+    classify: impl Fn(&str) -> Option<(ZenTokenType, u32)>,
+) -> Vec<SemanticToken> {
+    let mut tokens: Vec<(u32, u32, u32, u32, u32)> = names
+        .iter()
+        .filter_map(|global| {
+            let (ty, mut modifiers) = classify(&global.name)?;
+            if global.declaration {
+                modifiers |= MODIFIER_DECLARATION;
+            }
+            let begin = codemap.resolve_span(global.span).begin;
+            // `load()` locals given as `"name"` have a span covering the quotes.
+            let skip = codemap
+                .source_span(global.span)
+                .find(&global.name)
+                .unwrap_or(0);
+            Some((
+                begin.line as u32,
+                (begin.column + skip) as u32,
+                global.name.chars().count() as u32,
+                ty as u32,
+                modifiers,
+            ))
+        })
+        .collect();
+    tokens.sort();
+    tokens.dedup_by_key(|t| (t.0, t.1));
+
+    let mut data = Vec::with_capacity(tokens.len());
+    let (mut prev_line, mut prev_start) = (0, 0);
+    for (line, start, length, token_type, modifiers) in tokens {
+        let delta_line = line - prev_line;
+        data.push(SemanticToken {
+            delta_line,
+            delta_start: if delta_line == 0 {
+                start - prev_start
+            } else {
+                start
+            },
+            length,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        });
+        prev_line = line;
+        prev_start = start;
+    }
+    data
+}
+
+/// Collect identifiers that refer to module-level bindings, skipping names shadowed
+/// by function parameters or locals.
+fn global_names(ast: &AstModule) -> Vec<GlobalName> {
+    fn walk(node: Visit<AstNoPayload>, locals: &HashSet<String>, out: &mut Vec<GlobalName>) {
+        match node {
+            Visit::Expr(Spanned {
+                node: ExprP::Identifier(ident),
+                span,
+            }) => {
+                if !locals.contains(&ident.node.ident) {
+                    out.push(GlobalName {
+                        span: *span,
+                        name: ident.node.ident.clone(),
+                        declaration: false,
+                    });
+                }
+            }
+            Visit::Stmt(Spanned {
+                node: StmtP::Load(load),
+                ..
+            }) => {
+                for arg in &load.args {
+                    out.push(GlobalName {
+                        span: arg.local.span,
+                        name: arg.local.node.ident.clone(),
+                        declaration: true,
+                    });
+                }
+            }
+            Visit::Stmt(Spanned {
+                node: StmtP::Assign(assign),
+                ..
+            }) if locals.is_empty() => {
+                assign.lhs.visit_lvalue(|ident| {
+                    out.push(GlobalName {
+                        span: ident.span,
+                        name: ident.node.ident.clone(),
+                        declaration: true,
+                    })
+                });
+                walk(Visit::Expr(&assign.rhs), locals, out);
+            }
+            Visit::Stmt(Spanned {
+                node: StmtP::Def(def),
+                ..
+            }) => {
+                let mut inner = locals.clone();
+                inner.extend(def_locals(def));
+                walk(Visit::Stmt(&def.body), &inner, out);
+            }
+            v => v.visit_children(|child| walk(child, locals, out)),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(Visit::Stmt(ast.statement()), &HashSet::new(), &mut out);
+    out
+}
+
+/// Names bound inside a `def`: its parameters plus anything assigned in its body.
+fn def_locals(def: &DefP<AstNoPayload>) -> HashSet<String> {
+    fn collect(stmt: &starlark::syntax::ast::AstStmt, names: &mut HashSet<String>) {
+        match &stmt.node {
+            StmtP::Assign(assign) => assign.lhs.visit_lvalue(|ident| {
+                names.insert(ident.node.ident.clone());
+            }),
+            StmtP::AssignModify(lhs, _, _) => lhs.visit_lvalue(|ident| {
+                names.insert(ident.node.ident.clone());
+            }),
+            StmtP::For(for_) => for_.var.visit_lvalue(|ident| {
+                names.insert(ident.node.ident.clone());
+            }),
+            StmtP::Def(nested) => {
+                // A nested def binds its name here; its own locals are separate.
+                names.insert(nested.name.node.ident.clone());
+                return;
+            }
+            _ => {}
+        }
+        stmt.visit_stmt(|child| collect(child, names));
+    }
+
+    let mut names: HashSet<String> = def
+        .params
+        .iter()
+        .filter_map(|param| param.split().0.map(|ident| ident.node.ident.clone()))
+        .collect();
+    collect(&def.body, &mut names);
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(src: &str) -> Vec<(String, bool)> {
+        let ast = quickfix::parse(Path::new("test.zen"), src).unwrap();
+        global_names(&ast)
+            .into_iter()
+            .map(|g| (g.name, g.declaration))
+            .collect()
+    }
+
+    #[test]
+    fn skips_parameters_and_locals() {
+        let found =
+            names("VCC = Net(\"VCC\")\ndef f(VCC):\n    GND = VCC\n    return GND\nf(VCC)\n");
+        assert_eq!(
+            found,
+            vec![
+                ("VCC".to_string(), true),
+                ("Net".to_string(), false),
+                ("f".to_string(), false),
+                ("VCC".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_relative_positions() {
+        let src = "load(\"./r.zen\", \"R\")\nVCC = Net(\"VCC\")\nR(p = VCC)\n";
+        let ast = quickfix::parse(Path::new("test.zen"), src).unwrap();
+        let tokens = encode(ast.codemap(), &global_names(&ast), |name| match name {
+            "VCC" => Some((ZenTokenType::Net, 0)),
+            "R" => Some((ZenTokenType::Module, 0)),
+            _ => builtin_token_type(name).map(|ty| (ty, MODIFIER_DEFAULT_LIBRARY)),
+        });
+        let flat: Vec<(u32, u32, u32, u32, u32)> = tokens
+            .iter()
+            .map(|t| {
+                (
+                    t.delta_line,
+                    t.delta_start,
+                    t.length,
+                    t.token_type,
+                    t.token_modifiers_bitset,
+                )
+            })
+            .collect();
+        assert_eq!(
+            flat,
+            vec![
+                (0, 17, 1, 2, MODIFIER_DECLARATION),
+                (1, 0, 3, 0, MODIFIER_DECLARATION),
+                (0, 6, 3, 0, MODIFIER_DEFAULT_LIBRARY),
+                (1, 0, 1, 2, 0),
+                (0, 6, 3, 0, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! Live schematic state for the viewer.
+//!
+//! Once the viewer has asked for the state of a file (`viewer/getState`), the file is
+//! watched: after every successful re-evaluation of it the server pushes a
+//! `viewer/didChangeState` notification, built from that evaluation. Editing a module
+//! the file instantiates re-evaluates the file too, through the scheduler's propagation
+//! to dependents, and unsaved edits are seen through the editor's contents. The first
+//! notification for a file carries the whole schematic; later ones carry a
+//! [`SchematicDiff`] against the previously sent state. `viewer/resolveInstance` maps an instance back to the call that
+//! created it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use lsp_server::Notification;
+use lsp_types::{Location, Range, Url};
+use pcb_sch::{InstanceRef, Schematic};
+use pcb_starlark_lsp::server::LspUrl;
+use pcb_zen_core::config::find_workspace_root;
+use pcb_zen_core::convert::ToSchematic;
+use pcb_zen_core::EvalOutput;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use starlark::syntax::ast::{ArgumentP, AstLiteral, ExprP};
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use super::quickfix;
+use super::LspEvalContext;
+
+/// The last schematic sent to the viewer for each watched file.
+///
+/// Shared between the main and background contexts, so that files watched through a
+/// request handled on the main thread are updated by evaluations on the worker.
+pub(super) type ViewerStates = Arc<Mutex<HashMap<PathBuf, Arc<Schematic>>>>;
+
+/// Server→client notification sent when the schematic of a watched file changes.
+pub(super) struct ViewerDidChangeState;
+impl lsp_types::notification::Notification for ViewerDidChangeState {
+    type Params = ViewerDidChangeStateParams;
+    const METHOD: &'static str = "viewer/didChangeState";
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ViewerDidChangeStateParams {
+    uri: LspUrl,
+    /// The full schematic, sent the first time a file's state is pushed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<JsonValue>,
+    /// The changes since the previous notification for this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<SchematicDiff>,
+}
+
+/// Instances and nets that changed between two schematics, keyed by their serialized
+/// reference and name respectively. Added and modified entries carry their new value.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct SchematicDiff {
+    instances: BTreeMap<String, JsonValue>,
+    removed_instances: Vec<String>,
+    nets: BTreeMap<String, JsonValue>,
+    removed_nets: Vec<String>,
+}
+
+impl SchematicDiff {
+    pub(super) fn between(old: &Schematic, new: &Schematic) -> Self {
+        let (instances, removed_instances) = diff_maps(
+            &serialize_map(old.instances.iter().map(|(r, i)| (r.to_string(), i))),
+            serialize_map(new.instances.iter().map(|(r, i)| (r.to_string(), i))),
+        );
+        let (nets, removed_nets) = diff_maps(
+            &serialize_map(old.nets.iter().map(|(n, net)| (n.clone(), net))),
+            serialize_map(new.nets.iter().map(|(n, net)| (n.clone(), net))),
+        );
+        Self {
+            instances,
+            removed_instances,
+            nets,
+            removed_nets,
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+            && self.removed_instances.is_empty()
+            && self.nets.is_empty()
+            && self.removed_nets.is_empty()
+    }
+}
+
+fn serialize_map<'a, T: Serialize + 'a>(
+    entries: impl Iterator<Item = (String, &'a T)>,
+) -> BTreeMap<String, JsonValue> {
+    entries
+        .filter_map(|(key, value)| Some((key, serde_json::to_value(value).ok()?)))
+        .collect()
+}
+
+/// The entries of `new` that are not in `old` or differ, and the keys only in `old`.
+fn diff_maps(
+    old: &BTreeMap<String, JsonValue>,
+    new: BTreeMap<String, JsonValue>,
+) -> (BTreeMap<String, JsonValue>, Vec<String>) {
+    let removed = old
+        .keys()
+        .filter(|key| !new.contains_key(*key))
+        .cloned()
+        .collect();
+    let changed = new
+        .into_iter()
+        .filter(|(key, value)| old.get(key) != Some(value))
+        .collect();
+    (changed, removed)
+}
+
+impl LspEvalContext {
+    /// Evaluate `path` into a schematic, preferring the editor's contents of the file.
+    fn viewer_schematic(&self, path: &Path) -> Option<Schematic> {
+        let output = self.evaluate(path, self.read(path)?).output?;
+        output.sch_module.to_schematic().ok()
+    }
+
+    /// Evaluate `path` for `viewer/getState`, and watch it for changes from now on.
+    pub(super) fn viewer_state(&self, path: &Path) -> Option<JsonValue> {
+        let schematic = self.viewer_schematic(path)?;
+        let state = serde_json::to_value(&schematic).ok();
+        self.viewer
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), Arc::new(schematic));
+        state
+    }
+
+    /// Keep the schematic from an evaluation of `path` if the file is watched, for
+    /// `did_evaluate` to push once the evaluation turns out to be free of errors.
+    pub(super) fn record_evaluation(&self, path: &Path, output: &EvalOutput) {
+        if !self.viewer.lock().unwrap().contains_key(path) {
+            return;
+        }
+        if let Ok(schematic) = output.sch_module.to_schematic() {
+            self.evaluated
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), schematic);
+        }
+    }
+
+    /// The notification for the new `schematic` of the watched file `path`, if it changed.
+    pub(super) fn viewer_update(&self, path: &Path, schematic: Schematic) -> Option<Notification> {
+        let schematic = Arc::new(schematic);
+        let previous = self
+            .viewer
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), schematic.clone());
+
+        let params = match previous {
+            Some(previous) => {
+                let diff = SchematicDiff::between(&previous, &schematic);
+                if diff.is_empty() {
+                    return None;
+                }
+                ViewerDidChangeStateParams {
+                    uri: LspUrl::File(path.to_path_buf()),
+                    state: None,
+                    diff: Some(diff),
+                }
+            }
+            None => ViewerDidChangeStateParams {
+                uri: LspUrl::File(path.to_path_buf()),
+                state: serde_json::to_value(schematic.as_ref()).ok(),
+                diff: None,
+            },
+        };
+        Some(Notification::new(
+            <ViewerDidChangeState as lsp_types::notification::Notification>::METHOD.to_string(),
+            params,
+        ))
+    }
+
+    /// Find the call that created `instance` in the schematic of `path`.
+    ///
+    /// Instances created inside files outside the workspace (e.g. the stdlib) resolve to
+    /// the closest enclosing instance created inside it, so that clicking a resistor jumps
+    /// to the `Resistor(...)` call rather than into the stdlib.
+    pub(super) fn resolve_instance(&self, path: &Path, instance: &str) -> Option<Location> {
+        let cached = self.viewer.lock().unwrap().get(path).cloned();
+        let schematic = match cached {
+            Some(schematic) => schematic,
+            None => Arc::new(self.viewer_schematic(path)?),
+        };
+        let instance_ref = schematic
+            .instances
+            .keys()
+            .find(|r| r.to_string() == instance)?
+            .clone();
+        let workspace_root = find_workspace_root(self.file_provider.as_ref(), path);
+
+        let mut fallback = None;
+        for (file, name) in call_sites(&schematic, &instance_ref) {
+            let Some(range) = self.find_named_call(&file, &name) else {
+                continue;
+            };
+            let location = Location {
+                uri: Url::from_file_path(&file).ok()?,
+                range,
+            };
+            if file.starts_with(&workspace_root) {
+                return Some(location);
+            }
+            fallback.get_or_insert(location);
+        }
+        fallback
+    }
+
+    /// The range of the call in `file` passing `name = "<name>"`.
+    fn find_named_call(&self, file: &Path, name: &str) -> Option<Range> {
+        let ast = quickfix::parse(file, &self.read(file)?)?;
+        named_call(&ast, name)
+    }
+}
+
+/// The file and name of the call creating `instance` and each of its ancestors, innermost
+/// first. An instance is created while evaluating its parent module's file.
+fn call_sites(schematic: &Schematic, instance: &InstanceRef) -> Vec<(PathBuf, String)> {
+    let mut sites = Vec::new();
+    let mut path = instance.instance_path.clone();
+    while let Some(name) = path.pop() {
+        let parent = InstanceRef::new(instance.module.clone(), path.clone());
+        if let Some(parent) = schematic.instances.get(&parent) {
+            sites.push((parent.type_ref.source_path.clone(), name));
+        }
+    }
+    sites
+}
+
+fn named_call(ast: &starlark::syntax::AstModule, name: &str) -> Option<Range> {
+    quickfix::all_exprs(ast).into_iter().find_map(|expr| {
+        let ExprP::Call(_, args) = &expr.node else {
+            return None;
+        };
+        let names_it = args.args.iter().any(|arg| match &arg.node {
+            ArgumentP::Named(keyword, value) if keyword.node == "name" => matches!(
+                &value.node,
+                ExprP::Literal(AstLiteral::String(s)) if s.node == name
+            ),
+            _ => false,
+        });
+        names_it.then(|| quickfix::to_range(ast.codemap().resolve_span(expr.span)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{Instance, ModuleRef};
+
+    fn schematic(components: &[(&str, &str)]) -> Schematic {
+        let root = ModuleRef::new("/ws/board.zen", "<root>");
+        let mut schematic = Schematic::new();
+        schematic.instances.insert(
+            InstanceRef::new(root.clone(), vec![]),
+            Instance::module(root.clone()),
+        );
+        for (name, mpn) in components {
+            let mut instance = Instance::component(ModuleRef::new("/ws/board.zen", *name));
+            instance.add_attribute("mpn", mpn.to_string());
+            schematic.instances.insert(
+                InstanceRef::new(root.clone(), vec![name.to_string()]),
+                instance,
+            );
+        }
+        schematic
+    }
+
+    #[test]
+    fn diffs_added_changed_and_removed_instances() {
+        let old = schematic(&[("R1", "RC0402"), ("R2", "RC0603")]);
+        let new = schematic(&[("R1", "RC0402"), ("R2", "RC0805"), ("C1", "GRM155")]);
+        let diff = SchematicDiff::between(&old, &new);
+        assert_eq!(
+            diff.instances.keys().collect::<Vec<_>>(),
+            vec!["/ws/board.zen:<root>.C1", "/ws/board.zen:<root>.R2"]
+        );
+        assert!(diff.removed_instances.is_empty());
+
+        let diff = SchematicDiff::between(&new, &old);
+        assert_eq!(diff.removed_instances, vec!["/ws/board.zen:<root>.C1"]);
+        assert!(SchematicDiff::between(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn finds_calls_by_name() {
+        let ast = quickfix::parse(
+            Path::new("board.zen"),
+            "R = Module(\"r.zen\")\nR(name = \"R1\")\nR(\n    name = \"R2\",\n)\n",
+        )
+        .unwrap();
+        assert_eq!(
+            named_call(&ast, "R2"),
+            Some(Range::new(
+                lsp_types::Position::new(2, 0),
+                lsp_types::Position::new(4, 1)
+            ))
+        );
+        assert_eq!(named_call(&ast, "R3"), None);
+    }
+
+    #[test]
+    fn call_sites_walk_up_the_hierarchy() {
+        let root = ModuleRef::new("/ws/board.zen", "<root>");
+        let mut schematic = schematic(&[]);
+        let child = ModuleRef::new("/stdlib/Resistor.zen", "Resistor");
+        schematic.instances.insert(
+            InstanceRef::new(root.clone(), vec!["R1".to_string()]),
+            Instance::module(child.clone()),
+        );
+        let part = InstanceRef::new(root, vec!["R1".to_string(), "R".to_string()]);
+        schematic
+            .instances
+            .insert(part.clone(), Instance::component(child));
+        assert_eq!(
+            call_sites(&schematic, &part),
+            vec![
+                (PathBuf::from("/stdlib/Resistor.zen"), "R".to_string()),
+                (PathBuf::from("/ws/board.zen"), "R1".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! Headless JSON-RPC evaluation server behind `pcb serve`.
+//!
+//! Requests and responses are JSON-RPC 2.0 messages, one per line, read from stdin or
+//! from the connections of a Unix socket. The server keeps one warm [`EvalContext`] per
+//! workspace and evaluation mode, so the modules loaded by earlier requests and the
+//! on-disk cache of remote packages are reused until a file they were read from changes.
+//!
+//! Every method but `shutdown` takes `{"path": ..., "inputs": {...}}`, where `inputs` are
+//! optional values for the module's `io()`/`config()` parameters:
+//!
+//! * `evaluate` – success flag, parameters, schematic and diagnostics of the module.
+//! * `schematic` – the module's schematic.
+//! * `bom` – its bill of materials; pass `"grouped": true` to aggregate equal parts.
+//! * `test` – the results of its test benches.
+//! * `diagnostics` – the diagnostics of evaluating it.
+//! * `shutdown` – stop serving.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use pcb_sch::{generate_bom_entries, group_bom_entries, Schematic};
+use pcb_zen_core::config::find_workspace_root;
+use pcb_zen_core::convert::ToSchematic;
+use pcb_zen_core::lang::error::BenchTestResult;
+use pcb_zen_core::lang::type_info::ParameterInfo;
+use pcb_zen_core::{DefaultFileProvider, EvalContext, EvalOutput, InputMap};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use starlark::errors::EvalMessage;
+
+use crate::cache::DiskCache;
+use crate::lsp::{diagnostic_to_info, json_to_input_value, DiagnosticInfo};
+use crate::{create_eval_context, Diagnostics, EvalMode, WithDiagnostics};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The module could not be evaluated; `data` holds its diagnostics.
+const EVALUATION_FAILED: i64 = -32000;
+
+/// Serve requests read line by line from stdin, answering on stdout.
+pub fn stdio(offline: bool) -> anyhow::Result<()> {
+    serve(std::io::stdin().lock(), std::io::stdout(), offline)
+}
+
+/// Serve requests from the connections of a Unix socket bound at `path`, one connection
+/// at a time, until a client sends `shutdown`.
+#[cfg(unix)]
+pub fn unix_socket(path: &Path, offline: bool) -> anyhow::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    // Replace a socket left behind by a previous server, but never any other file.
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    log::info!("Listening on {}", path.display());
+
+    let mut server = Server::new(offline);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = std::io::BufReader::new(stream.try_clone()?);
+        match server.serve(reader, stream) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => log::warn!("Connection closed: {e}"),
+        }
+    }
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
+
+/// Serve requests read line by line from `input` until it ends or a client sends
+/// `shutdown`, writing one response line to `output` per request.
+pub fn serve(input: impl BufRead, output: impl Write, offline: bool) -> anyhow::Result<()> {
+    Server::new(offline).serve(input, output)?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// Absent for notifications, which get no response.
+    #[serde(default)]
+    id: Option<JsonValue>,
+    method: String,
+    #[serde(default)]
+    params: JsonValue,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    fn new(id: JsonValue, result: Result<JsonValue, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<JsonValue>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn evaluation_failed(path: &Path, diagnostics: &Diagnostics) -> Self {
+        Self {
+            code: EVALUATION_FAILED,
+            message: format!("failed to evaluate {}", path.display()),
+            data: serde_json::to_value(diagnostic_infos(diagnostics)).ok(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModuleParams {
+    path: PathBuf,
+    #[serde(default)]
+    inputs: HashMap<String, JsonValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BomParams {
+    #[serde(flatten)]
+    module: ModuleParams,
+    #[serde(default)]
+    grouped: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct EvaluateResponse {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameters: Option<Vec<ParameterInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schematic: Option<Schematic>,
+    diagnostics: Vec<DiagnosticInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct TestResponse {
+    results: Vec<BenchTestResult>,
+    diagnostics: Vec<DiagnosticInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnosticsResponse {
+    success: bool,
+    diagnostics: Vec<DiagnosticInfo>,
+}
+
+/// The warm evaluation state of one workspace in one [`EvalMode`].
+struct Workspace {
+    ctx: EvalContext,
+    /// Modification times of the files cached in `ctx`, as of when they were loaded.
+    mtimes: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Workspace {
+    fn new(root: &Path, offline: bool) -> Self {
+        Self {
+            ctx: create_eval_context(root, offline)
+                .set_cache(Arc::new(DiskCache::for_workspace(root))),
+            mtimes: HashMap::new(),
+        }
+    }
+
+    /// Drop cached modules whose file changed on disk since it was loaded.
+    fn refresh(&mut self) {
+        let changed: Vec<PathBuf> = self
+            .mtimes
+            .iter()
+            .filter(|(path, mtime)| modified(path) != **mtime)
+            .map(|(path, _)| path.clone())
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        log::debug!("Reloading {} changed file(s)", changed.len());
+        for path in &changed {
+            self.mtimes.remove(path);
+        }
+        self.ctx.invalidate_loads(&changed);
+    }
+
+    /// Remember the modification time of every newly cached module.
+    fn record(&mut self) {
+        let loaded: HashSet<PathBuf> = self.ctx.loaded_files().into_iter().collect();
+        self.mtimes.retain(|path, _| loaded.contains(path));
+        for path in loaded {
+            self.mtimes
+                .entry(path)
+                .or_insert_with_key(|path| modified(path));
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+struct Server {
+    offline: bool,
+    workspaces: HashMap<(PathBuf, EvalMode), Workspace>,
+}
+
+impl Server {
+    fn new(offline: bool) -> Self {
+        Self {
+            offline,
+            workspaces: HashMap::new(),
+        }
+    }
+
+    /// Answer the requests in `input`. Returns whether a client asked to shut down.
+    fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> std::io::Result<bool> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request: Request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    let error = RpcError::new(PARSE_ERROR, e.to_string());
+                    write_response(&mut output, Response::new(JsonValue::Null, Err(error)))?;
+                    continue;
+                }
+            };
+
+            let result = self.handle(&request.method, request.params);
+            if let Some(id) = request.id {
+                write_response(&mut output, Response::new(id, result))?;
+            }
+            if request.method == "shutdown" {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle(&mut self, method: &str, params: JsonValue) -> Result<JsonValue, RpcError> {
+        match method {
+            "evaluate" => self.evaluate(parse_params(params)?),
+            "schematic" => self.schematic(parse_params(params)?),
+            "bom" => self.bom(parse_params(params)?),
+            "test" => self.test(parse_params(params)?),
+            "diagnostics" => self.diagnostics(parse_params(params)?),
+            "shutdown" => Ok(JsonValue::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{method}`"),
+            )),
+        }
+    }
+
+    fn evaluate(&mut self, params: ModuleParams) -> Result<JsonValue, RpcError> {
+        let (_, result) = self.eval(&params, EvalMode::Build)?;
+        let parameters = result
+            .output
+            .as_ref()
+            .map(|output| output.signature.clone());
+        let schematic = result
+            .output
+            .as_ref()
+            .and_then(|output| output.sch_module.to_schematic().ok());
+        to_json(EvaluateResponse {
+            success: result.is_success(),
+            parameters,
+            schematic,
+            diagnostics: diagnostic_infos(&result.diagnostics),
+        })
+    }
+
+    fn schematic(&mut self, params: ModuleParams) -> Result<JsonValue, RpcError> {
+        to_json(self.build_schematic(&params)?)
+    }
+
+    fn bom(&mut self, params: BomParams) -> Result<JsonValue, RpcError> {
+        let schematic = self.build_schematic(&params.module)?;
+        let entries = generate_bom_entries(&schematic);
+        if params.grouped {
+            to_json(group_bom_entries(entries))
+        } else {
+            to_json(entries)
+        }
+    }
+
+    fn test(&mut self, params: ModuleParams) -> Result<JsonValue, RpcError> {
+        let (_, result) = self.eval(&params, EvalMode::Test)?;
+        let (results, diagnostics): (Vec<_>, Vec<_>) = result
+            .diagnostics
+            .iter()
+            .partition(|diag| diag.downcast_error_ref::<BenchTestResult>().is_some());
+        to_json(TestResponse {
+            results: results
+                .into_iter()
+                .filter_map(|diag| diag.downcast_error_ref::<BenchTestResult>())
+                .cloned()
+                .collect(),
+            diagnostics: diagnostics.into_iter().map(diagnostic_to_info).collect(),
+        })
+    }
+
+    fn diagnostics(&mut self, params: ModuleParams) -> Result<JsonValue, RpcError> {
+        let (_, result) = self.eval(&params, EvalMode::Build)?;
+        to_json(DiagnosticsResponse {
+            success: result.is_success(),
+            diagnostics: diagnostic_infos(&result.diagnostics),
+        })
+    }
+
+    /// Evaluate the module of `params` and convert it to a schematic, failing with its
+    /// diagnostics if either step has errors.
+    fn build_schematic(&mut self, params: &ModuleParams) -> Result<Schematic, RpcError> {
+        let (path, result) = self.eval(params, EvalMode::Build)?;
+        let result = result.try_map(|output| {
+            output
+                .sch_module
+                .to_schematic()
+                .map_err(|e| EvalMessage::from_error(&path, &e.into()))
+        });
+        match result.output {
+            Some(schematic) if !result.diagnostics.has_errors() => Ok(schematic),
+            _ => Err(RpcError::evaluation_failed(&path, &result.diagnostics)),
+        }
+    }
+
+    /// Evaluate the module of `params` as a root module in the warm context of its
+    /// workspace, reloading whatever changed on disk since the previous request.
+    fn eval(
+        &mut self,
+        params: &ModuleParams,
+        mode: EvalMode,
+    ) -> Result<(PathBuf, WithDiagnostics<EvalOutput>), RpcError> {
+        let path = params.path.canonicalize().map_err(|e| {
+            RpcError::new(INVALID_PARAMS, format!("{}: {e}", params.path.display()))
+        })?;
+        let mut inputs = InputMap::new();
+        for (name, value) in &params.inputs {
+            let value = json_to_input_value(value).ok_or_else(|| {
+                RpcError::new(INVALID_PARAMS, format!("invalid input type for '{name}'"))
+            })?;
+            inputs.insert(name.clone(), value);
+        }
+
+        let root = find_workspace_root(&DefaultFileProvider, &path);
+        let offline = self.offline;
+        let workspace = self
+            .workspaces
+            .entry((root.clone(), mode))
+            .or_insert_with(|| Workspace::new(&root, offline));
+        workspace.refresh();
+        let result = workspace
+            .ctx
+            .child_context()
+            .set_source_path(path.clone())
+            .set_module_name("<root>")
+            .set_inputs(inputs)
+            .set_eval_mode(mode)
+            .eval();
+        workspace.record();
+        Ok((path, result))
+    }
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: JsonValue) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_json(value: impl Serialize) -> Result<JsonValue, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(EVALUATION_FAILED, e.to_string()))
+}
+
+fn diagnostic_infos(diagnostics: &Diagnostics) -> Vec<DiagnosticInfo> {
+    diagnostics.iter().map(diagnostic_to_info).collect()
+}
+
+fn write_response(output: &mut impl Write, response: Response) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *output, &response)?;
+    output.write_all(b"\n")?;
+    output.flush()
+}
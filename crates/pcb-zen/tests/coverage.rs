@@ -0,0 +1,112 @@
+mod common;
+use common::TestProject;
+
+use std::sync::Arc;
+
+use pcb_zen::{Coverage, EvalMode};
+
+const MODULE_ZEN: &str = r#"
+Mode = enum("A", "B", "C")
+
+mode = config("mode", Mode)
+
+Sub = Module("sub.zen")
+
+if mode == Mode("A"):
+    label = "a"
+else:
+    Sub(name = "S")
+"#;
+
+const SUB_ZEN: &str = r#"
+x = 1
+"#;
+
+const BENCH_ZEN: &str = r#"
+M = Module("module.zen")
+
+def ok(module, inputs):
+    check(True, "unreachable")
+
+TestBench(
+    name = "Modes",
+    module = M,
+    test_cases = {"a": {"mode": "A"}},
+    checks = [ok],
+)
+"#;
+
+/// 1-based line of the first line of `source` containing `needle`
+fn line_of(source: &str, needle: &str) -> u32 {
+    source
+        .lines()
+        .position(|line| line.contains(needle))
+        .map(|index| index as u32 + 1)
+        .unwrap_or_else(|| panic!("{needle} not found"))
+}
+
+fn run(env: &TestProject) -> Arc<Coverage> {
+    env.add_file("module.zen", MODULE_ZEN);
+    env.add_file("sub.zen", SUB_ZEN);
+    let bench = env.add_file("bench.zen", BENCH_ZEN);
+
+    let coverage = Arc::new(Coverage::new());
+    let (_, diagnostics) =
+        pcb_zen::run_with_coverage(&bench, true, EvalMode::Test, coverage.clone()).unpack();
+    assert!(!diagnostics.has_errors(), "{diagnostics:?}");
+    coverage
+}
+
+#[test]
+fn records_lines_instances_and_config_values() {
+    let env = TestProject::new();
+    let coverage = run(&env);
+    let files = coverage.files();
+
+    let module_path = env.root().join("module.zen");
+    let module = &files[module_path.to_str().unwrap()];
+    assert!(module.is_module);
+    assert_eq!(module.instances, 1);
+    assert!(module.lines[&line_of(MODULE_ZEN, "label = \"a\"")] > 0);
+    assert_eq!(module.lines[&line_of(MODULE_ZEN, "Sub(name")], 0);
+
+    let mode = &module.params["mode"];
+    assert_eq!(mode.line, line_of(MODULE_ZEN, "config(\"mode\""));
+    assert_eq!(mode.taken.keys().collect::<Vec<_>>(), ["A"]);
+    assert_eq!(mode.missed(), ["B", "C"]);
+
+    // Referenced with Module() but never instantiated: introspection does not count
+    let sub = &files[env.root().join("sub.zen").to_str().unwrap()];
+    assert!(sub.is_module);
+    assert_eq!(sub.instances, 0);
+    assert_eq!(sub.lines_hit(), 0);
+    assert_eq!(sub.lines.len(), 1);
+}
+
+#[test]
+fn lcov_reports_lines_modules_and_enum_branches() {
+    let env = TestProject::new();
+    let lcov = run(&env).to_lcov();
+
+    let module_path = env.root().join("module.zen");
+    let record = lcov
+        .split("end_of_record\n")
+        .find(|record| record.contains(&format!("SF:{}\n", module_path.display())))
+        .expect("module.zen should have a record");
+
+    let config_line = line_of(MODULE_ZEN, "config(\"mode\"");
+    assert!(record.contains("FNDA:1,module\n"), "{record}");
+    assert!(
+        record.contains(&format!("BRDA:{config_line},0,0,1\n")),
+        "{record}"
+    );
+    assert!(
+        record.contains(&format!("BRDA:{config_line},0,1,0\n")),
+        "{record}"
+    );
+    assert!(record.contains("BRF:3\nBRH:1\n"), "{record}");
+    let sub_line = line_of(MODULE_ZEN, "Sub(name");
+    assert!(record.contains(&format!("DA:{sub_line},0\n")), "{record}");
+
+    assert!(lcov.contains("FNDA:0,sub\n"), "{lcov}");
+}
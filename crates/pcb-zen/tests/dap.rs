@@ -0,0 +1,215 @@
+mod common;
+use common::TestProject;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use serde_json::{json, Value};
+
+const TOP_ZEN: &str = r#"
+Sub = Module("sub.zen")
+
+Sub(name = "S1", P1 = Net("VCC"))
+"#;
+
+const SUB_ZEN: &str = r#"
+P1 = io("P1", Net)
+gnd = Net("GND")
+
+Component(
+    name = "R1",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": P1, "2": gnd},
+)
+"#;
+
+/// Reads the bytes the test sends to the adapter.
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.buffer.is_empty() {
+            match self.receiver.recv() {
+                Ok(bytes) => self.buffer = bytes,
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.buffer.len());
+        buf[..n].copy_from_slice(&self.buffer[..n]);
+        self.buffer.drain(..n);
+        Ok(n)
+    }
+}
+
+/// Forwards the bytes the adapter writes to the test.
+struct ChannelWriter(Sender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let _ = self.0.send(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A DAP client talking to an in-process adapter.
+struct Session {
+    to_adapter: Sender<Vec<u8>>,
+    from_adapter: BufReader<ChannelReader>,
+    seq: i64,
+}
+
+impl Session {
+    fn start() -> Self {
+        let (to_adapter, adapter_input) = channel();
+        let (adapter_output, from_adapter) = channel();
+        std::thread::spawn(move || {
+            let input = BufReader::new(ChannelReader {
+                receiver: adapter_input,
+                buffer: Vec::new(),
+            });
+            pcb_zen::dap::serve(input, Box::new(ChannelWriter(adapter_output))).unwrap();
+        });
+        Self {
+            to_adapter,
+            from_adapter: BufReader::new(ChannelReader {
+                receiver: from_adapter,
+                buffer: Vec::new(),
+            }),
+            seq: 0,
+        }
+    }
+
+    fn read(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.from_adapter.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.from_adapter.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// Send a request and return its response, skipping any events sent before it.
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let body = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        self.to_adapter
+            .send(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes())
+            .unwrap();
+        loop {
+            let message = self.read();
+            if message["type"] == "response" && message["request_seq"] == self.seq {
+                assert_eq!(message["success"], true, "{command} failed: {message}");
+                return message["body"].clone();
+            }
+        }
+    }
+
+    /// Wait for the event called `name` and return its body.
+    fn event(&mut self, name: &str) -> Value {
+        loop {
+            let message = self.read();
+            if message["type"] == "event" && message["event"] == name {
+                return message["body"].clone();
+            }
+        }
+    }
+}
+
+#[test]
+fn breakpoint_in_loaded_module_shows_module_inputs() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    let top = env.add_file("top.zen", TOP_ZEN);
+    let sub = env.add_file("sub.zen", SUB_ZEN);
+
+    let mut session = Session::start();
+    session.request("initialize", json!({ "adapterID": "zen" }));
+    session.request(
+        "launch",
+        json!({ "program": top.to_string_lossy(), "offline": true }),
+    );
+
+    // Line 5 is the `Component(` call in sub.zen; line 1 is blank.
+    let breakpoints = session.request(
+        "setBreakpoints",
+        json!({
+            "source": { "path": sub.to_string_lossy() },
+            "breakpoints": [{ "line": 5 }, { "line": 1 }],
+        }),
+    );
+    assert_eq!(breakpoints["breakpoints"][0]["verified"], true);
+    assert_eq!(breakpoints["breakpoints"][1]["verified"], false);
+
+    session.request("configurationDone", Value::Null);
+    let stopped = session.event("stopped");
+    assert_eq!(stopped["reason"], "breakpoint");
+
+    let stack = session.request("stackTrace", json!({ "threadId": 0 }));
+    let frames = stack["stackFrames"].as_array().unwrap();
+    assert_eq!(frames[0]["line"], 5);
+    assert!(frames[0]["source"]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("sub.zen"));
+    // The instantiating statement in top.zen is an enclosing frame.
+    assert!(
+        frames
+            .iter()
+            .any(|f| f["line"] == 4 && f["source"]["path"].as_str().unwrap().ends_with("top.zen")),
+        "{frames:?}"
+    );
+
+    let scopes = session.request("scopes", json!({ "frameId": 0 }));
+    let scope_names: Vec<_> = scopes["scopes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(scope_names, ["Locals", "Inputs", "Nets", "Components"]);
+
+    let inputs_ref = scopes["scopes"][1]["variablesReference"].clone();
+    let inputs = session.request("variables", json!({ "variablesReference": inputs_ref }));
+    let inputs = inputs["variables"].as_array().unwrap();
+    assert!(inputs.iter().any(|v| v["name"] == "P1"), "{inputs:?}");
+
+    let nets_ref = scopes["scopes"][2]["variablesReference"].clone();
+    let nets = session.request("variables", json!({ "variablesReference": nets_ref }));
+    assert!(
+        nets["variables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|v| v["name"] == "GND"),
+        "{nets}"
+    );
+
+    session.request("continue", json!({ "threadId": 0 }));
+    let exited = session.event("exited");
+    assert_eq!(exited["exitCode"], 0);
+    session.event("terminated");
+    session.request("disconnect", json!({}));
+}
@@ -0,0 +1,217 @@
+mod common;
+use common::TestProject;
+
+use pcb_sch::kicad_netlist::to_kicad_netlist;
+use pcb_sch::{InstanceKind, Schematic};
+use pcb_zen::{EvalMode, WithDiagnostics};
+use pcb_zen_core::lang::error::BenchTestResult;
+use pcb_zen_core::RequirementViolation;
+
+const TOP_ZEN: &str = r#"
+Sub = Module("sub.zen")
+
+Sub(name = "S1", P1 = Net("P1"))
+"#;
+
+fn netlist(env: &TestProject) -> String {
+    let top = env.root().join("top.zen");
+    let (output, diagnostics) = pcb_zen::run_cached(&top, false, EvalMode::Build)
+        .map(|s| to_kicad_netlist(&s))
+        .unpack();
+    assert!(!diagnostics.has_errors(), "{diagnostics:?}");
+    output.expect("evaluation produced no netlist")
+}
+
+/// The instance paths of the components in `top.zen`, one per line
+fn components(env: &TestProject) -> String {
+    let top = env.root().join("top.zen");
+    let (output, diagnostics) = pcb_zen::run_cached(&top, false, EvalMode::Build).unpack();
+    assert!(!diagnostics.has_errors(), "{diagnostics:?}");
+    let schematic = output.expect("evaluation produced no schematic");
+    let mut paths: Vec<String> = schematic
+        .instances
+        .iter()
+        .filter(|(_, instance)| matches!(instance.kind, InstanceKind::Component))
+        .map(|(reference, _)| reference.instance_path.join("."))
+        .collect();
+    paths.sort();
+    paths.join("\n")
+}
+
+#[test]
+fn cached_evaluation_is_reused_until_a_load_changes() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    env.add_file("top.zen", TOP_ZEN);
+    env.add_file(
+        "sub.zen",
+        r#"
+P1 = io("P1", Net)
+
+Component(
+    name = "R_OLD",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": P1, "2": P1},
+)
+"#,
+    );
+
+    let first = netlist(&env);
+    assert!(env.root().join(".pcb/eval-cache/eval").is_dir());
+    assert_eq!(netlist(&env), first);
+
+    // Editing a loaded module must invalidate the cached result of the root module.
+    env.add_file(
+        "sub.zen",
+        r#"
+P1 = io("P1", Net)
+
+Component(
+    name = "R_NEW",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": P1, "2": P1},
+)
+"#,
+    );
+    insta::assert_snapshot!(components(&env));
+}
+
+#[test]
+fn leaf_modules_are_restored_until_a_file_they_load_changes() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    env.add_file("top.zen", TOP_ZEN);
+    env.add_file("parts.zen", "FOOTPRINT = \"SMD:0805\"\n");
+    env.add_file(
+        "sub.zen",
+        r#"
+load("parts.zen", "FOOTPRINT")
+
+P1 = io("P1", Net)
+
+Component(
+    name = "R1",
+    footprint = FOOTPRINT,
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": P1, "2": P1},
+)
+"#,
+    );
+    let modules = env.root().join(".pcb/eval-cache/modules");
+    let entries = || {
+        let mut entries: Vec<_> = std::fs::read_dir(&modules)
+            .map(|dir| dir.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        entries.sort();
+        entries
+    };
+
+    let first = netlist(&env);
+    let stored = entries();
+    assert_eq!(stored.len(), 1, "the instance of sub.zen should be stored");
+    let written = std::fs::metadata(&stored[0]).unwrap().modified().unwrap();
+
+    // A change to the root module alone re-evaluates it, but restores `S1` unchanged.
+    env.add_file("top.zen", &format!("# edited\n{TOP_ZEN}"));
+    assert_eq!(netlist(&env), first);
+    assert_eq!(entries(), stored);
+    assert_eq!(
+        std::fs::metadata(&stored[0]).unwrap().modified().unwrap(),
+        written,
+        "a restored instance should not be stored again"
+    );
+
+    // Editing a file the module loads evaluates it again.
+    env.add_file("parts.zen", "FOOTPRINT = \"SMD:0603\"\n");
+    let second = netlist(&env);
+    assert!(second.contains("0603"), "{second}");
+    assert!(!second.contains("0805"), "{second}");
+}
+
+#[test]
+fn warnings_are_cached_and_replayed() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    env.add_file(
+        "top.zen",
+        r#"
+VIN = Net("VIN")
+GND = Net("GND")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("VIN", ["1"]), ("GND", ["2"])]),
+    pins = {"VIN": VIN, "GND": GND},
+    requirements = [{"pin": "VIN", "rule": "decoupling", "min": "1uF"}],
+)
+"#,
+    );
+    let top = env.root().join("top.zen");
+    let violations = |result: &WithDiagnostics<Schematic>| {
+        result
+            .diagnostics
+            .iter()
+            .filter_map(|diag| {
+                let violation = diag.downcast_error_ref::<RequirementViolation>()?;
+                Some((diag.body.clone(), diag.span, violation.message.clone()))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first = pcb_zen::run_cached(&top, false, EvalMode::Build);
+    assert_eq!(violations(&first).len(), 1, "{:?}", first.diagnostics);
+    let entries = std::fs::read_dir(env.root().join(".pcb/eval-cache/eval"))
+        .map(|dir| dir.count())
+        .unwrap_or(0);
+    assert_eq!(entries, 1, "a result with warnings should be stored");
+
+    // The replayed warning keeps its structured error, so `-D` can still promote it
+    let second = pcb_zen::run_cached(&top, false, EvalMode::Build);
+    assert_eq!(violations(&second), violations(&first));
+}
+
+#[test]
+fn replayed_test_results_report_no_time() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    env.add_file(
+        "sub.zen",
+        r#"
+P1 = io("P1", Net)
+"#,
+    );
+    env.add_file(
+        "top.zen",
+        r#"
+Sub = Module("sub.zen")
+
+def ok(module, inputs):
+    check(True, "unreachable")
+
+TestBench(
+    name = "Bench",
+    module = Sub,
+    test_cases = {"default": {"P1": Net("P1")}},
+    checks = [ok],
+)
+"#,
+    );
+    let top = env.root().join("top.zen");
+    let durations = |result: &WithDiagnostics<Schematic>| {
+        result
+            .diagnostics
+            .iter()
+            .filter_map(|diag| diag.downcast_error_ref::<BenchTestResult>())
+            .map(|test| test.duration_secs)
+            .collect::<Vec<_>>()
+    };
+
+    let first = pcb_zen::run_cached(&top, false, EvalMode::Test);
+    assert_eq!(durations(&first).len(), 1, "{:?}", first.diagnostics);
+
+    let second = pcb_zen::run_cached(&top, false, EvalMode::Test);
+    assert_eq!(durations(&second), [0.0]);
+}
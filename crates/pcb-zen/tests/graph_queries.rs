@@ -0,0 +1,93 @@
+mod common;
+use common::TestProject;
+
+use pcb_zen::EvalMode;
+use pcb_zen_core::lang::error::BenchTestResult;
+
+/// VIN -(F1)- VBUS -(C1)- GND, with a divider R1/R2 from VBUS through OUT to GND
+const SUPPLY_ZEN: &str = r#"
+VIN = Net("VIN")
+VBUS = Net("VBUS")
+OUT = Net("OUT")
+GND = Net("GND")
+
+def part(name, a, b):
+    Component(
+        name = name,
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+part("F1", VIN, VBUS)
+part("C1", VBUS, GND)
+part("R1", VBUS, OUT)
+part("R2", OUT, GND)
+"#;
+
+const BENCH_ZEN: &str = r#"
+Supply = Module("supply.zen")
+
+def is_capacitor(component):
+    check(component.name.startswith("C"), "not a capacitor")
+
+def is_resistor(component):
+    check(component.name.startswith("R"), "not a resistor")
+
+def names(components):
+    return sorted([c.name for c in components])
+
+def shortest_path(module, inputs):
+    graph = module.graph()
+    path = graph.shortest_path(start = "VBUS", end = "GND")
+    check(names(path.components) == ["C1"], "expected the decoupling capacitor")
+
+    path = graph.shortest_path(start = "VBUS", end = "GND", through = is_resistor)
+    check(names(path.components) == ["R1", "R2"], "expected the divider")
+
+    check(graph.shortest_path(start = "VIN", end = "OUT", through = is_resistor) == None, "fuse is not a resistor")
+
+def reachability(module, inputs):
+    graph = module.graph()
+    check(graph.reachable(start = ("C1", "1"), end = "GND", through = is_capacitor, max_components = 1), "C1 decouples VBUS")
+    check(not graph.reachable(start = "OUT", end = "GND", through = is_capacitor), "OUT only reaches GND through R2")
+    check(not graph.reachable(start = "VBUS", end = "GND", through = is_resistor, max_components = 1), "divider has two resistors")
+
+def subgraph(module, inputs):
+    sub = module.graph().subgraph(start = "VBUS", through = is_resistor)
+    check(names(sub.components) == ["R1", "R2"], "divider components")
+    check(sub.nets == ["GND", "OUT", "VBUS"], "divider nets")
+    check(("R2", "2") in sub.ports, "divider ports")
+
+def cuts(module, inputs):
+    graph = module.graph()
+    check(names(graph.cuts(start = "VIN", end = "GND")) == ["F1"], "only the fuse is on every path")
+    check(names(graph.cuts(start = "VIN", end = "OUT")) == ["F1"], "C1 and R2 bypass R1")
+    check(names(graph.cuts(start = "VIN", end = "OUT", through = is_resistor)) == [], "no path without the fuse")
+
+TestBench(
+    name = "Queries",
+    module = Supply,
+    test_cases = {"default": {}},
+    checks = [shortest_path, reachability, subgraph, cuts],
+)
+"#;
+
+#[test]
+fn graph_queries() {
+    let env = TestProject::new();
+    env.add_file("supply.zen", SUPPLY_ZEN);
+    let bench = env.add_file("bench.zen", BENCH_ZEN);
+
+    let (_, diagnostics) = pcb_zen::run(&bench, true, EvalMode::Test).unpack();
+    let results: Vec<BenchTestResult> = diagnostics
+        .iter()
+        .filter_map(|diag| diag.downcast_error_ref::<BenchTestResult>())
+        .cloned()
+        .collect();
+
+    assert_eq!(results.len(), 4, "{diagnostics:?}");
+    for result in &results {
+        assert!(result.passed, "{}: {:?}", result.check_name, result.message);
+    }
+}
@@ -0,0 +1,335 @@
+mod common;
+use common::TestProject;
+
+use std::path::Path;
+
+use pcb_zen::{Diagnostic, Diagnostics, EvalMode};
+use pcb_zen_core::lang::error::LintViolation;
+use pcb_zen_core::{DiagnosticsPass, PromoteDeniedPass};
+use starlark::errors::EvalSeverity;
+
+const LINTS_ZEN: &str = r#"
+def _resistor_count(module):
+    resistors = [c.name for c in module.components().values() if c.prefix == "R"]
+    if len(resistors) > 1:
+        return "too many resistors: {}".format(", ".join(sorted(resistors)))
+
+TooManyResistors = Lint(name = "too-many-resistors", check = _resistor_count)
+ALL = [TooManyResistors, builtin_lint("floating-net")]
+"#;
+
+/// NC only connects to R1.1
+const SENSOR_ZEN: &str = r#"
+NC = Net("NC")
+GND = Net("GND")
+
+def resistor(name, a, b):
+    Component(
+        name = name,
+        prefix = "R",
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+resistor("R1", NC, GND)
+resistor("R2", GND, GND)
+"#;
+
+const BOARD_ZEN: &str = r#"
+load("lints.zen", "ALL")
+
+LINTS = ALL
+
+Sensor = Module("sensor.zen")
+Sensor(name = "S1")
+"#;
+
+/// Builds `BOARD_ZEN` with the sensor.zen the test wrote
+fn build(env: &TestProject, mode: EvalMode) -> Diagnostics {
+    env.add_file("lints.zen", LINTS_ZEN);
+    let board = env.add_file("board.zen", BOARD_ZEN);
+    let (_, diagnostics) = pcb_zen::run(&board, true, mode).unpack();
+    diagnostics
+}
+
+/// Lint violations reported for `file`, sorted by lint name
+fn violations<'a>(
+    diagnostics: &'a Diagnostics,
+    file: &Path,
+) -> Vec<(&'a Diagnostic, LintViolation)> {
+    let mut violations: Vec<_> = diagnostics
+        .iter()
+        .filter(|diag| Path::new(&diag.path) == file)
+        .filter_map(|diag| {
+            diag.downcast_error_ref::<LintViolation>()
+                .map(|violation| (diag, violation.clone()))
+        })
+        .collect();
+    violations.sort_by(|a, b| a.1.lint.cmp(&b.1.lint));
+    violations
+}
+
+#[test]
+fn lints_run_over_every_module_in_build_mode() {
+    let env = TestProject::new();
+    env.add_file("sensor.zen", SENSOR_ZEN);
+    let diagnostics = build(&env, EvalMode::Build);
+
+    let sensor = violations(&diagnostics, &env.root().join("sensor.zen"));
+    assert!(
+        sensor
+            .iter()
+            .all(|(diag, _)| matches!(diag.severity, EvalSeverity::Warning)),
+        "{diagnostics:?}"
+    );
+    let bodies: Vec<&str> = sensor.iter().map(|(diag, _)| diag.body.as_str()).collect();
+    insta::assert_snapshot!(bodies.join("\n"));
+}
+
+#[test]
+fn lints_do_not_run_in_test_mode() {
+    let env = TestProject::new();
+    env.add_file("sensor.zen", SENSOR_ZEN);
+    let diagnostics = build(&env, EvalMode::Test);
+    assert!(violations(&diagnostics, &env.root().join("sensor.zen")).is_empty());
+}
+
+#[test]
+fn allow_comment_suppresses_lints_in_that_module() {
+    let env = TestProject::new();
+    env.add_file(
+        "sensor.zen",
+        r#"# pcb: allow(floating-net)
+NC = Net("NC")
+GND = Net("GND")
+
+def resistor(name, a, b):
+    Component(
+        name = name,
+        prefix = "R",
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+resistor("R1", NC, GND)
+resistor("R2", GND, GND)
+"#,
+    );
+    let diagnostics = build(&env, EvalMode::Build);
+
+    let sensor = violations(&diagnostics, &env.root().join("sensor.zen"));
+    let lints: Vec<&str> = sensor.iter().map(|(_, v)| v.lint.as_str()).collect();
+    assert_eq!(lints, ["too-many-resistors"]);
+
+    // S1's resistors belong to S1, so the root module does not report them again
+    let board = violations(&diagnostics, &env.root().join("board.zen"));
+    assert!(board.is_empty(), "{board:?}");
+}
+
+#[test]
+fn allow_comment_after_code_suppresses_lints() {
+    let env = TestProject::new();
+    env.add_file(
+        "sensor.zen",
+        r#"UNUSED = 1  # pcb: allow(too-many-resistors)
+NC = Net("NC")
+GND = Net("GND")
+
+def resistor(name, a, b):
+    Component(
+        name = name,
+        prefix = "R",
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+resistor("R1", NC, GND)
+resistor("R2", GND, GND)
+"#,
+    );
+    let diagnostics = build(&env, EvalMode::Build);
+
+    let sensor = violations(&diagnostics, &env.root().join("sensor.zen"));
+    let lints: Vec<&str> = sensor.iter().map(|(_, v)| v.lint.as_str()).collect();
+    assert_eq!(lints, ["floating-net"]);
+}
+
+#[test]
+fn lint_checks_run_with_a_context() {
+    let env = TestProject::new();
+    env.add_file(
+        "lints.zen",
+        r#"
+def _no_components(module):
+    # File() resolves against the module being checked, so it needs its context
+    File("sensor.zen")
+    check(len(module.components()) == 0, "module has components")
+
+ALL = [Lint(name = "no-components", check = _no_components)]
+"#,
+    );
+    env.add_file("sensor.zen", SENSOR_ZEN);
+    let board = env.add_file("board.zen", BOARD_ZEN);
+    let (_, diagnostics) = pcb_zen::run(&board, true, EvalMode::Build).unpack();
+
+    let sensor = violations(&diagnostics, &env.root().join("sensor.zen"));
+    let [(diag, violation)] = sensor.as_slice() else {
+        panic!("expected one violation: {diagnostics:?}");
+    };
+    assert!(matches!(diag.severity, EvalSeverity::Warning));
+    assert!(
+        violation.message.contains("module has components"),
+        "{violation:?}"
+    );
+}
+
+const CHIP_SYM: &str = r#"(kicad_symbol_lib (version 20211014) (generator kicad_symbol_editor)
+  (symbol "Chip" (in_bom yes) (on_board yes)
+    (property "Reference" "U" (id 0) (at 0 0 0))
+    (symbol "Chip_1_1"
+      (pin power_in line (at 0 0 0) (length 2.54)
+        (name "VDD" (effects (font (size 1.27 1.27))))
+        (number "1" (effects (font (size 1.27 1.27))))
+      )
+      (pin power_in line (at 0 0 0) (length 2.54)
+        (name "GND" (effects (font (size 1.27 1.27))))
+        (number "2" (effects (font (size 1.27 1.27))))
+      )
+      (pin input line (at 0 0 0) (length 2.54)
+        (name "EN" (effects (font (size 1.27 1.27))))
+        (number "3" (effects (font (size 1.27 1.27))))
+      )
+      (pin open_collector line (at 0 0 0) (length 2.54)
+        (name "INT" (effects (font (size 1.27 1.27))))
+        (number "4" (effects (font (size 1.27 1.27))))
+      )
+      (pin bidirectional line (at 0 0 0) (length 2.54)
+        (name "SDA" (effects (font (size 1.27 1.27))))
+        (number "5" (effects (font (size 1.27 1.27))))
+      )
+    )
+  )
+)"#;
+
+/// Builds `board` next to chip.kicad_sym and returns its lint messages, sorted
+fn build_chip(env: &TestProject, board: &str) -> Vec<String> {
+    env.add_file("chip.kicad_sym", CHIP_SYM);
+    let board = env.add_file("board.zen", board);
+    let (_, diagnostics) = pcb_zen::run(&board, true, EvalMode::Build).unpack();
+    assert!(!diagnostics.has_errors(), "{diagnostics:?}");
+    let mut bodies: Vec<String> = violations(&diagnostics, &board)
+        .into_iter()
+        .map(|(diag, _)| diag.body.clone())
+        .collect();
+    bodies.sort();
+    bodies
+}
+
+#[test]
+fn builtin_rules_use_pin_types_and_connectivity() {
+    let env = TestProject::new();
+    let bodies = build_chip(
+        &env,
+        r#"
+LINTS = [
+    builtin_lint(name)
+    for name in ["floating-input", "missing-decoupling", "open-drain-pull-up", "i2c-pull-ups"]
+]
+
+VDD = Net("VDD")
+GND = Net("GND")
+EN = Net("EN")
+INT = Net("INT")
+SDA = Net("I2C_SDA")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23-5",
+    symbol = Symbol(library = "./chip.kicad_sym"),
+    pins = {"VDD": VDD, "GND": GND, "EN": EN, "INT": INT, "SDA": SDA},
+)
+"#,
+    );
+    insta::assert_snapshot!(bodies.join("\n"));
+}
+
+#[test]
+fn builtin_rules_accept_connected_pins() {
+    let env = TestProject::new();
+    let bodies = build_chip(
+        &env,
+        r#"
+LINTS = [
+    builtin_lint(name)
+    for name in ["floating-input", "missing-decoupling", "open-drain-pull-up", "i2c-pull-ups"]
+]
+
+VDD = Net("VDD")
+GND = Net("GND")
+EN = Net("EN")
+INT = Net("INT")
+SDA = Net("I2C_SDA")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23-5",
+    symbol = Symbol(library = "./chip.kicad_sym"),
+    pins = {"VDD": VDD, "GND": GND, "EN": EN, "INT": INT, "SDA": SDA},
+)
+
+def passive(name, prefix, a, b):
+    Component(
+        name = name,
+        prefix = prefix,
+        footprint = "SMD:0402",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+passive("C1", "C", VDD, GND)
+passive("R1", "R", INT, VDD)
+passive("R2", "R", SDA, VDD)
+passive("R3", "R", EN, GND)
+"#,
+    );
+    assert!(bodies.is_empty(), "{bodies:?}");
+}
+
+#[test]
+fn unknown_deny_names_are_reported() {
+    let env = TestProject::new();
+    env.add_file("sensor.zen", SENSOR_ZEN);
+    let diagnostics = build(&env, EvalMode::Build);
+    let deny = [
+        "warnings".to_string(),
+        "floating-net".to_string(),
+        "too-many-resistors".to_string(),
+        "missing-decoupling".to_string(),
+        "datasheet-requirements".to_string(),
+        "no-such-lint".to_string(),
+    ];
+    assert_eq!(
+        pcb_zen_core::lang::lint::unknown_lints(&deny, diagnostics.iter()),
+        ["no-such-lint"]
+    );
+}
+
+#[test]
+fn deny_promotes_only_the_named_lint() {
+    let env = TestProject::new();
+    env.add_file("sensor.zen", SENSOR_ZEN);
+    let mut diagnostics = build(&env, EvalMode::Build);
+    PromoteDeniedPass::new(&["too-many-resistors".to_string()]).apply(&mut diagnostics);
+
+    for (diag, violation) in violations(&diagnostics, &env.root().join("sensor.zen")) {
+        let promoted = matches!(diag.severity, EvalSeverity::Error);
+        assert_eq!(
+            promoted,
+            violation.lint == "too-many-resistors",
+            "{violation:?}"
+        );
+    }
+}
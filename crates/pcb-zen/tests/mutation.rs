@@ -0,0 +1,292 @@
+mod common;
+use common::TestProject;
+
+use pcb_zen::{Diagnostics, EvalMode};
+use pcb_zen_core::MutationResult;
+
+/// The mutation results reported in `diagnostics`
+fn mutations(diagnostics: &Diagnostics) -> Vec<MutationResult> {
+    diagnostics
+        .iter()
+        .filter_map(|diag| diag.downcast_error_ref::<MutationResult>())
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn reports_which_mutations_the_checks_kill() {
+    let env = TestProject::new();
+    env.add_file(
+        "module.zen",
+        r#"
+VCC = Net("VCC")
+GND = Net("GND")
+
+Component(
+    name = "R1",
+    prefix = "R",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": VCC, "2": GND},
+    properties = {"value": "10kOhm"},
+)
+"#,
+    );
+    let bench = env.add_file(
+        "bench.zen",
+        r#"
+M = Module("module.zen")
+
+def has_r1(module, inputs):
+    check("R1" in module, "R1 is missing")
+
+TestBench(
+    name = "Divider",
+    module = M,
+    test_cases = {"default": {}},
+    checks = [has_r1],
+)
+"#,
+    );
+
+    let result = pcb_zen::run_with_mutations(&bench, true, EvalMode::Test);
+    assert!(!result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+    let mutations = mutations(&result.diagnostics);
+
+    let mut kinds: Vec<&str> = mutations.iter().map(|m| m.kind.as_str()).collect();
+    kinds.sort();
+    assert_eq!(
+        kinds,
+        ["change-value", "drop-component", "short-nets", "swap-pins"]
+    );
+
+    for mutation in &mutations {
+        assert_eq!(mutation.test_bench_name, "Divider");
+        assert_eq!(mutation.case_name.as_deref(), Some("default"));
+        let expected = (mutation.kind == "drop-component").then(|| "has_r1".to_string());
+        assert_eq!(mutation.killed_by, expected, "{mutation:?}");
+        assert_eq!(mutation.error, None, "{mutation:?}");
+    }
+}
+
+#[test]
+fn mutations_only_run_when_enabled() {
+    let env = TestProject::new();
+    env.add_file(
+        "module.zen",
+        r#"
+VCC = Net("VCC")
+GND = Net("GND")
+
+Component(
+    name = "R1",
+    prefix = "R",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": VCC, "2": GND},
+    properties = {"value": "10kOhm"},
+)
+"#,
+    );
+    let bench = env.add_file(
+        "bench.zen",
+        r#"
+M = Module("module.zen")
+
+def has_r1(module, inputs):
+    check("R1" in module, "R1 is missing")
+
+TestBench(
+    name = "Divider",
+    module = M,
+    test_cases = {"default": {}},
+    checks = [has_r1],
+)
+"#,
+    );
+
+    let result = pcb_zen::run(&bench, true, EvalMode::Test);
+    assert!(!result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+    assert!(mutations(&result.diagnostics).is_empty());
+}
+
+#[test]
+fn nets_with_the_same_name_are_told_apart() {
+    let env = TestProject::new();
+    // Both instances of sub.zen have their own A and B nets
+    env.add_file(
+        "sub.zen",
+        r#"
+A = Net("A")
+B = Net("B")
+
+Component(
+    name = "R1",
+    prefix = "R",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": A, "2": B},
+    properties = {"value": "10kOhm"},
+)
+"#,
+    );
+    env.add_file(
+        "module.zen",
+        r#"
+Sub = Module("sub.zen")
+Sub(name = "S1")
+Sub(name = "S2")
+"#,
+    );
+    let bench = env.add_file(
+        "bench.zen",
+        r#"
+M = Module("module.zen")
+
+def has_r1(module, inputs):
+    check("S1.R1" in module, "S1.R1 is missing")
+
+TestBench(
+    name = "Divider",
+    module = M,
+    test_cases = {"default": {}},
+    checks = [has_r1],
+)
+"#,
+    );
+
+    let result = pcb_zen::run_with_mutations(&bench, true, EvalMode::Test);
+    assert!(!result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+    let mut shorts: Vec<String> = mutations(&result.diagnostics)
+        .into_iter()
+        .filter(|m| m.kind == "short-nets" || m.kind == "swap-pins")
+        .map(|m| m.mutation)
+        .collect();
+    shorts.sort();
+    assert_eq!(
+        shorts,
+        [
+            "short A to B",
+            "short A to B",
+            "swap pins 1 and 2 of S1.R1",
+            "swap pins 1 and 2 of S2.R1",
+        ]
+    );
+}
+
+#[test]
+fn errors_other_than_failing_checks_are_not_kills() {
+    let env = TestProject::new();
+    env.add_file(
+        "module.zen",
+        r#"
+VCC = Net("VCC")
+GND = Net("GND")
+
+Component(
+    name = "R1",
+    prefix = "R",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": VCC, "2": GND},
+    properties = {"value": "10kOhm"},
+)
+"#,
+    );
+    let bench = env.add_file(
+        "bench.zen",
+        r#"
+M = Module("module.zen")
+
+def r1_is_10k(module, inputs):
+    check(module["R1"].properties["value"] == "10kOhm", "R1 is not 10k")
+
+TestBench(
+    name = "Divider",
+    module = M,
+    test_cases = {"default": {}},
+    checks = [r1_is_10k],
+)
+"#,
+    );
+
+    let result = pcb_zen::run_with_mutations(&bench, true, EvalMode::Test);
+    assert!(result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+    let mutations = mutations(&result.diagnostics);
+
+    // Indexing the module for a dropped R1 raises an error, which kills nothing
+    let dropped = mutations
+        .iter()
+        .find(|m| m.kind == "drop-component")
+        .expect("no drop-component mutation");
+    assert_eq!(dropped.killed_by, None);
+    assert!(dropped.error.is_some(), "{dropped:?}");
+
+    let scaled = mutations
+        .iter()
+        .find(|m| m.kind == "change-value")
+        .expect("no change-value mutation");
+    assert_eq!(scaled.killed_by.as_deref(), Some("r1_is_10k"));
+    assert_eq!(scaled.error, None);
+}
+
+#[test]
+fn scaled_records_keep_their_type() {
+    let env = TestProject::new();
+    env.add_file(
+        "units.zen",
+        r#"
+Resistance = record(value = float, tolerance = field(float, 0.01))
+"#,
+    );
+    env.add_file(
+        "module.zen",
+        r#"
+load("units.zen", "Resistance")
+
+VCC = Net("VCC")
+GND = Net("GND")
+
+Component(
+    name = "R1",
+    prefix = "R",
+    footprint = "SMD:0805",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": VCC, "2": GND},
+    properties = {"value": Resistance(value = 10000.0)},
+)
+"#,
+    );
+    let bench = env.add_file(
+        "bench.zen",
+        r#"
+load("units.zen", "Resistance")
+
+M = Module("module.zen")
+
+def ohms(resistance: Resistance):
+    return resistance.value
+
+def r1_is_10k(module, inputs):
+    check("R1" in module, "R1 is missing")
+    check(ohms(module["R1"].properties["value"]) == 10000.0, "R1 is not 10k")
+
+TestBench(
+    name = "Divider",
+    module = M,
+    test_cases = {"default": {}},
+    checks = [r1_is_10k],
+)
+"#,
+    );
+
+    let result = pcb_zen::run_with_mutations(&bench, true, EvalMode::Test);
+    assert!(!result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+    let scaled = mutations(&result.diagnostics)
+        .into_iter()
+        .find(|m| m.kind == "change-value")
+        .expect("no change-value mutation");
+    // A struct would fail the `Resistance` annotation with a type error instead
+    assert_eq!(scaled.killed_by.as_deref(), Some("r1_is_10k"));
+    assert_eq!(scaled.error, None);
+}
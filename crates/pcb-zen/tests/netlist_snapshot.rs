@@ -0,0 +1,146 @@
+mod common;
+use common::TestProject;
+
+use std::path::Path;
+
+use pcb_zen::EvalMode;
+use pcb_zen_core::lang::error::{BenchTestResult, SnapshotMismatch};
+
+const BENCH_ZEN: &str = r#"
+M = Module("divider.zen")
+
+def netlist(module, inputs):
+    snapshot(module)
+
+TestBench(
+    name = "Divider",
+    module = M,
+    test_cases = {"default": {}},
+    checks = [netlist],
+)
+"#;
+
+fn run_bench(bench: &Path) -> (BenchTestResult, Option<SnapshotMismatch>) {
+    let (_, diagnostics) = pcb_zen::run(bench, true, EvalMode::Test).unpack();
+    let result = diagnostics
+        .iter()
+        .find_map(|diag| diag.downcast_error_ref::<BenchTestResult>())
+        .cloned()
+        .unwrap_or_else(|| panic!("no test result: {diagnostics:?}"));
+    let mismatch = diagnostics
+        .iter()
+        .find_map(|diag| diag.downcast_error_ref::<SnapshotMismatch>())
+        .cloned();
+    (result, mismatch)
+}
+
+#[test]
+fn missing_snapshot_fails_with_the_contents_to_record() {
+    let env = TestProject::new();
+    env.add_file(
+        "divider.zen",
+        r#"
+VCC = Net("VCC")
+OUT = Net("OUT")
+GND = Net("GND")
+
+def resistor(name, a, b):
+    Component(
+        name = name,
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+resistor("R1", VCC, OUT)
+resistor("R2", OUT, GND)
+"#,
+    );
+    let bench = env.add_file("bench.zen", BENCH_ZEN);
+
+    let (result, mismatch) = run_bench(&bench);
+    assert!(!result.passed);
+    assert!(
+        result
+            .message
+            .as_deref()
+            .is_some_and(|m| m.contains("has not been recorded")),
+        "{result:?}"
+    );
+
+    let mismatch = mismatch.expect("missing snapshot should be reported");
+    assert_eq!(
+        mismatch.path,
+        env.root().join("__snapshots__/Divider__default.snap")
+    );
+    insta::assert_snapshot!(mismatch.contents);
+}
+
+#[test]
+fn matching_snapshot_passes() {
+    let env = TestProject::new();
+    env.add_file(
+        "divider.zen",
+        r#"
+VCC = Net("VCC")
+OUT = Net("OUT")
+GND = Net("GND")
+
+def resistor(name, a, b):
+    Component(
+        name = name,
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+resistor("R1", VCC, OUT)
+resistor("R2", OUT, GND)
+"#,
+    );
+    env.add_file(
+        "__snapshots__/Divider__default.snap",
+        "GND: R2.2\nOUT: R1.2, R2.1\nVCC: R1.1\n",
+    );
+    let bench = env.add_file("bench.zen", BENCH_ZEN);
+
+    let (result, mismatch) = run_bench(&bench);
+    assert!(result.passed, "{result:?}");
+    assert!(mismatch.is_none());
+}
+
+#[test]
+fn changed_connectivity_reports_moved_ports() {
+    let env = TestProject::new();
+    env.add_file(
+        "divider.zen",
+        r#"
+VCC = Net("VCC")
+OUT = Net("OUT")
+GND = Net("GND")
+
+def resistor(name, a, b):
+    Component(
+        name = name,
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+    )
+
+resistor("R1", VCC, OUT)
+resistor("R2", OUT, VCC)
+"#,
+    );
+    env.add_file(
+        "__snapshots__/Divider__default.snap",
+        "GND: R2.2\nOUT: R1.2, R2.1\nVCC: R1.1\n",
+    );
+    let bench = env.add_file("bench.zen", BENCH_ZEN);
+
+    let (result, mismatch) = run_bench(&bench);
+    assert!(!result.passed);
+    let message = result.message.unwrap_or_default();
+    assert!(message.contains("R2.2: GND -> VCC"), "{message}");
+    let mismatch = mismatch.expect("changed snapshot should be reported");
+    insta::assert_snapshot!(mismatch.contents);
+}
@@ -0,0 +1,324 @@
+mod common;
+use common::TestProject;
+
+use std::path::Path;
+
+use pcb_zen::{Diagnostic, Diagnostics, EvalMode};
+use pcb_zen_core::{DiagnosticsPass, PromoteDeniedPass, RequirementViolation};
+use starlark::errors::EvalSeverity;
+
+const PART_TOML: &str = r#"
+[[requirement]]
+pin = "VIN"
+rule = "decoupling"
+min = "10uF"
+source = "Datasheet §8.2.1"
+
+[[requirement]]
+pin = "EN"
+rule = "pull_up"
+to = "VIN"
+min = "10k"
+max = "100k"
+"#;
+
+fn build(env: &TestProject, board: &str) -> Diagnostics {
+    env.add_file("part.toml", PART_TOML);
+    let board = env.add_file("board.zen", board);
+    let (_, diagnostics) = pcb_zen::run(&board, true, EvalMode::Build).unpack();
+    assert!(!diagnostics.has_errors(), "{diagnostics:?}");
+    diagnostics
+}
+
+/// Requirement violations, sorted by message
+fn violations(diagnostics: &Diagnostics) -> Vec<(&Diagnostic, RequirementViolation)> {
+    let mut violations: Vec<_> = diagnostics
+        .iter()
+        .filter_map(|diag| {
+            diag.downcast_error_ref::<RequirementViolation>()
+                .map(|violation| (diag, violation.clone()))
+        })
+        .collect();
+    violations.sort_by(|a, b| a.1.message.cmp(&b.1.message));
+    violations
+}
+
+#[test]
+fn unmet_requirements_are_reported_at_their_source() {
+    let env = TestProject::new();
+    let diagnostics = build(
+        &env,
+        r#"
+VIN = Net("VIN")
+EN = Net("EN")
+GND = Net("GND")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("VIN", ["1"]), ("EN", ["2"]), ("GND", ["3"])]),
+    pins = {"VIN": VIN, "EN": EN, "GND": GND},
+    requirements = "part.toml",
+)
+
+def passive(name, prefix, value, a, b):
+    Component(
+        name = name,
+        prefix = prefix,
+        footprint = "SMD:0402",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+        properties = {"value": value},
+    )
+
+passive("C1", "C", "4.7uF", VIN, GND)
+passive("R1", "R", "1k", EN, VIN)
+"#,
+    );
+
+    let violations = violations(&diagnostics);
+    let messages: Vec<&str> = violations.iter().map(|(_, v)| v.message.as_str()).collect();
+    insta::assert_snapshot!(messages.join("\n"));
+
+    let (diag, violation) = &violations[1];
+    assert!(matches!(diag.severity, EvalSeverity::Warning));
+    assert!(diag.path.ends_with("part.toml"), "{}", diag.path);
+    assert_eq!(violation.instance, "U1");
+    assert_eq!(violation.requirement, "VIN: decoupling >= 10uF to GND");
+    assert_eq!(
+        diag.body,
+        "[datasheet-requirements] U1.VIN has 4.7uF of decoupling to GND, needs at least 10uF (Datasheet §8.2.1)"
+    );
+}
+
+#[test]
+fn met_requirements_are_silent() {
+    let env = TestProject::new();
+    let diagnostics = build(
+        &env,
+        r#"
+VIN = Net("VIN")
+EN = Net("EN")
+GND = Net("GND")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("VIN", ["1"]), ("EN", ["2"]), ("GND", ["3"])]),
+    pins = {"VIN": VIN, "EN": EN, "GND": GND},
+    requirements = [
+        {"pin": "VIN", "rule": "decoupling", "min": "10uF"},
+        {"pin": "EN", "rule": "pull_up", "to": "VIN", "min": "10k", "max": "100k"},
+    ],
+)
+
+def passive(name, prefix, value, a, b):
+    Component(
+        name = name,
+        prefix = prefix,
+        footprint = "SMD:0402",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+        properties = {"value": value},
+    )
+
+passive("C1", "C", "10uF", VIN, GND)
+passive("R1", "R", "47k", EN, VIN)
+"#,
+    );
+    assert!(violations(&diagnostics).is_empty(), "{diagnostics:?}");
+}
+
+#[test]
+fn module_requirements_name_pins_relative_to_the_module() {
+    let env = TestProject::new();
+    env.add_file(
+        "sub.zen",
+        r#"
+EN = Net("EN")
+GND = io("GND", Net)
+
+requirements([{"pin": "U1.EN", "rule": "pull_down", "source": "Datasheet §7.3"}])
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("EN", ["1"]), ("GND", ["2"])]),
+    pins = {"EN": EN, "GND": GND},
+)
+"#,
+    );
+    let mut diagnostics = build(
+        &env,
+        r#"
+Sub = Module("sub.zen")
+Sub(name = "S1", GND = Net("GND"))
+"#,
+    );
+    PromoteDeniedPass::new(&["datasheet-requirements".to_string()]).apply(&mut diagnostics);
+
+    let violations = violations(&diagnostics);
+    let [(diag, violation)] = violations.as_slice() else {
+        panic!("expected one violation: {diagnostics:?}");
+    };
+    assert_eq!(violation.instance, "S1");
+    assert_eq!(
+        violation.message,
+        "S1.U1.EN has no pull-down resistor to GND"
+    );
+    assert_eq!(Path::new(&diag.path), env.root().join("sub.zen"));
+    assert!(matches!(diag.severity, EvalSeverity::Error));
+}
+
+#[test]
+fn requirements_on_unknown_pins_are_errors() {
+    let env = TestProject::new();
+    let board = env.add_file(
+        "board.zen",
+        r#"
+VIN = Net("VIN")
+EN = Net("EN")
+GND = Net("GND")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("VIN", ["1"]), ("EN", ["2"]), ("GND", ["3"])]),
+    pins = {"VIN": VIN, "EN": EN, "GND": GND},
+    requirements = [{"pin": "VCC", "rule": "decoupling", "min": "1uF"}],
+)
+
+def passive(name, prefix, value, a, b):
+    Component(
+        name = name,
+        prefix = prefix,
+        footprint = "SMD:0402",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+        properties = {"value": value},
+    )
+
+passive("C1", "C", "1uF", VIN, GND)
+passive("R1", "R", "10k", EN, VIN)
+"#,
+    );
+    let result = pcb_zen::run(&board, true, EvalMode::Build);
+    assert!(result.diagnostics.has_errors());
+    assert!(
+        result.diagnostics.iter().any(|diag| diag
+            .to_string()
+            .contains("Requirement on unknown pin 'VCC'")),
+        "{:?}",
+        result.diagnostics
+    );
+}
+
+#[test]
+fn instances_check_their_own_nets() {
+    let env = TestProject::new();
+    env.add_file(
+        "sub.zen",
+        r#"
+cap = config("cap", str)
+
+VIN = Net("VIN")
+EN = Net("EN")
+GND = Net("GND")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("VIN", ["1"]), ("EN", ["2"]), ("GND", ["3"])]),
+    pins = {"VIN": VIN, "EN": EN, "GND": GND},
+    requirements = "part.toml",
+)
+
+def passive(name, prefix, value, a, b):
+    Component(
+        name = name,
+        prefix = prefix,
+        footprint = "SMD:0402",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+        properties = {"value": value},
+    )
+
+passive("C1", "C", cap, VIN, GND)
+passive("R1", "R", "47k", EN, VIN)
+"#,
+    );
+    let diagnostics = build(
+        &env,
+        r#"
+Sub = Module("sub.zen")
+Sub(name = "S1", cap = "10uF")
+Sub(name = "S2", cap = "4.7uF")
+"#,
+    );
+
+    let violations = violations(&diagnostics);
+    let messages: Vec<&str> = violations.iter().map(|(_, v)| v.message.as_str()).collect();
+    insta::assert_snapshot!(messages.join("\n"));
+}
+
+#[test]
+fn capacitors_behind_a_series_part_count_within_more_components() {
+    let env = TestProject::new();
+    let diagnostics = build(
+        &env,
+        r#"
+VIN = Net("VIN")
+AVDD = Net("AVDD")
+GND = Net("GND")
+VIN_F = Net("VIN_F")
+AVDD_F = Net("AVDD_F")
+
+Component(
+    name = "U1",
+    footprint = "SMD:SOT-23",
+    symbol = Symbol(definition = [("VIN", ["1"]), ("AVDD", ["2"]), ("GND", ["3"])]),
+    pins = {"VIN": VIN, "AVDD": AVDD, "GND": GND},
+    requirements = [
+        {"pin": "VIN", "rule": "decoupling", "min": "1uF"},
+        {"pin": "AVDD", "rule": "decoupling", "min": "1uF", "within": 2},
+    ],
+)
+
+# Both capacitors sit behind a ferrite bead, which only AVDD's requirement allows
+Component(
+    name = "FB1",
+    prefix = "FB",
+    footprint = "SMD:0402",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": AVDD, "2": AVDD_F},
+)
+Component(
+    name = "C1",
+    prefix = "C",
+    footprint = "SMD:0402",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": AVDD_F, "2": GND},
+    properties = {"value": "1uF"},
+)
+Component(
+    name = "FB2",
+    prefix = "FB",
+    footprint = "SMD:0402",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": VIN, "2": VIN_F},
+)
+Component(
+    name = "C2",
+    prefix = "C",
+    footprint = "SMD:0402",
+    symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+    pins = {"1": VIN_F, "2": GND},
+    properties = {"value": "1uF"},
+)
+"#,
+    );
+
+    let violations = violations(&diagnostics);
+    let messages: Vec<&str> = violations.iter().map(|(_, v)| v.message.as_str()).collect();
+    insta::assert_snapshot!(messages.join("\n"));
+}
@@ -0,0 +1,151 @@
+#![cfg(unix)]
+
+mod common;
+use common::TestProject;
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+
+use serde_json::{json, Value};
+
+const TOP_ZEN: &str = r#"
+load("parts.zen", "part")
+
+VCC = Net("VCC")
+GND = Net("GND")
+
+part("R1", VCC, GND)
+"#;
+
+/// A JSON-RPC client talking to an in-process server.
+struct Session {
+    stream: UnixStream,
+    reader: BufReader<UnixStream>,
+    id: i64,
+}
+
+impl Session {
+    fn start() -> Self {
+        let (client, server) = UnixStream::pair().unwrap();
+        std::thread::spawn(move || {
+            let input = BufReader::new(server.try_clone().unwrap());
+            pcb_zen::serve::serve(input, server, true).unwrap();
+        });
+        Self {
+            reader: BufReader::new(client.try_clone().unwrap()),
+            stream: client,
+            id: 0,
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Value {
+        writeln!(self.stream, "{line}").unwrap();
+        let mut response = String::new();
+        self.reader.read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        self.id += 1;
+        let request = json!({"jsonrpc": "2.0", "id": self.id, "method": method, "params": params});
+        let response = self.send(&request.to_string());
+        assert_eq!(response["id"], self.id);
+        response
+    }
+
+    fn result(&mut self, method: &str, params: Value) -> Value {
+        let response = self.call(method, params);
+        assert!(response["error"].is_null(), "{method} failed: {response}");
+        response["result"].clone()
+    }
+}
+
+fn mpns(bom: &Value) -> Vec<String> {
+    bom.as_object()
+        .unwrap()
+        .values()
+        .filter_map(|entry| entry["mpn"].as_str().map(str::to_owned))
+        .collect()
+}
+
+#[test]
+fn serves_evaluations_from_a_warm_context() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    let top = env.add_file("top.zen", TOP_ZEN);
+    env.add_file(
+        "parts.zen",
+        r#"
+def part(name, a, b):
+    Component(
+        name = name,
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+        mpn = "MPN-OLD",
+    )
+"#,
+    );
+    let params = json!({"path": top});
+
+    let mut session = Session::start();
+
+    let evaluated = session.result("evaluate", params.clone());
+    assert_eq!(evaluated["success"], true, "{evaluated}");
+    assert!(evaluated["schematic"]["instances"].is_object());
+
+    let bom = session.result("bom", params.clone());
+    insta::assert_snapshot!("bom_before_edit", mpns(&bom).join("\n"));
+
+    // Editing a loaded file is picked up by the next request.
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    env.add_file(
+        "parts.zen",
+        r#"
+def part(name, a, b):
+    Component(
+        name = name,
+        footprint = "SMD:0805",
+        symbol = Symbol(definition = [("1", ["1"]), ("2", ["2"])]),
+        pins = {"1": a, "2": b},
+        mpn = "MPN-NEW",
+    )
+"#,
+    );
+    let bom = session.result("bom", params.clone());
+    insta::assert_snapshot!("bom_after_edit", mpns(&bom).join("\n"));
+
+    let tested = session.result("test", params.clone());
+    assert!(tested["results"].is_array(), "{tested}");
+
+    let diagnostics = session.result("diagnostics", params);
+    assert_eq!(diagnostics["success"], true, "{diagnostics}");
+
+    session.result("shutdown", Value::Null);
+}
+
+#[test]
+fn reports_json_rpc_errors() {
+    let env = TestProject::new();
+    env.add_file("pcb.toml", "[workspace]\n");
+    let broken = env.add_file("broken.zen", "Net(\n");
+
+    let mut session = Session::start();
+
+    let parse_error = session.send("not json");
+    assert_eq!(parse_error["error"]["code"], -32700);
+
+    let unknown = session.call("frobnicate", json!({}));
+    assert_eq!(unknown["error"]["code"], -32601);
+
+    let missing_path = session.call("schematic", json!({}));
+    assert_eq!(missing_path["error"]["code"], -32602);
+
+    let failed = session.call("schematic", json!({"path": broken}));
+    assert_eq!(failed["error"]["code"], -32000);
+    assert!(failed["error"]["data"]
+        .as_array()
+        .is_some_and(|d| !d.is_empty()));
+
+    session.result("shutdown", Value::Null);
+}
@@ -0,0 +1,171 @@
+mod common;
+use common::TestProject;
+
+use pcb_zen::{Diagnostics, EvalMode, EvalSeverity};
+use pcb_zen_core::lang::error::BenchTestResult;
+
+const MODULE_ZEN: &str = r#"
+Mode = enum("A", "B")
+
+mode = config("mode", Mode)
+n = config("n", int)
+"#;
+
+/// Runs `bench_zen` as `bench.zen` next to [`MODULE_ZEN`]
+fn run_bench(bench_zen: &str) -> (Vec<BenchTestResult>, Diagnostics) {
+    let env = TestProject::new();
+    env.add_file("module.zen", MODULE_ZEN);
+    let bench = env.add_file("bench.zen", bench_zen);
+    let (_, diagnostics) = pcb_zen::run(&bench, true, EvalMode::Test).unpack();
+    let results = diagnostics
+        .iter()
+        .filter_map(|diag| diag.downcast_error_ref::<BenchTestResult>())
+        .cloned()
+        .collect();
+    (results, diagnostics)
+}
+
+fn case_names(results: &[BenchTestResult]) -> Vec<String> {
+    results
+        .iter()
+        .filter_map(|result| result.case_name.clone())
+        .collect()
+}
+
+#[test]
+fn sweeps_the_product_of_enum_variants_and_ranges() {
+    let (results, diagnostics) = run_bench(
+        r#"
+load("module.zen", "Mode")
+
+M = Module("module.zen")
+
+def small(module, inputs):
+    check(inputs["n"] < 3, "n too large")
+
+TestBench(
+    name = "Sweep",
+    module = M,
+    test_cases = Sweep({"mode": Mode, "n": range(5)}),
+    checks = [small],
+)
+"#,
+    );
+
+    assert_eq!(results.len(), 10, "{diagnostics:?}");
+    let cases: Vec<String> = results
+        .iter()
+        .map(|result| {
+            let outcome = if result.passed { "passed" } else { "failed" };
+            format!(
+                "{}: {outcome}",
+                result.case_name.as_deref().unwrap_or_default()
+            )
+        })
+        .collect();
+    insta::assert_snapshot!(cases.join("\n"));
+}
+
+#[test]
+fn reports_the_shrunk_failing_case() {
+    // The single sample drawn with this seed is mode=B, n=3, which shrinks to mode=A.
+    let (results, diagnostics) = run_bench(
+        r#"
+load("module.zen", "Mode")
+
+M = Module("module.zen")
+
+def small(module, inputs):
+    check(inputs["n"] < 3, "n too large")
+
+TestBench(
+    name = "Sweep",
+    module = M,
+    test_cases = Sweep({"mode": Mode, "n": range(5)}, samples = 1, seed = 4),
+    checks = [small],
+)
+"#,
+    );
+    assert_eq!(results.len(), 1, "{diagnostics:?}");
+    assert!(!results[0].passed);
+
+    let shrunk: Vec<&str> = diagnostics
+        .iter()
+        .filter(|diag| diag.severity == EvalSeverity::Warning)
+        .map(|diag| diag.body.as_str())
+        .filter(|body| body.contains("smallest failing case"))
+        .collect();
+    assert_eq!(shrunk.len(), 1, "{diagnostics:?}");
+    insta::assert_snapshot!(shrunk[0]);
+}
+
+#[test]
+fn samples_are_reproducible() {
+    let bench = r#"
+load("module.zen", "Mode")
+
+M = Module("module.zen")
+
+def small(module, inputs):
+    check(inputs["n"] < 3, "n too large")
+
+TestBench(
+    name = "Sweep",
+    module = M,
+    test_cases = Sweep({"mode": Mode, "n": range(5)}, samples = 3, seed = 7),
+    checks = [small],
+)
+"#;
+    let (first, _) = run_bench(bench);
+    let (second, _) = run_bench(bench);
+
+    assert_eq!(first.len(), 3);
+    assert_eq!(case_names(&first), case_names(&second));
+}
+
+#[test]
+fn large_sweeps_need_samples() {
+    let (results, diagnostics) = run_bench(
+        r#"
+load("module.zen", "Mode")
+
+M = Module("module.zen")
+
+def small(module, inputs):
+    check(inputs["n"] < 3, "n too large")
+
+TestBench(
+    name = "Sweep",
+    module = M,
+    test_cases = Sweep({"mode": Mode, "n": range(20000)}),
+    checks = [small],
+)
+"#,
+    );
+    assert!(results.is_empty());
+    assert!(
+        diagnostics
+            .iter()
+            .any(|diag| diag.to_string().contains("40000 combinations")),
+        "{diagnostics:?}"
+    );
+
+    let (results, _) = run_bench(
+        r#"
+load("module.zen", "Mode")
+
+M = Module("module.zen")
+
+def small(module, inputs):
+    check(inputs["n"] < 3, "n too large")
+
+TestBench(
+    name = "Sweep",
+    module = M,
+    test_cases = Sweep({"mode": Mode, "n": range(20000)}, samples = 2),
+    checks = [small],
+)
+"#,
+    );
+    assert_eq!(results.len(), 2);
+}
@@ -0,0 +1,80 @@
+mod common;
+use common::TestProject;
+
+use pcb_zen::EvalMode;
+
+#[test]
+fn run_tracked_reports_loaded_files_and_modules() {
+    let env = TestProject::new();
+    env.add_file("consts.zen", "VALUE = 1\n");
+    env.add_file("sub.zen", "load(\"consts.zen\", \"VALUE\")\n");
+    env.add_file("unused.zen", "X = 1\n");
+    let board = env.add_file(
+        "board.zen",
+        r#"
+Sub = Module("sub.zen")
+Sub(name = "S1")
+"#,
+    );
+
+    let (result, files) = pcb_zen::run_tracked(&board, true, EvalMode::Build);
+    assert!(!result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+
+    let root = env.root().canonicalize().unwrap();
+    for name in ["board.zen", "sub.zen", "consts.zen"] {
+        assert!(files.contains(&root.join(name)), "{name} in {files:?}");
+    }
+    assert!(!files.contains(&root.join("unused.zen")), "{files:?}");
+}
+
+#[test]
+fn run_tracked_reports_every_file_read() {
+    let env = TestProject::new();
+    env.add_file(
+        "part.kicad_sym",
+        r#"(kicad_symbol_lib (version 20211014) (generator kicad_symbol_editor)
+  (symbol "Part" (in_bom yes) (on_board yes)
+    (symbol "Part_1_1"
+      (pin passive line (at 0 0 0) (length 2.54)
+        (name "A" (effects (font (size 1.27 1.27))))
+        (number "1" (effects (font (size 1.27 1.27))))
+      )
+    )
+  )
+)"#,
+    );
+    env.add_file(
+        "part.toml",
+        "[[requirement]]\npin = \"A\"\nrule = \"pull_down\"\n",
+    );
+    let board = env.add_file(
+        "board.zen",
+        r#"
+Component(
+    name = "U1",
+    footprint = "SMD:0402",
+    symbol = Symbol(library = "./part.kicad_sym"),
+    pins = {"A": Net("A")},
+    requirements = "part.toml",
+)
+"#,
+    );
+
+    let (_, files) = pcb_zen::run_tracked(&board, true, EvalMode::Build);
+
+    let root = env.root().canonicalize().unwrap();
+    for name in ["part.kicad_sym", "part.toml"] {
+        assert!(files.contains(&root.join(name)), "{name} in {files:?}");
+    }
+}
+
+#[test]
+fn run_tracked_reports_a_missing_board_as_an_error() {
+    let env = TestProject::new();
+    let board = env.root().join("deleted.zen");
+
+    let (result, files) = pcb_zen::run_tracked(&board, true, EvalMode::Build);
+    assert!(result.output.is_none());
+    assert!(result.diagnostics.has_errors(), "{:?}", result.diagnostics);
+    assert!(files.contains(&board), "{files:?}");
+}
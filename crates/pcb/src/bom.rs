@@ -65,7 +65,7 @@ pub fn execute(args: BomArgs) -> Result<()> {
     let spinner = Spinner::builder(format!("{file_name}: Building")).start();
 
     // Evaluate the design
-    let mut schematic = pcb_zen::run(&args.file, false, pcb_zen::EvalMode::Build)
+    let schematic = pcb_zen::run(&args.file, false, pcb_zen::EvalMode::Build)
         .output_result()
         .map_err(|mut diagnostics| {
             // Apply passes and render diagnostics if there are errors
@@ -75,7 +75,7 @@ pub fn execute(args: BomArgs) -> Result<()> {
 
     // Generate BOM entries
     spinner.set_message(format!("{file_name}: Generating BOM"));
-    let ungrouped_entries = generate_bom_entries(&mut schematic);
+    let ungrouped_entries = generate_bom_entries(&schematic);
     spinner.finish();
 
     // Write output to stdout
@@ -7,6 +7,12 @@ use pcb_zen::file_extensions;
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Stack size for worker threads; matches the main thread so deeply nested designs
+/// evaluate the same way regardless of `--jobs`.
+const WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
 
 /// Create diagnostics passes for the given deny list
 pub fn create_diagnostics_passes(deny: &[String]) -> Vec<Box<dyn pcb_zen_core::DiagnosticsPass>> {
@@ -40,9 +46,23 @@ pub struct BuildArgs {
     pub offline: bool,
 
     /// Set lint level to deny (treat as error). Use 'warnings' for all warnings,
-    /// or specific lint names like 'unstable-refs'
+    /// or specific lint names like 'unstable-refs' or 'floating-net'. Unknown names
+    /// are an error.
     #[arg(short = 'D', long = "deny", value_name = "LINT")]
     pub deny: Vec<String>,
+
+    /// Number of files to evaluate in parallel (defaults to the number of CPUs)
+    #[arg(short = 'j', long = "jobs", value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Re-evaluate every file instead of reusing cached results from `.pcb/eval-cache`
+    /// (also disabled by setting `PCB_NO_CACHE`)
+    #[arg(long = "no-cache")]
+    pub no_cache: bool,
+
+    /// Keep running and rebuild the affected files whenever a file they depend on changes
+    #[arg(short = 'w', long = "watch")]
+    pub watch: bool,
 }
 
 /// Evaluate a single Starlark file and print any diagnostics
@@ -68,6 +88,50 @@ pub fn build(
     }
     spinner.finish();
 
+    report_build(zen_path, eval, passes, has_errors)
+}
+
+/// Evaluate all `zen_paths` in `mode` using up to `jobs` threads, through the evaluation
+/// cache when `cache` is set. Results are returned in the same order as `zen_paths`.
+pub fn evaluate_all(
+    zen_paths: &[PathBuf],
+    offline: bool,
+    mode: pcb_zen::EvalMode,
+    jobs: Option<usize>,
+    cache: bool,
+) -> Vec<pcb_zen::WithDiagnostics<Schematic>> {
+    let verb = match mode {
+        pcb_zen::EvalMode::Build => "Building",
+        pcb_zen::EvalMode::Test => "Testing",
+    };
+    let message = match zen_paths {
+        [single] => format!("{}: {verb}", single.file_name().unwrap().to_string_lossy()),
+        _ => format!("{verb} {} files", zen_paths.len()),
+    };
+
+    let spinner = Spinner::builder(message).start();
+    let results = par_map(zen_paths, jobs, |zen_path| {
+        debug!("Evaluating Zener file: {}", zen_path.display());
+        if cache {
+            pcb_zen::run_cached(zen_path, offline, mode)
+        } else {
+            pcb_zen::run(zen_path, offline, mode)
+        }
+    });
+    spinner.finish();
+    results
+}
+
+/// Apply `passes` to the diagnostics of an already evaluated file and print a
+/// failure line if it produced no schematic.
+pub fn report_build(
+    zen_path: &Path,
+    eval: pcb_zen::WithDiagnostics<Schematic>,
+    passes: Vec<Box<dyn pcb_zen_core::DiagnosticsPass>>,
+    has_errors: &mut bool,
+) -> Option<Schematic> {
+    let file_name = zen_path.file_name().unwrap().to_string_lossy();
+
     // Apply all passes including rendering
     let mut diagnostics = eval.diagnostics.clone();
     diagnostics.apply_passes(&passes);
@@ -93,6 +157,71 @@ pub fn build(
         .ok()
 }
 
+/// Print an error for each `-D` name that is neither a compiler lint nor enabled by one
+/// of the evaluated designs, returning whether there were any
+pub fn report_unknown_lints(
+    deny: &[String],
+    evals: &[pcb_zen::WithDiagnostics<Schematic>],
+) -> bool {
+    let unknown = pcb_zen_core::lang::lint::unknown_lints(
+        deny,
+        evals.iter().flat_map(|eval| eval.diagnostics.iter()),
+    );
+    for lint in &unknown {
+        eprintln!(
+            "{} Unknown lint '{}' passed to -D",
+            pcb_ui::icons::error(),
+            lint.with_style(Style::Red).bold()
+        );
+    }
+    !unknown.is_empty()
+}
+
+/// Map `f` over `items` on up to `jobs` worker threads (defaulting to the number of
+/// CPUs), returning the results in input order.
+pub fn par_map<T, R, F>(items: &[T], jobs: Option<usize>, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+        .clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            std::thread::Builder::new()
+                .stack_size(WORKER_STACK_SIZE)
+                .spawn_scoped(scope, || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results.lock().unwrap()[index] = Some(result);
+                })
+                .expect("failed to spawn worker thread");
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("worker thread did not produce a result"))
+        .collect()
+}
+
 pub fn execute(args: BuildArgs) -> Result<()> {
     // Determine which .zen files to compile
     let zen_paths = if args.recursive {
@@ -109,14 +238,45 @@ pub fn execute(args: BuildArgs) -> Result<()> {
         );
     }
 
-    let mut has_errors = false;
+    if args.watch {
+        return crate::watch::watch(
+            &zen_paths,
+            args.offline,
+            pcb_zen::EvalMode::Build,
+            args.jobs,
+            |zen_paths, evals| {
+                report_all(zen_paths, evals, &args);
+            },
+        );
+    }
 
-    // Process each .zen file
-    for zen_path in zen_paths {
+    // Evaluate all files, then report them in a deterministic order
+    let evals = evaluate_all(
+        &zen_paths,
+        args.offline,
+        pcb_zen::EvalMode::Build,
+        args.jobs,
+        !args.no_cache,
+    );
+    if report_all(&zen_paths, evals, &args) {
+        anyhow::bail!("Build failed with errors");
+    }
+
+    Ok(())
+}
+
+/// Report the results of building `zen_paths`, returning whether any had errors
+fn report_all(
+    zen_paths: &[PathBuf],
+    evals: Vec<pcb_zen::WithDiagnostics<Schematic>>,
+    args: &BuildArgs,
+) -> bool {
+    let mut has_errors = report_unknown_lints(&args.deny, &evals);
+    for (zen_path, eval) in zen_paths.iter().zip(evals) {
         let file_name = zen_path.file_name().unwrap().to_string_lossy();
-        let Some(schematic) = build(
-            &zen_path,
-            args.offline,
+        let Some(schematic) = report_build(
+            zen_path,
+            eval,
             create_diagnostics_passes(&args.deny),
             &mut has_errors,
         ) else {
@@ -146,12 +306,7 @@ pub fn execute(args: BuildArgs) -> Result<()> {
             );
         }
     }
-
-    if has_errors {
-        anyhow::bail!("Build failed with errors");
-    }
-
-    Ok(())
+    has_errors
 }
 
 /// Collect .zen files from the provided paths
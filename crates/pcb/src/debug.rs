@@ -0,0 +1,8 @@
+use clap::Args;
+
+#[derive(Args)]
+pub struct DebugArgs {}
+
+pub fn execute(_args: DebugArgs) -> anyhow::Result<()> {
+    pcb_zen::dap::server()
+}
@@ -0,0 +1,377 @@
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use pcb_sch::diff::{AttributeChange, ComponentSummary};
+use pcb_sch::{diff_schematics, DiffSide, Schematic, SchematicDiff};
+use pcb_ui::prelude::*;
+use pcb_zen::{file_extensions, git, WithDiagnostics};
+use serde::Serialize;
+
+use crate::build::create_diagnostics_passes;
+
+/// Revision name that stands for the working tree, including uncommitted changes
+const WORKING_TREE: &str = ".";
+
+#[derive(ValueEnum, Debug, Clone, Default)]
+pub enum DiffFormat {
+    #[default]
+    Human,
+    Json,
+    Markdown,
+}
+
+impl std::fmt::Display for DiffFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffFormat::Human => write!(f, "human"),
+            DiffFormat::Json => write!(f, "json"),
+            DiffFormat::Markdown => write!(f, "markdown"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(about = "Compare the netlists of a design at two git revisions")]
+pub struct DiffArgs {
+    /// Old revision (any git revision, or '.' for the working tree)
+    #[arg(value_name = "REV_A")]
+    pub rev_a: String,
+
+    /// New revision (any git revision, or '.' for the working tree)
+    #[arg(value_name = "REV_B")]
+    pub rev_b: String,
+
+    /// .zen file to compare. When omitted, all .zen files in the current directory
+    /// are compared.
+    #[arg(value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub file: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, default_value_t = DiffFormat::Human)]
+    pub format: DiffFormat,
+
+    /// Disable network access (offline mode) - only use vendored dependencies
+    #[arg(long = "offline")]
+    pub offline: bool,
+}
+
+/// The diff of one file between the two revisions
+#[derive(Serialize)]
+struct FileDiff {
+    file: String,
+    #[serde(flatten)]
+    diff: SchematicDiff,
+}
+
+/// A revision checked out to evaluate; worktrees are removed on drop
+struct Checkout {
+    repo_root: PathBuf,
+    root: PathBuf,
+    temp_dir: Option<tempfile::TempDir>,
+}
+
+impl Checkout {
+    fn new(repo_root: &Path, rev: &str) -> Result<Self> {
+        if rev == WORKING_TREE {
+            return Ok(Self {
+                repo_root: repo_root.to_path_buf(),
+                root: repo_root.to_path_buf(),
+                temp_dir: None,
+            });
+        }
+
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().join("checkout");
+        git::add_worktree(repo_root, rev, &root)?;
+        let mut checkout = Self {
+            repo_root: repo_root.to_path_buf(),
+            root,
+            temp_dir: Some(temp_dir),
+        };
+        // Paths in the schematic are canonical (e.g. /private/var rather than /var on
+        // macOS), so the root must be too for it to be stripped from them
+        checkout.root = checkout.root.canonicalize()?;
+        Ok(checkout)
+    }
+
+    /// Evaluate `file` (relative to the repository root) at this revision; a file that
+    /// does not exist at this revision is an empty design
+    fn evaluate(&self, file: &Path, offline: bool) -> WithDiagnostics<Schematic> {
+        let path = self.root.join(file);
+        if !path.exists() {
+            return WithDiagnostics::success(Schematic::new());
+        }
+        pcb_zen::run(&path, offline, pcb_zen::EvalMode::Build)
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        if self.temp_dir.is_some() {
+            let _ = git::remove_worktree(&self.repo_root, &self.root);
+        }
+    }
+}
+
+/// The schematic of `file` at `rev`. If it failed to build, its diagnostics are rendered
+/// so the user can see why before the error is returned.
+fn built_schematic(eval: WithDiagnostics<Schematic>, file: &Path, rev: &str) -> Result<Schematic> {
+    eval.output_result().map_err(|mut diagnostics| {
+        diagnostics.apply_passes(&create_diagnostics_passes(&[]));
+        anyhow::anyhow!("Failed to build {} at {}", file.display(), rev)
+    })
+}
+
+pub fn execute(args: DiffArgs) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let repo_root = git::show_toplevel(&cwd)
+        .context("pcb diff must be run inside a git repository")?
+        .canonicalize()?;
+    let cwd = cwd.canonicalize()?;
+
+    let old = Checkout::new(&repo_root, &args.rev_a)
+        .with_context(|| format!("Failed to check out {}", args.rev_a))?;
+    let new = Checkout::new(&repo_root, &args.rev_b)
+        .with_context(|| format!("Failed to check out {}", args.rev_b))?;
+
+    let files = match &args.file {
+        Some(file) => {
+            let path = cwd.join(file);
+            let path = path.canonicalize().unwrap_or(path);
+            let relative = path
+                .strip_prefix(&repo_root)
+                .with_context(|| format!("{} is outside the repository", file.display()))?;
+            vec![relative.to_path_buf()]
+        }
+        None => {
+            let dir = cwd.strip_prefix(&repo_root).unwrap_or(Path::new(""));
+            let files = zen_files(&old.root.join(dir))
+                .into_iter()
+                .chain(zen_files(&new.root.join(dir)))
+                .map(|name| dir.join(name))
+                .collect::<BTreeSet<_>>();
+            if files.is_empty() {
+                anyhow::bail!("No .zen source files found in {}", cwd.display());
+            }
+            files.into_iter().collect()
+        }
+    };
+
+    let mut diffs = Vec::new();
+    for file in &files {
+        let spinner = Spinner::builder(format!("{}: Comparing", file.display())).start();
+        let old_eval = old.evaluate(file, args.offline);
+        let new_eval = new.evaluate(file, args.offline);
+        spinner.finish();
+        let old_schematic = built_schematic(old_eval, file, &args.rev_a)?;
+        let new_schematic = built_schematic(new_eval, file, &args.rev_b)?;
+
+        diffs.push(FileDiff {
+            file: file.display().to_string(),
+            diff: diff_schematics(
+                DiffSide {
+                    schematic: &old_schematic,
+                    root: &old.root,
+                },
+                DiffSide {
+                    schematic: &new_schematic,
+                    root: &new.root,
+                },
+            ),
+        });
+    }
+
+    match args.format {
+        DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diffs)?),
+        DiffFormat::Human => print!("{}", render_human(&diffs)),
+        DiffFormat::Markdown => print!("{}", render_markdown(&diffs, &args.rev_a, &args.rev_b)),
+    }
+    Ok(())
+}
+
+/// Names of the .zen files directly inside `dir`
+fn zen_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && file_extensions::is_starlark_file(path.extension()))
+        .filter_map(|path| path.file_name().map(PathBuf::from))
+        .collect()
+}
+
+fn component_label(component: &ComponentSummary) -> String {
+    let mut label = component.path.clone();
+    if let Some(designator) = &component.designator {
+        label = format!("{designator} ({label})");
+    }
+    let details: Vec<&str> = [&component.mpn, &component.footprint]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    if !details.is_empty() {
+        let _ = write!(label, ": {}", details.join(", "));
+    }
+    label
+}
+
+fn attribute_label(change: &AttributeChange) -> String {
+    let value = |v: &Option<String>| match v {
+        Some(v) if v.lines().count() > 1 => format!("<{} lines>", v.lines().count()),
+        Some(v) => v.clone(),
+        None => "(none)".to_string(),
+    };
+    format!(
+        "{}: {} -> {}",
+        change.name,
+        value(&change.old),
+        value(&change.new)
+    )
+}
+
+fn render_human(diffs: &[FileDiff]) -> String {
+    let mut out = String::new();
+    for FileDiff { file, diff } in diffs {
+        let _ = writeln!(out, "{}", file.as_str().bold());
+        if diff.is_empty() {
+            let _ = writeln!(out, "  No netlist changes");
+            continue;
+        }
+
+        let added = |label: String| format!("  + {label}").with_style(Style::Green);
+        let removed = |label: String| format!("  - {label}").with_style(Style::Red);
+        let changed = |label: String| format!("  ~ {label}").with_style(Style::Yellow);
+
+        for component in &diff.added_components {
+            let _ = writeln!(out, "{}", added(component_label(component)));
+        }
+        for component in &diff.removed_components {
+            let _ = writeln!(out, "{}", removed(component_label(component)));
+        }
+        for change in &diff.changed_components {
+            let _ = writeln!(out, "{}", changed(change.path.clone()));
+            for attribute in &change.attributes {
+                let _ = writeln!(out, "      {}", attribute_label(attribute));
+            }
+        }
+        for net in &diff.added_nets {
+            let label = format!("net {}: {}", net.name, net.ports.join(", "));
+            let _ = writeln!(out, "{}", added(label));
+        }
+        for net in &diff.removed_nets {
+            let label = format!("net {}: {}", net.name, net.ports.join(", "));
+            let _ = writeln!(out, "{}", removed(label));
+        }
+        for rename in &diff.renamed_nets {
+            let label = format!("net {} renamed to {}", rename.old, rename.new);
+            let _ = writeln!(out, "{}", changed(label));
+        }
+        for net in &diff.changed_nets {
+            let _ = writeln!(out, "{}", changed(format!("net {}", net.name)));
+            for port in &net.added_ports {
+                let _ = writeln!(out, "      + {port}");
+            }
+            for port in &net.removed_ports {
+                let _ = writeln!(out, "      - {port}");
+            }
+        }
+    }
+    out
+}
+
+/// Escape text for a Markdown table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn render_markdown(diffs: &[FileDiff], rev_a: &str, rev_b: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "## Netlist changes `{rev_a}` → `{rev_b}`");
+    for FileDiff { file, diff } in diffs {
+        let _ = writeln!(out, "\n### `{file}`\n");
+        if diff.is_empty() {
+            let _ = writeln!(out, "No netlist changes.");
+            continue;
+        }
+
+        let has_components = !diff.added_components.is_empty()
+            || !diff.removed_components.is_empty()
+            || !diff.changed_components.is_empty();
+        if has_components {
+            let _ = writeln!(out, "| | Component | Change |");
+            let _ = writeln!(out, "|---|---|---|");
+            for component in &diff.added_components {
+                let _ = writeln!(
+                    out,
+                    "| ➕ | `{}` | {} |",
+                    component.path,
+                    cell(&component_label(component))
+                );
+            }
+            for component in &diff.removed_components {
+                let _ = writeln!(
+                    out,
+                    "| ➖ | `{}` | {} |",
+                    component.path,
+                    cell(&component_label(component))
+                );
+            }
+            for change in &diff.changed_components {
+                let changes: Vec<String> = change
+                    .attributes
+                    .iter()
+                    .map(|attribute| cell(&attribute_label(attribute)))
+                    .collect();
+                let _ = writeln!(out, "| ✏️ | `{}` | {} |", change.path, changes.join("<br>"));
+            }
+            let _ = writeln!(out);
+        }
+
+        let has_nets = !diff.added_nets.is_empty()
+            || !diff.removed_nets.is_empty()
+            || !diff.renamed_nets.is_empty()
+            || !diff.changed_nets.is_empty();
+        if has_nets {
+            let ports = |ports: &[String]| {
+                ports
+                    .iter()
+                    .map(|port| format!("`{port}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let _ = writeln!(out, "| | Net | Ports |");
+            let _ = writeln!(out, "|---|---|---|");
+            for net in &diff.added_nets {
+                let _ = writeln!(out, "| ➕ | `{}` | {} |", net.name, ports(&net.ports));
+            }
+            for net in &diff.removed_nets {
+                let _ = writeln!(out, "| ➖ | `{}` | {} |", net.name, ports(&net.ports));
+            }
+            for rename in &diff.renamed_nets {
+                let _ = writeln!(
+                    out,
+                    "| ✏️ | `{}` → `{}` | unchanged |",
+                    rename.old, rename.new
+                );
+            }
+            for net in &diff.changed_nets {
+                let mut changes = Vec::new();
+                if !net.added_ports.is_empty() {
+                    changes.push(format!("added {}", ports(&net.added_ports)));
+                }
+                if !net.removed_ports.is_empty() {
+                    changes.push(format!("removed {}", ports(&net.removed_ports)));
+                }
+                let _ = writeln!(out, "| ✏️ | `{}` | {} |", net.name, changes.join("<br>"));
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,671 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::build::{collect_files, collect_files_recursive, create_diagnostics_passes};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use pcb_sch::{AttributeValue, InstanceKind, Schematic};
+use pcb_ui::prelude::*;
+use pcb_zen_core::config::find_workspace_root;
+use pcb_zen_core::convert::ToSchematic;
+use pcb_zen_core::lang::type_info::{ParameterInfo, TypeInfo};
+use pcb_zen_core::{attrs, DefaultFileProvider, EvalMode, InputMap};
+use starlark::syntax::AstModule;
+use starlark_syntax::syntax::ast::{AstLiteral, ExprP, StmtP};
+use starlark_syntax::syntax::module::AstModuleFields;
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "md",
+            DocFormat::Html => "html",
+        }
+    }
+}
+
+impl std::fmt::Display for DocFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocFormat::Markdown => write!(f, "markdown"),
+            DocFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(about = "Generate reference documentation for modules")]
+pub struct DocArgs {
+    /// One or more .zen files or directories containing .zen files (non-recursive) to document.
+    /// When omitted, all .zen files in the current directory are documented.
+    #[arg(value_name = "PATHS", value_hint = clap::ValueHint::AnyPath)]
+    pub paths: Vec<PathBuf>,
+
+    /// Recursively traverse directories to find .zen files
+    #[arg(short = 'r', long = "recursive", default_value_t = false)]
+    pub recursive: bool,
+
+    /// Output format
+    #[arg(short, long, default_value_t = DocFormat::Markdown)]
+    pub format: DocFormat,
+
+    /// Output directory (defaults to .pcb/docs in the workspace root)
+    #[arg(short, long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub output: Option<PathBuf>,
+
+    /// Disable network access (offline mode) - only use vendored dependencies
+    #[arg(long = "offline")]
+    pub offline: bool,
+}
+
+/// Everything we know about a single module, gathered from one evaluation.
+struct ModuleDoc {
+    /// Absolute path of the module's source file.
+    source: PathBuf,
+    /// Page path relative to the output directory.
+    page: PathBuf,
+    name: String,
+    docstring: Option<String>,
+    io: Vec<ParameterInfo>,
+    config: Vec<ParameterInfo>,
+    components: Vec<ComponentDoc>,
+    submodules: Vec<SubmoduleDoc>,
+}
+
+struct ComponentDoc {
+    name: String,
+    mpn: Option<String>,
+    kind: Option<String>,
+    footprint: Option<String>,
+}
+
+struct SubmoduleDoc {
+    name: String,
+    source: PathBuf,
+}
+
+pub fn execute(args: DocArgs) -> Result<()> {
+    let zen_paths: Vec<PathBuf> = if args.recursive {
+        collect_files_recursive(&args.paths)?
+    } else {
+        collect_files(&args.paths)?
+    }
+    .into_iter()
+    .filter(|p| p.extension().is_some_and(|ext| ext == "zen"))
+    .collect();
+
+    if zen_paths.is_empty() {
+        let cwd = std::env::current_dir()?;
+        anyhow::bail!(
+            "No .zen source files found in {}",
+            cwd.canonicalize().unwrap_or(cwd).display()
+        );
+    }
+
+    let cwd = std::env::current_dir()?;
+    let workspace_root = find_workspace_root(&DefaultFileProvider, &zen_paths[0]);
+    let workspace_root = workspace_root.canonicalize().unwrap_or(workspace_root);
+    let output_dir = match &args.output {
+        Some(dir) if dir.is_absolute() => dir.clone(),
+        Some(dir) => cwd.join(dir),
+        None => workspace_root.join(".pcb/docs"),
+    };
+
+    let mut has_errors = false;
+    let mut modules = Vec::new();
+
+    for zen_path in &zen_paths {
+        let file_name = zen_path.file_name().unwrap().to_string_lossy();
+        let spinner = Spinner::builder(format!("{file_name}: Evaluating")).start();
+        let doc = document_module(zen_path, &workspace_root, args.format, args.offline);
+        spinner.finish();
+
+        match doc {
+            Some(doc) => modules.push(doc),
+            None => has_errors = true,
+        }
+    }
+
+    modules.sort_by(|a, b| a.page.cmp(&b.page));
+    let pages: HashMap<&Path, &Path> = modules
+        .iter()
+        .map(|m| (m.source.as_path(), m.page.as_path()))
+        .collect();
+
+    for module in &modules {
+        let content = match args.format {
+            DocFormat::Markdown => render_markdown(module, &pages),
+            DocFormat::Html => render_html(module, &pages),
+        };
+        let path = output_dir.join(&module.page);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    fs::create_dir_all(&output_dir)?;
+    let index = match args.format {
+        DocFormat::Markdown => render_markdown_index(&modules),
+        DocFormat::Html => render_html_index(&modules),
+    };
+    let index_path = output_dir.join(format!("index.{}", args.format.extension()));
+    fs::write(&index_path, index)
+        .with_context(|| format!("Failed to write {}", index_path.display()))?;
+
+    let display_dir = output_dir.strip_prefix(&cwd).unwrap_or(&output_dir);
+    eprintln!(
+        "{} Documented {} module(s) in {}",
+        pcb_ui::icons::success(),
+        modules.len(),
+        display_dir.display()
+    );
+
+    if has_errors {
+        anyhow::bail!("Failed to document one or more modules");
+    }
+
+    Ok(())
+}
+
+/// Evaluate `zen_path` and collect its documentation. Diagnostics are rendered
+/// and `None` is returned if the module fails to evaluate.
+fn document_module(
+    zen_path: &Path,
+    workspace_root: &Path,
+    format: DocFormat,
+    offline: bool,
+) -> Option<ModuleDoc> {
+    let abs_path = zen_path
+        .canonicalize()
+        .unwrap_or_else(|_| zen_path.to_path_buf());
+
+    let eval = pcb_zen::create_eval_context(workspace_root, offline)
+        .set_source_path(abs_path.clone())
+        .set_module_name("<root>".to_string())
+        .set_inputs(InputMap::new())
+        .set_eval_mode(EvalMode::Build)
+        .eval();
+
+    let mut diagnostics = eval.diagnostics.clone();
+    diagnostics.apply_passes(&create_diagnostics_passes(&[]));
+    if diagnostics.has_errors() {
+        return None;
+    }
+    let output = eval.output?;
+
+    let schematic = match output.sch_module.to_schematic() {
+        Ok(schematic) => schematic,
+        Err(e) => {
+            eprintln!(
+                "{} {}: {e}",
+                pcb_ui::icons::error().with_style(Style::Red),
+                zen_path.display()
+            );
+            return None;
+        }
+    };
+
+    let (components, submodules) = collect_children(&schematic);
+    let (io, config) = output.signature.into_iter().partition(|p| !p.is_config());
+
+    let page = abs_path
+        .strip_prefix(workspace_root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| PathBuf::from(abs_path.file_name().unwrap()))
+        .with_extension(format.extension());
+
+    Some(ModuleDoc {
+        name: abs_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        source: abs_path,
+        page,
+        docstring: docstring(&output.ast),
+        io,
+        config,
+        components,
+        submodules,
+    })
+}
+
+/// A module's docstring is a string literal as its first top-level statement.
+fn docstring(ast: &AstModule) -> Option<String> {
+    let stmt = starlark_syntax::syntax::top_level_stmts::top_level_stmts(ast.statement())
+        .into_iter()
+        .next()?;
+    match &stmt.node {
+        StmtP::Expression(expr) => match &expr.node {
+            ExprP::Literal(AstLiteral::String(s)) => Some(dedent(&s.node)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Strip surrounding blank lines and the common indentation of a docstring.
+fn dedent(text: &str) -> String {
+    let lines: Vec<&str> = text.trim_matches('\n').lines().collect();
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            if i == 0 {
+                l.trim()
+            } else {
+                l.get(indent..).unwrap_or_else(|| l.trim_start())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Direct components and submodules of the schematic's root module.
+fn collect_children(schematic: &Schematic) -> (Vec<ComponentDoc>, Vec<SubmoduleDoc>) {
+    let mut components = Vec::new();
+    let mut submodules = Vec::new();
+
+    let Some(root) = schematic
+        .root_ref
+        .as_ref()
+        .and_then(|r| schematic.instances.get(r))
+    else {
+        return (components, submodules);
+    };
+
+    for (name, child_ref) in &root.children {
+        let Some(child) = schematic.instances.get(child_ref) else {
+            continue;
+        };
+        let attr = |key: &str| {
+            child
+                .attributes
+                .get(key)
+                .and_then(AttributeValue::string)
+                .map(str::to_string)
+        };
+        match child.kind {
+            InstanceKind::Component => components.push(ComponentDoc {
+                name: name.to_string(),
+                mpn: attr(attrs::MPN),
+                kind: attr(attrs::TYPE),
+                footprint: attr(attrs::FOOTPRINT).map(|fp| footprint_label(&fp)),
+            }),
+            InstanceKind::Module => submodules.push(SubmoduleDoc {
+                name: name.to_string(),
+                source: child.type_ref.source_path.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    components.sort_by(|a, b| a.name.cmp(&b.name));
+    submodules.sort_by(|a, b| a.name.cmp(&b.name));
+    (components, submodules)
+}
+
+/// Footprints resolved to `.kicad_mod` files are shown by their file stem.
+fn footprint_label(footprint: &str) -> String {
+    let path = Path::new(footprint);
+    if path.extension().is_some_and(|ext| ext == "kicad_mod") {
+        if let Some(stem) = path.file_stem() {
+            return stem.to_string_lossy().into_owned();
+        }
+    }
+    footprint.to_string()
+}
+
+fn type_label(type_info: &TypeInfo) -> String {
+    match type_info {
+        TypeInfo::String => "str".to_string(),
+        TypeInfo::Int => "int".to_string(),
+        TypeInfo::Float => "float".to_string(),
+        TypeInfo::Bool => "bool".to_string(),
+        TypeInfo::List { .. } => "list".to_string(),
+        TypeInfo::Dict { .. } => "dict".to_string(),
+        TypeInfo::Net => "Net".to_string(),
+        TypeInfo::Enum { name, .. }
+        | TypeInfo::Record { name, .. }
+        | TypeInfo::Interface { name, .. } => name.clone(),
+        TypeInfo::Unknown { type_name } => type_name.clone(),
+    }
+}
+
+/// Interface pins or enum variants, when the type has any.
+fn type_members(type_info: &TypeInfo) -> Option<String> {
+    match type_info {
+        TypeInfo::Interface { pins, .. } if !pins.is_empty() => Some(
+            pins.iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        TypeInfo::Enum { variants, .. } if !variants.is_empty() => Some(variants.join(", ")),
+        _ => None,
+    }
+}
+
+fn default_label(param: &ParameterInfo) -> String {
+    match &param.default_value {
+        Some(value) => value.to_string(),
+        None if param.required => "required".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Path of `target` relative to the directory containing `from`, using `/`.
+fn relative_link(from: &Path, target: &Path) -> String {
+    let base = from.parent().unwrap_or(Path::new(""));
+    let rel = pathdiff::diff_paths(target, base).unwrap_or_else(|| target.to_path_buf());
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn submodule_type(sub: &SubmoduleDoc) -> String {
+    sub.source
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn summary(doc: &ModuleDoc) -> &str {
+    doc.docstring
+        .as_deref()
+        .and_then(|d| d.lines().next())
+        .unwrap_or_default()
+}
+
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn render_markdown(doc: &ModuleDoc, pages: &HashMap<&Path, &Path>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", doc.name);
+    let _ = writeln!(out, "`{}`\n", doc.page.with_extension("zen").display());
+    if let Some(docstring) = &doc.docstring {
+        let _ = writeln!(out, "{docstring}\n");
+    }
+
+    if !doc.io.is_empty() {
+        let _ = writeln!(out, "## IO\n");
+        let _ = writeln!(out, "| Name | Type | Signals | Description |");
+        let _ = writeln!(out, "| --- | --- | --- | --- |");
+        for p in &doc.io {
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | {} | {} |",
+                p.name,
+                md_cell(&type_label(&p.type_info)),
+                md_cell(&type_members(&p.type_info).unwrap_or_default()),
+                md_cell(p.help.as_deref().unwrap_or_default()),
+            );
+        }
+        out.push('\n');
+    }
+
+    if !doc.config.is_empty() {
+        let _ = writeln!(out, "## Configuration\n");
+        let _ = writeln!(out, "| Name | Type | Default | Description |");
+        let _ = writeln!(out, "| --- | --- | --- | --- |");
+        for p in &doc.config {
+            let mut type_text = type_label(&p.type_info);
+            if let Some(members) = type_members(&p.type_info) {
+                type_text = format!("{type_text} ({members})");
+            }
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | {} | {} |",
+                p.name,
+                md_cell(&type_text),
+                md_cell(&default_label(p)),
+                md_cell(p.help.as_deref().unwrap_or_default()),
+            );
+        }
+        out.push('\n');
+    }
+
+    if !doc.components.is_empty() {
+        let _ = writeln!(out, "## Components\n");
+        let _ = writeln!(out, "| Name | Type | MPN | Footprint |");
+        let _ = writeln!(out, "| --- | --- | --- | --- |");
+        for c in &doc.components {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                md_cell(&c.name),
+                md_cell(c.kind.as_deref().unwrap_or_default()),
+                md_cell(c.mpn.as_deref().unwrap_or_default()),
+                md_cell(c.footprint.as_deref().unwrap_or_default()),
+            );
+        }
+        out.push('\n');
+    }
+
+    if !doc.submodules.is_empty() {
+        let _ = writeln!(out, "## Submodules\n");
+        let _ = writeln!(out, "| Name | Module |");
+        let _ = writeln!(out, "| --- | --- |");
+        for sub in &doc.submodules {
+            let module = match pages.get(sub.source.as_path()) {
+                Some(page) => format!(
+                    "[{}]({})",
+                    submodule_type(sub),
+                    relative_link(&doc.page, page)
+                ),
+                None => submodule_type(sub),
+            };
+            let _ = writeln!(out, "| {} | {} |", md_cell(&sub.name), module);
+        }
+        out.push('\n');
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+fn render_markdown_index(modules: &[ModuleDoc]) -> String {
+    let mut out = String::from("# Modules\n\n");
+    for doc in modules {
+        let link = relative_link(Path::new("index.md"), &doc.page);
+        let _ = match summary(doc) {
+            "" => writeln!(out, "- [{}]({link})", doc.name),
+            s => writeln!(out, "- [{}]({link}) - {s}", doc.name),
+        };
+    }
+    out
+}
+
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str =
+    "body{font-family:sans-serif;max-width:60em;margin:2em auto;padding:0 1em}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}\
+pre{white-space:pre-wrap}";
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        html_escape(title)
+    )
+}
+
+fn html_table(out: &mut String, title: &str, headers: &[&str], rows: Vec<Vec<String>>) {
+    if rows.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "<h2>{title}</h2>\n<table>");
+    let _ = writeln!(
+        out,
+        "<tr>{}</tr>",
+        headers
+            .iter()
+            .map(|h| format!("<th>{h}</th>"))
+            .collect::<String>()
+    );
+    for row in rows {
+        let _ = writeln!(
+            out,
+            "<tr>{}</tr>",
+            row.iter()
+                .map(|cell| format!("<td>{cell}</td>"))
+                .collect::<String>()
+        );
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+/// HTML rendering of an optional string, escaped.
+fn html_opt(text: Option<&str>) -> String {
+    html_escape(text.unwrap_or_default())
+}
+
+fn render_html(doc: &ModuleDoc, pages: &HashMap<&Path, &Path>) -> String {
+    let mut body = String::new();
+    let index = relative_link(&doc.page, Path::new("index.html"));
+    let _ = writeln!(body, "<p><a href=\"{}\">Index</a></p>", html_escape(&index));
+    let _ = writeln!(body, "<h1>{}</h1>", html_escape(&doc.name));
+    let _ = writeln!(
+        body,
+        "<p><code>{}</code></p>",
+        html_escape(&doc.page.with_extension("zen").display().to_string())
+    );
+    if let Some(docstring) = &doc.docstring {
+        let _ = writeln!(body, "<pre>{}</pre>", html_escape(docstring));
+    }
+
+    html_table(
+        &mut body,
+        "IO",
+        &["Name", "Type", "Signals", "Description"],
+        doc.io
+            .iter()
+            .map(|p| {
+                vec![
+                    format!("<code>{}</code>", html_escape(&p.name)),
+                    html_escape(&type_label(&p.type_info)),
+                    html_opt(type_members(&p.type_info).as_deref()),
+                    html_opt(p.help.as_deref()),
+                ]
+            })
+            .collect(),
+    );
+
+    html_table(
+        &mut body,
+        "Configuration",
+        &["Name", "Type", "Default", "Description"],
+        doc.config
+            .iter()
+            .map(|p| {
+                let mut type_text = type_label(&p.type_info);
+                if let Some(members) = type_members(&p.type_info) {
+                    type_text = format!("{type_text} ({members})");
+                }
+                vec![
+                    format!("<code>{}</code>", html_escape(&p.name)),
+                    html_escape(&type_text),
+                    html_escape(&default_label(p)),
+                    html_opt(p.help.as_deref()),
+                ]
+            })
+            .collect(),
+    );
+
+    html_table(
+        &mut body,
+        "Components",
+        &["Name", "Type", "MPN", "Footprint"],
+        doc.components
+            .iter()
+            .map(|c| {
+                vec![
+                    html_escape(&c.name),
+                    html_opt(c.kind.as_deref()),
+                    html_opt(c.mpn.as_deref()),
+                    html_opt(c.footprint.as_deref()),
+                ]
+            })
+            .collect(),
+    );
+
+    html_table(
+        &mut body,
+        "Submodules",
+        &["Name", "Module"],
+        doc.submodules
+            .iter()
+            .map(|sub| {
+                let module = html_escape(&submodule_type(sub));
+                let module = match pages.get(sub.source.as_path()) {
+                    Some(page) => format!(
+                        "<a href=\"{}\">{module}</a>",
+                        html_escape(&relative_link(&doc.page, page))
+                    ),
+                    None => module,
+                };
+                vec![html_escape(&sub.name), module]
+            })
+            .collect(),
+    );
+
+    html_page(&doc.name, &body)
+}
+
+fn render_html_index(modules: &[ModuleDoc]) -> String {
+    let mut body = String::from("<h1>Modules</h1>\n<ul>\n");
+    for doc in modules {
+        let link = relative_link(Path::new("index.html"), &doc.page);
+        let _ = write!(
+            body,
+            "<li><a href=\"{}\">{}</a>",
+            html_escape(&link),
+            html_escape(&doc.name)
+        );
+        match summary(doc) {
+            "" => body.push_str("</li>\n"),
+            s => {
+                let _ = writeln!(body, " - {}</li>", html_escape(s));
+            }
+        }
+    }
+    body.push_str("</ul>\n");
+    html_page("Modules", &body)
+}
@@ -5,7 +5,9 @@ use pcb_layout::{process_layout, LayoutError};
 use pcb_ui::prelude::*;
 use std::path::PathBuf;
 
-use crate::build::{build, collect_files, collect_files_recursive, create_diagnostics_passes};
+use crate::build::{
+    collect_files, collect_files_recursive, create_diagnostics_passes, evaluate_all, report_build,
+};
 
 #[derive(Args, Debug, Default, Clone)]
 #[command(about = "Generate PCB layout files from .zen files")]
@@ -32,6 +34,20 @@ pub struct LayoutArgs {
     /// Disable network access (offline mode) - only use vendored dependencies
     #[arg(long = "offline")]
     pub offline: bool,
+
+    /// Number of files to evaluate in parallel (defaults to the number of CPUs)
+    #[arg(short = 'j', long = "jobs", value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Re-evaluate every file instead of reusing cached results from `.pcb/eval-cache`
+    /// (also disabled by setting `PCB_NO_CACHE`)
+    #[arg(long = "no-cache")]
+    pub no_cache: bool,
+
+    /// Keep running and regenerate the layouts of the affected files whenever a file
+    /// they depend on changes. Layouts are not opened in this mode.
+    #[arg(short = 'w', long = "watch")]
+    pub watch: bool,
 }
 
 pub fn execute(args: LayoutArgs) -> Result<()> {
@@ -50,18 +66,69 @@ pub fn execute(args: LayoutArgs) -> Result<()> {
         );
     }
 
+    if args.watch {
+        return crate::watch::watch(
+            &zen_paths,
+            args.offline,
+            pcb_zen::EvalMode::Build,
+            args.jobs,
+            |zen_paths, evals| {
+                generate_layouts(zen_paths, evals, &mut false);
+            },
+        );
+    }
+
     let mut has_errors = false;
-    let mut generated_layouts = Vec::new();
 
-    // Process each .zen file
-    for zen_path in zen_paths {
+    // Evaluate all files up front; layout generation itself stays sequential
+    let evals = evaluate_all(
+        &zen_paths,
+        args.offline,
+        pcb_zen::EvalMode::Build,
+        args.jobs,
+        !args.no_cache,
+    );
+    let generated_layouts = generate_layouts(&zen_paths, evals, &mut has_errors);
+
+    if has_errors {
+        anyhow::bail!("Layout generation failed with errors");
+    }
+
+    if generated_layouts.is_empty() {
+        println!("\nNo layouts found.");
+        return Ok(());
+    }
+
+    // Open the selected layout if not disabled
+    if !args.no_open && !generated_layouts.is_empty() {
+        let layout_to_open = if generated_layouts.len() == 1 && !args.select {
+            // Only one layout and not forcing selection - open it directly
+            &generated_layouts[0].1
+        } else {
+            // Multiple layouts or forced selection - let user choose
+            let selected_idx = choose_layout(&generated_layouts)?;
+            &generated_layouts[selected_idx].1
+        };
+
+        open::that(layout_to_open)?;
+    }
+
+    Ok(())
+}
+
+/// Generate the layouts of the evaluated `zen_paths`, returning each generated
+/// (.zen file, layout file) pair
+fn generate_layouts(
+    zen_paths: &[PathBuf],
+    evals: Vec<pcb_zen::WithDiagnostics<pcb_sch::Schematic>>,
+    has_errors: &mut bool,
+) -> Vec<(PathBuf, PathBuf)> {
+    let mut generated_layouts = Vec::new();
+    for (zen_path, eval) in zen_paths.iter().zip(evals) {
         let file_name = zen_path.file_name().unwrap().to_string_lossy();
-        let Some(schematic) = build(
-            &zen_path,
-            args.offline,
-            create_diagnostics_passes(&[]),
-            &mut has_errors,
-        ) else {
+        let Some(schematic) =
+            report_build(zen_path, eval, create_diagnostics_passes(&[]), has_errors)
+        else {
             continue;
         };
 
@@ -69,7 +136,7 @@ pub fn execute(args: LayoutArgs) -> Result<()> {
         let spinner = Spinner::builder(format!("{file_name}: Generating layout")).start();
 
         // Check if the schematic has a layout
-        match process_layout(&schematic, &zen_path) {
+        match process_layout(&schematic, zen_path) {
             Ok(layout_result) => {
                 spinner.finish();
                 // Print success with the layout path relative to the star file
@@ -105,35 +172,11 @@ pub fn execute(args: LayoutArgs) -> Result<()> {
                     file_name.with_style(Style::Red).bold()
                 );
                 eprintln!("  Error: {e}");
-                has_errors = true;
+                *has_errors = true;
             }
         }
     }
-
-    if has_errors {
-        anyhow::bail!("Layout generation failed with errors");
-    }
-
-    if generated_layouts.is_empty() {
-        println!("\nNo layouts found.");
-        return Ok(());
-    }
-
-    // Open the selected layout if not disabled
-    if !args.no_open && !generated_layouts.is_empty() {
-        let layout_to_open = if generated_layouts.len() == 1 && !args.select {
-            // Only one layout and not forcing selection - open it directly
-            &generated_layouts[0].1
-        } else {
-            // Multiple layouts or forced selection - let user choose
-            let selected_idx = choose_layout(&generated_layouts)?;
-            &generated_layouts[selected_idx].1
-        };
-
-        open::that(layout_to_open)?;
-    }
-
-    Ok(())
+    generated_layouts
 }
 
 /// Let the user choose which layout to open
@@ -6,17 +6,23 @@ use std::process::Command;
 mod bom;
 mod build;
 mod clean;
+mod debug;
+mod diff;
+mod doc;
 mod fmt;
 mod info;
 mod layout;
 mod lsp;
 mod open;
+mod power;
 mod release;
+mod serve;
 mod sim;
 mod tag;
 mod test;
 mod upgrade;
 mod vendor;
+mod watch;
 mod workspace;
 
 #[derive(Parser)]
@@ -48,6 +54,15 @@ enum Commands {
     /// Generate Bill of Materials (BOM)
     Bom(bom::BomArgs),
 
+    /// Analyze power rails, loads and voltage ratings
+    Power(power::PowerArgs),
+
+    /// Generate reference documentation for modules
+    Doc(doc::DocArgs),
+
+    /// Compare the netlists of a design at two git revisions
+    Diff(diff::DiffArgs),
+
     /// Display workspace and board information
     Info(info::InfoArgs),
 
@@ -64,6 +79,12 @@ enum Commands {
     /// Language Server Protocol support
     Lsp(lsp::LspArgs),
 
+    /// Debug .zen files over the Debug Adapter Protocol
+    Debug(debug::DebugArgs),
+
+    /// Serve evaluations over JSON-RPC for editors, CI and other tools
+    Serve(serve::ServeArgs),
+
     /// Open PCB layout files
     #[command(alias = "o")]
     Open(open::OpenArgs),
@@ -102,11 +123,16 @@ fn main() -> anyhow::Result<()> {
         Commands::Test(args) => test::execute(args),
         Commands::Upgrade(args) => upgrade::execute(args),
         Commands::Bom(args) => bom::execute(args),
+        Commands::Power(args) => power::execute(args),
+        Commands::Doc(args) => doc::execute(args),
+        Commands::Diff(args) => diff::execute(args),
         Commands::Info(args) => info::execute(args),
         Commands::Layout(args) => layout::execute(args),
         Commands::Clean(args) => clean::execute(args),
         Commands::Fmt(args) => fmt::execute(args),
         Commands::Lsp(args) => lsp::execute(args),
+        Commands::Debug(args) => debug::execute(args),
+        Commands::Serve(args) => serve::execute(args),
         Commands::Open(args) => open::execute(args),
         Commands::Release(args) => release::execute(args),
         Commands::Tag(args) => tag::execute(args),
@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::build::create_diagnostics_passes;
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::Table;
+use pcb_sch::{analyze_power, PowerReport};
+use pcb_ui::prelude::*;
+
+#[derive(ValueEnum, Debug, Clone, Default)]
+pub enum PowerFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl std::fmt::Display for PowerFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerFormat::Table => write!(f, "table"),
+            PowerFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(about = "Analyze power rails, loads and voltage ratings")]
+pub struct PowerArgs {
+    /// .zen file to process
+    #[arg(value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub file: PathBuf,
+
+    /// Output format
+    #[arg(short, long, default_value_t = PowerFormat::Table)]
+    pub format: PowerFormat,
+
+    /// Disable network access (offline mode) - only use vendored dependencies
+    #[arg(long = "offline")]
+    pub offline: bool,
+}
+
+pub fn execute(args: PowerArgs) -> Result<()> {
+    let file_name = args.file.file_name().unwrap().to_string_lossy();
+
+    // Show spinner while processing
+    let spinner = Spinner::builder(format!("{file_name}: Building")).start();
+
+    // Evaluate the design
+    let schematic = pcb_zen::run(&args.file, args.offline, pcb_zen::EvalMode::Build)
+        .output_result()
+        .map_err(|mut diagnostics| {
+            // Apply passes and render diagnostics if there are errors
+            diagnostics.apply_passes(&create_diagnostics_passes(&[]));
+            anyhow::anyhow!("Failed to build {} - cannot analyze power", file_name)
+        })?;
+
+    spinner.set_message(format!("{file_name}: Analyzing power"));
+    let report = analyze_power(&schematic);
+    spinner.finish();
+
+    match args.format {
+        PowerFormat::Json => write_power_json(&report, io::stdout().lock())?,
+        PowerFormat::Table => write_power_table(&report, io::stdout().lock())?,
+    }
+
+    if report.has_violations() {
+        anyhow::bail!(
+            "Power analysis found {} violation(s)",
+            report.violations.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn write_power_json<W: Write>(report: &PowerReport, writer: W) -> Result<()> {
+    serde_json::to_writer_pretty(writer, report).context("Failed to write power report")?;
+    Ok(())
+}
+
+fn write_power_table<W: Write>(report: &PowerReport, mut writer: W) -> Result<()> {
+    if report.rails.is_empty() {
+        writeln!(writer, "No power rails found")?;
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
+
+    table.set_header(vec![
+        "Rail",
+        "Voltage",
+        "Load",
+        "Capacity",
+        "Supplied By",
+        "Fed From",
+        "Loads",
+    ]);
+
+    for rail in &report.rails {
+        table.add_row(vec![
+            rail.name.clone(),
+            rail.voltage
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
+            rail.load.to_string(),
+            rail.capacity
+                .as_ref()
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            rail.supplies
+                .iter()
+                .map(|s| s.designator.clone())
+                .collect::<Vec<_>>()
+                .join(","),
+            rail.sources.join(","),
+            rail.loads
+                .iter()
+                .map(|l| format!("{} ({})", l.designator, l.current))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]);
+    }
+
+    writeln!(writer, "{table}")?;
+
+    for violation in &report.violations {
+        writeln!(
+            writer,
+            "{} {}",
+            pcb_ui::icons::error().with_style(Style::Red),
+            violation
+        )?;
+    }
+
+    Ok(())
+}
@@ -600,7 +600,7 @@ fn validate_build(info: &ReleaseInfo) -> Result<()> {
 /// Generate design BOM JSON file
 fn generate_design_bom(info: &ReleaseInfo) -> Result<()> {
     // Generate BOM entries from the schematic
-    let bom_entries = generate_bom_entries(&mut info.schematic.clone());
+    let bom_entries = generate_bom_entries(&info.schematic);
 
     // Create bom directory in staging
     let bom_dir = info.staging_dir.join("bom");
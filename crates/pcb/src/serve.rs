@@ -0,0 +1,24 @@
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+#[command(about = "Serve evaluations over JSON-RPC")]
+pub struct ServeArgs {
+    /// Listen on this Unix socket instead of stdin/stdout
+    #[arg(long, value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
+    pub socket: Option<PathBuf>,
+
+    /// Disable network access (offline mode) - only use vendored dependencies
+    #[arg(long = "offline")]
+    pub offline: bool,
+}
+
+pub fn execute(args: ServeArgs) -> anyhow::Result<()> {
+    match args.socket {
+        #[cfg(unix)]
+        Some(socket) => pcb_zen::serve::unix_socket(&socket, args.offline),
+        #[cfg(not(unix))]
+        Some(_) => anyhow::bail!("--socket is only supported on Unix"),
+        None => pcb_zen::serve::stdio(args.offline),
+    }
+}
@@ -1,12 +1,17 @@
 use anyhow::Result;
 use clap::{Args, ValueEnum};
 use comfy_table::{presets::UTF8_FULL_CONDENSED, Cell, Color, Table};
-use log::debug;
 use pcb_ui::prelude::*;
+use pcb_zen_core::lang::error::{MutationResult, SnapshotMismatch, SourceSpan};
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::build::{collect_files, collect_files_recursive, create_diagnostics_passes};
+use crate::build::{
+    collect_files, collect_files_recursive, create_diagnostics_passes, evaluate_all, par_map,
+    report_unknown_lints,
+};
 
 #[derive(Args, Debug, Default, Clone)]
 #[command(about = "Run tests in .zen files")]
@@ -25,13 +30,53 @@ pub struct TestArgs {
     pub offline: bool,
 
     /// Set lint level to deny (treat as error). Use 'warnings' for all warnings,
-    /// or specific lint names like 'unstable-refs'
+    /// or specific lint names like 'unstable-refs' or 'floating-net'. Unknown names
+    /// are an error.
     #[arg(short = 'D', long = "deny", value_name = "LINT")]
     pub deny: Vec<String>,
 
     /// Output format for test results
     #[arg(short = 'f', long = "format", value_enum, default_value_t = OutputFormat::Table)]
     pub format: OutputFormat,
+
+    /// Number of files to evaluate in parallel (defaults to the number of CPUs)
+    #[arg(short = 'j', long = "jobs", value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Re-evaluate every file instead of reusing cached results from `.pcb/eval-cache`
+    /// (also disabled by setting `PCB_NO_CACHE`)
+    #[arg(long = "no-cache")]
+    pub no_cache: bool,
+
+    /// Write netlist snapshots that are missing or differ from the evaluated modules
+    /// to their `.snap` files instead of failing
+    #[arg(long = "update-snapshots")]
+    pub update_snapshots: bool,
+
+    /// Collect line coverage of the evaluated .zen files, the modules they instantiate
+    /// and the values each config()/io() took, and write it as an LCOV tracefile
+    #[arg(
+        long = "coverage",
+        value_name = "LCOV",
+        num_args = 0..=1,
+        default_missing_value = "lcov.info"
+    )]
+    pub coverage: Option<PathBuf>,
+
+    /// Re-run the checks of every passing test case against mutations of its module
+    /// (a dropped component, swapped pins, shorted nets, a changed passive value) and
+    /// report the mutations no check caught
+    #[arg(long = "mutate", conflicts_with = "coverage")]
+    pub mutate: bool,
+
+    /// Keep running and re-run the tests of the affected files whenever a file they
+    /// depend on changes
+    #[arg(
+        short = 'w',
+        long = "watch",
+        conflicts_with_all = ["coverage", "update_snapshots", "mutate"]
+    )]
+    pub watch: bool,
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -40,6 +85,7 @@ pub enum OutputFormat {
     Json,
     #[default]
     Table,
+    Junit,
 }
 
 #[derive(Serialize, Clone)]
@@ -49,6 +95,13 @@ pub struct TestResult {
     pub check_name: String,
     pub file_path: String,
     pub status: String, // "pass" or "fail"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub case_params: BTreeMap<String, String>,
+    pub duration_secs: f64,
 }
 
 #[derive(Serialize)]
@@ -64,26 +117,12 @@ pub struct TestSummary {
     pub failed: usize,
 }
 
-/// Test a single Starlark file by evaluating it and running testbench() calls
+/// Collect the structured test results of an evaluated file and print its diagnostics
 /// Returns structured test results including both successes and failures
 pub fn test(
-    zen_path: &Path,
-    offline: bool,
+    mut diagnostics: pcb_zen::Diagnostics,
     passes: Vec<Box<dyn pcb_zen_core::DiagnosticsPass>>,
 ) -> (Vec<pcb_zen_core::lang::error::BenchTestResult>, bool) {
-    let file_name = zen_path.file_name().unwrap().to_string_lossy();
-
-    // Show spinner while testing
-    debug!("Testing Zener file: {}", zen_path.display());
-    let spinner = Spinner::builder(format!("{file_name}: Testing")).start();
-
-    // Evaluate the design in test mode
-    let (_output, mut diagnostics) =
-        pcb_zen::run(zen_path, offline, pcb_zen::EvalMode::Test).unpack();
-
-    // Finish spinner before printing diagnostics
-    spinner.finish();
-
     // Collect structured test results before applying passes
     let test_results: Vec<pcb_zen_core::lang::error::BenchTestResult> = diagnostics
         .diagnostics
@@ -117,16 +156,62 @@ pub fn execute(args: TestArgs) -> Result<()> {
         );
     }
 
-    let mut all_test_results: Vec<pcb_zen_core::lang::error::BenchTestResult> = Vec::new();
-    let mut has_errors = false;
-
-    // Process each .zen file
-    for zen_path in zen_paths {
-        let (results, had_errors_file) = test(
-            &zen_path,
+    if args.watch {
+        return crate::watch::watch(
+            &zen_paths,
             args.offline,
-            create_diagnostics_passes(&args.deny),
+            pcb_zen::EvalMode::Test,
+            args.jobs,
+            |_, evals| {
+                if let Err(e) = report_tests(evals, &args) {
+                    eprintln!("Error: {e:#}");
+                }
+            },
         );
+    }
+
+    // Evaluate all files in test mode, then collect results in a deterministic order
+    let (mut evals, mut coverage) = evaluate_tests(&zen_paths, &args);
+    if args.update_snapshots && update_snapshots(&evals)? > 0 {
+        // Re-run so results reflect the recorded snapshots
+        (evals, coverage) = evaluate_tests(&zen_paths, &args);
+    }
+    let mutations = collect_mutations(&evals);
+    let failed = report_tests(evals, &args)?;
+
+    if args.mutate {
+        output_mutations(&mutations);
+    }
+
+    if let (Some(coverage), Some(lcov_path)) = (&coverage, &args.coverage) {
+        output_coverage(coverage);
+        std::fs::write(lcov_path, coverage.to_lcov())?;
+        eprintln!(
+            "{} {}",
+            "Wrote coverage to".with_style(Style::Green),
+            lcov_path.display()
+        );
+    }
+
+    // Exit with error if there were failures
+    if failed {
+        anyhow::bail!("Test run failed");
+    }
+
+    Ok(())
+}
+
+/// Output the test results of `evals` in the requested format, returning whether any
+/// test failed or any file had errors
+fn report_tests(
+    evals: Vec<pcb_zen::WithDiagnostics<pcb_sch::Schematic>>,
+    args: &TestArgs,
+) -> Result<bool> {
+    let mut all_test_results: Vec<pcb_zen_core::lang::error::BenchTestResult> = Vec::new();
+    let mut has_errors = report_unknown_lints(&args.deny, &evals);
+    for eval in evals {
+        let (results, had_errors_file) =
+            test(eval.diagnostics, create_diagnostics_passes(&args.deny));
         all_test_results.extend(results);
         if had_errors_file {
             has_errors = true;
@@ -142,6 +227,10 @@ pub fn execute(args: TestArgs) -> Result<()> {
             check_name: result.check_name.clone(),
             file_path: result.file_path.clone(),
             status: if result.passed { "pass" } else { "fail" }.to_string(),
+            span: result.span.clone(),
+            message: result.message.clone(),
+            case_params: result.case_params.clone(),
+            duration_secs: result.duration_secs,
         })
         .collect();
 
@@ -150,15 +239,230 @@ pub fn execute(args: TestArgs) -> Result<()> {
         OutputFormat::Tap => output_tap(&all_results),
         OutputFormat::Json => output_json(&all_results)?,
         OutputFormat::Table => output_table(&all_results),
+        OutputFormat::Junit => output_junit(&all_results),
     }
 
-    // Exit with error if there were failures
     let has_failures = all_test_results.iter().any(|r| !r.passed);
-    if has_failures || has_errors {
-        anyhow::bail!("Test run failed");
+    Ok(has_failures || has_errors)
+}
+
+/// Evaluate `zen_paths` in test mode, bypassing the cache and collecting fresh coverage
+/// when `--coverage` is set or mutation testing with `--mutate`
+fn evaluate_tests(
+    zen_paths: &[PathBuf],
+    args: &TestArgs,
+) -> (
+    Vec<pcb_zen::WithDiagnostics<pcb_sch::Schematic>>,
+    Option<Arc<pcb_zen::Coverage>>,
+) {
+    if args.mutate {
+        let spinner =
+            Spinner::builder(format!("Mutation testing {} files", zen_paths.len())).start();
+        let evals = par_map(zen_paths, args.jobs, |zen_path| {
+            pcb_zen::run_with_mutations(zen_path, args.offline, pcb_zen::EvalMode::Test)
+        });
+        spinner.finish();
+        return (evals, None);
     }
 
-    Ok(())
+    if args.coverage.is_none() {
+        let evals = evaluate_all(
+            zen_paths,
+            args.offline,
+            pcb_zen::EvalMode::Test,
+            args.jobs,
+            !args.no_cache,
+        );
+        return (evals, None);
+    }
+
+    let coverage = Arc::new(pcb_zen::Coverage::new());
+    let spinner =
+        Spinner::builder(format!("Testing {} files with coverage", zen_paths.len())).start();
+    let evals = par_map(zen_paths, args.jobs, |zen_path| {
+        pcb_zen::run_with_coverage(
+            zen_path,
+            args.offline,
+            pcb_zen::EvalMode::Test,
+            coverage.clone(),
+        )
+    });
+    spinner.finish();
+    (evals, Some(coverage))
+}
+
+/// The mutation results reported by every evaluated file
+fn collect_mutations(
+    evals: &[pcb_zen::WithDiagnostics<pcb_sch::Schematic>],
+) -> Vec<MutationResult> {
+    evals
+        .iter()
+        .flat_map(|eval| eval.diagnostics.iter())
+        .filter_map(|diag| diag.downcast_error_ref::<MutationResult>())
+        .cloned()
+        .collect()
+}
+
+/// Print the mutations no check caught to stderr, followed by the number of mutations
+/// that could not be scored and the mutation score
+fn output_mutations(mutations: &[MutationResult]) {
+    if mutations.is_empty() {
+        eprintln!("No mutations were run: mutation testing needs passing cases with checks");
+        return;
+    }
+
+    // Mutations a check raised an error on are reported as errors, not scored
+    let (errored, scored): (Vec<&MutationResult>, Vec<&MutationResult>) = mutations
+        .iter()
+        .partition(|mutation| mutation.error.is_some());
+    let survived: Vec<&MutationResult> = scored
+        .iter()
+        .copied()
+        .filter(|mutation| mutation.killed_by.is_none())
+        .collect();
+    if !survived.is_empty() {
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL_CONDENSED);
+        table.set_header(["TestBench", "Case", "Surviving mutation"].map(|header| {
+            Cell::new(header)
+                .fg(Color::Blue)
+                .add_attribute(comfy_table::Attribute::Bold)
+        }));
+        for mutation in &survived {
+            table.add_row(vec![
+                Cell::new(&mutation.test_bench_name),
+                Cell::new(mutation.case_name.as_deref().unwrap_or("-")),
+                Cell::new(&mutation.mutation).fg(Color::Red),
+            ]);
+        }
+        eprintln!("{table}");
+    }
+
+    if !errored.is_empty() {
+        eprintln!(
+            "{}",
+            format!(
+                "{} mutations were not scored because a check raised an error",
+                errored.len()
+            )
+            .with_style(Style::Red)
+        );
+    }
+    if scored.is_empty() {
+        return;
+    }
+
+    let killed = scored.len() - survived.len();
+    let score = format!(
+        "Mutation score: {killed}/{} killed ({:.1}%)",
+        scored.len(),
+        100.0 * killed as f64 / scored.len() as f64
+    );
+    if survived.is_empty() {
+        eprintln!("{}", score.with_style(Style::Green));
+    } else {
+        eprintln!("{}", score.with_style(Style::Yellow));
+    }
+}
+
+/// Print per-file coverage to stderr: lines run, module instantiations and the enum or
+/// bool values of each config()/io() that no test took
+fn output_coverage(coverage: &pcb_zen::Coverage) {
+    let files = coverage.files();
+    if files.is_empty() {
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(
+        ["File", "Lines", "Instances", "Values never taken"].map(|header| {
+            Cell::new(header)
+                .fg(Color::Blue)
+                .add_attribute(comfy_table::Attribute::Bold)
+        }),
+    );
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let (mut total, mut hit) = (0, 0);
+    for (path, file) in &files {
+        let display = Path::new(path)
+            .strip_prefix(&cwd)
+            .unwrap_or(Path::new(path))
+            .display()
+            .to_string();
+        let lines = file.lines.len();
+        let lines_hit = file.lines_hit();
+        total += lines;
+        hit += lines_hit;
+
+        let percent = if lines == 0 {
+            100.0
+        } else {
+            100.0 * lines_hit as f64 / lines as f64
+        };
+        let color = match percent {
+            p if p >= 90.0 => Color::Green,
+            p if p >= 50.0 => Color::Yellow,
+            _ => Color::Red,
+        };
+        let instances = if file.is_module {
+            file.instances.to_string()
+        } else {
+            "-".to_string()
+        };
+        let missed: Vec<String> = file
+            .params
+            .iter()
+            .filter(|(_, param)| !param.missed().is_empty())
+            .map(|(name, param)| format!("{name}: {}", param.missed().join(", ")))
+            .collect();
+
+        table.add_row(vec![
+            Cell::new(display),
+            Cell::new(format!("{lines_hit}/{lines} ({percent:.0}%)")).fg(color),
+            Cell::new(instances).fg(if file.is_module && file.instances == 0 {
+                Color::Red
+            } else {
+                Color::Reset
+            }),
+            Cell::new(missed.join("\n")),
+        ]);
+    }
+
+    eprintln!("{table}");
+    if total > 0 {
+        eprintln!(
+            "Line coverage: {hit}/{total} ({:.1}%)",
+            100.0 * hit as f64 / total as f64
+        );
+    }
+}
+
+/// Write the netlist snapshots that did not match their `.snap` files, returning how
+/// many were written
+fn update_snapshots(evals: &[pcb_zen::WithDiagnostics<pcb_sch::Schematic>]) -> Result<usize> {
+    let mut written = BTreeMap::new();
+    for eval in evals {
+        for diag in eval.diagnostics.iter() {
+            if let Some(mismatch) = diag.downcast_error_ref::<SnapshotMismatch>() {
+                written.insert(mismatch.path.clone(), mismatch.contents.clone());
+            }
+        }
+    }
+
+    for (path, contents) in &written {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, contents)?;
+        eprintln!(
+            "{} {}",
+            "Updated snapshot".with_style(Style::Green),
+            path.display()
+        );
+    }
+    Ok(written.len())
 }
 
 fn output_tap(results: &[TestResult]) {
@@ -183,6 +487,17 @@ fn output_tap(results: &[TestResult]) {
             "{} {} TestBench '{}'{} check '{}'",
             status, test_num, result.test_bench_name, case_suffix, result.check_name
         );
+
+        if result.status == "fail" {
+            println!("  ---");
+            if let Some(message) = &result.message {
+                println!("  message: {message:?}");
+            }
+            if let Some(span) = &result.span {
+                println!("  at: {span}");
+            }
+            println!("  ...");
+        }
     }
 }
 
@@ -271,3 +586,124 @@ fn output_json(results: &[TestResult]) -> Result<()> {
     println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
+
+/// Print results as JUnit XML, with one `<testsuite>` per TestBench and one `<testcase>`
+/// per check and test case.
+fn output_junit(results: &[TestResult]) {
+    // Group by TestBench, keeping the order in which benches first ran
+    let mut suites: Vec<((&str, &str), Vec<&TestResult>)> = Vec::new();
+    for result in results {
+        let key = (result.file_path.as_str(), result.test_bench_name.as_str());
+        match suites.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, cases)) => cases.push(result),
+            None => suites.push((key, vec![result])),
+        }
+    }
+
+    let failures = |cases: &[&TestResult]| cases.iter().filter(|r| r.status == "fail").count();
+    let time = |cases: &[&TestResult]| cases.iter().map(|r| r.duration_secs).sum::<f64>();
+    let all: Vec<&TestResult> = results.iter().collect();
+
+    println!(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    println!(
+        r#"<testsuites name="pcb test" tests="{}" failures="{}" time="{:.3}">"#,
+        all.len(),
+        failures(&all),
+        time(&all)
+    );
+    for ((file_path, test_bench_name), cases) in &suites {
+        println!(
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}" file="{}">"#,
+            xml_escape(test_bench_name),
+            cases.len(),
+            failures(cases),
+            time(cases),
+            xml_escape(file_path)
+        );
+        for result in cases {
+            let name = match &result.case_name {
+                Some(case_name) => format!("{case_name}/{}", result.check_name),
+                None => result.check_name.clone(),
+            };
+            let (file, line_attr) = match &result.span {
+                Some(span) => (span.path.as_str(), format!(r#" line="{}""#, span.line)),
+                None => (result.file_path.as_str(), String::new()),
+            };
+            println!(
+                r#"    <testcase name="{}" classname="{}" file="{}"{} time="{:.3}">"#,
+                xml_escape(&name),
+                xml_escape(test_bench_name),
+                xml_escape(file),
+                line_attr,
+                result.duration_secs
+            );
+            if !result.case_params.is_empty() {
+                println!("      <properties>");
+                for (key, value) in &result.case_params {
+                    println!(
+                        r#"        <property name="{}" value="{}"/>"#,
+                        xml_escape(key),
+                        xml_escape(value)
+                    );
+                }
+                println!("      </properties>");
+            }
+            if result.status == "fail" {
+                let message = result.message.as_deref().unwrap_or("check failed");
+                let location = result
+                    .span
+                    .as_ref()
+                    .map(|span| format!("{span}: "))
+                    .unwrap_or_default();
+                println!(
+                    r#"      <failure message="{}" type="check">{}{}</failure>"#,
+                    xml_escape(message),
+                    xml_escape(&location),
+                    xml_escape(message)
+                );
+            }
+            println!("    </testcase>");
+        }
+        println!("  </testsuite>");
+    }
+    println!("</testsuites>");
+}
+
+/// Escape `s` for XML, dropping ANSI escape sequences (e.g. colors in failure messages)
+/// and replacing other characters that XML 1.0 does not allow.
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\u{1b}' if chars.clone().next() == Some('[') => {
+                // A CSI sequence, e.g. "\x1b[31m", ends with a character in '@'..='~'
+                chars.next();
+                chars.find(|c| ('@'..='~').contains(c));
+            }
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_escape_drops_ansi_sequences_and_invalid_characters() {
+        assert_eq!(
+            xml_escape("\u{1b}[1m\u{1b}[31mfailed\u{1b}[0m: a < b\u{7}"),
+            "failed: a &lt; b\u{fffd}"
+        );
+        assert_eq!(xml_escape("line\n\tnext"), "line\n\tnext");
+    }
+}
@@ -2,11 +2,10 @@ use anyhow::Result;
 use clap::Args;
 use pcb_buildifier::Buildifier;
 use pcb_ui::prelude::*;
+use pcb_zen::codemods;
 use std::fs;
 use std::path::PathBuf;
 
-pub mod codemods;
-
 /// Arguments for the `upgrade` command
 #[derive(Args, Debug, Default, Clone)]
 #[command(about = "Upgrade PCB projects from .zen files")]
@@ -45,9 +44,7 @@ pub fn execute(args: UpgradeArgs) -> Result<()> {
     }
 
     // Initialize codemods sequence
-    let codemods: Vec<Box<dyn codemods::Codemod>> = vec![Box::new(
-        codemods::remove_directory_loads::RemoveDirectoryLoads,
-    )];
+    let codemods = codemods::all();
 
     let mut has_errors = false;
 
@@ -0,0 +1,240 @@
+use anyhow::Result;
+use log::debug;
+use pcb_sch::Schematic;
+use pcb_ui::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::build::par_map;
+
+/// How often watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Changes are batched until the watched files have been quiet for this long, so a
+/// save touching several files (or an editor writing in steps) triggers one re-run
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Evaluate `zen_paths` in `mode`, then re-evaluate each board whenever a file it
+/// depends on changes, until interrupted.
+///
+/// A board's dependencies are the files its last evaluation tracked (see
+/// [`pcb_zen::run_tracked`]), so only the boards affected by a change are re-run.
+/// Before each run the screen is cleared and `report` is called with the boards that
+/// were evaluated and their results, in the same order as `zen_paths`.
+pub fn watch<F>(
+    zen_paths: &[PathBuf],
+    offline: bool,
+    mode: pcb_zen::EvalMode,
+    jobs: Option<usize>,
+    mut report: F,
+) -> Result<()>
+where
+    F: FnMut(&[PathBuf], Vec<pcb_zen::WithDiagnostics<Schematic>>),
+{
+    let mut deps: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+    let mut boards = zen_paths.to_vec();
+    let mut changed: BTreeSet<PathBuf> = BTreeSet::new();
+
+    loop {
+        pcb_ui::clear_screen();
+        let _ = std::io::stdout().flush();
+        if !changed.is_empty() {
+            let names: Vec<String> = changed.iter().map(|path| display_name(path)).collect();
+            eprintln!(
+                "{} {}",
+                "Changed:".with_style(Style::Yellow).bold(),
+                names.join(", ")
+            );
+        }
+
+        let results = rerun(&boards, &mut deps, offline, mode, jobs);
+        report(&boards, results);
+
+        let mut mtimes = snapshot(&deps);
+        eprintln!(
+            "\n{}",
+            format!(
+                "Watching {} files for changes (press Ctrl+C to stop)",
+                mtimes.len()
+            )
+            .dimmed()
+        );
+
+        changed = wait_for_changes(&mut mtimes);
+        // A deleted board is re-run too, so its evaluation reports it as missing
+        boards = affected_boards(zen_paths, &deps, &changed);
+        debug!("Re-running {} boards after {:?}", boards.len(), changed);
+    }
+}
+
+/// Evaluate `boards`, recording the files each one depends on in `deps`, and return
+/// their results in order
+fn rerun(
+    boards: &[PathBuf],
+    deps: &mut BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    offline: bool,
+    mode: pcb_zen::EvalMode,
+    jobs: Option<usize>,
+) -> Vec<pcb_zen::WithDiagnostics<Schematic>> {
+    let evals = evaluate_tracked(boards, offline, mode, jobs);
+    let mut results = Vec::with_capacity(evals.len());
+    for (board, (eval, files)) in boards.iter().zip(evals) {
+        deps.insert(board.clone(), files);
+        results.push(eval);
+    }
+    results
+}
+
+/// Evaluate `zen_paths` in parallel, returning each result with its dependencies
+fn evaluate_tracked(
+    zen_paths: &[PathBuf],
+    offline: bool,
+    mode: pcb_zen::EvalMode,
+    jobs: Option<usize>,
+) -> Vec<(pcb_zen::WithDiagnostics<Schematic>, BTreeSet<PathBuf>)> {
+    let verb = match mode {
+        pcb_zen::EvalMode::Build => "Building",
+        pcb_zen::EvalMode::Test => "Testing",
+    };
+    let message = match zen_paths {
+        [single] => format!("{}: {verb}", display_name(single)),
+        _ => format!("{verb} {} files", zen_paths.len()),
+    };
+
+    let spinner = Spinner::builder(message).start();
+    let results = par_map(zen_paths, jobs, |zen_path| {
+        pcb_zen::run_tracked(zen_path, offline, mode)
+    });
+    spinner.finish();
+    results
+}
+
+/// Modification time of every file any board depends on; `None` for missing files
+fn snapshot(deps: &BTreeMap<PathBuf, BTreeSet<PathBuf>>) -> BTreeMap<PathBuf, Option<SystemTime>> {
+    deps.values()
+        .flatten()
+        .map(|path| (path.clone(), modified(path)))
+        .collect()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Block until at least one file in `mtimes` changes and no further change follows
+/// within [`DEBOUNCE`], updating `mtimes` and returning every file that changed
+fn wait_for_changes(mtimes: &mut BTreeMap<PathBuf, Option<SystemTime>>) -> BTreeSet<PathBuf> {
+    let mut changed = BTreeSet::new();
+    let mut quiet_since = None;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let mut any = false;
+        for (path, mtime) in mtimes.iter_mut() {
+            let current = modified(path);
+            if current != *mtime {
+                *mtime = current;
+                changed.insert(path.clone());
+                any = true;
+            }
+        }
+
+        if any {
+            quiet_since = Some(std::time::Instant::now());
+        } else if quiet_since.is_some_and(|since| since.elapsed() >= DEBOUNCE) {
+            return changed;
+        }
+    }
+}
+
+/// Boards, in `zen_paths` order, whose dependencies include a changed file
+fn affected_boards(
+    zen_paths: &[PathBuf],
+    deps: &BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    changed: &BTreeSet<PathBuf>,
+) -> Vec<PathBuf> {
+    zen_paths
+        .iter()
+        .filter(|board| {
+            deps.get(*board)
+                .is_some_and(|files| !files.is_disjoint(changed))
+        })
+        .cloned()
+        .collect()
+}
+
+fn display_name(path: &Path) -> String {
+    std::env::current_dir()
+        .and_then(|cwd| cwd.canonicalize())
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_boards_depending_on_a_changed_file_are_affected() {
+        let path = PathBuf::from;
+        let boards = [path("/p/a.zen"), path("/p/b.zen"), path("/p/c.zen")];
+        let deps = BTreeMap::from([
+            (
+                path("/p/a.zen"),
+                BTreeSet::from([path("/p/a.zen"), path("/p/lib.zen")]),
+            ),
+            (path("/p/b.zen"), BTreeSet::from([path("/p/b.zen")])),
+            (
+                path("/p/c.zen"),
+                BTreeSet::from([path("/p/c.zen"), path("/p/lib.zen")]),
+            ),
+        ]);
+
+        let changed = BTreeSet::from([path("/p/lib.zen")]);
+        assert_eq!(
+            affected_boards(&boards, &deps, &changed),
+            [path("/p/a.zen"), path("/p/c.zen")]
+        );
+
+        let changed = BTreeSet::from([path("/p/b.zen"), path("/p/other.zen")]);
+        assert_eq!(
+            affected_boards(&boards, &deps, &changed),
+            [path("/p/b.zen")]
+        );
+    }
+
+    #[test]
+    fn deleted_boards_are_re_run_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let board = dir.path().canonicalize().unwrap().join("board.zen");
+        std::fs::write(&board, "x = 1\n").unwrap();
+        let boards = [board.clone()];
+        let mut deps = BTreeMap::new();
+
+        let results = rerun(&boards, &mut deps, true, pcb_zen::EvalMode::Build, None);
+        assert!(!results[0].diagnostics.has_errors());
+
+        std::fs::remove_file(&board).unwrap();
+        let changed = BTreeSet::from([board.clone()]);
+        let affected = affected_boards(&boards, &deps, &changed);
+        assert_eq!(affected, [board.clone()]);
+
+        let results = rerun(&affected, &mut deps, true, pcb_zen::EvalMode::Build, None);
+        assert!(results[0].diagnostics.has_errors());
+        assert!(
+            results[0]
+                .diagnostics
+                .iter()
+                .any(|diag| diag.body.starts_with("Failed to read")),
+            "{:?}",
+            results[0].diagnostics
+        );
+        // Still watched, so the board is re-run once it comes back
+        assert!(deps[&board].contains(&board));
+    }
+}
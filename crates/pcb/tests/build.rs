@@ -606,3 +606,36 @@ SimpleResistor = Module("@github/mycompany/components:{}/SimpleResistor.zen")
         .snapshot_run("pcb", ["build", "board.zen"]);
     assert_snapshot!("commit_stable_ref", output);
 }
+
+#[test]
+fn test_parallel_build_shared_remote() {
+    let mut sandbox = Sandbox::new();
+
+    sandbox
+        .git_fixture("https://github.com/mycompany/components.git")
+        .write("SimpleResistor.zen", SIMPLE_RESISTOR_ZEN)
+        .write("test.kicad_mod", TEST_KICAD_MOD)
+        .commit("Add simple resistor component")
+        .tag("v1.0.0", false)
+        .push_mirror();
+
+    let board = |value: &str| {
+        format!(
+            r#"
+SimpleResistor = Module("@github/mycompany/components:v1.0.0/SimpleResistor.zen")
+
+SimpleResistor(name = "R1", value = "{value}", P1 = Net("VCC"), P2 = Net("GND"))
+"#
+        )
+    };
+
+    // Every board fetches the same remote concurrently; one of them fails to evaluate.
+    // Output must still be reported in file order.
+    let output = sandbox
+        .write("a.zen", board("1kOhm"))
+        .write("b.zen", board("2kOhm"))
+        .write("c.zen", WARNING_AND_ERROR_ZEN)
+        .write("d.zen", board("4kOhm"))
+        .snapshot_run("pcb", ["build", "-j", "4", "."]);
+    assert_snapshot!("parallel_build_shared_remote", output);
+}
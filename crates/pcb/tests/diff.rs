@@ -0,0 +1,131 @@
+#![cfg(not(target_os = "windows"))]
+
+use pcb_test_utils::sandbox::Sandbox;
+use serde_json::{json, Value};
+
+const RESISTOR_SYM: &str = r#"(kicad_symbol_lib (version 20211014) (generator kicad_symbol_editor)
+  (symbol "R" (in_bom yes) (on_board yes)
+    (property "Reference" "R" (id 0) (at 0 0 0))
+    (symbol "R_1_1"
+      (pin passive line (at 0 3.81 270) (length 1.27)
+        (name "~" (effects (font (size 1.27 1.27))))
+        (number "1" (effects (font (size 1.27 1.27))))
+      )
+      (pin passive line (at 0 -3.81 90) (length 1.27)
+        (name "~" (effects (font (size 1.27 1.27))))
+        (number "2" (effects (font (size 1.27 1.27))))
+      )
+    )
+  )
+)"#;
+
+/// Sandbox repository with two commits: R1 is 10k, then R1 is 4.7k and R2 is added
+fn two_commits() -> Sandbox {
+    let mut sb = Sandbox::new();
+    sb.write("resistor.kicad_sym", RESISTOR_SYM)
+        .write(
+            "board.zen",
+            r#"
+VCC = Net("VCC")
+GND = Net("GND")
+
+def resistor(name, value, a, b):
+    Component(
+        name = name,
+        prefix = "R",
+        footprint = "SMD:0402",
+        symbol = Symbol(library = "./resistor.kicad_sym"),
+        pins = {"1": a, "2": b},
+        properties = {"value": value},
+    )
+
+resistor("R1", "10k", VCC, GND)
+"#,
+        )
+        .init_git()
+        .commit("Initial commit");
+    sb.write(
+        "board.zen",
+        r#"
+VCC = Net("VCC")
+GND = Net("GND")
+
+def resistor(name, value, a, b):
+    Component(
+        name = name,
+        prefix = "R",
+        footprint = "SMD:0402",
+        symbol = Symbol(library = "./resistor.kicad_sym"),
+        pins = {"1": a, "2": b},
+        properties = {"value": value},
+    )
+
+resistor("R1", "4.7k", VCC, GND)
+resistor("R2", "1k", VCC, GND)
+"#,
+    )
+    .commit("Change R1 and add R2");
+    sb
+}
+
+fn diff_json(sb: &mut Sandbox, rev_a: &str, rev_b: &str) -> Value {
+    let output = sb
+        .run("pcb", ["diff", rev_a, rev_b, "board.zen", "-f", "json"])
+        .read()
+        .expect("pcb diff failed");
+    let diffs: Value = serde_json::from_str(&output).expect("pcb diff printed invalid JSON");
+    diffs[0].clone()
+}
+
+/// The added, removed and changed components of `diff`, one per line
+fn component_changes(diff: &Value) -> String {
+    let mut lines = Vec::new();
+    for (key, label) in [
+        ("added_components", "added"),
+        ("removed_components", "removed"),
+    ] {
+        for component in diff[key].as_array().unwrap() {
+            lines.push(format!("{label} {}", component["path"].as_str().unwrap()));
+        }
+    }
+    for change in diff["changed_components"].as_array().unwrap() {
+        for attribute in change["attributes"].as_array().unwrap() {
+            lines.push(format!(
+                "changed {} {}: {} -> {}",
+                change["path"].as_str().unwrap(),
+                attribute["name"].as_str().unwrap(),
+                attribute["old"],
+                attribute["new"],
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+#[test]
+fn diff_between_commits_reports_changed_and_added_components() {
+    let mut sb = two_commits();
+    let diff = diff_json(&mut sb, "HEAD~1", "HEAD");
+
+    assert_eq!(diff["file"], "board.zen");
+    // Only R1's value changed; the symbol path is the same file in both checkouts
+    insta::assert_snapshot!(component_changes(&diff));
+}
+
+#[test]
+fn diff_of_a_commit_against_an_unchanged_working_tree_is_empty() {
+    let mut sb = two_commits();
+    let diff = diff_json(&mut sb, "HEAD", ".");
+
+    for key in [
+        "added_components",
+        "removed_components",
+        "changed_components",
+        "added_nets",
+        "removed_nets",
+        "renamed_nets",
+        "changed_nets",
+    ] {
+        assert_eq!(diff[key], json!([]), "{key}: {diff:#}");
+    }
+}
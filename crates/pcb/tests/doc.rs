@@ -0,0 +1,79 @@
+#![cfg(not(target_os = "windows"))]
+
+use pcb_test_utils::assert_snapshot;
+use pcb_test_utils::sandbox::Sandbox;
+
+const DIVIDER_ZEN: &str = r#"
+"""
+Resistive voltage divider.
+
+Scales VIN down to VOUT with two resistors.
+"""
+
+Power = interface(vcc = Net, gnd = Net)
+
+ratio = config("ratio", float, default = 0.5, help = "Output / input ratio")
+package = config("package", str, optional = True, help = "Resistor package | size")
+
+VIN = io("VIN", Power, help = "Input rail")
+VOUT = io("VOUT", Net)
+
+def resistor(name, p1, p2):
+    Component(
+        name = name,
+        prefix = "R",
+        footprint = File("test.kicad_mod"),
+        pin_defs = {"P1": "1", "P2": "2"},
+        pins = {"P1": p1, "P2": p2},
+        properties = {"type": "resistor", "mpn": "RC0603"},
+    )
+
+resistor("R_TOP", VIN.vcc, VOUT)
+resistor("R_BOT", VOUT, VIN.gnd)
+"#;
+
+const BOARD_ZEN: &str = r#"
+"""Demo board."""
+
+Divider = Module("Divider.zen")
+Power = interface(vcc = Net, gnd = Net)
+
+Divider(name = "DIV", VIN = Power("VIN"), VOUT = Net("VOUT"))
+"#;
+
+const TEST_KICAD_MOD: &str = r#"(footprint "test"
+  (layer "F.Cu")
+  (pad "1" smd rect (at -1 0) (size 1 1) (layers "F.Cu"))
+  (pad "2" smd rect (at 1 0) (size 1 1) (layers "F.Cu"))
+)
+"#;
+
+fn seed(sb: &mut Sandbox) -> &mut Sandbox {
+    sb.write("Divider.zen", DIVIDER_ZEN)
+        .write("Board.zen", BOARD_ZEN)
+        .write("test.kicad_mod", TEST_KICAD_MOD)
+}
+
+fn read(sb: &Sandbox, rel: &str) -> String {
+    std::fs::read_to_string(sb.default_cwd().join(rel)).expect("read generated doc")
+}
+
+#[test]
+fn test_doc_markdown() {
+    let mut sb = Sandbox::new();
+    let output = seed(&mut sb).snapshot_run("pcb", ["doc", ".", "-o", "docs"]);
+    assert_snapshot!("doc_markdown_output", output);
+    assert_snapshot!("doc_markdown_divider", read(&sb, "docs/Divider.md"));
+    assert_snapshot!("doc_markdown_board", read(&sb, "docs/Board.md"));
+    assert_snapshot!("doc_markdown_index", read(&sb, "docs/index.md"));
+}
+
+#[test]
+fn test_doc_html() {
+    let mut sb = Sandbox::new();
+    seed(&mut sb)
+        .run("pcb", ["doc", "Divider.zen", "-f", "html", "-o", "site"])
+        .run()
+        .unwrap();
+    assert_snapshot!("doc_html_divider", read(&sb, "site/Divider.html"));
+}
@@ -76,3 +76,19 @@ fn test_tap_output() {
 
     assert_snapshot!("tap_output", output);
 }
+
+#[test]
+fn test_junit_output() {
+    let output = Sandbox::new()
+        .seed_stdlib(&["v0.2.8"])
+        .seed_kicad(&["9.0.0"])
+        .write("matchers.zen", MATCHERS_ZEN)
+        .write("simple_module.zen", SIMPLE_MODULE_ZEN)
+        .write("failing_checks_testbench.zen", FAILING_CHECKS_TESTBENCH_ZEN)
+        .snapshot_run(
+            "pcb",
+            ["test", "failing_checks_testbench.zen", "-f", "junit"],
+        );
+
+    assert_snapshot!("junit_output", output);
+}